@@ -19,6 +19,17 @@ public:
   }
   using ReceiveCBack = std::function<void(const std::string& to, const std::string& from, const std::string& data)>;
   using StatusCBack = std::function<void(int kind, const std::string& topic, const std::string& peer, const std::string& message)>;
+  using PeerCBack = std::function<void(const lnr_peer_info& peer)>;
+  using MempoolCBack = std::function<void(const lnr_mempool_info& info)>;
+  struct DeliveryInfo{
+      std::string topic;
+      std::string from;
+      std::string senderName;
+      unsigned long long number;
+      unsigned long long sentAtMs;
+      bool replayed;
+  };
+  using ReceiveExCBack = std::function<void(const DeliveryInfo& info, const std::string& data)>;
   
   bool run(ReceiveCBack cb){
       if (m_hClient){
@@ -28,6 +39,14 @@ public:
       return false;
   }
 
+  bool runEx(ReceiveExCBack cb){
+      if (m_hClient){
+          m_receiveExCBack = cb;
+          return lnr_run_ex(m_hClient, receiveExCb, this);
+      }
+      return false;
+  }
+
   /// Status / background-error callback. Pass empty function or call with nullptr-equivalent via clear.
   bool setStatusCallback(StatusCBack cb){
       if (!m_hClient){
@@ -50,6 +69,106 @@ public:
       return false;
   }
 
+  /// Same as `sendTo`, but the replica is chosen by `key` (consistent hash, per-key affinity).
+  bool sendToKeyed(const std::string& topic, const std::string& key, const std::string& data, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_to_keyed(m_hClient, topic.c_str(), key.c_str(), data.data(), data.size(),
+                                   at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// Same as `sendTo`, on the LNR_PRIORITY_* lane of the peer.
+  bool sendToPriority(const std::string& topic, const std::string& data, int priority, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_to_priority(m_hClient, topic.c_str(), data.data(), data.size(),
+                                      at_least_once_delivery ? TRUE : FALSE, priority);
+      }
+      return false;
+  }
+
+  /// Same as `sendTo`, but only to the replica registered as `unique_name`.
+  bool sendToPeer(const std::string& topic, const std::string& unique_name, const std::string& data, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_to_peer(m_hClient, topic.c_str(), unique_name.c_str(), data.data(), data.size(),
+                                  at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// `sendTo` at `deliver_at_ms` (ms since the Unix epoch); kept in the store until then.
+  bool sendToAt(const std::string& topic, const std::string& data, unsigned long long deliver_at_ms, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_to_at(m_hClient, topic.c_str(), data.data(), data.size(), deliver_at_ms,
+                                at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// `sendToAt` `delay_ms` from now.
+  bool sendToAfter(const std::string& topic, const std::string& data, unsigned long long delay_ms, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_to_after(m_hClient, topic.c_str(), data.data(), data.size(), delay_ms,
+                                   at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// Threads running receive callbacks of different senders in parallel; call before run.
+  bool setReceiveWorkers(size_t n){
+      if (m_hClient){
+          return lnr_set_receive_workers(m_hClient, n);
+      }
+      return false;
+  }
+
+  size_t receiveWorkers(){
+      if (m_hClient){
+          return lnr_get_receive_workers(m_hClient);
+      }
+      return 0;
+  }
+
+  /// LNR_ROUTE_ROUND_ROBIN / LNR_ROUTE_LEAST_LOADED for every topic without an override.
+  bool setRoutePolicy(int policy){
+      if (m_hClient){
+          return lnr_set_route_policy(m_hClient, policy);
+      }
+      return false;
+  }
+
+  /// Per-topic override; negative `policy` clears it.
+  bool setTopicRoutePolicy(const std::string& topic, int policy){
+      if (m_hClient){
+          return lnr_set_topic_route_policy(m_hClient, topic.c_str(), policy);
+      }
+      return false;
+  }
+
+  /// Re-route unacked messages of a lost replica of `topic` to a live one.
+  bool setTopicFailover(const std::string& topic, bool enabled){
+      if (m_hClient){
+          return lnr_set_topic_failover(m_hClient, topic.c_str(), enabled ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// Move the offline queue of a removed listener to another one; `-1` on error.
+  long long reassignPending(const std::string& from_unique_name, const std::string& to_unique_name){
+      if (m_hClient){
+          return lnr_reassign_pending(m_hClient, from_unique_name.c_str(), to_unique_name.c_str());
+      }
+      return -1;
+  }
+
+  /// Same, with the target picked from `topic`'s directory.
+  long long reassignPendingToTopic(const std::string& from_unique_name, const std::string& topic){
+      if (m_hClient){
+          return lnr_reassign_pending_to_topic(m_hClient, from_unique_name.c_str(), topic.c_str());
+      }
+      return -1;
+  }
+
   bool sendAll(const std::string& topic, const std::string& data, bool at_least_once_delivery = true){
       if (m_hClient){
           return lnr_send_all(m_hClient, topic.c_str(), data.data(), data.size(),
@@ -58,6 +177,49 @@ public:
       return false;
   }
 
+  /// Same as `sendAll`, on the LNR_PRIORITY_* lane of every peer.
+  bool sendAllPriority(const std::string& topic, const std::string& data, int priority, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_all_priority(m_hClient, topic.c_str(), data.data(), data.size(),
+                                       at_least_once_delivery ? TRUE : FALSE, priority);
+      }
+      return false;
+  }
+
+  /// Broadcast and keep `data` as the retained value of `topic` for later subscribers.
+  bool sendAllRetained(const std::string& topic, const std::string& data, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_all_retained(m_hClient, topic.c_str(), data.data(), data.size(),
+                                       at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  bool clearRetained(const std::string& topic){
+      if (m_hClient){
+          return lnr_clear_retained(m_hClient, topic.c_str());
+      }
+      return false;
+  }
+
+  /// One copy per consumer group (to one member) plus every ungrouped subscriber.
+  bool sendAllGroups(const std::string& topic, const std::string& data, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_all_groups(m_hClient, topic.c_str(), data.data(), data.size(),
+                                     at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
+  /// Same as `sendAllGroups`, but the member of each group is chosen by `key`.
+  bool sendAllGroupsKeyed(const std::string& topic, const std::string& key, const std::string& data, bool at_least_once_delivery = true){
+      if (m_hClient){
+          return lnr_send_all_groups_keyed(m_hClient, topic.c_str(), key.c_str(), data.data(), data.size(),
+                                           at_least_once_delivery ? TRUE : FALSE);
+      }
+      return false;
+  }
+
   bool subscribe(const std::string& topic){
       if (m_hClient){
           return lnr_subscribe(m_hClient, topic.c_str());
@@ -65,6 +227,22 @@ public:
       return false;
   }
 
+  /// Registration outlives stop(); senders queue at-least-once messages offline meanwhile.
+  bool subscribeDurable(const std::string& topic){
+      if (m_hClient){
+          return lnr_subscribe_durable(m_hClient, topic.c_str());
+      }
+      return false;
+  }
+
+  /// Subscribe as a member of consumer group `group`.
+  bool subscribeGroup(const std::string& topic, const std::string& group){
+      if (m_hClient){
+          return lnr_subscribe_group(m_hClient, topic.c_str(), group.c_str());
+      }
+      return false;
+  }
+
   bool unsubscribe(const std::string& topic){
       if (m_hClient){
           return lnr_unsubscribe(m_hClient, topic.c_str());
@@ -86,6 +264,83 @@ public:
       return false;
   }
 
+  /// Live state of every outbound route lane and inbound connection, one call each.
+  bool peers(const PeerCBack& cb){
+      if (m_hClient && cb){
+          return lnr_peers(m_hClient, peerCb, const_cast<PeerCBack*>(&cb));
+      }
+      return false;
+  }
+
+  /// One call per sender and listener slot; see `lnr_mempool_stats`.
+  bool mempoolStats(const MempoolCBack& cb){
+      if (m_hClient && cb){
+          return lnr_mempool_stats(m_hClient, mempoolCb, const_cast<MempoolCBack*>(&cb));
+      }
+      return false;
+  }
+
+  /// Bytes released, `-1` on error; see `lnr_compact_memory`.
+  long long compactMemory(){
+      if (m_hClient){
+          return lnr_compact_memory(m_hClient);
+      }
+      return -1;
+  }
+
+  /// Readiness / liveness report; see `lnr_health`.
+  bool health(lnr_health_info& out){
+      if (m_hClient){
+          return lnr_health(m_hClient, &out);
+      }
+      return false;
+  }
+
+  /// Record every frame written or read to `path`; see `lnr_start_capture`.
+  bool startCapture(const std::string& path){
+      if (m_hClient){
+          return lnr_start_capture(m_hClient, path.c_str());
+      }
+      return false;
+  }
+
+  bool stopCapture(){
+      if (m_hClient){
+          return lnr_stop_capture(m_hClient);
+      }
+      return false;
+  }
+
+  /// Client totals; see `peerMetrics` / `topicMetrics` for a single peer or topic.
+  bool metricsSnapshot(lnr_metrics& out){
+      if (m_hClient){
+          return lnr_metrics_snapshot(m_hClient, &out);
+      }
+      return false;
+  }
+
+  bool peerMetrics(const std::string& peer, lnr_metrics& out){
+      if (m_hClient){
+          return lnr_peer_metrics(m_hClient, peer.c_str(), &out);
+      }
+      return false;
+  }
+
+  bool topicMetrics(const std::string& topic, lnr_metrics& out){
+      if (m_hClient){
+          return lnr_topic_metrics(m_hClient, topic.c_str(), &out);
+      }
+      return false;
+  }
+
+  /// Needs the library built with `--features prometheus`.
+  bool serveMetrics(const std::string& addr){
+      if (m_hClient){
+          return lnr_serve_metrics(m_hClient, addr.c_str());
+      }
+      return false;
+  }
+
   bool clear_stored_messages(){
       if (m_hClient){
           return lnr_clear_stored_messages(m_hClient);
@@ -102,6 +357,27 @@ public:
             }
         }   
     }
+    static void receiveExCb(const char* to, const char* from, const char* senderName,
+                            unsigned long long number, unsigned long long sentAtMs, int replayed,
+                            const char* data, size_t data_size, void* udata){
+        if (udata){
+            auto clt = static_cast<LinerBroker*>(udata);
+            if (clt->m_receiveExCBack){
+                clt->m_receiveExCBack(DeliveryInfo{to, from, senderName, number, sentAtMs, replayed != 0},
+                                      std::string(data, data_size));
+            }
+        }
+    }
+    static void peerCb(const lnr_peer_info* peer, void* udata){
+        if (peer && udata){
+            (*static_cast<PeerCBack*>(udata))(*peer);
+        }
+    }
+    static void mempoolCb(const lnr_mempool_info* info, void* udata){
+        if (info && udata){
+            (*static_cast<MempoolCBack*>(udata))(*info);
+        }
+    }
     static void statusCb(int kind, const char* topic, const char* peer, const char* message, void* udata){
         if (udata){
             auto clt = static_cast<LinerBroker*>(udata);
@@ -116,6 +392,7 @@ public:
     }
     lnr_hClient m_hClient{};
     ReceiveCBack m_receiveCBack{};
+    ReceiveExCBack m_receiveExCBack{};
     StatusCBack m_statusCBack{};
 
 };
\ No newline at end of file
@@ -38,6 +38,14 @@ typedef enum BOOL{ FALSE = 0, TRUE = 1}BOOL;
 
 typedef void* lnr_uData;
 typedef void(*lnr_receive_cb)(const char* to, const char* from, const char* data, size_t data_size, lnr_uData);
+/// Receive callback with delivery metadata (see `lnr_run_ex`).
+/// @param sender_name - sender's unique_name ("" for retained values)
+/// @param number - per-connection message number (0 for retained values)
+/// @param sent_at_ms - sender clock when encoded, ms since Unix epoch (0 if unknown)
+/// @param replayed - 1 when resent from an offline queue, else 0
+typedef void(*lnr_receive_ex_cb)(const char* to, const char* from, const char* sender_name,
+                                 unsigned long long number, unsigned long long sent_at_ms, int replayed,
+                                 const char* data, size_t data_size, lnr_uData);
 
 typedef void* lnr_hClient;
 
@@ -99,6 +107,24 @@ enum {
     LNR_ERR_BUSY = 11
 };
 
+/// Replica selection for lnr_send_to when a topic has several addresses.
+enum {
+    /** Rotate over the cached address list (default). */
+    LNR_ROUTE_ROUND_ROBIN = 0,
+    /** Smallest in-memory send queue + offline backlog; skips replicas without a live stream. */
+    LNR_ROUTE_LEAST_LOADED = 1
+};
+
+/// Send lane for lnr_send_to_priority / lnr_send_all_priority.
+enum {
+    /** Default lane of lnr_send_to / lnr_send_all. */
+    LNR_PRIORITY_NORMAL = 0,
+    /** Own queue, connection and offline list per peer; written before NORMAL. */
+    LNR_PRIORITY_HIGH = 1,
+    /** Written before HIGH and NORMAL. */
+    LNR_PRIORITY_URGENT = 2
+};
+
 /// Last sync-API error code for this client (`LNR_OK` after success). See `LNR_ERR_*`.
 LINER_API int lnr_last_error_code(lnr_hClient client);
 
@@ -111,6 +137,9 @@ LINER_API const char* lnr_last_error_message(lnr_hClient client);
 /// @return true - ok
 LINER_API BOOL lnr_run(lnr_hClient client, lnr_receive_cb receive_cb, lnr_uData);
 
+/// Same as `lnr_run`, but the callback also gets sender name, message number, send time and replay flag.
+LINER_API BOOL lnr_run_ex(lnr_hClient client, lnr_receive_ex_cb receive_cb, lnr_uData);
+
 /// Stop listener/sender and unregister from the store (idempotent). Allows `clear_*` / `run` again.
 LINER_API BOOL lnr_stop(lnr_hClient client);
 
@@ -129,6 +158,99 @@ LINER_API BOOL lnr_send_to(lnr_hClient client,
                           const char* data, size_t data_size,
                           BOOL at_least_once_delivery);
 
+/// Send data to other topic - only to one, the replica that owns `key` on a consistent-hash ring
+/// (same key -> same replica while the topic's address set is unchanged)
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param key - routing key (e.g. account id), not empty
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @return true - ok
+LINER_API BOOL lnr_send_to_keyed(lnr_hClient client,
+                          const char* topic,
+                          const char* key,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery);
+
+/// Send data to other topic - only to one, on a priority lane: raised lanes do not wait behind
+/// NORMAL traffic and the listener runs their callbacks first (order holds within one lane)
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @param priority - LNR_PRIORITY_*
+/// @return true - ok, false - see lnr_last_error_code
+LINER_API BOOL lnr_send_to_priority(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery,
+                          int priority);
+
+/// Send message to other clients. 
+/// Call only when the client is already running.
+/// 
+/// Send data to one specific replica of other topic - the client registered as `unique_name`
+/// (looked up in the store topic directory; route policy is bypassed)
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param unique_name - unique name of the target replica, not empty
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @return true - ok, false - see lnr_last_error_code (LNR_ERR_NO_ADDR if unknown on the topic)
+LINER_API BOOL lnr_send_to_peer(lnr_hClient client,
+                          const char* topic,
+                          const char* unique_name,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery);
+
+/// Scheduled send: data is kept in the store and sent like lnr_send_to when due.
+/// Survives a restart of the sender (released by its next lnr_run).
+/// Call only when the client is already running.
+/// @param lnr_hClient
+/// @param topic - other topic, not a pattern
+/// @param data
+/// @param data_size
+/// @param deliver_at_ms - due time, ms since the Unix epoch (past = next scheduler tick)
+/// @param at_least_once_delivery - same semantics as lnr_send_to; a due message without a route is retried, otherwise dropped
+/// @return true - stored, false - see lnr_last_error_code
+LINER_API BOOL lnr_send_to_at(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          unsigned long long deliver_at_ms,
+                          BOOL at_least_once_delivery);
+
+/// lnr_send_to_at with due time `delay_ms` from now
+LINER_API BOOL lnr_send_to_after(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          unsigned long long delay_ms,
+                          BOOL at_least_once_delivery);
+
+/// Default replica selection for lnr_send_to (LNR_ROUTE_*)
+/// @param lnr_hClient
+/// @param policy
+/// @return true - ok, false - bad handle or unknown policy
+LINER_API BOOL lnr_set_route_policy(lnr_hClient client, int policy);
+
+/// Replica selection for lnr_send_to on one topic
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param policy - LNR_ROUTE_*, negative clears the override (client policy applies)
+/// @return true - ok
+LINER_API BOOL lnr_set_topic_route_policy(lnr_hClient client, const char* topic, int policy);
+
+/// Failover for a multi-replica topic: when a replica's route is lost (LNR_SENDER_ROUTE_LOST),
+/// its unacked messages for `topic` (in memory and offline) are re-sent to a live replica
+/// within ~100 ms (or on the next lnr_send_to / lnr_send_all); reported as LNR_SENDER_FAILOVER
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param enabled
+/// @return true - ok
+LINER_API BOOL lnr_set_topic_failover(lnr_hClient client, const char* topic, BOOL enabled);
+
 /// Send data to other topics - broadcast
 /// @param lnr_hClient
 /// @param topic - other topic
@@ -141,12 +263,117 @@ LINER_API BOOL lnr_send_all(lnr_hClient client,
                           const char* data, size_t data_size,
                           BOOL at_least_once_delivery);
 
+/// Send data to other topics - broadcast, on a priority lane of every peer (see lnr_send_to_priority)
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @param priority - LNR_PRIORITY_*
+/// @return true - ok
+LINER_API BOOL lnr_send_all_priority(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery,
+                          int priority);
+
+/// Send data to other topics - broadcast, and keep data as the retained value of the topic:
+/// clients subscribing later receive it before live messages. Succeeds without subscribers
+/// @param lnr_hClient
+/// @param topic - other topic, not a pattern
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @return true - ok
+LINER_API BOOL lnr_send_all_retained(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery);
+
+/// Drop the retained value of the topic
+/// @param lnr_hClient
+/// @param topic
+/// @return true - ok
+LINER_API BOOL lnr_clear_retained(lnr_hClient client, const char* topic);
+
+/// Send data to other topics - broadcast to consumer groups.
+/// Each ungrouped subscriber gets a copy; each group (lnr_subscribe_group) one copy,
+/// delivered to one member chosen by the route policy
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @return true - ok
+LINER_API BOOL lnr_send_all_groups(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery);
+
+/// Same as lnr_send_all_groups, but the member of each group is chosen by `key`
+/// (consistent hash inside the group)
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param key - routing key, not empty
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @return true - ok
+LINER_API BOOL lnr_send_all_groups_keyed(lnr_hClient client,
+                          const char* topic,
+                          const char* key,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery);
+
+/// Per-peer outcome of lnr_send_all_detailed.
+enum {
+    LNR_SEND_OK = 0,
+    /** Peer's in-memory queue is at lnr_set_max_send_queue. */
+    LNR_SEND_BUSY = 1,
+    /** Route could not be prepared (store) or the message could not be queued. */
+    LNR_SEND_FAIL = 2
+};
+
+/// Callback for lnr_send_all_detailed: one call per peer; `error` is empty for LNR_SEND_OK.
+typedef void(*lnr_send_result_cb)(const char* addr, const char* unique_name, int status, const char* error, lnr_uData);
+
+/// Send data to other topics - broadcast, reporting the result per peer
+/// @param lnr_hClient
+/// @param topic - other topic
+/// @param data
+/// @param data_size
+/// @param at_least_once_delivery - same semantics as lnr_send_to
+/// @param cb - called for every peer of the topic (not called if the send fails as a whole)
+/// @param udata
+/// @return true - every peer LNR_SEND_OK
+LINER_API BOOL lnr_send_all_detailed(lnr_hClient client,
+                          const char* topic,
+                          const char* data, size_t data_size,
+                          BOOL at_least_once_delivery,
+                          lnr_send_result_cb cb, lnr_uData);
+
 /// Subscribe on topic for broadcast
 /// @param lnr_hClient
 /// @param topic
 /// @return true - ok
 LINER_API BOOL lnr_subscribe(lnr_hClient client, const char* topic);
 
+/// Subscribe on topic durably: the registration stays in the catalog after lnr_stop
+/// or a crash, so senders queue at-least-once messages offline; subscribe durably again
+/// with the same unique_name after a restart to receive them. lnr_unsubscribe ends it
+/// @param lnr_hClient
+/// @param topic
+/// @return true - ok
+LINER_API BOOL lnr_subscribe_durable(lnr_hClient client, const char* topic);
+
+/// Subscribe on topic as a member of a consumer group:
+/// lnr_send_all_groups delivers each message to one member of every group
+/// @param lnr_hClient
+/// @param topic
+/// @param group - consumer group name, not empty
+/// @return true - ok
+LINER_API BOOL lnr_subscribe_group(lnr_hClient client, const char* topic, const char* group);
+
 /// Unsubscribe on topic for broadcast
 /// @param lnr_hClient
 /// @param topic
@@ -192,7 +419,13 @@ enum {
     /** Listener: background store error (ack / lookup). */
     LNR_LISTENER_STORE_ERROR = 8,
     /** Sender: in-memory send queue full for a peer (`max_send_queue`). */
-    LNR_SENDER_BUSY = 9
+    LNR_SENDER_BUSY = 9,
+    /** Sender: unacked messages of a lost route re-routed to another replica (`lnr_set_topic_failover`). */
+    LNR_SENDER_FAILOVER = 10,
+    /** Listener: a peer's frame was rejected (oversized, truncated, malformed); `peer` = its address. */
+    LNR_LISTENER_FRAME_ERROR = 11,
+    /** Listener: a received payload could not be decompressed; `peer` = sender unique_name. */
+    LNR_LISTENER_DECODE_ERROR = 12
 };
 
 /// Asynchronous status and background errors. Pointers are valid only for the duration of the call.
@@ -205,9 +438,34 @@ typedef void(*lnr_status_cb)(int kind, const char* topic, const char* peer, cons
 LINER_API BOOL lnr_set_status_cb(lnr_hClient client, lnr_status_cb cb, lnr_uData);
 
 /// Process-global error log callback. Pass `cb == NULL` to restore stderr. Not per-client.
+/// Lines read "<Level> <file>:<line>: [<unique_name>] <message>" (no brackets outside a client).
 typedef void(*lnr_log_cb)(const char* message, lnr_uData);
 LINER_API BOOL lnr_set_log_cb(lnr_log_cb cb, lnr_uData);
 
+/// Log levels (`lnr_log_ex_cb`, `lnr_set_log_level`).
+enum {
+    LNR_LOG_ERROR = 1,
+    LNR_LOG_WARN = 2,
+    LNR_LOG_INFO = 3,
+    LNR_LOG_DEBUG = 4
+};
+
+/// Process-global log callback with the level and the `unique_name` of the client that logged
+/// ("" outside any client); `message` is "<file>:<line>: <text>". Replaces `lnr_set_log_cb` and vice versa.
+typedef void(*lnr_log_ex_cb)(int level, const char* client, const char* message, lnr_uData);
+LINER_API BOOL lnr_set_log_cb_ex(lnr_log_ex_cb cb, lnr_uData);
+
+/// Most verbose level emitted (default `LNR_LOG_WARN`). `FALSE` for an unknown level.
+LINER_API BOOL lnr_set_log_level(int level);
+LINER_API int lnr_get_log_level();
+
+/// W3C `traceparent` stamped into sends from the calling thread ("00-<trace id>-<span id>-<flags>").
+/// NULL or "" clears it. `FALSE` on a malformed value (the current one is kept).
+LINER_API BOOL lnr_set_trace_context(const char* traceparent);
+/// `traceparent` of the calling thread, or NULL. Inside a receive callback: the message's
+/// (so sends from the callback continue its trace). Valid until the next call on this thread.
+LINER_API const char* lnr_get_trace_context();
+
 /// Callback for [`lnr_list_addresses`]: one call per (addr, unique_name) row.
 typedef void(*lnr_addr_cb)(const char* addr, const char* unique_name, lnr_uData);
 
@@ -221,6 +479,145 @@ LINER_API long long lnr_pending_count(lnr_hClient client);
 typedef void(*lnr_pending_cb)(const char* addr, const char* topic, const char* unique_name, long long count, lnr_uData);
 LINER_API BOOL lnr_pending_by_peer(lnr_hClient client, lnr_pending_cb cb, lnr_uData);
 
+/// Live state of one peer link; the strings are valid during the callback only.
+typedef struct lnr_peer_info{
+    /** Outbound: the listener's address. Inbound: the sender's socket address. */
+    const char* addr;
+    const char* topic;
+    const char* unique_name;
+    /** FALSE: a listener this client sends to; TRUE: a sender connected to this client. */
+    BOOL inbound;
+    /** Outbound lane, LNR_PRIORITY_* (`LNR_PRIORITY_NORMAL` for inbound). */
+    int priority;
+    /** `-1` for an inbound link before its first message. */
+    int connection_key;
+    BOOL connected;
+    /** Last connect or message, ms since Unix epoch; 0 if never. */
+    unsigned long long last_activity_ms;
+    /** Outbound: last message written. Inbound: last message received. */
+    unsigned long long last_send_mess_number;
+    /** Acked by the listener. */
+    unsigned long long last_mess_number;
+    /** Outbound: messages waiting to be written or acked. Inbound: waiting for the receive callback. */
+    unsigned long long queue_depth;
+    unsigned long long mempool_bytes;
+}lnr_peer_info;
+
+/// One call per outbound route lane and inbound connection. Not running → success with zero callbacks.
+typedef void(*lnr_peer_cb)(const lnr_peer_info* peer, lnr_uData);
+LINER_API BOOL lnr_peers(lnr_hClient client, lnr_peer_cb cb, lnr_uData);
+
+/// Mempool of one sender slot (route lane) or listener connection slot; `addr` is valid during the callback only.
+typedef struct lnr_mempool_info{
+    /** Sender slot: the listener's address. Listener slot: the sender's socket address. */
+    const char* addr;
+    BOOL inbound;
+    /** `-1` for a listener slot before its first message. */
+    int connection_key;
+    unsigned long long slot;
+    /** Backing size: chunks × chunk size. */
+    unsigned long long capacity_bytes;
+    /** Held by queued, unacked or undelivered messages. */
+    unsigned long long used_bytes;
+    unsigned long long free_bytes;
+    /** Contiguous free regions. */
+    unsigned long long free_blocks;
+    unsigned long long largest_free_block;
+    unsigned long long chunks;
+    /** Frees since the pool last merged its free space. */
+    unsigned long long pending_frees;
+    /** 1 - largest_free_block / free_bytes (0 when free space is one block or none). */
+    double fragmentation;
+}lnr_mempool_info;
+
+/// One call per sender and listener slot. Not running → success with zero callbacks.
+typedef void(*lnr_mempool_cb)(const lnr_mempool_info* info, lnr_uData);
+LINER_API BOOL lnr_mempool_stats(lnr_hClient client, lnr_mempool_cb cb, lnr_uData);
+/// Merge free mempool space and release whole free chunks at the end of each idle pool now.
+/// Returns the bytes released, `-1` on a NULL client.
+LINER_API long long lnr_compact_memory(lnr_hClient client);
+
+/// Readiness / liveness report; every check runs at call time.
+typedef struct lnr_health_info{
+    /** `lnr_run` succeeded and `lnr_stop` was not called. */
+    BOOL running;
+    /** The store answered a ping. */
+    BOOL store_ok;
+    BOOL listener_alive;
+    BOOL sender_alive;
+    /** The catalog lists this client for its topic at `lnr_published_addr`. */
+    BOOL registered;
+    /** Listener addresses whose last connect failed or whose stream was lost (retried in the background). */
+    unsigned long long failing_routes;
+    /** running && listener_alive && sender_alive */
+    BOOL live;
+    /** live && store_ok && registered */
+    BOOL ready;
+}lnr_health_info;
+
+LINER_API BOOL lnr_health(lnr_hClient client, lnr_health_info* out);
+
+/// Record every frame the client writes or reads to `path` (created or truncated) for the
+/// `liner_capture` tool; works before and after `lnr_run`. A second call switches files.
+LINER_API BOOL lnr_start_capture(lnr_hClient client, const char* path);
+/// Stop recording and close the file. FALSE if no capture was on.
+LINER_API BOOL lnr_stop_capture(lnr_hClient client);
+
+enum {
+    /** Buckets of `lnr_latency`: <= 1, 2, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000 ms, then overflow. */
+    LNR_LATENCY_BUCKETS = 14
+};
+
+/// Latency histogram in ms (per-bucket counts, not cumulative).
+typedef struct lnr_latency{
+    unsigned long long buckets[LNR_LATENCY_BUCKETS];
+    unsigned long long count;
+    unsigned long long sum_ms;
+    unsigned long long max_ms;
+}lnr_latency;
+
+/// Counters since the client was created. `send_queue_depth` / `offline_backlog` are read at call time.
+typedef struct lnr_metrics{
+    unsigned long long messages_sent;
+    unsigned long long bytes_sent;
+    unsigned long long messages_received;
+    unsigned long long bytes_received;
+    /** Payload bytes before compression, and the same payloads as carried on the wire. */
+    unsigned long long payload_bytes;
+    unsigned long long encoded_bytes;
+    unsigned long long reconnects;
+    unsigned long long route_losses;
+    unsigned long long store_errors;
+    /** Best-effort messages discarded unwritten (route lost, stop). */
+    unsigned long long drops;
+    unsigned long long send_queue_depth;
+    unsigned long long offline_backlog;
+    lnr_latency enqueue_to_write;
+    /** From the sender's header timestamp to the receive callback (needs synchronized clocks). */
+    lnr_latency send_to_receive;
+}lnr_metrics;
+
+/// Client totals.
+LINER_API BOOL lnr_metrics_snapshot(lnr_hClient client, lnr_metrics* out);
+
+/// Counters of one peer (its `unique_name`); all zero for a peer without traffic.
+LINER_API BOOL lnr_peer_metrics(lnr_hClient client, const char* peer, lnr_metrics* out);
+
+/// Counters of one topic; all zero for a topic without traffic.
+LINER_API BOOL lnr_topic_metrics(lnr_hClient client, const char* topic, lnr_metrics* out);
+
+/// Serve the metrics in Prometheus text format on `GET http://<addr>/metrics` (e.g. "127.0.0.1:9102").
+/// Exported only when the library is built with `--features prometheus`. `FALSE` + `LNR_ERR_BIND` if `addr` can't be bound.
+LINER_API BOOL lnr_serve_metrics(lnr_hClient client, const char* addr);
+
+/// Move this sender's offline queue for listener `from_unique_name` (e.g. a decommissioned
+/// replica) to listener `to_unique_name`, renumbered for the new connection.
+/// Returns the number of messages moved, `-1` on error (see `lnr_last_error_code`).
+LINER_API long long lnr_reassign_pending(lnr_hClient client, const char* from_unique_name, const char* to_unique_name);
+
+/// Same, with the target taken from `topic`'s directory (first listener other than the source).
+LINER_API long long lnr_reassign_pending_to_topic(lnr_hClient client, const char* from_unique_name, const char* topic);
+
 /// Max framed TCP message size in bytes (default 1GiB). Prefer before `lnr_run`.
 LINER_API BOOL lnr_set_max_message_size(size_t bytes);
 LINER_API size_t lnr_get_max_message_size(void);
@@ -233,6 +630,13 @@ LINER_API size_t lnr_get_compress_threshold(void);
 LINER_API BOOL lnr_set_max_send_queue(size_t n);
 LINER_API size_t lnr_get_max_send_queue(void);
 
+/// Threads of `client` running receive callbacks of different senders in parallel (default 1).
+/// `FALSE` if `n == 0` or the client is running. Order per sender is kept; with `n > 1` the
+/// callback must be thread-safe. Call before `lnr_run`.
+LINER_API BOOL lnr_set_receive_workers(lnr_hClient client, size_t n);
+/// `0` for a bad handle.
+LINER_API size_t lnr_get_receive_workers(lnr_hClient client);
+
 /// Optional address published to the store catalog instead of the bind string.
 /// Call before `lnr_run`. `NULL` or `""` clears. Fails with `LNR_ERR_ALREADY_RUNNING` while running.
 LINER_API BOOL lnr_set_advertise_addr(lnr_hClient client, const char* addr);
@@ -14,6 +14,15 @@ SENDER_STORE_ERROR = 6
 SENDER_SEND_ERROR = 7
 LISTENER_STORE_ERROR = 8
 SENDER_BUSY = 9
+SENDER_FAILOVER = 10
+LISTENER_FRAME_ERROR = 11
+LISTENER_DECODE_ERROR = 12
+
+# Log levels (match include/liner.h LNR_LOG_*)
+LOG_ERROR = 1
+LOG_WARN = 2
+LOG_INFO = 3
+LOG_DEBUG = 4
 
 # Sync last-error codes (match include/liner.h)
 OK = 0
@@ -29,6 +38,95 @@ ERR_CLEAR_WHILE_RUNNING = 9
 ERR_STARTUP = 10
 ERR_BUSY = 11
 
+# send_to replica selection (match include/liner.h LNR_ROUTE_*)
+ROUTE_ROUND_ROBIN = 0
+ROUTE_LEAST_LOADED = 1
+
+# send_to_priority / send_all_priority lanes (match include/liner.h LNR_PRIORITY_*)
+PRIORITY_NORMAL = 0
+PRIORITY_HIGH = 1
+PRIORITY_URGENT = 2
+
+# send_all_detailed per-peer status (match include/liner.h LNR_SEND_*)
+SEND_OK = 0
+SEND_BUSY = 1
+SEND_FAIL = 2
+
+# Latency histogram upper bounds in ms (match include/liner.h LNR_LATENCY_BUCKETS; last bucket is overflow)
+LATENCY_BUCKETS_MS = (1, 2, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000)
+
+
+class LnrLatency(ctypes.Structure):
+    _fields_ = [
+        ("buckets", ctypes.c_ulonglong * (len(LATENCY_BUCKETS_MS) + 1)),
+        ("count", ctypes.c_ulonglong),
+        ("sum_ms", ctypes.c_ulonglong),
+        ("max_ms", ctypes.c_ulonglong),
+    ]
+
+
+class LnrMetrics(ctypes.Structure):
+    _fields_ = [
+        ("messages_sent", ctypes.c_ulonglong),
+        ("bytes_sent", ctypes.c_ulonglong),
+        ("messages_received", ctypes.c_ulonglong),
+        ("bytes_received", ctypes.c_ulonglong),
+        ("payload_bytes", ctypes.c_ulonglong),
+        ("encoded_bytes", ctypes.c_ulonglong),
+        ("reconnects", ctypes.c_ulonglong),
+        ("route_losses", ctypes.c_ulonglong),
+        ("store_errors", ctypes.c_ulonglong),
+        ("drops", ctypes.c_ulonglong),
+        ("send_queue_depth", ctypes.c_ulonglong),
+        ("offline_backlog", ctypes.c_ulonglong),
+        ("enqueue_to_write", LnrLatency),
+        ("send_to_receive", LnrLatency),
+    ]
+
+class LnrPeerInfo(ctypes.Structure):
+    _fields_ = [
+        ("addr", ctypes.c_char_p),
+        ("topic", ctypes.c_char_p),
+        ("unique_name", ctypes.c_char_p),
+        ("inbound", ctypes.c_int),
+        ("priority", ctypes.c_int),
+        ("connection_key", ctypes.c_int),
+        ("connected", ctypes.c_int),
+        ("last_activity_ms", ctypes.c_ulonglong),
+        ("last_send_mess_number", ctypes.c_ulonglong),
+        ("last_mess_number", ctypes.c_ulonglong),
+        ("queue_depth", ctypes.c_ulonglong),
+        ("mempool_bytes", ctypes.c_ulonglong),
+    ]
+
+class LnrMempoolInfo(ctypes.Structure):
+    _fields_ = [
+        ("addr", ctypes.c_char_p),
+        ("inbound", ctypes.c_int),
+        ("connection_key", ctypes.c_int),
+        ("slot", ctypes.c_ulonglong),
+        ("capacity_bytes", ctypes.c_ulonglong),
+        ("used_bytes", ctypes.c_ulonglong),
+        ("free_bytes", ctypes.c_ulonglong),
+        ("free_blocks", ctypes.c_ulonglong),
+        ("largest_free_block", ctypes.c_ulonglong),
+        ("chunks", ctypes.c_ulonglong),
+        ("pending_frees", ctypes.c_ulonglong),
+        ("fragmentation", ctypes.c_double),
+    ]
+
+class LnrHealth(ctypes.Structure):
+    _fields_ = [
+        ("running", ctypes.c_int),
+        ("store_ok", ctypes.c_int),
+        ("listener_alive", ctypes.c_int),
+        ("sender_alive", ctypes.c_int),
+        ("registered", ctypes.c_int),
+        ("failing_routes", ctypes.c_ulonglong),
+        ("live", ctypes.c_int),
+        ("ready", ctypes.c_int),
+    ]
+
 def version() -> str:
     if not lib_:
         raise Exception('lib not load')
@@ -59,6 +157,70 @@ def set_log_callback(log_cback):
     _logCBack = LogCb(c_cb)
     return pfun(_logCBack, None)
 
+def set_log_callback_ex(log_cback):
+    """Process-global log callback: ``fn(level: int, client: str, message: str)``.
+
+    ``client`` is the ``unique_name`` of the client that logged, ``""`` outside any client.
+    Pass ``None`` to restore stderr.
+    """
+    global lib_, _logCBack
+    if not lib_:
+        raise Exception('lib not load')
+    LogExCb = ctypes.CFUNCTYPE(None, ctypes.c_int, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_void_p)
+    pfun = lib_.lnr_set_log_cb_ex
+    pfun.restype = ctypes.c_bool
+    pfun.argtypes = (LogExCb, ctypes.c_void_p)
+    if log_cback is None:
+        _logCBack = None
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_void_p)
+        return pfun(None, None)
+
+    def c_cb(level, client, msg, _udata):
+        log_cback(
+            int(level),
+            client.decode("utf-8") if client else "",
+            msg.decode("utf-8") if msg else "",
+        )
+
+    _logCBack = LogExCb(c_cb)
+    return pfun(_logCBack, None)
+
+def set_log_level(level: int) -> bool:
+    """Most verbose level emitted (``LOG_*``, default ``LOG_WARN``)."""
+    if not lib_:
+        raise Exception('lib not load')
+    pfun = lib_.lnr_set_log_level
+    pfun.restype = ctypes.c_bool
+    pfun.argtypes = (ctypes.c_int,)
+    return pfun(level)
+
+def log_level() -> int:
+    if not lib_:
+        raise Exception('lib not load')
+    pfun = lib_.lnr_get_log_level
+    pfun.restype = ctypes.c_int
+    pfun.argtypes = ()
+    return int(pfun())
+
+def set_trace_context(traceparent) -> bool:
+    """W3C ``traceparent`` stamped into sends from this thread; ``None`` or ``""`` clears it."""
+    if not lib_:
+        raise Exception('lib not load')
+    pfun = lib_.lnr_set_trace_context
+    pfun.restype = ctypes.c_bool
+    pfun.argtypes = (ctypes.c_char_p,)
+    return pfun(traceparent.encode("utf-8") if traceparent else None)
+
+def trace_context():
+    """``traceparent`` of this thread; inside a receive callback, the message's."""
+    if not lib_:
+        raise Exception('lib not load')
+    pfun = lib_.lnr_get_trace_context
+    pfun.restype = ctypes.c_char_p
+    pfun.argtypes = ()
+    value = pfun()
+    return value.decode("utf-8") if value else None
+
 
 def set_max_message_size(bytes_: int) -> bool:
     if not lib_:
@@ -233,6 +395,26 @@ class Client:
         pfun.argtypes = (ctypes.c_void_p, recvCBackType, ctypes.c_void_p)
         return pfun(self.hClient_, self.recvCBack_, ctypes.c_void_p())
 
+    def run_ex(self, receive_cback)->bool:
+        """
+        :param ucb: def func(to: str, from: str, data: bytes, sender_name: str, number: int, sent_at_ms: int, replayed: bool)
+        """
+
+        def c_rcb(to, from_, sender_name, number, sent_at_ms, replayed, data, dlen, udata):
+            data = ctypes.string_at(data, dlen)
+            receive_cback(to.decode("utf-8"), from_.decode("utf-8"), data,
+                          sender_name.decode("utf-8"), number, sent_at_ms, bool(replayed))
+
+        recvCBackType = ctypes.CFUNCTYPE(None, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_char_p,
+                                         ctypes.c_ulonglong, ctypes.c_ulonglong, ctypes.c_int,
+                                         ctypes.c_void_p, ctypes.c_size_t, ctypes.c_void_p)
+        self.recvCBack_ = recvCBackType(c_rcb)
+
+        pfun = lib_.lnr_run_ex
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, recvCBackType, ctypes.c_void_p)
+        return pfun(self.hClient_, self.recvCBack_, ctypes.c_void_p())
+
     def set_status_callback(self, status_cback)->bool:
         """Register status/background-error callback: ``fn(kind: int, topic: str, peer: str, message: str)``.
 
@@ -295,6 +477,40 @@ class Client:
             return None
         return out
 
+    def set_receive_workers(self, n: int) -> bool:
+        """Threads running receive callbacks of different senders in parallel; call before ``run``."""
+        pfun = lib_.lnr_set_receive_workers
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_size_t)
+        return pfun(self.hClient_, n)
+
+    def get_receive_workers(self) -> int:
+        pfun = lib_.lnr_get_receive_workers
+        pfun.restype = ctypes.c_size_t
+        pfun.argtypes = (ctypes.c_void_p,)
+        return int(pfun(self.hClient_))
+
+    def set_route_policy(self, policy: int) -> bool:
+        """Default ``send_to`` replica selection (``ROUTE_ROUND_ROBIN`` / ``ROUTE_LEAST_LOADED``)."""
+        pfun = lib_.lnr_set_route_policy
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_int)
+        return pfun(self.hClient_, policy)
+
+    def set_topic_route_policy(self, topic: str, policy) -> bool:
+        """Per-topic override; ``None`` clears it (client policy applies)."""
+        pfun = lib_.lnr_set_topic_route_policy
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_int)
+        return pfun(self.hClient_, topic.encode("utf-8"), -1 if policy is None else policy)
+
+    def set_topic_failover(self, topic: str, enabled: bool) -> bool:
+        """Re-route unacked messages of a lost replica of ``topic`` to a live one."""
+        pfun = lib_.lnr_set_topic_failover
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_bool)
+        return pfun(self.hClient_, topic.encode("utf-8"), enabled)
+
     def pending_count(self) -> int:
         """Offline queue depth for this sender; ``-1`` on error."""
         pfun = lib_.lnr_pending_count
@@ -325,6 +541,148 @@ class Client:
             return None
         return out
 
+    def peers(self):
+        """Return one ``dict`` per outbound route lane and inbound connection, or ``None`` on error."""
+        out = []
+        PeerCb = ctypes.CFUNCTYPE(None, ctypes.POINTER(LnrPeerInfo), ctypes.c_void_p)
+
+        def c_cb(peer, _udata):
+            p = peer.contents
+            out.append({
+                "addr": p.addr.decode("utf-8") if p.addr else "",
+                "topic": p.topic.decode("utf-8") if p.topic else "",
+                "unique_name": p.unique_name.decode("utf-8") if p.unique_name else "",
+                "inbound": bool(p.inbound),
+                "priority": int(p.priority),
+                "connection_key": int(p.connection_key),
+                "connected": bool(p.connected),
+                "last_activity_ms": int(p.last_activity_ms),
+                "last_send_mess_number": int(p.last_send_mess_number),
+                "last_mess_number": int(p.last_mess_number),
+                "queue_depth": int(p.queue_depth),
+                "mempool_bytes": int(p.mempool_bytes),
+            })
+
+        cb = PeerCb(c_cb)
+        pfun = lib_.lnr_peers
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, PeerCb, ctypes.c_void_p)
+        if not pfun(self.hClient_, cb, None):
+            return None
+        return out
+
+    def mempool_stats(self):
+        """Return one ``dict`` per sender and listener slot mempool, or ``None`` on error."""
+        out = []
+        MempoolCb = ctypes.CFUNCTYPE(None, ctypes.POINTER(LnrMempoolInfo), ctypes.c_void_p)
+
+        def c_cb(info, _udata):
+            m = info.contents
+            out.append({
+                "addr": m.addr.decode("utf-8") if m.addr else "",
+                "inbound": bool(m.inbound),
+                "connection_key": int(m.connection_key),
+                "slot": int(m.slot),
+                "capacity_bytes": int(m.capacity_bytes),
+                "used_bytes": int(m.used_bytes),
+                "free_bytes": int(m.free_bytes),
+                "free_blocks": int(m.free_blocks),
+                "largest_free_block": int(m.largest_free_block),
+                "chunks": int(m.chunks),
+                "pending_frees": int(m.pending_frees),
+                "fragmentation": float(m.fragmentation),
+            })
+
+        cb = MempoolCb(c_cb)
+        pfun = lib_.lnr_mempool_stats
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, MempoolCb, ctypes.c_void_p)
+        if not pfun(self.hClient_, cb, None):
+            return None
+        return out
+
+    def compact_memory(self) -> int:
+        """Release free mempool memory now; bytes released, ``-1`` on error."""
+        pfun = lib_.lnr_compact_memory
+        pfun.restype = ctypes.c_longlong
+        pfun.argtypes = (ctypes.c_void_p,)
+        return int(pfun(self.hClient_))
+
+    def health(self):
+        """Readiness / liveness report as ``LnrHealth`` or ``None`` on error."""
+        out = LnrHealth()
+        pfun = lib_.lnr_health
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.POINTER(LnrHealth))
+        if not pfun(self.hClient_, ctypes.byref(out)):
+            return None
+        return out
+
+    def start_capture(self, path: str) -> bool:
+        """Record every frame written or read to ``path`` for the ``liner_capture`` tool."""
+        pfun = lib_.lnr_start_capture
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p)
+        return pfun(self.hClient_, path.encode("utf-8"))
+
+    def stop_capture(self) -> bool:
+        """Stop recording; ``False`` if no capture was on."""
+        pfun = lib_.lnr_stop_capture
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p,)
+        return pfun(self.hClient_)
+
+    def metrics_snapshot(self):
+        """Client totals as ``LnrMetrics`` or ``None`` on error."""
+        out = LnrMetrics()
+        pfun = lib_.lnr_metrics_snapshot
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.POINTER(LnrMetrics))
+        if not pfun(self.hClient_, ctypes.byref(out)):
+            return None
+        return out
+
+    def peer_metrics(self, peer: str):
+        """Counters of one peer ``unique_name`` as ``LnrMetrics`` or ``None`` on error."""
+        out = LnrMetrics()
+        pfun = lib_.lnr_peer_metrics
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.POINTER(LnrMetrics))
+        if not pfun(self.hClient_, peer.encode("utf-8"), ctypes.byref(out)):
+            return None
+        return out
+
+    def topic_metrics(self, topic: str):
+        """Counters of one topic as ``LnrMetrics`` or ``None`` on error."""
+        out = LnrMetrics()
+        pfun = lib_.lnr_topic_metrics
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.POINTER(LnrMetrics))
+        if not pfun(self.hClient_, topic.encode("utf-8"), ctypes.byref(out)):
+            return None
+        return out
+
+    def serve_metrics(self, addr: str) -> bool:
+        """Prometheus endpoint ``GET http://<addr>/metrics``; needs the library built with ``--features prometheus``."""
+        pfun = lib_.lnr_serve_metrics
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p)
+        return pfun(self.hClient_, addr.encode("utf-8"))
+
+    def reassign_pending(self, from_unique_name: str, to_unique_name: str) -> int:
+        """Move the offline queue for ``from_unique_name`` to ``to_unique_name``; ``-1`` on error."""
+        pfun = lib_.lnr_reassign_pending
+        pfun.restype = ctypes.c_longlong
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p)
+        return int(pfun(self.hClient_, from_unique_name.encode("utf-8"), to_unique_name.encode("utf-8")))
+
+    def reassign_pending_to_topic(self, from_unique_name: str, topic: str) -> int:
+        """Like ``reassign_pending`` with the target picked from ``topic``'s directory."""
+        pfun = lib_.lnr_reassign_pending_to_topic
+        pfun.restype = ctypes.c_longlong
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p)
+        return int(pfun(self.hClient_, from_unique_name.encode("utf-8"), topic.encode("utf-8")))
+
     def set_advertise_addr(self, addr)->bool:
         """Publish ``addr`` to the store catalog instead of the bind string. Call before ``run``.
 
@@ -386,6 +744,68 @@ class Client:
         pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
         return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
     
+    def send_to_keyed(self, to_topic: str, key: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_to`; the replica is chosen by ``key`` (consistent hash, per-key affinity)."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_key = key.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_to_keyed
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_key, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
+    
+    def send_to_priority(self, to_topic: str, data: bytearray, priority: int, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_to`, on the ``PRIORITY_*`` lane of the peer."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_to_priority
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool, ctypes.c_int)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery, priority)
+    
+    def send_to_peer(self, to_topic: str, unique_name: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_to`, but only to the replica registered as ``unique_name``."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_name = unique_name.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_to_peer
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_name, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
+    
+    def send_to_at(self, to_topic: str, data: bytearray, deliver_at_ms: int, at_least_once_delivery: bool = True) -> bool:
+        """:meth:`send_to` at ``deliver_at_ms`` (ms since the Unix epoch); kept in the store until then."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_to_at
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_ulonglong, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, deliver_at_ms, c_at_least_once_delivery)
+    
+    def send_to_after(self, to_topic: str, data: bytearray, delay_ms: int, at_least_once_delivery: bool = True) -> bool:
+        """:meth:`send_to_at` ``delay_ms`` from now."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_to_after
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_ulonglong, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, delay_ms, c_at_least_once_delivery)
+    
     def send_all(self, to_topic: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
         """Same third-argument semantics as :meth:`send_to`."""
         c_to_topic = to_topic.encode("utf-8")
@@ -398,6 +818,89 @@ class Client:
         pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
         return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
     
+    def send_all_priority(self, to_topic: str, data: bytearray, priority: int, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_all`, on the ``PRIORITY_*`` lane of every peer."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_all_priority
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool, ctypes.c_int)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery, priority)
+    
+    def send_all_retained(self, to_topic: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_all`; ``data`` also becomes the retained value of ``to_topic``."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_all_retained
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
+    
+    def clear_retained(self, to_topic: str) -> bool:
+        pfun = lib_.lnr_clear_retained
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p)
+        return pfun(self.hClient_, to_topic.encode("utf-8"))
+    
+    def send_all_groups(self, to_topic: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
+        """One copy per consumer group (to one member) plus every ungrouped subscriber."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_all_groups
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
+    
+    def send_all_groups_keyed(self, to_topic: str, key: str, data: bytearray, at_least_once_delivery: bool = True) -> bool:
+        """Like :meth:`send_all_groups`; the member of each group is chosen by ``key``."""
+        c_to_topic = to_topic.encode("utf-8")
+        c_key = key.encode("utf-8")
+        c_at_least_once_delivery = ctypes.c_bool(at_least_once_delivery)
+        c_dlen = ctypes.c_size_t(len(data))
+        c_data = ctypes.c_char * len(data)
+   
+        pfun = lib_.lnr_send_all_groups_keyed
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool)
+        return pfun(self.hClient_, c_to_topic, c_key, c_data.from_buffer_copy(data), c_dlen, c_at_least_once_delivery)
+    
+    def send_all_detailed(self, to_topic: str, data: bytearray, at_least_once_delivery: bool = True):
+        """Like :meth:`send_all`; returns ``[(addr, unique_name, status, error), ...]`` with
+        ``SEND_*`` statuses, or ``None`` if the send failed as a whole."""
+        out = []
+        ResultCb = ctypes.CFUNCTYPE(
+            None, ctypes.c_char_p, ctypes.c_char_p, ctypes.c_int, ctypes.c_char_p, ctypes.c_void_p
+        )
+
+        def c_cb(addr, name, status, error, _udata):
+            out.append((
+                addr.decode("utf-8") if addr else "",
+                name.decode("utf-8") if name else "",
+                int(status),
+                error.decode("utf-8") if error else "",
+            ))
+
+        cb = ResultCb(c_cb)
+        c_data = ctypes.c_char * len(data)
+        pfun = lib_.lnr_send_all_detailed
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_void_p, ctypes.c_size_t, ctypes.c_bool,
+                         ResultCb, ctypes.c_void_p)
+        pfun(self.hClient_, to_topic.encode("utf-8"), c_data.from_buffer_copy(data), ctypes.c_size_t(len(data)),
+             ctypes.c_bool(at_least_once_delivery), cb, None)
+        if not out:
+            return None
+        return out
+
     def subscribe(self, to_topic: str)->bool:
         c_to_topic = to_topic.encode("utf-8")
         
@@ -406,6 +909,20 @@ class Client:
         pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p)
         return pfun(self.hClient_, c_to_topic)
     
+    def subscribe_durable(self, to_topic: str)->bool:
+        """Subscribe so the registration outlives :meth:`stop`; senders queue offline meanwhile."""
+        pfun = lib_.lnr_subscribe_durable
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p)
+        return pfun(self.hClient_, to_topic.encode("utf-8"))
+    
+    def subscribe_group(self, to_topic: str, group: str)->bool:
+        """Subscribe as a member of consumer group ``group`` (see :meth:`send_all_groups`)."""
+        pfun = lib_.lnr_subscribe_group
+        pfun.restype = ctypes.c_bool
+        pfun.argtypes = (ctypes.c_void_p, ctypes.c_char_p, ctypes.c_char_p)
+        return pfun(self.hClient_, to_topic.encode("utf-8"), group.encode("utf-8"))
+    
     def unsubscribe(self, to_topic: str)->bool:
         c_to_topic = to_topic.encode("utf-8")
         
@@ -0,0 +1,566 @@
+//! Async facade over [`Client`] for tokio services (feature **`tokio`**).
+//!
+//! Client calls run on tokio's blocking pool, so the executor never waits on the store or
+//! the client mutex. The listener and status threads push received messages and status
+//! events into bounded channels (`ASYNC_STREAM_CAPACITY` items) that are read as
+//! [`Stream`]s. A full [`Subscription`] blocks the receive thread until it is read (or the
+//! client stops), so the listener stops reading peer sockets and senders keep the rest
+//! queued, as with `Liner::run_with_channel`. A full [`StatusStream`] drops and counts events
+//! instead: they are emitted from client calls that the reader itself may be awaiting.
+
+use crate::client::{Client, Delivery, DeliveryTicket};
+use crate::error::ErrorCode;
+use crate::settings;
+use crate::topic_pattern;
+use crate::UData;
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+use tokio::sync::watch;
+
+use std::ffi::CStr;
+use std::fmt;
+use std::future::{self, Future};
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, Wake, Waker};
+use std::time::Duration;
+
+/// Failed call: the client's last error code and message at the time of the failure.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsyncError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl fmt::Display for AsyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for AsyncError {}
+
+/// One status callback invocation (`LNR_*` kind, topic, peer, rendered message).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StatusUpdate {
+    pub kind: i32,
+    pub topic: String,
+    pub peer: String,
+    pub message: String,
+}
+
+/// Received messages whose topic matches a filter (exact topic or `*` / `#` pattern).
+/// Ends when the [`AsyncLiner`] and all its clones are dropped.
+pub struct Subscription {
+    rx: Receiver<Delivery>,
+}
+
+impl Subscription {
+    pub async fn recv(&mut self) -> Option<Delivery> {
+        self.rx.recv().await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Delivery;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Delivery>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Status events of the client (see [`AsyncLiner::status`]).
+pub struct StatusStream {
+    rx: Receiver<StatusUpdate>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl StatusStream {
+    pub async fn recv(&mut self) -> Option<StatusUpdate> {
+        self.rx.recv().await
+    }
+
+    /// Events dropped because this stream was full (not read fast enough).
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for StatusStream {
+    type Item = StatusUpdate;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<StatusUpdate>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+/// Sending half of a [`StatusStream`] and its drop counter.
+struct StatusFeed {
+    tx: Sender<StatusUpdate>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl StatusFeed {
+    /// Queue `update` without waiting; false once the stream is gone.
+    fn push(&self, update: StatusUpdate) -> bool {
+        match self.tx.try_send(update) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// Fan-out target of the receive and status callbacks (their `udata`).
+struct Router {
+    subs: Mutex<Vec<(String, Sender<Delivery>)>>,
+    status: Mutex<Vec<StatusFeed>>,
+    unmatched: AtomicU64,
+    /// `true` while the client stops: deliveries waiting for room give up.
+    closed: watch::Sender<bool>,
+}
+
+impl Router {
+    fn new() -> Router {
+        Router {
+            subs: Mutex::new(Vec::new()),
+            status: Mutex::new(Vec::new()),
+            unmatched: AtomicU64::new(0),
+            closed: watch::Sender::new(false),
+        }
+    }
+
+    fn subscription(&self, filter: &str) -> Subscription {
+        let (tx, rx) = mpsc::channel(settings::ASYNC_STREAM_CAPACITY);
+        self.subs.lock().unwrap().push((filter.to_owned(), tx));
+        Subscription { rx }
+    }
+
+    fn deliver(&self, delivery: Delivery) {
+        // Not held while waiting: `subscribe` / `incoming` from the reader must not block on it.
+        let targets: Vec<Sender<Delivery>> = {
+            let mut subs = self.subs.lock().unwrap();
+            // Dropped streams are pruned here; a send to them fails anyway.
+            subs.retain(|(_, tx)| !tx.is_closed());
+            subs.iter()
+                .filter(|(filter, _)| topic_pattern::matches(filter, &delivery.topic))
+                .map(|(_, tx)| tx.clone())
+                .collect()
+        };
+        if targets.is_empty() {
+            self.unmatched.fetch_add(1, Ordering::Relaxed);
+        }
+        for tx in targets {
+            self.send_waiting(&tx, delivery.clone());
+        }
+    }
+
+    /// Queue `delivery`, waiting on the calling (listener) thread while the stream is full.
+    /// Gives up, dropping it, once the client stops.
+    fn send_waiting(&self, tx: &Sender<Delivery>, delivery: Delivery) {
+        let delivery = match tx.try_send(delivery) {
+            Ok(()) | Err(TrySendError::Closed(_)) => return,
+            Err(TrySendError::Full(delivery)) => delivery,
+        };
+        let mut closed = self.closed.subscribe();
+        let mut delivery = Some(delivery);
+        let mut reserve = pin!(tx.reserve());
+        let mut stopped = pin!(closed.wait_for(|closed| *closed));
+        block_on(future::poll_fn(|cx| {
+            if let Poll::Ready(permit) = reserve.as_mut().poll(cx) {
+                if let (Ok(permit), Some(delivery)) = (permit, delivery.take()) {
+                    permit.send(delivery);
+                }
+                return Poll::Ready(());
+            }
+            stopped.as_mut().poll(cx).map(|_| ())
+        }));
+    }
+
+    /// Wake deliveries waiting for room (before the client's threads are joined).
+    fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    fn udata(&self) -> UData {
+        UData(self as *const Router as *mut libc::c_void)
+    }
+}
+
+/// Field order matters: the client (and its threads calling into `router`) drops first.
+struct Inner {
+    client: Mutex<Client>,
+    router: Router,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        // Dropping a running client joins its receive thread, which may wait on a full stream.
+        self.router.close();
+    }
+}
+
+/// Drive `fut` to completion on the calling std thread; tokio's sync primitives need no runtime.
+fn block_on<F: Future>(fut: F) -> F::Output {
+    struct Unpark(std::thread::Thread);
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+    let waker = Waker::from(Arc::new(Unpark(std::thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+    loop {
+        if let Poll::Ready(out) = fut.as_mut().poll(&mut cx) {
+            return out;
+        }
+        std::thread::park();
+    }
+}
+
+/// Tokio wrapper of a [`Client`]. Cheap to clone; clones share the client.
+///
+/// Call [`stop`](Self::stop) before dropping the last clone: dropping a running client
+/// joins its threads on the current thread.
+#[derive(Clone)]
+pub struct AsyncLiner {
+    inner: Arc<Inner>,
+}
+
+impl AsyncLiner {
+    /// Take over `client` (not yet running). Its status callback is replaced by [`status`](Self::status).
+    pub fn new(client: Client) -> AsyncLiner {
+        let inner = Arc::new(Inner {
+            client: Mutex::new(client),
+            router: Router::new(),
+        });
+        inner
+            .client
+            .lock()
+            .unwrap()
+            .set_status_cb(Some(status_cb), inner.router.udata());
+        AsyncLiner { inner }
+    }
+
+    /// Run `f` with the client on the blocking pool; the escape hatch for calls without an
+    /// async wrapper (`list_addresses`, `pending_count`, …).
+    pub async fn with_client<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Client) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let inner = self.inner.clone();
+        match tokio::task::spawn_blocking(move || f(&mut inner.client.lock().unwrap())).await {
+            Ok(r) => r,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    async fn call<F>(&self, f: F) -> Result<(), AsyncError>
+    where
+        F: FnOnce(&mut Client) -> bool + Send + 'static,
+    {
+        self.with_client(move |c| if f(c) { Ok(()) } else { Err(last_error(c)) })
+            .await
+    }
+
+    /// Start the client; received messages go to the matching [`Subscription`]s.
+    pub async fn run(&self) -> Result<(), AsyncError> {
+        let inner = self.inner.clone();
+        let res = tokio::task::spawn_blocking(move || {
+            let mut c = inner.client.lock().unwrap();
+            inner.router.closed.send_replace(false);
+            if c.run(receive_cb, inner.router.udata()) {
+                Ok(())
+            } else {
+                Err(last_error(&c))
+            }
+        })
+        .await;
+        match res {
+            Ok(r) => r,
+            Err(err) => std::panic::resume_unwind(err.into_panic()),
+        }
+    }
+
+    /// Stop the client. Received messages still waiting for room in a full [`Subscription`]
+    /// are dropped, so read the streams first. Use this rather than `Client::stop` through
+    /// [`with_client`](Self::with_client), which would wait for that room.
+    pub async fn stop(&self) -> Result<(), AsyncError> {
+        self.inner.router.close();
+        self.call(|c| c.stop()).await
+    }
+
+    /// [`Client::send_to`]; resolves once the message is queued.
+    pub async fn send_to(&self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> Result<(), AsyncError> {
+        let topic = topic.to_owned();
+        let data = data.to_vec();
+        self.call(move |c| c.send_to(&topic, &data, at_least_once_delivery)).await
+    }
+
+    /// [`Client::send_all`]; resolves once the message is queued for every peer.
+    pub async fn send_all(&self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> Result<(), AsyncError> {
+        let topic = topic.to_owned();
+        let data = data.to_vec();
+        self.call(move |c| c.send_all(&topic, &data, at_least_once_delivery)).await
+    }
+
+    /// At-least-once send whose ack can be awaited with [`confirmed`](Self::confirmed).
+    pub async fn send_to_tracked(&self, topic: &str, data: &[u8]) -> Result<DeliveryTicket, AsyncError> {
+        let topic = topic.to_owned();
+        let data = data.to_vec();
+        self.with_client(move |c| c.send_to_tracked(&topic, &data).ok_or_else(|| last_error(c)))
+            .await
+    }
+
+    /// Resolves when the listener has acked `ticket` ([`Client::is_confirmed`], re-checked
+    /// every `ASYNC_CONFIRM_POLL_MS`). Wrap in `tokio::time::timeout` to bound the wait.
+    pub async fn confirmed(&self, ticket: DeliveryTicket) -> Result<(), AsyncError> {
+        loop {
+            match self.with_client(move |c| c.is_confirmed(&ticket).ok_or_else(|| last_error(c))).await? {
+                true => return Ok(()),
+                false => tokio::time::sleep(Duration::from_millis(settings::ASYNC_CONFIRM_POLL_MS)).await,
+            }
+        }
+    }
+
+    /// [`Client::subscribe`] plus a stream of the messages received on `topic` (a pattern
+    /// yields the concrete topics it matches). Dropping the stream keeps the subscription,
+    /// but messages on `topic` are then acked and lost (see [`unmatched`](Self::unmatched)):
+    /// end it with [`unsubscribe`](Self::unsubscribe) instead.
+    pub async fn subscribe(&self, topic: &str) -> Result<Subscription, AsyncError> {
+        // Register the stream first so nothing delivered right after subscribe is missed.
+        let sub = self.inner.router.subscription(topic);
+        let topic = topic.to_owned();
+        self.call(move |c| c.subscribe(&topic)).await?;
+        Ok(sub)
+    }
+
+    /// [`Client::unsubscribe`]. Streams of `topic` stay open; messages already received
+    /// and not yet matched to a stream are lost.
+    pub async fn unsubscribe(&self, topic: &str) -> Result<(), AsyncError> {
+        let topic = topic.to_owned();
+        self.call(move |c| c.unsubscribe(&topic)).await
+    }
+
+    /// Stream of received messages matching `filter` without subscribing, e.g. the client's
+    /// own `source_topic` or `"#"` for everything.
+    pub fn incoming(&self, filter: &str) -> Subscription {
+        self.inner.router.subscription(filter)
+    }
+
+    /// Stream of status events from now on; every stream gets every event.
+    pub fn status(&self) -> StatusStream {
+        let (tx, rx) = mpsc::channel(settings::ASYNC_STREAM_CAPACITY);
+        let dropped = Arc::new(AtomicU64::new(0));
+        self.inner.router.status.lock().unwrap().push(StatusFeed { tx, dropped: dropped.clone() });
+        StatusStream { rx, dropped }
+    }
+
+    /// Received messages that matched no live [`Subscription`] and were lost: the listener
+    /// acks a message before it is routed to a stream. Keep an [`incoming`](Self::incoming)
+    /// stream on `"#"` to see every message.
+    pub fn unmatched(&self) -> u64 {
+        self.inner.router.unmatched.load(Ordering::Relaxed)
+    }
+}
+
+fn last_error(c: &Client) -> AsyncError {
+    AsyncError {
+        code: c.last_error(),
+        message: c.last_error_message().to_owned(),
+    }
+}
+
+fn c_str_or_empty(p: *const i8) -> String {
+    if p.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(p) }.to_string_lossy().into_owned()
+}
+
+extern "C" fn receive_cb(to: *const i8, from: *const i8, data: *const u8, dsize: usize, udata: *mut libc::c_void) {
+    let Some(router) = (unsafe { udata.cast::<Router>().as_ref() }) else {
+        return;
+    };
+    let data = if dsize == 0 || data.is_null() {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(data, dsize) }.to_vec()
+    };
+    router.deliver(Delivery {
+        topic: c_str_or_empty(to),
+        from: c_str_or_empty(from),
+        data,
+    });
+}
+
+extern "C" fn status_cb(kind: i32, topic: *const i8, peer: *const i8, message: *const i8, udata: *mut libc::c_void) {
+    let Some(router) = (unsafe { udata.cast::<Router>().as_ref() }) else {
+        return;
+    };
+    let update = StatusUpdate {
+        kind,
+        topic: c_str_or_empty(topic),
+        peer: c_str_or_empty(peer),
+        message: c_str_or_empty(message),
+    };
+    let mut status = router.status.lock().unwrap();
+    status.retain(|feed| feed.push(update.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+    }
+
+    fn delivery(topic: &str) -> Delivery {
+        Delivery {
+            topic: topic.to_owned(),
+            from: "peer".to_owned(),
+            data: b"x".to_vec(),
+        }
+    }
+
+    #[test]
+    fn router_waits_for_room_in_a_full_stream_until_close() {
+        let router = Router::new();
+        let mut sub = router.subscription("jobs.*");
+        let total = settings::ASYNC_STREAM_CAPACITY + 3;
+        std::thread::scope(|s| {
+            let listener = s.spawn(|| {
+                for _ in 0..total {
+                    router.deliver(delivery("jobs.a"));
+                }
+                router.deliver(delivery("other"));
+            });
+            std::thread::sleep(Duration::from_millis(100));
+            assert!(!listener.is_finished(), "a full stream holds the listener back");
+            let mut got = 0;
+            while got < total {
+                match sub.rx.try_recv() {
+                    Ok(_) => got += 1,
+                    Err(_) => std::thread::sleep(Duration::from_millis(1)),
+                }
+            }
+            listener.join().unwrap();
+        });
+        assert!(sub.rx.try_recv().is_err(), "nothing beyond the matching messages");
+        assert_eq!(router.unmatched.load(Ordering::Relaxed), 1);
+
+        // Stopping wakes a delivery that still waits for room; it is dropped.
+        for _ in 0..settings::ASYNC_STREAM_CAPACITY {
+            router.deliver(delivery("jobs.b"));
+        }
+        std::thread::scope(|s| {
+            let listener = s.spawn(|| router.deliver(delivery("jobs.c")));
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!listener.is_finished());
+            router.close();
+            listener.join().unwrap();
+        });
+
+        // A dropped stream is pruned; its topic counts as unmatched from then on.
+        drop(sub);
+        router.deliver(delivery("jobs.d"));
+        assert_eq!(router.unmatched.load(Ordering::Relaxed), 2);
+        assert!(router.subs.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn async_send_subscribe_and_confirm() {
+        let _run_lock = crate::client::tests::client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_async_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("async_t_{pid}");
+
+        runtime().block_on(async {
+            let r = AsyncLiner::new(Client::new_sqlite(&format!("async_r_{pid}"), &format!("async_r_{pid}"), "127.0.0.1:0", db, "").unwrap());
+            let s = AsyncLiner::new(Client::new_sqlite(&format!("async_s_{pid}"), &format!("async_s_{pid}"), "127.0.0.1:0", db, "").unwrap());
+            let err = s.send_to(&topic, b"x", true).await.unwrap_err();
+            assert_eq!(err.code, ErrorCode::NotRunning);
+
+            r.run().await.unwrap();
+            let mut sub = r.subscribe(&topic).await.unwrap();
+            s.run().await.unwrap();
+
+            let ticket = s.send_to_tracked(&topic, b"one").await.unwrap();
+            let got = tokio::time::timeout(Duration::from_secs(5), sub.recv()).await.unwrap().unwrap();
+            assert_eq!(got.topic, topic);
+            assert_eq!(got.from, format!("async_s_{pid}"));
+            assert_eq!(got.data, b"one");
+            tokio::time::timeout(Duration::from_secs(5), s.confirmed(ticket))
+                .await
+                .expect("ack not confirmed in time")
+                .unwrap();
+
+            s.stop().await.unwrap();
+            r.stop().await.unwrap();
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn async_slow_subscription_still_gets_every_message() {
+        let _run_lock = crate::client::tests::client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_async_slow_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("async_slow_t_{pid}");
+        let total = settings::ASYNC_STREAM_CAPACITY + 200;
+
+        runtime().block_on(async {
+            let r = AsyncLiner::new(Client::new_sqlite(&format!("async_slow_r_{pid}"), &format!("async_slow_r_{pid}"), "127.0.0.1:0", db, "").unwrap());
+            let s = AsyncLiner::new(Client::new_sqlite(&format!("async_slow_s_{pid}"), &format!("async_slow_s_{pid}"), "127.0.0.1:0", db, "").unwrap());
+            r.run().await.unwrap();
+            let mut sub = r.subscribe(&topic).await.unwrap();
+            s.run().await.unwrap();
+
+            for i in 0..total {
+                s.send_to(&topic, format!("m{i}").as_bytes(), true).await.unwrap();
+            }
+            // Not read yet: the stream fills up and the rest waits upstream.
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            for i in 0..total {
+                let got = tokio::time::timeout(Duration::from_secs(10), sub.recv())
+                    .await
+                    .unwrap_or_else(|_| panic!("message {i} not received"))
+                    .unwrap();
+                assert_eq!(got.data, format!("m{i}").into_bytes());
+                if i % 100 == 0 {
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                }
+            }
+            assert_eq!(r.unmatched(), 0);
+
+            s.stop().await.unwrap();
+            r.stop().await.unwrap();
+        });
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -10,8 +10,44 @@ fn would_block_timeout() -> Duration {
     Duration::from_millis(settings::BYTESTREAM_WOULD_BLOCK_TIMEOUT_MS)
 }
 
-// return: mem_pos, mem_alloc_length, is_shutdown
-pub fn read_stream<T>(stream: &mut T, mempool: &Arc<Mutex<Mempool>>) -> (usize, usize, bool)
+/// Why a frame could not be read from a peer's stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameError {
+    /// Peer closed the stream between frames (normal teardown, not reported as an event).
+    Closed,
+    /// Stream closed in the middle of a frame.
+    Truncated,
+    /// Length header of `0` or above `max` (`max_message_size`).
+    Oversized { len: usize, max: usize },
+    /// No progress mid-frame for longer than the would-block timeout.
+    Stalled,
+    /// Socket read error.
+    Io(std::io::ErrorKind),
+    /// Frame shorter than the message header.
+    TooShort { len: usize, min: usize },
+    /// Payload length from the message header runs past the frame.
+    PayloadOverrun { need: usize, have: usize },
+}
+
+impl std::fmt::Display for FrameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::Closed => write!(f, "stream closed"),
+            FrameError::Truncated => write!(f, "stream closed mid-frame"),
+            FrameError::Oversized { len, max } => write!(f, "invalid frame length {} (max {})", len, max),
+            FrameError::Stalled => write!(f, "frame stalled"),
+            FrameError::Io(kind) => write!(f, "{}", kind),
+            FrameError::TooShort { len, min } => write!(f, "message too short: {} (min {})", len, min),
+            FrameError::PayloadOverrun { need, have } => {
+                write!(f, "message payload overruns frame: need {}, have {}", need, have)
+            }
+        }
+    }
+}
+
+/// One frame into the mempool: `Some((mem_pos, mem_alloc_length))`, or `None` when no frame
+/// has started yet (would block). On error the stream must be shut down.
+pub fn read_frame<T>(stream: &mut T, mempool: &Arc<Mutex<Mempool>>) -> Result<Option<(usize, usize)>, FrameError>
 where
     T: Read,
 {
@@ -28,7 +64,7 @@ where
     while hdr_offs < len_hdr.len() {
         match stream.read(&mut len_hdr[hdr_offs..]) {
             Ok(0) => {
-                return (0, 0, true);
+                return Err(if hdr_offs == 0 { FrameError::Closed } else { FrameError::Truncated });
             }
             Ok(n) => {
                 hdr_offs += n;
@@ -42,18 +78,18 @@ where
                 }
                 if k == std::io::ErrorKind::WouldBlock {
                     if hdr_offs == 0 {
-                        return (0, 0, false);
+                        return Ok(None);
                     }
                     let started = wait_start.get_or_insert_with(Instant::now);
                     if started.elapsed() > would_block_timeout() {
                         print_error!("bytestream header WouldBlock timeout");
-                        return (0, 0, true);
+                        return Err(FrameError::Stalled);
                     }
                     thread::yield_now();
                     continue;
                 }
                 print_error!(&format!("{}", k));
-                return (0, 0, true);
+                return Err(FrameError::Io(k));
             }
         }
     }
@@ -65,7 +101,7 @@ where
             msg_len,
             settings::max_message_size()
         ));
-        return (0, 0, true);
+        return Err(FrameError::Oversized { len: msg_len, max: settings::max_message_size() });
     }
 
     // Allocate message buffer in mempool.
@@ -83,7 +119,7 @@ where
             Ok(0) => {
                 // Stream closed mid-message: free and signal shutdown.
                 mempool.lock().unwrap().free(mem_pos, mem_alloc_length);
-                return (0, 0, true);
+                return Err(FrameError::Truncated);
             }
             Ok(n) => {
                 mempool.lock().unwrap().write_data(mem_pos + mem_fill_length, &buff[..n]);
@@ -100,19 +136,19 @@ where
                     if started.elapsed() > would_block_timeout() {
                         print_error!("bytestream payload WouldBlock timeout");
                         mempool.lock().unwrap().free(mem_pos, mem_alloc_length);
-                        return (0, 0, true);
+                        return Err(FrameError::Stalled);
                     }
                     thread::yield_now();
                     continue;
                 }
                 print_error!(&format!("{}", k));
                 mempool.lock().unwrap().free(mem_pos, mem_alloc_length);
-                return (0, 0, true);
+                return Err(FrameError::Io(k));
             }
         }
     }
 
-    (mem_pos, mem_alloc_length, false)
+    Ok(Some((mem_pos, mem_alloc_length)))
 }
 
 pub fn write_stream<T>(stream: &mut T, mem_alloc_pos: usize, mem_alloc_length: usize, mempool: &Arc<Mutex<Mempool>>)->bool
@@ -165,6 +201,15 @@ mod tests {
         Arc::new(Mutex::new(Mempool::new()))
     }
 
+    // return: mem_pos, mem_alloc_length, is_shutdown
+    fn read_stream<T: Read>(stream: &mut T, mempool: &Arc<Mutex<Mempool>>) -> (usize, usize, bool) {
+        match read_frame(stream, mempool) {
+            Ok(Some((mem_pos, mem_alloc_length))) => (mem_pos, mem_alloc_length, false),
+            Ok(None) => (0, 0, false),
+            Err(_) => (0, 0, true),
+        }
+    }
+
     fn alloc_and_write(mp: &Arc<Mutex<Mempool>>, data: &[u8]) -> (usize, usize) {
         let (pos, len) = mp.lock().unwrap().alloc(data.len());
         assert_eq!(len, data.len());
@@ -426,6 +471,9 @@ mod tests {
         let mut cur = Cursor::new(msg);
         let (p, l, s) = read_stream(&mut cur, &mp);
         assert_eq!((p, l, s), (0, 0, true));
+        let mut cur = Cursor::new(65u32.to_be_bytes().to_vec());
+        assert_eq!(read_frame(&mut cur, &mp), Err(FrameError::Oversized { len: 65, max: 64 }));
+        assert_eq!(read_frame(&mut cur, &mp), Err(FrameError::Closed));
         assert!(settings::set_max_message_size(prev));
     }
 
@@ -0,0 +1,272 @@
+//! Frame capture ([`Client::start_capture`](crate::Client::start_capture)) and its file format.
+//!
+//! While a capture is on, every frame the sender writes and every frame the listener reads is
+//! appended to the file, duplicates included, before any dedup or delivery. A file starts with
+//! [`CAPTURE_MAGIC`]; each record then holds, big-endian like the wire:
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | capture time, ms since Unix epoch | `u64` |
+//! | direction, `0` sent / `1` received | `u8` |
+//! | peer address length, peer address | `u16`, bytes |
+//! | topic length, topic | `u16`, bytes |
+//! | frame length, frame as written by `Message::to_stream` | `u32`, bytes |
+//!
+//! Each record is written with one `write_all`, so a crash loses at most the record in flight.
+//! [`CaptureReader`] reads the records back and [`CaptureRecord::decode`] parses a frame.
+
+use crate::common;
+use crate::mempool::Mempool;
+use crate::message::{Message, Priority};
+use crate::print_error;
+use crate::trace::TraceContext;
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// First bytes of a capture file (format version in the last byte).
+pub const CAPTURE_MAGIC: [u8; 8] = *b"LNRCAP\x00\x01";
+
+/// The capture file of one client; shared by its sender and listener threads.
+#[derive(Default)]
+pub(crate) struct CaptureSink {
+    file: Mutex<Option<File>>,
+    /// Lock-free fast path while no capture is on.
+    enabled: AtomicBool,
+}
+
+impl CaptureSink {
+    /// Create or truncate `path` and start recording; replaces a running capture.
+    pub fn start(&self, path: &str) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(&CAPTURE_MAGIC)?;
+        if let Ok(mut f) = self.file.lock() {
+            *f = Some(file);
+            self.enabled.store(true, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Stop recording and close the file. Returns `false` if no capture was on.
+    pub fn stop(&self) -> bool {
+        self.enabled.store(false, Ordering::Relaxed);
+        self.file.lock().map(|mut f| f.take().is_some()).unwrap_or(false)
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    /// Append `mess` as a record. A write error stops the capture.
+    pub fn record(&self, inbound: bool, peer: &str, topic: &str, mess: &Message, mempool: &Arc<Mutex<Mempool>>) {
+        let mut frame = Vec::with_capacity(mess.wire_len());
+        if !mess.to_stream(mempool, &mut frame) {
+            return;
+        }
+        let rec = CaptureRecord {
+            ts_ms: common::current_time_ms(),
+            inbound,
+            peer: peer.to_owned(),
+            topic: topic.to_owned(),
+            frame,
+        };
+        let Ok(mut f) = self.file.lock() else {
+            return;
+        };
+        let Some(file) = f.as_mut() else {
+            return;
+        };
+        if let Err(err) = file.write_all(&rec.encode()) {
+            print_error!(&format!("capture write, {}; capture stopped", err));
+            *f = None;
+            self.enabled.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+/// One captured frame.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CaptureRecord {
+    pub ts_ms: u64,
+    /// `false`: written by the sender; `true`: read by the listener.
+    pub inbound: bool,
+    /// Listener address (sent) or sender socket address (received).
+    pub peer: String,
+    /// Topic of the frame's topic key as known to the client; empty if unknown.
+    pub topic: String,
+    /// Length-prefixed frame as on the wire.
+    pub frame: Vec<u8>,
+}
+
+/// Fields of a decoded frame ([`CaptureRecord::decode`]).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedMessage {
+    pub number: u64,
+    pub connection_key: i32,
+    pub topic_key: i32,
+    pub priority: Priority,
+    pub at_least_once: bool,
+    pub replayed: bool,
+    /// Sender clock at encode, ms since Unix epoch (`0` if the frame has none).
+    pub sent_at_ms: u64,
+    pub trace: Option<TraceContext>,
+    /// Payload, decompressed.
+    pub payload: Vec<u8>,
+}
+
+fn put_str(out: &mut Vec<u8>, s: &str) {
+    let b = &s.as_bytes()[..s.len().min(u16::MAX as usize)];
+    out.extend_from_slice(&(b.len() as u16).to_be_bytes());
+    out.extend_from_slice(b);
+}
+
+impl CaptureRecord {
+    fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + 1 + 4 + self.peer.len() + self.topic.len() + 4 + self.frame.len());
+        out.extend_from_slice(&self.ts_ms.to_be_bytes());
+        out.push(u8::from(self.inbound));
+        put_str(&mut out, &self.peer);
+        put_str(&mut out, &self.topic);
+        out.extend_from_slice(&(self.frame.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.frame);
+        out
+    }
+
+    /// Parse the frame; `None` if it is malformed.
+    pub fn decode(&self) -> Option<CapturedMessage> {
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let mess = Message::read_from(&mempool, &mut self.frame.as_slice()).ok()??;
+        let mut payload = Vec::new();
+        let len = mess.get_data(&mempool, &mut payload);
+        payload.truncate(len);
+        Some(CapturedMessage {
+            number: mess.number_mess,
+            connection_key: mess.connection_key(&mempool),
+            topic_key: mess.listener_topic_key,
+            priority: mess.priority(),
+            at_least_once: mess.at_least_once_delivery(),
+            replayed: mess.is_replayed(),
+            sent_at_ms: mess.sent_at_ms(),
+            trace: mess.trace_context(),
+            payload,
+        })
+    }
+}
+
+/// Records of a capture file, in capture order.
+pub struct CaptureReader<R> {
+    inner: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: &str) -> io::Result<Self> {
+        CaptureReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Fails with `InvalidData` unless the input starts with [`CAPTURE_MAGIC`].
+    pub fn new(mut inner: R) -> io::Result<Self> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a liner capture file"));
+        }
+        Ok(CaptureReader { inner })
+    }
+
+    fn read_bytes(&mut self, len: usize) -> io::Result<Vec<u8>> {
+        let mut b = vec![0u8; len];
+        self.inner.read_exact(&mut b)?;
+        Ok(b)
+    }
+
+    fn read_str(&mut self) -> io::Result<String> {
+        let mut len = [0u8; 2];
+        self.inner.read_exact(&mut len)?;
+        let b = self.read_bytes(u16::from_be_bytes(len) as usize)?;
+        String::from_utf8(b).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        let mut ts = [0u8; 8];
+        // End of file only between records; a partial record is `UnexpectedEof`.
+        loop {
+            match self.inner.read(&mut ts[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        self.inner.read_exact(&mut ts[1..])?;
+        let mut dir = [0u8; 1];
+        self.inner.read_exact(&mut dir)?;
+        let peer = self.read_str()?;
+        let topic = self.read_str()?;
+        let mut len = [0u8; 4];
+        self.inner.read_exact(&mut len)?;
+        let frame = self.read_bytes(u32::from_be_bytes(len) as usize)?;
+        Ok(Some(CaptureRecord {
+            ts_ms: u64::from_be_bytes(ts),
+            inbound: dir[0] != 0,
+            peer,
+            topic,
+            frame,
+        }))
+    }
+}
+
+/// A record cut short by a crash ends the iteration with an `UnexpectedEof` error.
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CaptureRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_round_trip_and_frames_decode() {
+        let path = std::env::temp_dir().join(format!("liner_capture_{}.cap", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let mut mess = Message::new(mempool.clone(), 5, 9, 42, b"hello", true).unwrap();
+        mess.set_priority(Priority::High);
+
+        let sink = CaptureSink::default();
+        sink.record(false, "a", "t", &mess, &mempool);
+        sink.start(path).unwrap();
+        sink.record(false, "127.0.0.1:1", "orders", &mess, &mempool);
+        sink.record(true, "127.0.0.1:2", "", &mess, &mempool);
+        assert!(sink.stop());
+        sink.record(true, "b", "t", &mess, &mempool);
+
+        let recs: Vec<CaptureRecord> = CaptureReader::open(path).unwrap().map(|r| r.unwrap()).collect();
+        assert_eq!(recs.len(), 2, "only records between start and stop");
+        assert_eq!((recs[0].inbound, recs[0].peer.as_str(), recs[0].topic.as_str()), (false, "127.0.0.1:1", "orders"));
+        assert!(recs[1].inbound);
+        assert_eq!(recs[0].frame.len(), 4 + mess.wire_len(), "length prefix and the encoded message");
+        let m = recs[1].decode().unwrap();
+        assert_eq!((m.number, m.connection_key, m.topic_key), (42, 5, 9));
+        assert_eq!((m.priority, m.at_least_once, m.replayed), (Priority::High, true, false));
+        assert_eq!(m.payload, b"hello");
+        assert!(m.sent_at_ms > 0);
+
+        // A truncated tail is reported, earlier records stay readable.
+        let mut bytes = std::fs::read(path).unwrap();
+        bytes.truncate(bytes.len() - 3);
+        let mut rd = CaptureReader::new(bytes.as_slice()).unwrap();
+        assert!(rd.next().unwrap().is_ok());
+        assert_eq!(rd.next().unwrap().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(CaptureReader::new(&b"nope...."[..]).is_err());
+        let _ = std::fs::remove_file(path);
+    }
+}
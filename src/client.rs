@@ -1,21 +1,99 @@
 use crate::store::Store;
-use crate::{UCbackIntern, UData};
+use crate::{UCbackExIntern, UCbackIntern, UData};
 use crate::error::ErrorCode;
-use crate::listener::Listener;
-use crate::message;
+use crate::hashring::HashRing;
+use crate::topic_pattern;
+use crate::listener::{Listener, ReceiveCallback};
+use crate::scheduler::Scheduler;
+#[cfg(feature = "prometheus")]
+use crate::exporter::{self, MetricsExporter};
+use crate::common;
+use crate::log;
+use crate::message::{self, Priority};
+use crate::metrics::MetricsSnapshot;
+use crate::peers::PeerInfo;
+use crate::health::Health;
+use crate::mempool::MempoolSlotStats;
+use crate::trace::{self, TraceContext};
 use crate::sender::{EnqueueResult, Sender};
-use crate::print_error;
-use crate::settings::INTERNAL_CHANNEL_TOPIC;
-use crate::status::{
-    StatusCbackIntern, StatusEmitter, StatusMsg, LNR_PEER_CONNECTED, LNR_PEER_DISCONNECTED,
-    LNR_PEER_SUBSCRIBED, LNR_PEER_UNSUBSCRIBED, LNR_SENDER_BUSY,
-};
+use crate::{print_error, print_info};
+use crate::settings::{self, INTERNAL_CHANNEL_TOPIC};
+use crate::status::{StatusCbackIntern, StatusEmitter, StatusEvent, StatusEventCb};
 
 use std::net::{SocketAddr, ToSocketAddrs};
 use std::ffi::{CStr, CString};
 use mio::net::TcpListener;
 use std::sync::{Arc, Mutex};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Replica selection for [`Client::send_to`] when a topic has several addresses.
+/// Values match C `LNR_ROUTE_*`.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoutePolicy {
+    /// Rotate over the cached address list (default).
+    #[default]
+    RoundRobin = 0,
+    /// Smallest in-memory send queue plus offline backlog; replicas without a live stream
+    /// are skipped while any other replica is up. Ties rotate like round-robin.
+    LeastLoaded = 1,
+}
+
+impl RoutePolicy {
+    pub fn from_i32(v: i32) -> Option<RoutePolicy> {
+        match v {
+            0 => Some(RoutePolicy::RoundRobin),
+            1 => Some(RoutePolicy::LeastLoaded),
+            _ => None,
+        }
+    }
+}
+
+/// Outcome of [`Client::send_all_detailed`] for one peer of the topic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerSendResult {
+    pub addr: String,
+    /// Listener `unique_name` from the store (empty if it could not be resolved).
+    pub unique_name: String,
+    pub status: EnqueueResult,
+    /// Empty for [`EnqueueResult::Ok`].
+    pub error: String,
+}
+
+/// One received message with owned fields, for consumers off the receive thread.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delivery {
+    /// Subscribed topic the message arrived on (the concrete topic for a pattern).
+    pub topic: String,
+    /// Sender's `source_topic`.
+    pub from: String,
+    pub data: Vec<u8>,
+}
+
+/// Metadata of one received message, passed next to the payload by [`crate::Liner::run_ex`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeliveryInfo<'a> {
+    pub topic: &'a str,
+    pub from: &'a str,
+    /// Sender's `unique_name`; empty for retained values and peers that do not record it.
+    pub sender_name: &'a str,
+    /// Wire number, increasing per sender connection (`0` for retained values). With
+    /// `sender_name` and `from` it identifies the message for idempotency checks.
+    pub number: u64,
+    /// Sender clock when the message was encoded, ms since the Unix epoch; `0` if unknown.
+    pub sent_at_ms: u64,
+    /// Resent from the sender's offline queue (after a reconnect or restart), so it may be a duplicate.
+    pub replayed: bool,
+    /// Sender's trace context (W3C `traceparent`); current on the receive thread during the callback.
+    pub trace: Option<TraceContext>,
+}
+
+/// Wire position of a message accepted by [`Client::send_to_tracked`]; see [`Client::is_confirmed`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DeliveryTicket {
+    pub connection_key: i32,
+    pub number: u64,
+}
 
 /// Heap-stable state. `Client` is a thin `Box` wrapper so moving the handle
 /// after `run` does not invalidate the raw pointer passed to listener threads.
@@ -31,13 +109,34 @@ pub struct ClientRepr {
     db: Arc<Mutex<dyn Store>>,
     listener: Option<Listener>,
     sender: Option<Sender>,
+    /// Releases due `send_to_at` / `send_to_after` messages while running.
+    scheduler: Option<Scheduler>,
+    /// Prometheus endpoint started by `serve_metrics`; lives until the client is dropped.
+    #[cfg(feature = "prometheus")]
+    exporter: Option<MetricsExporter>,
     last_send_index: HashMap<String, usize>,
+    /// Consistent-hash ring per topic for `send_to_keyed`; rebuilt when the address list changes.
+    key_rings: HashMap<String, HashRing>,
+    /// Threads running receive callbacks of different sender connections (`1`: the
+    /// listener's receive thread).
+    receive_workers: usize,
+    route_policy: RoutePolicy,
+    /// Per-topic overrides of `route_policy`.
+    topic_route_policy: HashMap<String, RoutePolicy>,
+    /// addr -> (checked at ms, offline queue length) for `RoutePolicy::LeastLoaded`.
+    route_backlog: HashMap<String, (u64, u64)>,
+    /// Topics whose unacked messages move to another replica when a route is lost.
+    topic_failover: HashSet<String>,
+    /// topic -> (addr -> consumer group) for `send_all_groups`; dropped when routes change.
+    topic_groups: HashMap<String, HashMap<String, String>>,
     is_run: bool,
     mtx: Mutex<()>,
     address_topic: HashMap<String, Vec<String>>,
     /// Topics this client has sent to, subscribed to, or explicitly refreshed (status filter).
     related_topics: HashSet<String>,
     subscriptions: HashMap<i32, String>,
+    /// Subscriptions left in the catalog on `stop` (see `subscribe_durable`).
+    durable_topics: HashSet<String>,
     /// Actual `SocketAddr` after `run` binds `localhost` (e.g. when port is `0`). Kept after `stop`.
     bound_listen_addr: Option<String>,
     c_bound_listen_addr: Option<CString>,
@@ -47,11 +146,18 @@ pub struct ClientRepr {
     last_error: ErrorCode,
     last_error_msg: String,
     c_last_error_msg: Option<CString>,
-    user_receive_cb: Option<UCbackIntern>,
+    user_receive_cb: Option<UserReceiveCb>,
     user_receive_udata: UData,
     status_emitter: StatusEmitter,
 }
 
+/// Application receive callback: `run` passes `(to, from, data)`, `run_ex` also the delivery metadata.
+#[derive(Clone, Copy)]
+enum UserReceiveCb {
+    Plain(UCbackIntern),
+    Ex(UCbackExIntern),
+}
+
 fn cstring_lossy(s: &str) -> CString {
     CString::new(s).unwrap_or_else(|_| CString::new("").unwrap_or_default())
 }
@@ -86,6 +192,7 @@ fn client_fields(
     localhost: String,
     db: Arc<Mutex<dyn Store>>,
 ) -> Client {
+    let status_emitter = StatusEmitter::for_client(&unique_name);
     Client {
         inner: Box::new(ClientRepr {
             unique_name,
@@ -96,12 +203,23 @@ fn client_fields(
             db,
             listener: None,
             sender: None,
+            scheduler: None,
+            #[cfg(feature = "prometheus")]
+            exporter: None,
             last_send_index: HashMap::new(),
+            key_rings: HashMap::new(),
+            receive_workers: 1,
+            route_policy: RoutePolicy::default(),
+            topic_route_policy: HashMap::new(),
+            route_backlog: HashMap::new(),
+            topic_failover: HashSet::new(),
+            topic_groups: HashMap::new(),
             is_run: false,
             mtx: Mutex::new(()),
             address_topic: HashMap::new(),
             related_topics: HashSet::new(),
             subscriptions: HashMap::new(),
+            durable_topics: HashSet::new(),
             bound_listen_addr: None,
             c_bound_listen_addr: None,
             published_addr: None,
@@ -111,7 +229,7 @@ fn client_fields(
             c_last_error_msg: None,
             user_receive_cb: None,
             user_receive_udata: UData::null(),
-            status_emitter: StatusEmitter::new(),
+            status_emitter,
         }),
     }
 }
@@ -154,7 +272,8 @@ macro_rules! client_ok {
 }
 
 macro_rules! client_fail {
-    ($self:expr, $code:expr, $msg:expr $(,)?) => {
+    ($self:expr, $code:expr, $msg:expr $(,)?) => {{
+        let _log = log::client_scope($self.log_name());
         set_fail(
             &mut $self.last_error,
             &mut $self.last_error_msg,
@@ -162,7 +281,7 @@ macro_rules! client_fail {
             $code,
             $msg,
         )
-    };
+    }};
 }
 
 impl Client {
@@ -331,6 +450,7 @@ impl ClientRepr {
     /// `None` or empty clears advertise (publish bind / bound address again).
     pub fn set_advertise_addr(&mut self, addr: Option<&str>) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if self.is_run {
             return client_fail!(self, 
                 ErrorCode::AlreadyRunning,
@@ -365,16 +485,41 @@ impl ClientRepr {
         }
     }
 
+    /// `unique_name` as shared with log scopes of this client's threads.
+    pub(crate) fn log_name(&self) -> &Arc<str> {
+        self.status_emitter.client_name()
+    }
+
     /// Set or clear the status / background-error callback. Pass `None` to clear.
     pub fn set_status_cb(&mut self, cb: Option<StatusCbackIntern>, udata: UData) {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         self.status_emitter
             .set_callback(cb, udata);
     }
 
+    /// Typed status events ([`StatusEvent`]) on the thread that raised them. Independent of
+    /// [`Client::set_status_cb`]; `None` clears.
+    pub fn set_status_event_cb(&mut self, cb: Option<StatusEventCb>) {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        self.status_emitter.set_event_callback(cb);
+    }
+
     pub fn run(&mut self, receive_cb: UCbackIntern, udata: UData) -> bool {
+        self.run_inner(UserReceiveCb::Plain(receive_cb), udata)
+    }
+
+    /// [`Client::run`] with a callback that also gets the sender's unique name, message number,
+    /// sender timestamp and replay flag (see `lnr_receive_ex_cb`).
+    pub fn run_ex(&mut self, receive_cb: UCbackExIntern, udata: UData) -> bool {
+        self.run_inner(UserReceiveCb::Ex(receive_cb), udata)
+    }
+
+    fn run_inner(&mut self, receive_cb: UserReceiveCb, udata: UData) -> bool {
         let client_ptr = std::ptr::from_mut(self);
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if self.is_run {
             client_ok!(self);
             return true;
@@ -428,8 +573,11 @@ impl ClientRepr {
             self.db.clone(),
             &self.source_topic,
             &self.subscriptions,
-            client_receive_wrapper,
-            UData(client_ptr as *mut libc::c_void),
+            ReceiveCallback {
+                cb: client_receive_wrapper,
+                udata: UData(client_ptr as *mut libc::c_void),
+                workers: self.receive_workers,
+            },
             self.status_emitter.clone(),
         ) {
             Ok(l) => l,
@@ -440,6 +588,13 @@ impl ClientRepr {
                 return client_fail!(self, ErrorCode::Startup, &err);
             }
         };
+        {
+            // Retained values of earlier subscriptions go ahead of live traffic.
+            let mut db = self.db.lock().unwrap();
+            for topic in self.subscriptions.values() {
+                deliver_retained(topic, &mut *db, &listener);
+            }
+        }
         self.listener = Some(listener);
         self.sender = Some(Sender::new(
             self.db.clone(),
@@ -488,7 +643,17 @@ impl ClientRepr {
             "client_connected",
             None,
         );
+        // Messages scheduled by an earlier run are released from here on.
+        self.scheduler = Some(Scheduler::new(
+            scheduler_tick,
+            UData(client_ptr as *mut libc::c_void),
+        ));
 
+        print_info!(format!(
+            "running, topic {}, listening on {}",
+            self.source_topic,
+            self.published_addr.as_deref().unwrap_or_default()
+        ));
         client_ok!(self);
         true
     }
@@ -496,8 +661,9 @@ impl ClientRepr {
     /// Stop listener/sender threads and unregister from the store. Idempotent.
     /// Keeps [`Client::bound_listen_addr`]; clears [`Client::published_addr`].
     pub fn stop(&mut self) -> bool {
-        let (listener, sender) = {
+        let (scheduler, listener, sender) = {
             let _lock = self.mtx.lock();
+            let _log = log::client_scope(self.log_name());
             if !self.is_run {
                 client_ok!(self);
                 return true;
@@ -506,6 +672,9 @@ impl ClientRepr {
             // "unsubscribed" here — crash/teardown must keep sender_listener so
             // at-least-once offline delivery still works; only explicit
             // `unsubscribe` clears those routes via the internal event.
+            // Durable subscriptions keep their catalog rows, so senders queue for us offline.
+            let durable = std::mem::take(&mut self.durable_topics);
+            self.subscriptions.retain(|_, t| !durable.contains(t));
             let extra: Vec<String> = self
                 .subscriptions
                 .values()
@@ -551,18 +720,218 @@ impl ClientRepr {
             self.published_addr = None;
             self.c_published_addr = None;
             client_ok!(self);
-            (self.listener.take(), self.sender.take())
+            (self.scheduler.take(), self.listener.take(), self.sender.take())
         };
-        // Join threads outside Client.mtx — receive path also takes that lock.
+        // Join threads outside Client.mtx — receive and release paths also take that lock.
+        let _log = log::client_scope(self.log_name());
+        drop(scheduler);
         drop(listener);
         drop(sender);
+        print_info!("stopped");
+        true
+    }
+
+    /// Threads running receive callbacks of different sender connections in parallel
+    /// (default `1`). Order per connection is kept; with `n > 1` the callback must be
+    /// thread-safe. Call before [`Client::run`].
+    pub fn set_receive_workers(&mut self, n: usize) -> bool {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if self.is_run {
+            return client_fail!(self,
+                ErrorCode::AlreadyRunning,
+                "you can't set_receive_workers because client already is running",
+            );
+        }
+        if n == 0 {
+            return client_fail!(self, ErrorCode::InvalidArg, "receive_workers == 0");
+        }
+        self.receive_workers = n;
+        client_ok!(self);
         true
     }
 
+    pub fn receive_workers(&self) -> usize {
+        self.receive_workers
+    }
+
+    /// Default replica selection for `send_to` on every topic without an override.
+    pub fn set_route_policy(&mut self, policy: RoutePolicy) {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        self.route_policy = policy;
+    }
+
+    /// Replica selection for `send_to` on `topic`; `None` falls back to the client policy.
+    pub fn set_topic_route_policy(&mut self, topic: &str, policy: Option<RoutePolicy>) {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        match policy {
+            Some(p) => {
+                self.topic_route_policy.insert(topic.to_owned(), p);
+            }
+            None => {
+                self.topic_route_policy.remove(topic);
+            }
+        }
+    }
+
+    /// Opt-in failover for a multi-replica `topic`: when a replica's route is lost, its
+    /// unacked messages for `topic` (in memory and in its offline queue) are re-sent to a
+    /// live replica by the next scheduler tick (every 100 ms) or `send_to` / `send_all`,
+    /// whichever comes first.
+    pub fn set_topic_failover(&mut self, topic: &str, enabled: bool) {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if enabled {
+            self.topic_failover.insert(topic.to_owned());
+        } else {
+            self.topic_failover.remove(topic);
+        }
+    }
+
+    pub fn route_policy(&self, topic: &str) -> RoutePolicy {
+        self.topic_route_policy
+            .get(topic)
+            .copied()
+            .unwrap_or(self.route_policy)
+    }
+
     pub fn send_to(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_to_route(topic, SendRoute::Policy, data, SendOpts::new(at_least_once_delivery))
+    }
+
+    /// [`send_to`](Self::send_to) on the `priority` lane of the chosen peer. Each raised lane
+    /// has its own queue, connection and offline list, is written before lower lanes, and
+    /// the listener runs its callbacks first; order is kept only within one lane.
+    pub fn send_to_priority(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool, priority: Priority) -> bool {
+        self.send_to_route(topic, SendRoute::Policy, data, SendOpts { at_least_once_delivery, priority })
+    }
+
+    /// Like [`send_to`](Self::send_to), but picks the replica by hashing `key` onto a
+    /// consistent-hash ring of the topic's addresses: the same key keeps going to the same
+    /// replica while it stays in the route cache, and only keys owned by a joining or
+    /// leaving replica move.
+    pub fn send_to_keyed(&mut self, topic: &str, key: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_to_route(topic, SendRoute::Keyed(key), data, SendOpts::new(at_least_once_delivery))
+    }
+
+    /// Send to the replica of `topic` registered as `unique_name` (looked up in the store
+    /// directory on every call), bypassing the route policy. Fails with `NoAddr` if that
+    /// instance is not in the directory.
+    pub fn send_to_peer(&mut self, topic: &str, unique_name: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_to_route(topic, SendRoute::Peer(unique_name), data, SendOpts::new(at_least_once_delivery))
+    }
+
+    /// At-least-once [`send_to`](Self::send_to) that returns a ticket for the chosen peer's
+    /// route, so the caller can wait for the listener's ack with [`is_confirmed`](Self::is_confirmed).
+    /// `None` on failure (see [`last_error`](Self::last_error)).
+    pub fn send_to_tracked(&mut self, topic: &str, data: &[u8]) -> Option<DeliveryTicket> {
+        let client = std::ptr::from_mut(self);
+        // SAFETY: as in `send_to_route`; the ticket is read under the same lock as the send.
+        unsafe {
+            let _lock = (*client).mtx.lock().unwrap();
+            let _log = log::client_scope((*client).log_name());
+            if !(*client).send_to_route_locked(topic, SendRoute::Policy, data, SendOpts::new(true)) {
+                return None;
+            }
+            let (connection_key, number) = (*client).sender.as_ref()?.last_enqueued()?;
+            Some(DeliveryTicket { connection_key, number })
+        }
+    }
+
+    /// Whether the listener has acked `ticket` (its ack number in the store reached the
+    /// message). A message later moved by failover or `reassign_pending` gets a new number
+    /// and never confirms under the old ticket. `None` + `Store` on store errors.
+    pub fn is_confirmed(&mut self, ticket: &DeliveryTicket) -> Option<bool> {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let acked = self
+            .db
+            .lock()
+            .unwrap()
+            .get_last_mess_number_for_sender(ticket.connection_key);
+        match acked {
+            Ok(n) => {
+                client_ok!(self);
+                Some(n >= ticket.number)
+            }
+            Err(err) => {
+                client_fail!(self, ErrorCode::Store, &format!("{}", err));
+                None
+            }
+        }
+    }
+
+    /// Hold `data` in the store and release it to `topic` through [`send_to`](Self::send_to)
+    /// at `deliver_at_ms` (ms since the Unix epoch; a past time releases on the next tick).
+    /// Scheduled messages survive a restart of this client and are released by its next
+    /// [`run`](Self::run). A due at-least-once message without a route is retried; a
+    /// best-effort one is dropped.
+    pub fn send_to_at(&mut self, topic: &str, data: &[u8], deliver_at_ms: u64, at_least_once_delivery: bool) -> bool {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if !self.is_run {
+            return client_fail!(self,
+                ErrorCode::NotRunning,
+                "you can't send_to_at because client not is running",
+            );
+        }
+        if topic.is_empty() || topic_pattern::is_pattern(topic) {
+            return client_fail!(self, ErrorCode::InvalidArg, "can't schedule on an empty topic or a topic pattern");
+        }
+        if topic == self.source_topic {
+            return client_fail!(self, ErrorCode::SelfTopic, "you can't send on your own topic");
+        }
+        if data.is_empty() {
+            return client_fail!(self, ErrorCode::InvalidArg, "payload empty");
+        }
+        // The scheduler re-enters this context when it releases the message.
+        let trace = trace::current();
+        if message::payload_exceeds_max_message_size(data.len(), trace.is_some()) {
+            return client_fail!(self, ErrorCode::InvalidArg,
+                &format!(
+                    "payload too large for max_message_size (payload {}, max {})",
+                    data.len(),
+                    crate::settings::max_message_size()
+                ),
+            );
+        }
+        let res = self.db.lock().unwrap().schedule_message(
+            topic,
+            at_least_once_delivery,
+            deliver_at_ms,
+            data,
+            trace,
+        );
+        if let Err(err) = res {
+            return client_fail!(self, ErrorCode::Store, &format!("{}", err));
+        }
+        mark_related_topic(&mut self.related_topics, topic);
+        client_ok!(self);
+        true
+    }
+
+    /// [`send_to_at`](Self::send_to_at) `delay_ms` from now.
+    pub fn send_to_after(&mut self, topic: &str, data: &[u8], delay_ms: u64, at_least_once_delivery: bool) -> bool {
+        let deliver_at_ms = common::current_time_ms().saturating_add(delay_ms);
+        self.send_to_at(topic, data, deliver_at_ms, at_least_once_delivery)
+    }
+
+    fn send_to_route(&mut self, topic: &str, route: SendRoute<'_>, data: &[u8], opts: SendOpts) -> bool {
+        let client = std::ptr::from_mut(self);
         // Hold mtx for route + ensure + enqueue so concurrent FFI calls stay serialized
         // (see docs/using-the-api.md). Store is still only locked briefly in ensure_send_route.
-        let _lock = self.mtx.lock().unwrap();
+        // SAFETY: the locked body never touches `mtx`; same access as the receive thread.
+        unsafe {
+            let _lock = (*client).mtx.lock().unwrap();
+            let _log = log::client_scope((*client).log_name());
+            (*client).send_to_route_locked(topic, route, data, opts)
+        }
+    }
+
+    fn send_to_route_locked(&mut self, topic: &str, route: SendRoute<'_>, data: &[u8], opts: SendOpts) -> bool {
+        let SendOpts { at_least_once_delivery, priority } = opts;
         if !self.is_run {
             return client_fail!(self, 
                 ErrorCode::NotRunning,
@@ -577,81 +946,131 @@ impl ClientRepr {
                 "payload empty",
             );
         }
-        if message::payload_exceeds_max_message_size(data.len()) {
+        if matches!(route, SendRoute::Keyed(key) if key.is_empty()) {
+            return client_fail!(self, ErrorCode::InvalidArg, "routing key empty");
+        }
+        if matches!(route, SendRoute::Peer(name) if name.is_empty()) {
+            return client_fail!(self, ErrorCode::InvalidArg, "unique_name empty");
+        }
+        let traced = trace::current().is_some();
+        if message::payload_exceeds_max_message_size(data.len(), traced) {
             return client_fail!(self, ErrorCode::InvalidArg,
                 &format!(
                     "payload too large for max_message_size (payload {}, framed body {}, max {})",
                     data.len(),
-                    message::framed_body_size_raw(data.len()),
+                    message::framed_body_size_raw(data.len(), traced),
                     crate::settings::max_message_size()
                 ),
             );
         }
+        apply_failover(
+            &self.topic_failover,
+            &self.address_topic,
+            &self.db,
+            self.sender.as_mut(),
+            &self.status_emitter,
+        );
         apply_failed_routes(&mut self.address_topic, self.sender.as_mut());
-        // Resolve routes first so round-robin can borrow the address without cloning.
-        if self
-            .address_topic
-            .get(topic)
-            .map(|a| a.is_empty())
-            .unwrap_or(true)
-        {
-            match resolve_send_addresses(
-                topic,
-                at_least_once_delivery,
-                &mut self.address_topic,
-                &mut *self.db.lock().unwrap(),
-            ) {
-                ResolveAddrs::Ok(_) => {}
-                ResolveAddrs::NoAddr => {
-                    self.address_topic.remove(topic);
+        let peer_addr: String;
+        let addr: &str = match route {
+            SendRoute::Peer(name) => {
+                // Exact instance: look it up in the store directory, not the round-robin cache.
+                let found = match self.db.lock().unwrap().get_topic_directory(topic) {
+                    Ok(rows) => rows.into_iter().find(|(_, n)| n == name).map(|(a, _)| a),
+                    Err(err) => return client_fail!(self, ErrorCode::Store, &format!("{}", err)),
+                };
+                let Some(found) = found else {
+                    return client_fail!(self,
+                        ErrorCode::NoAddr,
+                        &format!("not found peer {} for topic {}", name, topic),
+                    );
+                };
+                mark_related_topic(&mut self.related_topics, topic);
+                peer_addr = found;
+                peer_addr.as_str()
+            }
+            SendRoute::Policy | SendRoute::Keyed(_) => {
+                // Resolve routes first so round-robin can borrow the address without cloning.
+                if self
+                    .address_topic
+                    .get(topic)
+                    .map(|a| a.is_empty())
+                    .unwrap_or(true)
+                {
+                    match resolve_send_addresses(
+                        topic,
+                        at_least_once_delivery,
+                        &mut self.address_topic,
+                        &mut *self.db.lock().unwrap(),
+                    ) {
+                        ResolveAddrs::Ok(_) => {}
+                        ResolveAddrs::NoAddr => {
+                            self.address_topic.remove(topic);
+                            return client_fail!(self, 
+                                ErrorCode::NoAddr,
+                                &format!("not found addr for topic {}", topic),
+                            );
+                        }
+                        ResolveAddrs::Store(err) => {
+                            self.address_topic.remove(topic);
+                            return client_fail!(self, ErrorCode::Store, &err);
+                        }
+                    }
+                }
+                let addr_len = self.address_topic.get(topic).map(|a| a.len()).unwrap_or(0);
+                if addr_len == 0 {
                     return client_fail!(self, 
                         ErrorCode::NoAddr,
                         &format!("not found addr for topic {}", topic),
                     );
                 }
-                ResolveAddrs::Store(err) => {
-                    self.address_topic.remove(topic);
-                    return client_fail!(self, ErrorCode::Store, &err);
-                }
+                mark_related_topic(&mut self.related_topics, topic);
+                let addrs = self.address_topic.get(topic).unwrap().as_slice();
+                let index = match route {
+                    SendRoute::Keyed(key) => keyed_index(&mut self.key_rings, topic, addrs, key),
+                    _ => {
+                        let next = round_robin_next(&mut self.last_send_index, topic, addr_len);
+                        let policy = self
+                            .topic_route_policy
+                            .get(topic)
+                            .copied()
+                            .unwrap_or(self.route_policy);
+                        if policy == RoutePolicy::LeastLoaded && addr_len > 1 {
+                            pick_least_loaded(
+                                addrs,
+                                next,
+                                self.sender.as_ref().unwrap(),
+                                &self.db,
+                                &mut self.route_backlog,
+                            )
+                        } else {
+                            next
+                        }
+                    }
+                };
+                addrs[index].as_str()
             }
-        }
-        let addr_len = self.address_topic.get(topic).map(|a| a.len()).unwrap_or(0);
-        if addr_len == 0 {
-            return client_fail!(self, 
-                ErrorCode::NoAddr,
-                &format!("not found addr for topic {}", topic),
-            );
-        }
-        mark_related_topic(&mut self.related_topics, topic);
-        let index = if let Some(slot) = self.last_send_index.get_mut(topic) {
-            let i = *slot % addr_len;
-            *slot = (i + 1) % addr_len;
-            i
-        } else {
-            self.last_send_index.insert(topic.to_owned(), if addr_len > 1 { 1 } else { 0 });
-            0
         };
-        let addr = self.address_topic.get(topic).unwrap()[index].as_str();
         let sender = self.sender.as_mut().unwrap();
-        if sender.needs_store_for_send(addr, topic) {
+        if sender.needs_store_for_send(addr, topic, priority) {
             let mut db = self.db.lock().unwrap();
-            if !sender.ensure_send_route(&mut *db, addr, topic) {
+            if !sender.ensure_send_route(&mut *db, addr, topic, priority) {
                 return client_fail!(self, ErrorCode::Store, "ensure_send_route failed");
             }
         }
-        match sender.send_to(addr, topic, data, at_least_once_delivery) {
+        match sender.send_to(addr, topic, data, at_least_once_delivery, priority) {
             EnqueueResult::Ok => {
                 client_ok!(self);
                 true
             }
             EnqueueResult::Busy => {
-                self.status_emitter.emit_msg(
-                    LNR_SENDER_BUSY,
-                    topic,
-                    addr,
-                    StatusMsg::SendQueueFull,
-                    &[],
-                );
+                if self.status_emitter.is_enabled() {
+                    self.status_emitter.emit_event(StatusEvent::SenderBusy {
+                        topic: topic.to_owned(),
+                        addr: addr.to_owned(),
+                        queue_depth: sender.queue_depth(addr),
+                    });
+                }
                 client_fail!(self, ErrorCode::Busy, "send queue full")
             }
             EnqueueResult::Fail => client_fail!(self, ErrorCode::Store, "send_to failed"),
@@ -659,7 +1078,73 @@ impl ClientRepr {
     }
 
     pub fn send_all(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_all_inner(topic, Fanout::All, data, SendOpts::new(at_least_once_delivery), false, None)
+    }
+
+    /// [`send_all`](Self::send_all) on the `priority` lane of every peer
+    /// (see [`send_to_priority`](Self::send_to_priority)).
+    pub fn send_all_priority(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool, priority: Priority) -> bool {
+        self.send_all_inner(topic, Fanout::All, data, SendOpts { at_least_once_delivery, priority }, false, None)
+    }
+
+    /// [`send_all`](Self::send_all) that also keeps `data` as the retained value of `topic`:
+    /// a client subscribing later (or running with the subscription) receives it before live
+    /// traffic. Succeeds without subscribers, since the value is still retained.
+    pub fn send_all_retained(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_all_inner(topic, Fanout::All, data, SendOpts::new(at_least_once_delivery), true, None)
+    }
+
+    /// Drop the retained value of `topic`; later subscribers get nothing until the next
+    /// [`send_all_retained`](Self::send_all_retained).
+    pub fn clear_retained(&mut self, topic: &str) -> bool {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if let Err(err) = self.db.lock().unwrap().clear_retained(topic) {
+            return client_fail!(self, ErrorCode::Store, &format!("{}", err));
+        }
+        client_ok!(self);
+        true
+    }
+
+    /// Consumer-group fan-out: every ungrouped subscriber of `topic` gets a copy, and each
+    /// consumer group (see [`subscribe_group`](Self::subscribe_group)) gets one copy,
+    /// delivered to one member chosen by the topic's route policy.
+    pub fn send_all_groups(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_all_inner(topic, Fanout::Groups(None), data, SendOpts::new(at_least_once_delivery), false, None)
+    }
+
+    /// Like [`send_all_groups`](Self::send_all_groups), but the member of each group is picked
+    /// by consistent-hashing `key` over the group, as [`send_to_keyed`](Self::send_to_keyed)
+    /// does over a topic.
+    pub fn send_all_groups_keyed(&mut self, topic: &str, key: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        self.send_all_inner(topic, Fanout::Groups(Some(key)), data, SendOpts::new(at_least_once_delivery), false, None)
+    }
+
+    /// Same as [`Client::send_all`], but reports the outcome per peer so only failed peers
+    /// need a retry. `None` when the call fails as a whole (not running, no addresses, …);
+    /// otherwise one row per cached address, and `last_error` is set as for `send_all`.
+    pub fn send_all_detailed(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> Option<Vec<PeerSendResult>> {
+        let mut out = Vec::new();
+        self.send_all_inner(topic, Fanout::All, data, SendOpts::new(at_least_once_delivery), false, Some(&mut out));
+        if out.is_empty() {
+            None
+        } else {
+            Some(out)
+        }
+    }
+
+    fn send_all_inner(
+        &mut self,
+        topic: &str,
+        fanout: Fanout<'_>,
+        data: &[u8],
+        opts: SendOpts,
+        retain: bool,
+        mut detailed: Option<&mut Vec<PeerSendResult>>,
+    ) -> bool {
+        let SendOpts { at_least_once_delivery, priority } = opts;
         let _lock = self.mtx.lock().unwrap();
+        let _log = log::client_scope(self.log_name());
         if !self.is_run {
             return client_fail!(self, 
                 ErrorCode::NotRunning,
@@ -674,16 +1159,35 @@ impl ClientRepr {
                 "payload empty",
             );
         }
-        if message::payload_exceeds_max_message_size(data.len()) {
+        if matches!(fanout, Fanout::Groups(Some(key)) if key.is_empty()) {
+            return client_fail!(self, ErrorCode::InvalidArg, "routing key empty");
+        }
+        let traced = trace::current().is_some();
+        if message::payload_exceeds_max_message_size(data.len(), traced) {
             return client_fail!(self, ErrorCode::InvalidArg,
                 &format!(
                     "payload too large for max_message_size (payload {}, framed body {}, max {})",
                     data.len(),
-                    message::framed_body_size_raw(data.len()),
+                    message::framed_body_size_raw(data.len(), traced),
                     crate::settings::max_message_size()
                 ),
             );
         }
+        if retain {
+            if topic_pattern::is_pattern(topic) {
+                return client_fail!(self, ErrorCode::InvalidArg, "can't retain on a topic pattern");
+            }
+            if let Err(err) = self.db.lock().unwrap().save_retained(topic, &self.source_topic, data) {
+                return client_fail!(self, ErrorCode::Store, &format!("{}", err));
+            }
+        }
+        apply_failover(
+            &self.topic_failover,
+            &self.address_topic,
+            &self.db,
+            self.sender.as_mut(),
+            &self.status_emitter,
+        );
         apply_failed_routes(&mut self.address_topic, self.sender.as_mut());
         // Populate cache without retaining a borrow across the sender loop.
         if !self
@@ -691,6 +1195,7 @@ impl ClientRepr {
             .get(topic)
             .is_some_and(|a| !a.is_empty())
         {
+            self.topic_groups.remove(topic);
             match resolve_send_addresses(
                 topic,
                 at_least_once_delivery,
@@ -700,6 +1205,10 @@ impl ClientRepr {
                 ResolveAddrs::Ok(_) => {}
                 ResolveAddrs::NoAddr => {
                     self.address_topic.remove(topic);
+                    if retain {
+                        client_ok!(self);
+                        return true;
+                    }
                     return client_fail!(self, ErrorCode::NoAddr,
                         &format!("not found addr for topic {}", topic),
                     );
@@ -712,23 +1221,80 @@ impl ClientRepr {
         }
         let addr_len = self.address_topic.get(topic).map(|a| a.len()).unwrap_or(0);
         if addr_len == 0 {
+            if retain {
+                client_ok!(self);
+                return true;
+            }
             return client_fail!(self, ErrorCode::NoAddr,
                 &format!("not found addr for topic {}", topic),
             );
         }
         mark_related_topic(&mut self.related_topics, topic);
+        if matches!(fanout, Fanout::Groups(_)) && !self.topic_groups.contains_key(topic) {
+            let rows = self.db.lock().unwrap().get_topic_groups(topic);
+            match rows {
+                Ok(rows) => {
+                    self.topic_groups
+                        .insert(topic.to_owned(), rows.into_iter().collect());
+                }
+                Err(err) => return client_fail!(self, ErrorCode::Store, &format!("{}", err)),
+            }
+        }
         // Disjoint field borrows: addresses from cache, mutable sender — no addr Vec clone.
         let addrs = self.address_topic.get(topic).unwrap().as_slice();
+        let targets: Vec<usize> = match fanout {
+            Fanout::All => (0..addr_len).collect(),
+            Fanout::Groups(key) => {
+                let groups = &self.topic_groups[topic];
+                let mut targets = Vec::new();
+                let mut members: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+                for (i, addr) in addrs.iter().enumerate() {
+                    match groups.get(addr) {
+                        Some(group) => members.entry(group.as_str()).or_default().push(i),
+                        None => targets.push(i),
+                    }
+                }
+                let policy = self
+                    .topic_route_policy
+                    .get(topic)
+                    .copied()
+                    .unwrap_or(self.route_policy);
+                for (group, ixs) in members {
+                    let group_addrs: Vec<String> = ixs.iter().map(|&i| addrs[i].clone()).collect();
+                    let slot = format!("{topic}\x1f{group}");
+                    let pick = match key {
+                        Some(key) => keyed_index(&mut self.key_rings, &slot, &group_addrs, key),
+                        None => {
+                            let next = round_robin_next(&mut self.last_send_index, &slot, ixs.len());
+                            if policy == RoutePolicy::LeastLoaded && ixs.len() > 1 {
+                                pick_least_loaded(
+                                    &group_addrs,
+                                    next,
+                                    self.sender.as_ref().unwrap(),
+                                    &self.db,
+                                    &mut self.route_backlog,
+                                )
+                            } else {
+                                next
+                            }
+                        }
+                    };
+                    targets.push(ixs[pick]);
+                }
+                targets.sort_unstable();
+                targets
+            }
+        };
         let sender = self.sender.as_mut().unwrap();
         let mut warm_ok = vec![true; addr_len];
-        if addrs
+        if targets
             .iter()
-            .any(|addr| sender.needs_store_for_send(addr, topic))
+            .any(|&i| sender.needs_store_for_send(&addrs[i], topic, priority))
         {
             let mut db = self.db.lock().unwrap();
-            for (i, addr) in addrs.iter().enumerate() {
-                if sender.needs_store_for_send(addr, topic)
-                    && !sender.ensure_send_route(&mut *db, addr, topic)
+            for &i in &targets {
+                if sender.needs_store_for_send(&addrs[i], topic, priority)
+                    && !sender.ensure_send_route(&mut *db, &addrs[i], topic, priority)
                 {
                     warm_ok[i] = false;
                 }
@@ -736,27 +1302,44 @@ impl ClientRepr {
         }
         let mut ok = true;
         let mut saw_busy = false;
-        for (i, addr) in addrs.iter().enumerate() {
-            if !warm_ok[i] {
+        for &i in &targets {
+            let addr = &addrs[i];
+            let (status, error) = if !warm_ok[i] {
+                (EnqueueResult::Fail, "ensure_send_route failed")
+            } else {
+                match sender.send_to(addr, topic, data, at_least_once_delivery, priority) {
+                    EnqueueResult::Ok => (EnqueueResult::Ok, ""),
+                    EnqueueResult::Busy => {
+                        saw_busy = true;
+                        if self.status_emitter.is_enabled() {
+                            self.status_emitter.emit_event(StatusEvent::SenderBusy {
+                                topic: topic.to_owned(),
+                                addr: addr.to_owned(),
+                                queue_depth: sender.queue_depth(addr),
+                            });
+                        }
+                        (EnqueueResult::Busy, "send queue full")
+                    }
+                    EnqueueResult::Fail => (EnqueueResult::Fail, "send_to failed"),
+                }
+            };
+            if status != EnqueueResult::Ok {
                 ok = false;
-                continue;
             }
-            match sender.send_to(addr, topic, data, at_least_once_delivery) {
-                EnqueueResult::Ok => {}
-                EnqueueResult::Busy => {
-                    ok = false;
-                    saw_busy = true;
-                    self.status_emitter.emit_msg(
-                        LNR_SENDER_BUSY,
-                        topic,
-                        addr,
-                        StatusMsg::SendQueueFull,
-                        &[],
-                    );
-                }
-                EnqueueResult::Fail => {
-                    ok = false;
-                }
+            if let Some(out) = detailed.as_mut() {
+                out.push(PeerSendResult {
+                    addr: addr.clone(),
+                    unique_name: String::new(),
+                    status,
+                    error: error.to_string(),
+                });
+            }
+        }
+        if let Some(out) = detailed {
+            // Names only for the detailed call; the store keeps them in its route cache.
+            let mut db = self.db.lock().unwrap();
+            for row in out.iter_mut() {
+                row.unique_name = db.get_listener_unique_name(topic, &row.addr).unwrap_or_default();
             }
         }
         if ok {
@@ -770,7 +1353,32 @@ impl ClientRepr {
     }
 
     pub fn subscribe(&mut self, topic: &str) -> bool {
+        self.subscribe_with_group(topic, "", false)
+    }
+
+    /// Subscribe so that the catalog row outlives this client: `stop` (or a crash) leaves it
+    /// registered, and senders keep queueing at-least-once messages to its offline queue.
+    /// After a restart, `subscribe_durable` again with the same `unique_name` replaces the row
+    /// of the old address, and senders move the backlog to the new one. `unsubscribe` ends it.
+    pub fn subscribe_durable(&mut self, topic: &str) -> bool {
+        self.subscribe_with_group(topic, "", true)
+    }
+
+    /// Subscribe as a member of consumer group `group`: [`send_all_groups`](Self::send_all_groups)
+    /// delivers each message to one member of every group. Plain `send_all` / `send_to` treat
+    /// the member as an ordinary subscriber. Subscribing again with another group moves it.
+    pub fn subscribe_group(&mut self, topic: &str, group: &str) -> bool {
+        if group.is_empty() {
+            let _lock = self.mtx.lock();
+            let _log = log::client_scope(self.log_name());
+            return client_fail!(self, ErrorCode::InvalidArg, "group name empty");
+        }
+        self.subscribe_with_group(topic, group, false)
+    }
+
+    fn subscribe_with_group(&mut self, topic: &str, group: &str, durable: bool) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if topic == self.source_topic {
             return client_fail!(self, 
                 ErrorCode::SelfTopic,
@@ -783,6 +1391,13 @@ impl ClientRepr {
                 "you can't subscribe on internal channel topic",
             );
         }
+        if !topic_pattern::is_valid(topic) {
+            return client_fail!(self,
+                ErrorCode::InvalidArg,
+                "'#' must be the last segment of a topic pattern",
+            );
+        }
+        let was_subscribed = self.subscriptions.values().any(|t| t == topic);
         if !subscribe_inner(
             topic,
             &self.source_topic,
@@ -793,6 +1408,30 @@ impl ClientRepr {
         ) {
             return client_fail!(self, ErrorCode::Store, "subscribe failed");
         }
+        let res = {
+            let mut db = self.db.lock().unwrap();
+            db.set_topic_group(topic, group)
+                .and_then(|()| if durable { db.unregist_topic_elsewhere(topic) } else { Ok(()) })
+        };
+        if let Err(err) = res {
+            // A failed call leaves no new subscription behind, in the catalog or on the listener.
+            if !was_subscribed {
+                unsubscribe_inner(
+                    topic,
+                    &self.source_topic,
+                    &mut *self.db.lock().unwrap(),
+                    self.is_run,
+                    &mut self.listener,
+                    &mut self.subscriptions,
+                );
+            }
+            return client_fail!(self, ErrorCode::Store, &format!("{}", err));
+        }
+        if durable {
+            self.durable_topics.insert(topic.to_owned());
+        } else {
+            self.durable_topics.remove(topic);
+        }
         mark_related_topic(&mut self.related_topics, topic);
         if self.is_run {
             emit_internal_event(
@@ -813,6 +1452,7 @@ impl ClientRepr {
 
     pub fn unsubscribe(&mut self, topic: &str) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if topic == self.source_topic {
             return client_fail!(self, 
                 ErrorCode::SelfTopic,
@@ -835,6 +1475,7 @@ impl ClientRepr {
         ) {
             return client_fail!(self, ErrorCode::Store, "unsubscribe failed");
         }
+        self.durable_topics.remove(topic);
         if self.is_run {
             emit_internal_event(
                 self.is_run,
@@ -854,6 +1495,8 @@ impl ClientRepr {
 
     pub fn refresh_address_topic(&mut self, topic: &str) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        self.topic_groups.remove(topic);
         match refresh_address_topic_cache(
             &mut self.address_topic,
             &mut *self.db.lock().unwrap(),
@@ -874,6 +1517,7 @@ impl ClientRepr {
 
     pub fn clear_stored_messages(&mut self) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if self.is_run {
             return client_fail!(self, 
                 ErrorCode::ClearWhileRunning,
@@ -888,6 +1532,7 @@ impl ClientRepr {
     }
     pub fn clear_addresses_of_topic(&mut self) -> bool {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         if self.is_run {
             return client_fail!(self, 
                 ErrorCode::ClearWhileRunning,
@@ -904,6 +1549,7 @@ impl ClientRepr {
     /// Topic directory from the store: `(addr, unique_name)` pairs (empty vec is success).
     pub fn list_addresses(&mut self, topic: &str) -> Option<Vec<(String, String)>> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         match self.db.lock().unwrap().get_topic_directory(topic) {
             Ok(rows) => {
                 let addrs: Vec<String> = rows.iter().map(|(a, _)| a.clone()).collect();
@@ -925,6 +1571,7 @@ impl ClientRepr {
     /// Sum of offline queued message blobs for this sender identity.
     pub fn pending_count(&mut self) -> Option<u64> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         let mut db = self.db.lock().unwrap();
         let listeners = match db.get_listeners_of_sender() {
             Ok(l) => l,
@@ -939,17 +1586,8 @@ impl ClientRepr {
                 Ok(n) => n,
                 Err(_) => continue,
             };
-            let Some(ck) = (match db.find_connection_key_for_sender(&name) {
-                Ok(v) => v,
-                Err(err) => {
-                    client_fail!(self, ErrorCode::Store, &format!("{}", err));
-                    return None;
-                }
-            }) else {
-                continue;
-            };
-            match db.count_pending_messages(ck) {
-                Ok(n) => total = total.saturating_add(n as u64),
+            match lane_pending(&mut *db, &name) {
+                Ok(n) => total = total.saturating_add(n as u64),
                 Err(err) => {
                     client_fail!(self, ErrorCode::Store, &format!("{}", err));
                     return None;
@@ -964,6 +1602,7 @@ impl ClientRepr {
     #[cfg(test)]
     pub(crate) fn list_subscriptions(&self) -> Vec<String> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         self.subscriptions
             .values()
             .filter(|t| t.as_str() != INTERNAL_CHANNEL_TOPIC)
@@ -975,12 +1614,14 @@ impl ClientRepr {
     #[cfg(test)]
     pub(crate) fn list_related_topics(&self) -> Vec<String> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         self.related_topics.iter().cloned().collect()
     }
 
     /// Per-peer offline queue depths for this sender (`sender_listener` routes).
     pub fn pending_by_peer(&mut self) -> Option<Vec<(String, String, String, u64)>> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         let mut db = self.db.lock().unwrap();
         let listeners = match db.get_listeners_of_sender() {
             Ok(l) => l,
@@ -995,31 +1636,351 @@ impl ClientRepr {
                 Ok(n) => n,
                 Err(_) => continue,
             };
-            let Some(ck) = (match db.find_connection_key_for_sender(&name) {
-                Ok(v) => v,
+            match lane_pending(&mut *db, &name) {
+                Ok(n) => rows.push((addr, listener_topic, name, n as u64)),
+                Err(err) => {
+                    client_fail!(self, ErrorCode::Store, &format!("{}", err));
+                    return None;
+                }
+            }
+        }
+        client_ok!(self);
+        Some(rows)
+    }
+
+    /// Live state of every outbound route lane and inbound connection, read now. Empty while
+    /// the client is not running. Outbound `unique_name`s come from the store; a route first
+    /// opened by a subscribe notice reports a cached topic of its address (or `""`).
+    pub fn peers(&mut self) -> Vec<PeerInfo> {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let mut rows = match &self.sender {
+            Some(s) => s.peers(),
+            None => Vec::new(),
+        };
+        if !rows.is_empty() {
+            let mut db = self.db.lock().unwrap();
+            let mut names: HashMap<(String, String), String> = HashMap::new();
+            for row in rows.iter_mut() {
+                let key = (row.topic.clone(), row.addr.clone());
+                if !names.contains_key(&key) {
+                    let name = db.get_listener_unique_name(&row.topic, &row.addr).unwrap_or_default();
+                    names.insert(key.clone(), name);
+                }
+                row.unique_name = names[&key].clone();
+                if row.topic == INTERNAL_CHANNEL_TOPIC {
+                    // Opened for a subscribe notice: name an app topic served at the address.
+                    row.topic = self
+                        .address_topic
+                        .iter()
+                        .filter(|(t, addrs)| t.as_str() != INTERNAL_CHANNEL_TOPIC && addrs.contains(&row.addr))
+                        .map(|(t, _)| t)
+                        .min()
+                        .cloned()
+                        .unwrap_or_default();
+                }
+            }
+        }
+        if let Some(l) = &self.listener {
+            rows.extend(l.peers());
+        }
+        client_ok!(self);
+        rows
+    }
+
+    /// Occupancy of every sender slot (one per route lane) and listener connection slot, read
+    /// now. Empty while the client is not running.
+    pub fn mempool_stats(&mut self) -> Vec<MempoolSlotStats> {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let mut rows = self.sender.as_ref().map(|s| s.mempool_stats()).unwrap_or_default();
+        if let Some(l) = &self.listener {
+            rows.extend(l.mempool_stats());
+        }
+        client_ok!(self);
+        rows
+    }
+
+    /// Merge free space and give whole free chunks at the end of each slot's mempool back to
+    /// the allocator now, instead of after `MEMPOOL_FREE_COUNT_FOR_RESIZE` frees. Pools in use
+    /// at the moment are skipped; messages still held keep their chunks. Returns the bytes
+    /// released (`0` while not running).
+    pub fn compact_memory(&mut self) -> u64 {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let released = self.sender.as_ref().map_or(0, |s| s.compact_memory())
+            + self.listener.as_ref().map_or(0, |l| l.compact_memory());
+        client_ok!(self);
+        released
+    }
+
+    /// Readiness / liveness report, checked now: store ping, listener and sender threads,
+    /// catalog registration under [`Client::published_addr`] and failing routes. A stopped
+    /// client still pings the store.
+    pub fn health(&mut self) -> Health {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let mut health = Health {
+            running: self.is_run,
+            listener_alive: self.listener.as_ref().is_some_and(|l| l.is_alive()),
+            sender_alive: self.sender.as_ref().is_some_and(|s| s.is_alive()),
+            published_addr: self.published_addr.clone(),
+            failing_routes: self.sender.as_ref().map(|s| s.down_routes()).unwrap_or_default(),
+            ..Health::default()
+        };
+        let Ok(mut db) = self.db.lock() else {
+            health.store_error = Some("store lock poisoned".to_owned());
+            client_ok!(self);
+            return health;
+        };
+        match db.ping() {
+            Ok(()) => health.store_ok = true,
+            Err(err) => health.store_error = Some(err.to_string()),
+        }
+        if let (true, Some(addr)) = (health.store_ok, &self.published_addr) {
+            match db.get_topic_directory(&self.source_topic) {
+                Ok(dir) => {
+                    health.registered = dir.iter().any(|(a, name)| a == addr && *name == self.unique_name);
+                }
+                Err(err) => print_error!(&format!("health: get_topic_directory {}", err)),
+            }
+        }
+        drop(db);
+        client_ok!(self);
+        health
+    }
+
+    /// Record every frame this client writes or reads to `path` (created or truncated), see
+    /// [`capture`](crate::capture) for the format. Works whether or not the client is running;
+    /// a second call switches to the new file. Read it back with the `liner_capture` tool.
+    pub fn start_capture(&mut self, path: &str) -> bool {
+        if path.is_empty() {
+            return client_fail!(self, ErrorCode::InvalidArg, "capture path empty");
+        }
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if let Err(err) = self.status_emitter.capture().start(path) {
+            return client_fail!(self, ErrorCode::InvalidArg, &format!("capture file {}: {}", path, err));
+        }
+        client_ok!(self);
+        true
+    }
+
+    /// Stop [`Client::start_capture`] and close the file. Returns `false` if no capture was on.
+    pub fn stop_capture(&mut self) -> bool {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let was_on = self.status_emitter.capture().stop();
+        client_ok!(self);
+        was_on
+    }
+
+    /// Traffic counters and latency histograms since the client was created, in total, per
+    /// peer and per topic. Send queue depth and offline backlog are read now; a store error
+    /// leaves the backlog at `0`.
+    pub fn metrics_snapshot(&mut self) -> MetricsSnapshot {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let snapshot = self.collect_metrics();
+        client_ok!(self);
+        snapshot
+    }
+
+    /// Serve [`Client::metrics_snapshot`] in Prometheus text format on `GET http://<addr>/metrics`
+    /// (feature **`prometheus`**). Works whether or not the client is running; a second call
+    /// replaces the endpoint. `addr` may use port `0`, see [`Client::metrics_addr`].
+    #[cfg(feature = "prometheus")]
+    pub fn serve_metrics(&mut self, addr: &str) -> bool {
+        if addr.is_empty() {
+            return client_fail!(self, ErrorCode::InvalidArg, "metrics addr empty");
+        }
+        // Release the old port first so the same `addr` can be bound again. Not under `mtx`:
+        // a scrape in flight waits for it.
+        drop(self.exporter.take());
+        let client_ptr = std::ptr::from_mut(self);
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        let name = self.status_emitter.client_name().clone();
+        match MetricsExporter::new(addr, name, render_metrics, UData(client_ptr as *mut libc::c_void)) {
+            Ok(exporter) => self.exporter = Some(exporter),
+            Err(err) => return client_fail!(self, ErrorCode::Bind, &format!("serve_metrics: {}", err)),
+        }
+        client_ok!(self);
+        true
+    }
+
+    /// Listen address of the metrics endpoint (resolved when `serve_metrics` used port `0`).
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_addr(&self) -> Option<&str> {
+        self.exporter.as_ref().map(|e| e.addr())
+    }
+
+    /// Body of [`Client::metrics_snapshot`]; the caller holds `mtx`.
+    fn collect_metrics(&self) -> MetricsSnapshot {
+        let recorded = self.status_emitter.metrics().snapshot();
+        // Sends are counted per listener address: key them by the listener's unique_name,
+        // like received traffic.
+        let mut names: HashMap<String, String> = HashMap::new();
+        let mut backlog: BTreeMap<String, u64> = BTreeMap::new();
+        {
+            let mut db = self.db.lock().unwrap();
+            match db.get_listeners_of_sender() {
+                Ok(listeners) => {
+                    for (addr, listener_topic) in listeners {
+                        if let Ok(name) = db.get_listener_unique_name(&listener_topic, &addr) {
+                            if !name.is_empty() {
+                                names.insert(addr, name);
+                            }
+                        }
+                    }
+                }
+                Err(err) => print_error!(&format!("metrics_snapshot: {}", err)),
+            }
+            for name in names.values() {
+                if backlog.contains_key(name) {
+                    continue;
+                }
+                match lane_pending(&mut *db, name) {
+                    Ok(n) => {
+                        backlog.insert(name.clone(), n as u64);
+                    }
+                    Err(err) => print_error!(&format!("metrics_snapshot: {} {}", name, err)),
+                }
+            }
+        }
+        let peer_key = |addr: String| names.get(&addr).cloned().unwrap_or(addr);
+        let mut snapshot = MetricsSnapshot {
+            total: recorded.total,
+            peers: BTreeMap::new(),
+            topics: recorded.topics,
+            send_mempool_bytes: self.sender.as_ref().map_or(0, |s| s.mempool_bytes()),
+            receive_mempool_bytes: self.listener.as_ref().map_or(0, |l| l.mempool_bytes()),
+        };
+        for (peer, counters) in recorded.peers {
+            snapshot.peers.entry(peer_key(peer)).or_default().merge(&counters);
+        }
+        let depths = match &self.sender {
+            Some(s) => s.send_queue_depth_by_peer(),
+            None => Vec::new(),
+        };
+        for (addr, n) in depths.into_iter().filter(|(_, n)| *n > 0) {
+            snapshot.peers.entry(peer_key(addr)).or_default().send_queue_depth += n;
+            snapshot.total.send_queue_depth += n;
+        }
+        for (name, n) in backlog.into_iter().filter(|(_, n)| *n > 0) {
+            snapshot.peers.entry(name).or_default().offline_backlog += n;
+            snapshot.total.offline_backlog += n;
+        }
+        snapshot
+    }
+
+    /// Move this sender's offline queue for listener `from_unique_name` (e.g. a decommissioned
+    /// replica) to listener `to_unique_name`. Messages are renumbered for the new connection so
+    /// its dedup accepts them. Returns the number of messages moved.
+    pub fn reassign_pending(&mut self, from_unique_name: &str, to_unique_name: &str) -> Option<u64> {
+        self.reassign_pending_inner(from_unique_name, to_unique_name, None)
+    }
+
+    /// Like [`Client::reassign_pending`], with the target picked from `topic`'s directory
+    /// (first listener other than `from_unique_name`). Messages are retagged to `topic`.
+    pub fn reassign_pending_to_topic(&mut self, from_unique_name: &str, topic: &str) -> Option<u64> {
+        let (to_name, topic_key) = {
+            let _lock = self.mtx.lock();
+            let _log = log::client_scope(self.log_name());
+            let mut db = self.db.lock().unwrap();
+            let dir = match db.get_topic_directory(topic) {
+                Ok(d) => d,
                 Err(err) => {
                     client_fail!(self, ErrorCode::Store, &format!("{}", err));
                     return None;
                 }
-            }) else {
-                continue;
             };
-            match db.count_pending_messages(ck) {
-                Ok(n) => rows.push((addr, listener_topic, name, n as u64)),
+            let Some((_, name)) = dir
+                .into_iter()
+                .find(|(_, name)| !name.is_empty() && name != from_unique_name)
+            else {
+                client_fail!(self,
+                    ErrorCode::NoAddr,
+                    &format!("no listener to reassign to on topic {}", topic),
+                );
+                return None;
+            };
+            match db.get_topic_key(topic) {
+                Ok(tk) => (name, tk),
+                Err(err) => {
+                    client_fail!(self, ErrorCode::Store, &format!("{}", err));
+                    return None;
+                }
+            }
+        };
+        self.reassign_pending_inner(from_unique_name, &to_name, Some(topic_key))
+    }
+
+    fn reassign_pending_inner(
+        &mut self,
+        from_unique_name: &str,
+        to_unique_name: &str,
+        listener_topic_key: Option<i32>,
+    ) -> Option<u64> {
+        let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
+        if from_unique_name.is_empty() || to_unique_name.is_empty() {
+            client_fail!(self, ErrorCode::InvalidArg, "unique_name empty");
+            return None;
+        }
+        if from_unique_name == to_unique_name {
+            client_fail!(self, ErrorCode::InvalidArg, "reassign source and target are the same listener");
+            return None;
+        }
+        let mut db = self.db.lock().unwrap();
+        let mut total = 0usize;
+        // Each priority lane keeps its own queue: move it to the same lane of the target.
+        for priority in std::iter::once(Priority::Normal).chain(Priority::RAISED) {
+            let from_ck = match db.find_connection_key_for_sender(&priority.lane_listener_name(from_unique_name)) {
+                Ok(Some(ck)) => ck,
+                Ok(None) => continue,
+                Err(err) => {
+                    client_fail!(self, ErrorCode::Store, &format!("{}", err));
+                    return None;
+                }
+            };
+            let to_ck = match db.get_connection_key_for_sender(&priority.lane_listener_name(to_unique_name)) {
+                Ok(ck) => ck,
                 Err(err) => {
                     client_fail!(self, ErrorCode::Store, &format!("{}", err));
                     return None;
                 }
+            };
+            // A warm route may have numbered past what the store knows for the target.
+            let min_number = self
+                .sender
+                .as_ref()
+                .and_then(|s| s.last_mess_number_for_key(to_ck))
+                .unwrap_or(0);
+            let (moved, last_number) =
+                match db.reassign_pending_messages(from_ck, to_ck, listener_topic_key, min_number) {
+                    Ok(r) => r,
+                    Err(err) => {
+                        client_fail!(self, ErrorCode::Store, &format!("{}", err));
+                        return None;
+                    }
+                };
+            if moved > 0 {
+                if let Some(sender) = self.sender.as_mut() {
+                    sender.adopt_offline(&mut *db, to_ck, last_number);
+                }
             }
+            total += moved;
         }
         client_ok!(self);
-        Some(rows)
+        Some(total as u64)
     }
 
     /// Total in-memory sender queue depth (not store/offline). `0` if not running.
     #[cfg(test)]
     pub(crate) fn send_queue_depth(&self) -> u64 {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         match &self.sender {
             Some(s) => s.send_queue_depth(),
             None => 0,
@@ -1030,6 +1991,7 @@ impl ClientRepr {
     #[cfg(test)]
     pub(crate) fn send_queue_depth_by_peer(&self) -> Vec<(String, u64)> {
         let _lock = self.mtx.lock();
+        let _log = log::client_scope(self.log_name());
         match &self.sender {
             Some(s) => s.send_queue_depth_by_peer(),
             None => Vec::new(),
@@ -1110,7 +2072,11 @@ fn subscribe_inner(
     match db.get_topic_key(topic) {
         Ok(topic_key) => {
             if is_run {
-                listener.as_mut().unwrap().subscribe(topic, topic_key);
+                let listener = listener.as_mut().unwrap();
+                if topic != INTERNAL_CHANNEL_TOPIC {
+                    deliver_retained(topic, db, listener);
+                }
+                listener.subscribe(topic, topic_key);
             }
             subscriptions.insert(topic_key, topic.to_owned());
         }
@@ -1122,6 +2088,15 @@ fn subscribe_inner(
     true
 }
 
+/// Queue the retained values of `topic` (of every matching topic for a pattern) on the
+/// listener. A store error only loses the retained values, not the subscription.
+fn deliver_retained(topic: &str, db: &mut dyn Store, listener: &Listener) {
+    match db.load_retained(topic) {
+        Ok(rows) => listener.deliver_retained(rows),
+        Err(err) => print_error!(&format!("load_retained {}: {}", topic, err)),
+    }
+}
+
 fn unsubscribe_inner(
     topic: &str,
     source_topic: &str,
@@ -1207,7 +2182,7 @@ fn emit_internal_event(
                     continue;
                 }
             }
-            if !sender.ensure_send_route(&mut *db, &addr, INTERNAL_CHANNEL_TOPIC) {
+            if !sender.ensure_send_route(&mut *db, &addr, INTERNAL_CHANNEL_TOPIC, Priority::Normal) {
                 continue;
             }
         }
@@ -1215,13 +2190,17 @@ fn emit_internal_event(
         // and peers must clear sender_listener from "unsubscribed". Connect/disconnect
         // stay best-effort to avoid filling the offline queue on teardown races.
         let durable = matches!(event, "subscribed" | "unsubscribed");
-        let _ = sender.send_to(&addr, INTERNAL_CHANNEL_TOPIC, &bytes, durable);
+        let _ = sender.send_to(&addr, INTERNAL_CHANNEL_TOPIC, &bytes, durable, Priority::Normal);
     }
 }
 
 extern "C" fn client_receive_wrapper(
     to: *const i8,
     from: *const i8,
+    sender_name: *const i8,
+    number: u64,
+    sent_at_ms: u64,
+    replayed: i32,
     data: *const u8,
     dsize: usize,
     udata: *mut libc::c_void,
@@ -1240,8 +2219,14 @@ extern "C" fn client_receive_wrapper(
                 return;
             }
         }
-        if let Some(user_cb) = (*client).user_receive_cb {
-            user_cb(to, from, data, dsize, (*client).user_receive_udata.0);
+        match (*client).user_receive_cb {
+            Some(UserReceiveCb::Plain(user_cb)) => {
+                user_cb(to, from, data, dsize, (*client).user_receive_udata.0);
+            }
+            Some(UserReceiveCb::Ex(user_cb)) => {
+                user_cb(to, from, sender_name, number, sent_at_ms, replayed, data, dsize, (*client).user_receive_udata.0);
+            }
+            None => {}
         }
     }));
     if result.is_err() {
@@ -1266,11 +2251,29 @@ fn apply_internal_channel_event(client: &mut ClientRepr, data: &[u8]) {
         .unwrap_or("");
     let related = is_related_topic(topic, &client.related_topics);
     if !topic.is_empty() && topic != INTERNAL_CHANNEL_TOPIC {
+        client.topic_groups.remove(topic);
         let _ = refresh_address_topic_cache(
             &mut client.address_topic,
             &mut *client.db.lock().unwrap(),
             topic,
         );
+        if topic_pattern::is_pattern(topic) {
+            // A pattern (un)subscribe changes the routes of every cached topic it matches.
+            let matched: Vec<String> = client
+                .address_topic
+                .keys()
+                .filter(|t| *t != INTERNAL_CHANNEL_TOPIC && topic_pattern::matches(topic, t))
+                .cloned()
+                .collect();
+            for t in matched {
+                client.topic_groups.remove(&t);
+                let _ = refresh_address_topic_cache(
+                    &mut client.address_topic,
+                    &mut *client.db.lock().unwrap(),
+                    &t,
+                );
+            }
+        }
         if event == "unsubscribed" {
             let _ = client
                 .db
@@ -1278,6 +2281,9 @@ fn apply_internal_channel_event(client: &mut ClientRepr, data: &[u8]) {
                 .unwrap()
                 .remove_sender_listeners_on_topic(topic);
         }
+        if event == "subscribed" && !peer.is_empty() {
+            resume_returning_peer(client, topic, peer);
+        }
     }
     if matches!(event, "client_connected" | "client_disconnected") {
         let _ = refresh_address_topic_cache(
@@ -1287,25 +2293,160 @@ fn apply_internal_channel_event(client: &mut ClientRepr, data: &[u8]) {
         );
     }
     if related {
-        let kind = match event {
-            "client_connected" => Some(LNR_PEER_CONNECTED),
-            "client_disconnected" => Some(LNR_PEER_DISCONNECTED),
-            "subscribed" => Some(LNR_PEER_SUBSCRIBED),
-            "unsubscribed" => Some(LNR_PEER_UNSUBSCRIBED),
+        let (topic, unique_name) = (topic.to_owned(), peer.to_owned());
+        let status = match event {
+            "client_connected" => Some(StatusEvent::PeerConnected { topic, unique_name }),
+            "client_disconnected" => Some(StatusEvent::PeerDisconnected { topic, unique_name }),
+            "subscribed" => Some(StatusEvent::PeerSubscribed { topic, unique_name }),
+            "unsubscribed" => Some(StatusEvent::PeerUnsubscribed { topic, unique_name }),
             _ => None,
         };
-        if let Some(kind) = kind {
-            client
-                .status_emitter
-                .emit(kind, topic, peer, event);
+        if let Some(status) = status {
+            client.status_emitter.emit_event(status);
+        }
+    }
+}
+
+/// A subscriber that comes back under the same `unique_name` at a new address (durable
+/// subscription, restart on another port) takes over what this sender still holds for the
+/// old address: unacked messages of the matching topics move to the new route, and its
+/// connect loads the rest of the shared offline queue.
+fn resume_returning_peer(client: &mut ClientRepr, topic: &str, peer: &str) {
+    let Some(sender) = client.sender.as_mut() else {
+        return;
+    };
+    let mut db = client.db.lock().unwrap();
+    let connection_key = match db.find_connection_key_for_sender(peer) {
+        Ok(Some(ck)) => ck,
+        _ => return,
+    };
+    let old_addrs = sender.route_addrs_for_key(connection_key);
+    if old_addrs.is_empty() {
+        return;
+    }
+    let Ok(rows) = db.get_topic_directory(topic) else {
+        return;
+    };
+    let Some(new_addr) = rows.into_iter().find(|(_, n)| n == peer).map(|(a, _)| a) else {
+        return;
+    };
+    if old_addrs.contains(&new_addr) {
+        // Same address: the existing route reconnects and drains the queue itself.
+        return;
+    }
+    let backlog = lane_pending(&mut *db, peer).unwrap_or(0) > 0;
+    let topics: Vec<String> = sender
+        .sent_topics()
+        .filter(|t| *t != INTERNAL_CHANNEL_TOPIC && topic_pattern::matches(topic, t))
+        .cloned()
+        .collect();
+    for t in topics {
+        let Some(topic_key) = sender.topic_key(&t) else {
+            continue;
+        };
+        let mut moved: Vec<(Vec<u8>, bool, Priority, &str)> = Vec::new();
+        for old in &old_addrs {
+            for (payload, alo, priority) in sender.take_unacked(&mut *db, old, topic_key) {
+                moved.push((payload, alo, priority, old));
+            }
+        }
+        if moved.is_empty() && !backlog {
+            continue;
+        }
+        let warm = !sender.needs_store_for_send(&new_addr, &t, Priority::Normal)
+            || sender.ensure_send_route(&mut *db, &new_addr, &t, Priority::Normal);
+        for (payload, at_least_once_delivery, priority, old) in moved {
+            let lane_warm = warm
+                && (!sender.needs_store_for_send(&new_addr, &t, priority)
+                    || sender.ensure_send_route(&mut *db, &new_addr, &t, priority));
+            if !(lane_warm
+                && sender.resend_to(&new_addr, &t, &payload, at_least_once_delivery, priority) == EnqueueResult::Ok)
+                && sender.resend_to(old, &t, &payload, at_least_once_delivery, priority) != EnqueueResult::Ok
+            {
+                print_error!(&format!("resume: message for {} dropped on requeue", old));
+            }
+        }
+    }
+}
+
+/// Scheduler tick under `mtx`: fail over routes lost since the last tick or send, then
+/// release due scheduled messages.
+fn scheduler_tick(udata: &UData) {
+    let client = udata.0 as *mut ClientRepr;
+    if client.is_null() {
+        return;
+    }
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        let Ok(_lock) = (*client).mtx.lock() else {
+            return;
+        };
+        let _log = log::client_scope((*client).log_name());
+        let client = &mut *client;
+        if !client.is_run {
+            return;
+        }
+        apply_failover(
+            &client.topic_failover,
+            &client.address_topic,
+            &client.db,
+            client.sender.as_mut(),
+            &client.status_emitter,
+        );
+        release_due_scheduled(client);
+    }));
+    if result.is_err() {
+        print_error!("scheduler_tick panicked");
+    }
+}
+
+/// Hand due scheduled messages to the normal send path, each inside the trace context it was
+/// scheduled under. Failures are logged (and at-least-once messages retried) but never become
+/// the caller-visible `last_error`.
+fn release_due_scheduled(client: &mut ClientRepr) {
+    let now = common::current_time_ms();
+    let due = match client
+        .db
+        .lock()
+        .unwrap()
+        .load_due_scheduled(now, settings::SCHEDULED_RELEASE_BATCH)
+    {
+        Ok(due) => due,
+        Err(err) => {
+            print_error!(&format!("scheduler: {}", err));
+            return;
+        }
+    };
+    if due.is_empty() {
+        return;
+    }
+    let last_error = client.last_error;
+    let last_error_msg = std::mem::take(&mut client.last_error_msg);
+    let c_last_error_msg = client.c_last_error_msg.take();
+    for m in due {
+        let _trace = m.trace.map(trace::scope);
+        let sent =
+            client.send_to_route_locked(&m.topic, SendRoute::Policy, &m.payload, SendOpts::new(m.at_least_once));
+        let mut db = client.db.lock().unwrap();
+        let res = if sent || !m.at_least_once {
+            db.remove_scheduled(m.id)
+        } else {
+            db.reschedule_message(m.id, now.saturating_add(settings::SCHEDULED_RETRY_MS))
+        };
+        if let Err(err) = res {
+            print_error!(&format!("scheduler: {}", err));
         }
     }
+    client.last_error = last_error;
+    client.last_error_msg = last_error_msg;
+    client.c_last_error_msg = c_last_error_msg;
 }
 
 fn is_related_topic(topic: &str, related_topics: &HashSet<String>) -> bool {
     !topic.is_empty()
         && topic != INTERNAL_CHANNEL_TOPIC
-        && related_topics.contains(topic)
+        && (related_topics.contains(topic)
+            || (topic_pattern::is_pattern(topic)
+                && related_topics.iter().any(|t| topic_pattern::matches(topic, t))))
 }
 
 /// Mark topic as related for status filtering. No alloc when already present.
@@ -1369,13 +2510,122 @@ fn apply_failed_routes(
     }
 }
 
+/// [`exporter::RenderFn`] of `serve_metrics`: `udata` is the client, kept alive by its Drop
+/// joining the exporter thread.
+#[cfg(feature = "prometheus")]
+fn render_metrics(udata: &UData) -> Option<String> {
+    let client = udata.0 as *const ClientRepr;
+    if client.is_null() {
+        return None;
+    }
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe {
+        let _lock = (*client).mtx.lock().ok()?;
+        let client = &*client;
+        Some(exporter::render(&client.unique_name, &client.collect_metrics()))
+    }));
+    result.unwrap_or_else(|_| {
+        print_error!("render_metrics panicked");
+        None
+    })
+}
+
+/// Re-route unacked messages of lost routes for failover-enabled topics to the live replica
+/// with the shortest in-memory queue. Messages that cannot be re-routed go back to the lost
+/// address (in memory, persisted on the next reconnect attempt). A lost address whose topic
+/// has no live replica yet stays pending for the next scheduler tick or send.
+fn apply_failover(
+    topic_failover: &HashSet<String>,
+    address_topic: &HashMap<String, Vec<String>>,
+    db: &Arc<Mutex<dyn Store>>,
+    sender: Option<&mut Sender>,
+    status_emitter: &StatusEmitter,
+) {
+    let Some(sender) = sender else {
+        return;
+    };
+    // Without failover topics keep the losses: a topic enabled later still moves them.
+    if topic_failover.is_empty() {
+        return;
+    }
+    let lost = sender.drain_lost_addrs();
+    if lost.is_empty() {
+        return;
+    }
+    let mut retry = Vec::new();
+    for addr in &lost {
+        for topic in topic_failover {
+            let Some(topic_key) = sender.topic_key(topic) else {
+                continue;
+            };
+            let candidates = match address_topic.get(topic) {
+                Some(a) if !a.is_empty() => a.clone(),
+                _ => match get_address_topic(topic, &mut *db.lock().unwrap(), true) {
+                    Ok(Some(a)) => a,
+                    _ => continue,
+                },
+            };
+            let Some(target) = candidates
+                .iter()
+                .filter(|a| *a != addr && !sender.is_route_down(a))
+                .min_by_key(|a| sender.queue_depth(a))
+                .cloned()
+            else {
+                // No live replica yet: try again on a later send.
+                retry.push(addr.clone());
+                continue;
+            };
+            let moved = sender.take_unacked(&mut *db.lock().unwrap(), addr, topic_key);
+            if moved.is_empty() {
+                continue;
+            }
+            let mut rerouted = 0usize;
+            for (payload, at_least_once_delivery, priority) in moved {
+                let warm = !sender.needs_store_for_send(&target, topic, priority)
+                    || sender.ensure_send_route(&mut *db.lock().unwrap(), &target, topic, priority);
+                if warm
+                    && sender.resend_to(&target, topic, &payload, at_least_once_delivery, priority)
+                        == EnqueueResult::Ok
+                {
+                    rerouted += 1;
+                } else if sender.resend_to(addr, topic, &payload, at_least_once_delivery, priority)
+                    != EnqueueResult::Ok
+                {
+                    print_error!(&format!("failover: message for {} dropped on requeue", addr));
+                }
+            }
+            if rerouted > 0 && status_emitter.is_enabled() {
+                status_emitter.emit_event(StatusEvent::Failover {
+                    topic: topic.clone(),
+                    from_addr: addr.clone(),
+                    to_addr: target.clone(),
+                    rerouted,
+                });
+            }
+        }
+    }
+    sender.requeue_lost_addrs(retry);
+}
+
 fn get_address_topic(
     topic: &str,
     db: &mut dyn Store,
     without_cache: bool,
 ) -> Result<Option<Vec<String>>, String> {
     match db.get_addresses_of_topic(without_cache, topic) {
-        Ok(addresses) => {
+        Ok(mut addresses) => {
+            // Pattern subscribers (`orders.#`) of a concrete topic receive it too.
+            if topic != INTERNAL_CHANNEL_TOPIC && !topic_pattern::is_pattern(topic) {
+                match db.get_pattern_subscribers(topic) {
+                    Ok(rows) => {
+                        for (addr, _) in rows {
+                            if !addresses.contains(&addr) {
+                                addresses.push(addr);
+                            }
+                        }
+                    }
+                    Err(err) => return Err(format!("{}", err)),
+                }
+            }
             if addresses.is_empty() {
                 Ok(None)
             } else {
@@ -1386,40 +2636,165 @@ fn get_address_topic(
     }
 }
 
-enum ResolveAddrs<'a> {
-    Ok(&'a [String]),
-    NoAddr,
-    Store(String),
+/// Which subscribers of a topic a broadcast reaches.
+#[derive(Clone, Copy)]
+enum Fanout<'k> {
+    /// Every address (`send_all`).
+    All,
+    /// Every ungrouped address plus one member per consumer group, chosen by the route
+    /// policy or, with a key, by consistent hashing inside the group.
+    Groups(Option<&'k str>),
 }
 
-/// Prefer the in-memory route cache (kept fresh by the internal channel / `refresh_address_topic`).
-/// On miss, resolve from the store catalog and populate the cache. With `at_least_once_delivery`,
-/// fall back to `sender_listener` so offline queueing still works after the listener drops from
-/// the catalog on `Drop` (distinct from `unsubscribe`, which clears routes via refresh/internal events).
-fn resolve_send_addresses<'a>(
-    topic: &str,
+/// Per-message delivery options shared by the `send_to*` / `send_all*` paths.
+#[derive(Clone, Copy)]
+struct SendOpts {
     at_least_once_delivery: bool,
-    address_topic: &'a mut HashMap<String, Vec<String>>,
-    db: &mut dyn Store,
-) -> ResolveAddrs<'a> {
-    if address_topic.get(topic).is_some_and(|a| !a.is_empty()) {
-        return ResolveAddrs::Ok(address_topic[topic].as_slice());
+    priority: Priority,
+}
+
+impl SendOpts {
+    fn new(at_least_once_delivery: bool) -> SendOpts {
+        SendOpts { at_least_once_delivery, priority: Priority::Normal }
     }
-    address_topic.remove(topic);
-    let addrs = match get_address_topic(topic, db, true) {
-        Ok(Some(addr)) => addr,
-        Ok(None) => {
-            if at_least_once_delivery {
-                let listeners = match db.get_listeners_of_sender() {
-                    Ok(l) => l,
-                    Err(err) => return ResolveAddrs::Store(format!("{}", err)),
-                };
-                let mut from_listeners = Vec::new();
-                for (addr, listener_topic) in listeners {
-                    if listener_topic == topic {
-                        from_listeners.push(addr);
-                    }
-                }
+}
+
+/// Offline queue length of every lane toward listener `name`; `Ok(0)` without a route.
+fn lane_pending(db: &mut dyn Store, name: &str) -> crate::store::store::DbResult<usize> {
+    let mut total = 0;
+    for priority in std::iter::once(Priority::Normal).chain(Priority::RAISED) {
+        if let Some(ck) = db.find_connection_key_for_sender(&priority.lane_listener_name(name))? {
+            total += db.count_pending_messages(ck)?;
+        }
+    }
+    Ok(total)
+}
+
+/// Replica choice for a single-peer send on a multi-address topic.
+#[derive(Clone, Copy)]
+enum SendRoute<'k> {
+    /// Per-topic / client [`RoutePolicy`] (round-robin cursor in `last_send_index`).
+    Policy,
+    /// Consistent-hash the routing key onto the address list (`key_rings`).
+    Keyed(&'k str),
+    /// Exact replica by `unique_name`, resolved through the store topic directory.
+    Peer(&'k str),
+}
+
+/// Next round-robin index over `len` replicas for cursor `slot` (a topic, or topic + group).
+fn round_robin_next(last_send_index: &mut HashMap<String, usize>, slot: &str, len: usize) -> usize {
+    if let Some(cur) = last_send_index.get_mut(slot) {
+        let i = *cur % len;
+        *cur = (i + 1) % len;
+        i
+    } else {
+        last_send_index.insert(slot.to_owned(), if len > 1 { 1 } else { 0 });
+        0
+    }
+}
+
+/// Index of `key` on the consistent-hash ring of `addrs` cached under `slot`; the ring is
+/// rebuilt when the address list changes.
+fn keyed_index(key_rings: &mut HashMap<String, HashRing>, slot: &str, addrs: &[String], key: &str) -> usize {
+    let ring = match key_rings.get_mut(slot) {
+        Some(ring) => ring,
+        None => key_rings
+            .entry(slot.to_owned())
+            .or_insert_with(|| HashRing::new(addrs)),
+    };
+    if !ring.matches(addrs) {
+        *ring = HashRing::new(addrs);
+    }
+    ring.get(key.as_bytes()).unwrap_or(0)
+}
+
+/// Index of the replica with the smallest in-memory queue + offline backlog, scanning from
+/// the round-robin cursor `start` so equal loads still rotate. Replicas whose stream is down
+/// are skipped unless every replica is down (then the send is queued like round-robin).
+/// Offline backlog is read from the store at most every `ROUTE_BACKLOG_REFRESH_MS` per address.
+fn pick_least_loaded(
+    addrs: &[String],
+    start: usize,
+    sender: &Sender,
+    db: &Arc<Mutex<dyn Store>>,
+    route_backlog: &mut HashMap<String, (u64, u64)>,
+) -> usize {
+    let ctime = common::current_time_ms();
+    let stale = |addr: &String, backlog: &HashMap<String, (u64, u64)>| {
+        sender.route_connection_key(addr).is_some()
+            && backlog
+                .get(addr)
+                .is_none_or(|(at, _)| ctime.saturating_sub(*at) > settings::ROUTE_BACKLOG_REFRESH_MS)
+    };
+    if addrs.iter().any(|a| stale(a, route_backlog)) {
+        if let Ok(mut db) = db.lock() {
+            for addr in addrs {
+                if !stale(addr, route_backlog) {
+                    continue;
+                }
+                let ck = sender.route_connection_key(addr).unwrap();
+                let n = match db.count_pending_messages(ck) {
+                    Ok(n) => n as u64,
+                    Err(err) => {
+                        print_error!(&format!("count_pending_messages {}: {}", addr, err));
+                        0
+                    }
+                };
+                route_backlog.insert(addr.clone(), (ctime, n));
+            }
+        }
+    }
+    let all_down = addrs.iter().all(|a| sender.is_route_down(a));
+    let mut best: Option<(usize, u64)> = None;
+    for k in 0..addrs.len() {
+        let i = (start + k) % addrs.len();
+        let addr = &addrs[i];
+        if !all_down && sender.is_route_down(addr) {
+            continue;
+        }
+        let backlog = route_backlog.get(addr).map(|(_, n)| *n).unwrap_or(0);
+        let load = sender.queue_depth(addr).saturating_add(backlog);
+        if best.is_none_or(|(_, b)| load < b) {
+            best = Some((i, load));
+        }
+    }
+    best.map(|(i, _)| i).unwrap_or(start)
+}
+
+enum ResolveAddrs<'a> {
+    Ok(&'a [String]),
+    NoAddr,
+    Store(String),
+}
+
+/// Prefer the in-memory route cache (kept fresh by the internal channel / `refresh_address_topic`).
+/// On miss, resolve from the store catalog and populate the cache. With `at_least_once_delivery`,
+/// fall back to `sender_listener` so offline queueing still works after the listener drops from
+/// the catalog on `Drop` (distinct from `unsubscribe`, which clears routes via refresh/internal events).
+fn resolve_send_addresses<'a>(
+    topic: &str,
+    at_least_once_delivery: bool,
+    address_topic: &'a mut HashMap<String, Vec<String>>,
+    db: &mut dyn Store,
+) -> ResolveAddrs<'a> {
+    if address_topic.get(topic).is_some_and(|a| !a.is_empty()) {
+        return ResolveAddrs::Ok(address_topic[topic].as_slice());
+    }
+    address_topic.remove(topic);
+    let addrs = match get_address_topic(topic, db, true) {
+        Ok(Some(addr)) => addr,
+        Ok(None) => {
+            if at_least_once_delivery {
+                let listeners = match db.get_listeners_of_sender() {
+                    Ok(l) => l,
+                    Err(err) => return ResolveAddrs::Store(format!("{}", err)),
+                };
+                let mut from_listeners = Vec::new();
+                for (addr, listener_topic) in listeners {
+                    if listener_topic == topic {
+                        from_listeners.push(addr);
+                    }
+                }
                 if from_listeners.is_empty() {
                     return ResolveAddrs::NoAddr;
                 }
@@ -1469,14 +2844,19 @@ fn str_to_socket_addr(localhost: &str) -> Option<SocketAddr> {
 
 impl Drop for ClientRepr {
     fn drop(&mut self) {
+        // The exporter thread locks `mtx`: join it before any field goes away.
+        #[cfg(feature = "prometheus")]
+        drop(self.exporter.take());
         let _ = self.stop();
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
     use crate::UData;
+    use crate::mempool::Mempool;
+    use crate::message::Message;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::sync::Mutex;
     use std::time::Duration;
@@ -1484,7 +2864,7 @@ mod tests {
     /// Serializes tests that start listener/sender threads against a shared Redis/Postgres.
     static CLIENT_RUN_TEST_LOCK: Mutex<()> = Mutex::new(());
 
-    fn client_run_test_lock() -> std::sync::MutexGuard<'static, ()> {
+    pub(crate) fn client_run_test_lock() -> std::sync::MutexGuard<'static, ()> {
         CLIENT_RUN_TEST_LOCK
             .lock()
             .unwrap_or_else(|poisoned| poisoned.into_inner())
@@ -2007,6 +3387,10 @@ mod tests {
         client_receive_wrapper(
             internal_to.as_ptr(),
             from.as_ptr(),
+            from.as_ptr(),
+            1,
+            0,
+            0,
             internal_data.as_ptr(),
             internal_data.len(),
             client_ptr,
@@ -2023,6 +3407,10 @@ mod tests {
         client_receive_wrapper(
             app_to.as_ptr(),
             from.as_ptr(),
+            from.as_ptr(),
+            1,
+            0,
+            0,
             app_data.as_ptr(),
             app_data.len(),
             client_ptr,
@@ -2572,6 +3960,462 @@ mod tests {
         let _ = std::fs::remove_dir_all(&dir);
     }
 
+    #[test]
+    fn send_to_keyed_sticks_to_one_replica() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_keyed_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("keyed_r_{pid}");
+        let topic_s = format!("keyed_s_{pid}");
+
+        let mut r1 = Client::new_sqlite(&format!("keyed_r1_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r1");
+        let mut r2 = Client::new_sqlite(&format!("keyed_r2_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r2");
+        assert!(r1.run(recv_noop, UData::null()));
+        assert!(r2.run(recv_noop, UData::null()));
+        let addrs = vec![
+            r1.bound_listen_addr().unwrap().to_string(),
+            r2.bound_listen_addr().unwrap().to_string(),
+        ];
+
+        let mut s = Client::new_sqlite(&format!("keyed_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.refresh_address_topic(&topic_r));
+        // Drop listeners so enqueued messages stay in memory and can be counted per peer.
+        drop(r1);
+        drop(r2);
+        s.address_topic.insert(topic_r.clone(), addrs.clone());
+
+        assert!(!s.send_to_keyed(&topic_r, "", b"x", false));
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+
+        for _ in 0..3 {
+            assert!(s.send_to_keyed(&topic_r, "account-42", b"x", false));
+        }
+        let depths: Vec<u64> = addrs
+            .iter()
+            .map(|addr| {
+                s.send_queue_depth_by_peer()
+                    .into_iter()
+                    .find(|(a, _)| a == addr)
+                    .map(|(_, n)| n)
+                    .unwrap_or(0)
+            })
+            .collect();
+        assert!(
+            depths.contains(&3) && depths.contains(&0),
+            "same key must go to one replica, got {depths:?}"
+        );
+
+        drop(s);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn send_to_peer_reaches_only_named_replica() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_peer_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("peer_r_{pid}");
+        let topic_s = format!("peer_s_{pid}");
+        let r2_name = format!("peer_r2_{pid}");
+
+        let mut r1 = Client::new_sqlite(&format!("peer_r1_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r1");
+        let mut r2 = Client::new_sqlite(&r2_name, &topic_r, "127.0.0.1:0", db, "").expect("r2");
+        let got1 = Box::new(std::sync::atomic::AtomicUsize::new(0));
+        let got2 = Box::new(std::sync::atomic::AtomicUsize::new(0));
+        let got1_ptr = &*got1 as *const std::sync::atomic::AtomicUsize as *mut libc::c_void;
+        let got2_ptr = &*got2 as *const std::sync::atomic::AtomicUsize as *mut libc::c_void;
+        assert!(r1.run(recv_count, UData(got1_ptr)));
+        assert!(r2.run(recv_count, UData(got2_ptr)));
+
+        let mut s = Client::new_sqlite(&format!("peer_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+
+        assert!(!s.send_to_peer(&topic_r, "", b"x", false));
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+        assert!(!s.send_to_peer(&topic_r, "nobody", b"x", false));
+        assert_eq!(s.last_error(), ErrorCode::NoAddr);
+
+        for _ in 0..4 {
+            assert!(s.send_to_peer(&topic_r, &r2_name, b"flush", true));
+        }
+        let start = std::time::Instant::now();
+        while got2.load(Ordering::SeqCst) < 4 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(got2.load(Ordering::SeqCst), 4);
+        assert_eq!(got1.load(Ordering::SeqCst), 0);
+
+        drop(s);
+        drop(r1);
+        drop(r2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn send_all_detailed_reports_each_peer() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_detailed_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("det_r_{pid}");
+        let topic_s = format!("det_s_{pid}");
+        let names = [format!("det_r1_{pid}"), format!("det_r2_{pid}")];
+
+        let mut r1 = Client::new_sqlite(&names[0], &topic_r, "127.0.0.1:0", db, "").expect("r1");
+        let mut r2 = Client::new_sqlite(&names[1], &topic_r, "127.0.0.1:0", db, "").expect("r2");
+        assert!(r1.run(recv_noop, UData::null()));
+        assert!(r2.run(recv_noop, UData::null()));
+
+        let mut s = Client::new_sqlite(&format!("det_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.send_all_detailed(&topic_r, b"x", false).is_none());
+        assert_eq!(s.last_error(), ErrorCode::NotRunning);
+        assert!(s.run(recv_noop, UData::null()));
+
+        let rows = s.send_all_detailed(&topic_r, b"x", false).expect("rows");
+        assert_eq!(s.last_error(), ErrorCode::Ok);
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|r| r.status == EnqueueResult::Ok && r.error.is_empty()));
+        let mut got: Vec<String> = rows.iter().map(|r| r.unique_name.clone()).collect();
+        got.sort();
+        assert_eq!(got, names.to_vec());
+        let addr1 = r1.bound_listen_addr().unwrap().to_string();
+        assert_eq!(
+            rows.iter().find(|r| r.unique_name == names[0]).unwrap().addr,
+            addr1
+        );
+
+        drop(s);
+        drop(r1);
+        drop(r2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn least_loaded_policy_prefers_shorter_queue() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_least_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("least_r_{pid}");
+        let topic_s = format!("least_s_{pid}");
+
+        let mut r1 = Client::new_sqlite(&format!("least_r1_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r1");
+        let mut r2 = Client::new_sqlite(&format!("least_r2_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r2");
+        assert!(r1.run(recv_noop, UData::null()));
+        assert!(r2.run(recv_noop, UData::null()));
+        let addrs = vec![
+            r1.bound_listen_addr().unwrap().to_string(),
+            r2.bound_listen_addr().unwrap().to_string(),
+        ];
+
+        let mut s = Client::new_sqlite(&format!("least_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.refresh_address_topic(&topic_r));
+        drop(r1);
+        drop(r2);
+        s.address_topic.insert(topic_r.clone(), addrs.clone());
+
+        // Warm both routes (one message each) and let the connect attempts fail.
+        assert!(s.send_to(&topic_r, b"w", false));
+        assert!(s.send_to(&topic_r, b"w", false));
+        std::thread::sleep(Duration::from_millis(300));
+        s.address_topic.insert(topic_r.clone(), addrs.clone());
+
+        for _ in 0..3 {
+            assert!(s.send_to_keyed(&topic_r, "hot", b"x", false));
+        }
+        let depth = |s: &Client, addr: &str| {
+            s.send_queue_depth_by_peer()
+                .into_iter()
+                .find(|(a, _)| a == addr)
+                .map(|(_, n)| n)
+                .unwrap_or(0)
+        };
+        let hot = addrs
+            .iter()
+            .position(|a| depth(&s, a) >= 3)
+            .expect("keyed sends on one replica");
+        let cold = &addrs[1 - hot];
+        let cold_before = depth(&s, cold);
+
+        s.set_topic_route_policy(&topic_r, Some(RoutePolicy::LeastLoaded));
+        assert_eq!(s.route_policy(&topic_r), RoutePolicy::LeastLoaded);
+        for _ in 0..3 {
+            assert!(s.send_to(&topic_r, b"y", false));
+        }
+        assert_eq!(depth(&s, cold), cold_before + 3, "least-loaded must avoid the hot replica");
+
+        s.set_topic_route_policy(&topic_r, None);
+        assert_eq!(s.route_policy(&topic_r), RoutePolicy::RoundRobin);
+
+        drop(s);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn least_loaded_policy_skips_down_replica_with_shorter_queue() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_least_down_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("least_down_r_{pid}");
+        let topic_s = format!("least_down_s_{pid}");
+
+        let mut r1 = Client::new_sqlite(&format!("least_down_r1_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r1");
+        let mut r2 = Client::new_sqlite(&format!("least_down_r2_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r2");
+        let received = Box::new(std::sync::atomic::AtomicUsize::new(0));
+        let received_ptr = &*received as *const std::sync::atomic::AtomicUsize as *mut libc::c_void;
+        assert!(r1.run(recv_noop, UData::null()));
+        assert!(r2.run(recv_count, UData(received_ptr)));
+        let (down, up) = (
+            r1.bound_listen_addr().unwrap().to_string(),
+            r2.bound_listen_addr().unwrap().to_string(),
+        );
+        let addrs = vec![down.clone(), up.clone()];
+
+        let mut s = Client::new_sqlite(&format!("least_down_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.refresh_address_topic(&topic_r));
+        drop(r1);
+        s.address_topic.insert(topic_r.clone(), addrs.clone());
+
+        // Round robin warms both routes. `s` may still hold a stream to the dropped replica
+        // (internal channel), so its loss surfaces on a later write.
+        let start = std::time::Instant::now();
+        while !s.sender.as_ref().unwrap().is_route_down(&down) && start.elapsed() < Duration::from_secs(5) {
+            s.address_topic.insert(topic_r.clone(), addrs.clone());
+            assert!(s.send_to(&topic_r, b"w", false));
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        assert!(s.sender.as_ref().unwrap().is_route_down(&down));
+        assert!(!s.sender.as_ref().unwrap().is_route_down(&up));
+        std::thread::sleep(Duration::from_millis(200));
+        let down_depth = s.sender.as_ref().unwrap().queue_depth(&down);
+        let warm = received.load(Ordering::SeqCst);
+        assert!(warm >= 1);
+
+        // The live replica looks far busier: a backlog entry that never goes stale.
+        s.address_topic.insert(topic_r.clone(), addrs.clone());
+        s.route_backlog.insert(up.clone(), (u64::MAX, 100));
+        s.set_topic_route_policy(&topic_r, Some(RoutePolicy::LeastLoaded));
+        for _ in 0..3 {
+            assert!(s.send_to(&topic_r, b"y", false));
+        }
+        assert_eq!(
+            s.sender.as_ref().unwrap().queue_depth(&down),
+            down_depth,
+            "least-loaded must not pick a down replica while one is up"
+        );
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < warm + 3 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), warm + 3, "all three go to the live replica");
+
+        drop(s);
+        drop(r2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    extern "C" fn recv_count(
+        _to: *const i8,
+        _from: *const i8,
+        _data: *const u8,
+        _dsize: usize,
+        udata: *mut libc::c_void,
+    ) {
+        if !udata.is_null() {
+            let n = unsafe { &*(udata as *const std::sync::atomic::AtomicUsize) };
+            n.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn topic_failover_reroutes_lost_replica_messages() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_failover_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("fo_r_{pid}");
+        let topic_s = format!("fo_s_{pid}");
+
+        let mut r1 = Client::new_sqlite(&format!("fo_r1_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r1");
+        let mut r2 = Client::new_sqlite(&format!("fo_r2_{pid}"), &topic_r, "127.0.0.1:0", db, "")
+            .expect("r2");
+        let received: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let received_ptr = &*received as *const Mutex<Vec<String>> as *mut libc::c_void;
+        assert!(r1.run(recv_noop, UData::null()));
+        assert!(r2.run(recv_payloads, UData(received_ptr)));
+        let addr1 = r1.bound_listen_addr().unwrap().to_string();
+        let addr2 = r2.bound_listen_addr().unwrap().to_string();
+
+        let mut s = Client::new_sqlite(&format!("fo_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.refresh_address_topic(&topic_r));
+        s.set_topic_failover(&topic_r, true);
+        // The scheduler thread reads the route cache under `mtx` too.
+        let pin_routes = |s: &mut Client, addrs: Vec<String>| {
+            let c: &mut ClientRepr = s;
+            let _lock = c.mtx.lock();
+            c.address_topic.insert(topic_r.clone(), addrs);
+        };
+        // Replica 1 dies with unacked work queued for it; `s` still holds a stream to it
+        // (internal channel), so the loss surfaces on a later write.
+        drop(r1);
+        for i in 0..4 {
+            // Pin the cache: once the loss surfaces it would be refilled from the store,
+            // which also lists replica 2.
+            pin_routes(&mut s, vec![addr1.clone()]);
+            assert!(s.send_to(&topic_r, format!("job{i}").as_bytes(), true));
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(received.lock().unwrap().is_empty());
+
+        // Replica 2 becomes known: the scheduler tick moves the backlog without another send.
+        pin_routes(&mut s, vec![addr1.clone(), addr2.clone()]);
+        let start = std::time::Instant::now();
+        while received.lock().unwrap().len() < 4 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        // Late duplicates would show up here.
+        std::thread::sleep(Duration::from_millis(300));
+        let mut got = received.lock().unwrap().clone();
+        got.sort();
+        let want: Vec<String> = (0..4).map(|i| format!("{topic_r}=job{i}")).collect();
+        assert_eq!(got, want, "backlog of the lost replica reaches replica 2 once each");
+
+        drop(s);
+        drop(r2);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn reassign_pending_moves_orphaned_queue_to_live_listener() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_reassign_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic_r = format!("ra_r_{pid}");
+        let topic_s = format!("ra_s_{pid}");
+        let r_name = format!("ra_r_{pid}");
+        let gone = format!("ra_gone_{pid}");
+
+        let mut r = Client::new_sqlite(&r_name, &topic_r, "127.0.0.1:0", db, "").expect("r");
+        let received = Box::new(std::sync::atomic::AtomicUsize::new(0));
+        let received_ptr = &*received as *const std::sync::atomic::AtomicUsize as *mut libc::c_void;
+        assert!(r.run(recv_count, UData(received_ptr)));
+
+        let mut s = Client::new_sqlite(&format!("ra_s_{pid}"), &topic_s, "127.0.0.1:0", db, "")
+            .expect("s");
+        assert_eq!(s.reassign_pending(&gone, &r_name), Some(0));
+        assert!(s.reassign_pending(&r_name, &r_name).is_none());
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.refresh_address_topic(&topic_r));
+        for _ in 0..2 {
+            assert!(s.send_to(&topic_r, b"live", true));
+        }
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < 2 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), 2);
+
+        // Backlog left behind by a replica that will never come back; its numbers overlap
+        // the live route's, so they only get through if renumbered.
+        let gone_ck = {
+            let mut store = s.db.lock().unwrap();
+            let ck = store.get_connection_key_for_sender(&gone).unwrap();
+            let tk = store.get_topic_key(&topic_r).unwrap();
+            let pool = Arc::new(Mutex::new(Mempool::new()));
+            let mess = (1..=3)
+                .map(|n| Message::new(pool.clone(), ck, tk, n, b"orphan", true).unwrap())
+                .collect();
+            store.save_messages_from_sender(&pool, ck, mess).unwrap();
+            ck
+        };
+        assert_eq!(s.reassign_pending(&gone, &r_name), Some(3));
+        assert_eq!(s.db.lock().unwrap().count_pending_messages(gone_ck).unwrap(), 0);
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < 5 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), 5);
+
+        // New sends continue after the adopted numbers instead of being deduped.
+        assert!(s.send_to(&topic_r, b"live", true));
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < 6 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), 6);
+
+        drop(s);
+        drop(r);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn topic_bind_advertise_getters() {
         let dir = std::env::temp_dir().join(format!(
@@ -2596,4 +4440,441 @@ mod tests {
         drop(c);
         let _ = std::fs::remove_dir_all(&dir);
     }
+
+    extern "C" fn recv_topics(
+        to: *const i8,
+        _from: *const i8,
+        _data: *const u8,
+        _dsize: usize,
+        udata: *mut libc::c_void,
+    ) {
+        if !udata.is_null() {
+            let topics = unsafe { &*(udata as *const Mutex<Vec<String>>) };
+            let to = unsafe { std::ffi::CStr::from_ptr(to) }.to_string_lossy().into_owned();
+            topics.lock().unwrap().push(to);
+        }
+    }
+
+    extern "C" fn recv_payloads(
+        to: *const i8,
+        _from: *const i8,
+        data: *const u8,
+        dsize: usize,
+        udata: *mut libc::c_void,
+    ) {
+        if !udata.is_null() {
+            let got = unsafe { &*(udata as *const Mutex<Vec<String>>) };
+            let to = unsafe { std::ffi::CStr::from_ptr(to) }.to_string_lossy().into_owned();
+            let data = unsafe { std::slice::from_raw_parts(data, dsize) };
+            got.lock().unwrap().push(format!("{to}={}", String::from_utf8_lossy(data)));
+        }
+    }
+
+    #[test]
+    fn pattern_subscription_receives_concrete_topics() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_pattern_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let eu = format!("wc{pid}.eu.created");
+        let us = format!("wc{pid}.us.created");
+
+        let mut r = Client::new_sqlite(&format!("wc_r_{pid}"), &format!("wc_r_{pid}"), "127.0.0.1:0", db, "")
+            .expect("r");
+        let topics = Box::new(Mutex::new(Vec::<String>::new()));
+        let topics_ptr = &*topics as *const Mutex<Vec<String>> as *mut libc::c_void;
+        assert!(r.run(recv_topics, UData(topics_ptr)));
+        assert!(!r.subscribe(&format!("wc{pid}.#.created")));
+        assert_eq!(r.last_error(), ErrorCode::InvalidArg);
+        assert!(r.subscribe(&format!("wc{pid}.*.created")));
+        // Exact and pattern subscription on one topic: delivered once.
+        assert!(r.subscribe(&eu));
+
+        let mut s = Client::new_sqlite(&format!("wc_s_{pid}"), &format!("wc_s_{pid}"), "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+
+        assert!(s.send_all(&eu, b"a", true));
+        assert!(s.send_to(&us, b"b", true));
+        assert!(!s.send_all(&format!("wc{pid}.eu.deleted"), b"c", false));
+        assert_eq!(s.last_error(), ErrorCode::NoAddr);
+
+        let start = std::time::Instant::now();
+        while topics.lock().unwrap().len() < 2 && start.elapsed() < Duration::from_secs(5) {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        let mut got = topics.lock().unwrap().clone();
+        got.sort();
+        assert_eq!(got, vec![eu.clone(), us.clone()]);
+
+        drop(s);
+        drop(r);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn send_all_groups_delivers_once_per_group() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_groups_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("grp_events_{pid}");
+
+        let counts: Vec<Box<std::sync::atomic::AtomicUsize>> =
+            (0..4).map(|_| Box::new(std::sync::atomic::AtomicUsize::new(0))).collect();
+        let mut rs = Vec::new();
+        for (i, n) in counts.iter().enumerate() {
+            let name = format!("grp_r{i}_{pid}");
+            let mut r = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("r");
+            let ptr = &**n as *const std::sync::atomic::AtomicUsize as *mut libc::c_void;
+            assert!(r.run(recv_count, UData(ptr)));
+            rs.push(r);
+        }
+        assert!(!rs[0].subscribe_group(&topic, ""));
+        assert_eq!(rs[0].last_error(), ErrorCode::InvalidArg);
+        assert!(rs[0].subscribe_group(&topic, "billing"));
+        assert!(rs[1].subscribe_group(&topic, "billing"));
+        assert!(rs[2].subscribe_group(&topic, "audit"));
+        assert!(rs[3].subscribe(&topic));
+
+        let mut s = Client::new_sqlite(&format!("grp_s_{pid}"), &format!("grp_s_{pid}"), "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+
+        for _ in 0..4 {
+            assert!(s.send_all_groups(&topic, b"x", true));
+        }
+        let wait_total = |want: usize| {
+            let start = std::time::Instant::now();
+            while counts.iter().map(|n| n.load(Ordering::SeqCst)).sum::<usize>() < want
+                && start.elapsed() < Duration::from_secs(5)
+            {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+        wait_total(12);
+        let got: Vec<usize> = counts.iter().map(|n| n.load(Ordering::SeqCst)).collect();
+        assert_eq!(got, vec![2, 2, 4, 4], "billing members share, audit and ungrouped get all");
+
+        assert!(!s.send_all_groups_keyed(&topic, "", b"x", true));
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+        for _ in 0..3 {
+            assert!(s.send_all_groups_keyed(&topic, "order-42", b"k", true));
+        }
+        wait_total(21);
+        let got: Vec<usize> = counts.iter().map(|n| n.load(Ordering::SeqCst)).collect();
+        assert!(got[..2] == [5, 2] || got[..2] == [2, 5], "one key sticks to one member: {:?}", got);
+        assert_eq!(got[2..], [7, 7]);
+
+        drop(s);
+        drop(rs);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn failed_group_subscribe_leaves_no_subscription() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_groups_fail_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("grp_fail_{pid}");
+        let kept = format!("grp_kept_{pid}");
+        let rows = |t: &str| -> i64 {
+            let conn = rusqlite::Connection::open(db).unwrap();
+            conn.query_row("SELECT COUNT(*) FROM topic_addr WHERE topic = ?1", [t], |r| r.get(0)).unwrap()
+        };
+
+        let name = format!("grp_fail_r_{pid}");
+        let mut r = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("r");
+        assert!(r.run(recv_noop, UData::null()));
+        assert!(r.subscribe(&kept));
+        rusqlite::Connection::open(db)
+            .unwrap()
+            .execute_batch(
+                "CREATE TRIGGER fail_grp BEFORE UPDATE OF grp ON topic_addr
+                 BEGIN SELECT RAISE(ABORT, 'grp write failed'); END;",
+            )
+            .unwrap();
+
+        assert!(!r.subscribe_group(&topic, "billing"));
+        assert_eq!(r.last_error(), ErrorCode::Store);
+        assert_eq!(rows(&topic), 0);
+        assert!(!r.subscriptions.values().any(|t| *t == topic));
+        // An existing subscription is not removed by a failed regroup.
+        assert!(!r.subscribe_group(&kept, "billing"));
+        assert_eq!(rows(&kept), 1);
+        assert!(r.subscriptions.values().any(|t| *t == kept));
+
+        drop(r);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn retained_value_reaches_late_subscribers_first() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_retained_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let cfg_db = format!("cfg{pid}.db");
+        let cfg_net = format!("cfg{pid}.net");
+
+        let mut s = Client::new_sqlite(&format!("ret_s_{pid}"), &format!("ret_s_{pid}"), "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(!s.send_all_retained(&format!("cfg{pid}.*"), b"x", true));
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+        // No subscribers yet: the value is retained, so this is not NoAddr.
+        assert!(s.send_all_retained(&cfg_db, b"db-v1", true));
+        assert!(s.send_all_retained(&cfg_db, b"db-v2", true));
+        assert!(s.send_all_retained(&cfg_net, b"net-v1", true));
+
+        let wait_len = |got: &Mutex<Vec<String>>, want: usize| {
+            let start = std::time::Instant::now();
+            while got.lock().unwrap().len() < want && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        };
+
+        let got_a: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut a = Client::new_sqlite(&format!("ret_a_{pid}"), &format!("ret_a_{pid}"), "127.0.0.1:0", db, "")
+            .expect("a");
+        assert!(a.run(recv_payloads, UData(&*got_a as *const _ as *mut libc::c_void)));
+        assert!(a.subscribe(&cfg_db));
+        assert!(s.send_all(&cfg_db, b"live", true));
+        wait_len(&got_a, 2);
+        assert_eq!(
+            *got_a.lock().unwrap(),
+            vec![format!("{cfg_db}=db-v2"), format!("{cfg_db}=live")]
+        );
+
+        // Subscribed before run, by pattern: every matching retained topic, in topic order.
+        assert!(s.clear_retained(&cfg_db));
+        let got_b: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut b = Client::new_sqlite(&format!("ret_b_{pid}"), &format!("ret_b_{pid}"), "127.0.0.1:0", db, "")
+            .expect("b");
+        assert!(b.subscribe(&format!("cfg{pid}.#")));
+        assert!(b.run(recv_payloads, UData(&*got_b as *const _ as *mut libc::c_void)));
+        wait_len(&got_b, 1);
+        assert_eq!(*got_b.lock().unwrap(), vec![format!("{cfg_net}=net-v1")]);
+
+        drop(b);
+        drop(a);
+        drop(s);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn durable_subscription_queues_while_subscriber_is_down() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_durable_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("dur_events_{pid}");
+        let name = format!("dur_r_{pid}");
+        let wait_len = |got: &Mutex<Vec<String>>, want: usize| {
+            let start = std::time::Instant::now();
+            while got.lock().unwrap().len() < want && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        };
+
+        let got_a: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut a = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("a");
+        assert!(a.run(recv_payloads, UData(&*got_a as *const _ as *mut libc::c_void)));
+        assert!(a.subscribe_durable(&topic));
+        assert!(a.stop());
+        assert_eq!(a.list_addresses(&topic).map(|r| r.len()), Some(1), "row kept after stop");
+
+        // A sender that never saw the subscriber still finds it and queues offline.
+        let mut s = Client::new_sqlite(&format!("dur_s_{pid}"), &format!("dur_s_{pid}"), "127.0.0.1:0", db, "")
+            .expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(s.send_all(&topic, b"while-down", true));
+        std::thread::sleep(Duration::from_millis(300));
+
+        // Back under the same unique_name on a new port.
+        let got_b: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut b = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("b");
+        assert!(b.run(recv_payloads, UData(&*got_b as *const _ as *mut libc::c_void)));
+        assert!(b.subscribe_durable(&topic));
+        let rows = b.list_addresses(&topic).unwrap();
+        assert_eq!(rows.len(), 1, "old address replaced: {:?}", rows);
+        assert_eq!(Some(rows[0].0.as_str()), b.published_addr());
+        wait_len(&got_b, 1);
+        assert_eq!(got_b.lock().unwrap().len(), 1, "backlog moves to the new address on its own");
+        assert!(s.send_all(&topic, b"live", true));
+        wait_len(&got_b, 2);
+        assert_eq!(
+            *got_b.lock().unwrap(),
+            vec![format!("{topic}=while-down"), format!("{topic}=live")]
+        );
+        assert!(got_a.lock().unwrap().is_empty());
+
+        // Unsubscribe ends it: the row goes with stop.
+        assert!(b.unsubscribe(&topic));
+        assert!(b.subscribe(&topic));
+        assert!(b.stop());
+        assert_eq!(b.list_addresses(&topic).map(|r| r.len()), Some(0));
+
+        drop(s);
+        drop(b);
+        drop(a);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn scheduled_send_is_released_when_due_and_survives_restart() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_scheduled_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("sched_r_{pid}");
+        let name = format!("sched_s_{pid}");
+        let wait_len = |got: &Mutex<Vec<String>>, want: usize| {
+            let start = std::time::Instant::now();
+            while got.lock().unwrap().len() < want && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        };
+
+        let got: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut r = Client::new_sqlite(&topic, &topic, "127.0.0.1:0", db, "").expect("r");
+        assert!(r.run(recv_payloads, UData(&*got as *const _ as *mut libc::c_void)));
+
+        let mut s = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("s");
+        assert!(!s.send_to_after(&topic, b"x", 0, true));
+        assert_eq!(s.last_error(), ErrorCode::NotRunning);
+        assert!(s.run(recv_noop, UData::null()));
+        assert!(!s.send_to_after(&format!("{topic}.*"), b"x", 0, true));
+        assert_eq!(s.last_error(), ErrorCode::InvalidArg);
+        assert!(s.send_to_after(&topic, b"later", 1500, true));
+        assert!(s.send_to_at(&topic, b"due", 0, true));
+        // Releases must not overwrite the caller's last error.
+        assert!(!s.send_to(&format!("sched_nobody_{pid}"), b"x", true));
+        wait_len(&got, 1);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_eq!(*got.lock().unwrap(), vec![format!("{topic}=due")]);
+        assert_eq!(s.last_error(), ErrorCode::NoAddr);
+
+        // Still held in the store while the sender is down; the next run releases it.
+        assert!(s.stop());
+        drop(s);
+        std::thread::sleep(Duration::from_millis(1500));
+        assert_eq!(got.lock().unwrap().len(), 1);
+        let mut s = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("s2");
+        assert!(s.run(recv_noop, UData::null()));
+        wait_len(&got, 2);
+        assert_eq!(
+            *got.lock().unwrap(),
+            vec![format!("{topic}=due"), format!("{topic}=later")]
+        );
+
+        drop(s);
+        drop(r);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    extern "C" fn recv_gated(
+        to: *const i8,
+        from: *const i8,
+        data: *const u8,
+        dsize: usize,
+        udata: *mut libc::c_void,
+    ) {
+        // Hold the receive thread on "gate" so the later sends pile up in the listener.
+        if unsafe { std::slice::from_raw_parts(data, dsize) } == b"gate" {
+            std::thread::sleep(Duration::from_millis(1000));
+        }
+        recv_payloads(to, from, data, dsize, udata);
+    }
+
+    #[test]
+    fn urgent_lane_is_delivered_before_queued_bulk() {
+        let _run_lock = client_run_test_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_priority_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("prio_r_{pid}");
+        let name = format!("prio_s_{pid}");
+        let wait_len = |got: &Mutex<Vec<String>>, want: usize| {
+            let start = std::time::Instant::now();
+            while got.lock().unwrap().len() < want && start.elapsed() < Duration::from_secs(5) {
+                std::thread::sleep(Duration::from_millis(20));
+            }
+        };
+
+        let got: Box<Mutex<Vec<String>>> = Box::new(Mutex::new(Vec::new()));
+        let mut r = Client::new_sqlite(&topic, &topic, "127.0.0.1:0", db, "").expect("r");
+        assert!(r.run(recv_gated, UData(&*got as *const _ as *mut libc::c_void)));
+        let mut s = Client::new_sqlite(&name, &name, "127.0.0.1:0", db, "").expect("s");
+        assert!(s.run(recv_noop, UData::null()));
+
+        // The first send on a lane opens its connection.
+        assert!(s.send_to_priority(&topic, b"warm", true, Priority::Urgent));
+        wait_len(&got, 1);
+        assert!(s.send_to(&topic, b"gate", true));
+        wait_len(&got, 2);
+        for i in 0..50 {
+            assert!(s.send_to(&topic, format!("bulk{i}").as_bytes(), true));
+        }
+        assert!(s.send_to_priority(&topic, b"urgent", true, Priority::Urgent));
+        wait_len(&got, 53);
+
+        let got = got.lock().unwrap().clone();
+        assert_eq!(got.len(), 53);
+        assert_eq!(got[2], format!("{topic}=urgent"), "{got:?}");
+        // Bulk keeps its own order on the normal lane.
+        let bulk: Vec<String> = (0..50).map(|i| format!("{topic}=bulk{i}")).collect();
+        assert_eq!(got[3..], bulk[..]);
+        assert_eq!(s.pending_count(), Some(0));
+
+        drop(s);
+        drop(r);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
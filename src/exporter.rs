@@ -0,0 +1,233 @@
+//! Prometheus text-format endpoint for [`Client::serve_metrics`](crate::client::Client::serve_metrics)
+//! (feature **`prometheus`**).
+//!
+//! One thread accepts plain HTTP/1.x connections and answers `GET /metrics` with the current
+//! [`MetricsSnapshot`]; every request renders a fresh snapshot, nothing is cached between
+//! scrapes. Other paths get `404`, other methods `405`.
+
+use crate::metrics::{Counters, LatencyHistogram, MetricsSnapshot, LATENCY_BUCKETS_MS};
+use crate::log;
+use crate::print_error;
+use crate::settings;
+use crate::UData;
+
+use std::fmt::Write as _;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Renders the response body; `None` answers `503`.
+pub type RenderFn = fn(&UData) -> Option<String>;
+
+pub struct MetricsExporter {
+    addr: String,
+    close: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsExporter {
+    pub fn new(addr: &str, client: Arc<str>, render: RenderFn, udata: UData) -> Result<MetricsExporter, String> {
+        let listener = TcpListener::bind(addr).map_err(|e| format!("{}: {}", addr, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("{}: {}", addr, e))?;
+        let bound = listener
+            .local_addr()
+            .map(|a| a.to_string())
+            .unwrap_or_else(|_| addr.to_owned());
+        let close = Arc::new(AtomicBool::new(false));
+        let close_ = close.clone();
+        let thread = thread::spawn(move || {
+            let _log = log::client_scope(&client);
+            while !close_.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => serve(stream, render, &udata),
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(settings::EXPORTER_THREAD_WAIT_TIMEOUT_MS));
+                    }
+                    Err(err) => print_error!(&format!("metrics exporter accept: {}", err)),
+                }
+            }
+        });
+        Ok(MetricsExporter {
+            addr: bound,
+            close,
+            thread: Some(thread),
+        })
+    }
+
+    /// Actual listen address (resolves port `0`).
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+}
+
+impl Drop for MetricsExporter {
+    fn drop(&mut self) {
+        self.close.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if let Err(err) = thread.join() {
+                print_error!(&format!("metrics_exporter_thread.join, {:?}", err));
+            }
+        }
+    }
+}
+
+fn serve(mut stream: TcpStream, render: RenderFn, udata: &UData) {
+    let timeout = Some(Duration::from_millis(settings::EXPORTER_IO_TIMEOUT_MS));
+    if stream.set_nonblocking(false).is_err()
+        || stream.set_read_timeout(timeout).is_err()
+        || stream.set_write_timeout(timeout).is_err()
+    {
+        return;
+    }
+    let mut req = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !req.windows(4).any(|w| w == b"\r\n\r\n") && req.len() < settings::EXPORTER_MAX_REQUEST_SIZE {
+        match stream.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => req.extend_from_slice(&buf[..n]),
+            Err(_) => return,
+        }
+    }
+    let line = req.split(|b| *b == b'\n').next().unwrap_or_default();
+    let line = String::from_utf8_lossy(line);
+    let mut parts = line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = path.split('?').next().unwrap_or("");
+    let (status, body) = if method != "GET" {
+        ("405 Method Not Allowed", String::new())
+    } else if path != "/metrics" {
+        ("404 Not Found", String::new())
+    } else {
+        match render(udata) {
+            Some(body) => ("200 OK", body),
+            None => ("503 Service Unavailable", String::new()),
+        }
+    };
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    if let Err(err) = stream
+        .write_all(head.as_bytes())
+        .and_then(|_| stream.write_all(body.as_bytes()))
+    {
+        print_error!(&format!("metrics exporter write: {}", err));
+    }
+}
+
+/// Metric name suffix, type, help text and the [`Counters`] field it reads.
+type Column = (&'static str, &'static str, &'static str, fn(&Counters) -> u64);
+
+/// Counter / gauge columns exported per client, peer and topic.
+const COLUMNS: [Column; 12] = [
+    ("messages_sent_total", "counter", "Messages written to listener sockets.", |c| c.messages_sent),
+    ("sent_bytes_total", "counter", "Framed bytes written to listener sockets.", |c| c.bytes_sent),
+    ("messages_received_total", "counter", "Messages handed to the receive callback.", |c| c.messages_received),
+    ("received_bytes_total", "counter", "Framed bytes of received messages.", |c| c.bytes_received),
+    ("payload_bytes_total", "counter", "Sent payload bytes before compression.", |c| c.payload_bytes),
+    ("encoded_bytes_total", "counter", "Sent payload bytes as carried on the wire.", |c| c.encoded_bytes),
+    ("reconnects_total", "counter", "Routes restored after a loss.", |c| c.reconnects),
+    ("route_losses_total", "counter", "Routes lost (connect failure or closed stream).", |c| c.route_losses),
+    ("store_errors_total", "counter", "Store operations that failed.", |c| c.store_errors),
+    ("drops_total", "counter", "Best-effort messages discarded unwritten.", |c| c.drops),
+    ("send_queue_depth", "gauge", "Messages in the in-memory send queues.", |c| c.send_queue_depth),
+    ("offline_backlog", "gauge", "Messages in the store's offline queues.", |c| c.offline_backlog),
+];
+
+/// Prometheus text exposition of `snapshot`. Every series carries `client="<unique_name>"`;
+/// `liner_peer_*` / `liner_topic_*` add a `peer` / `topic` label. Histograms are client totals.
+pub fn render(client: &str, snapshot: &MetricsSnapshot) -> String {
+    let client = escape(client);
+    let mut out = String::new();
+    for (scope, label) in [("", ""), ("peer_", "peer"), ("topic_", "topic")] {
+        let rows: Vec<(String, &Counters)> = match label {
+            "" => vec![(String::new(), &snapshot.total)],
+            "peer" => snapshot.peers.iter().map(|(k, v)| (k.clone(), v)).collect(),
+            _ => snapshot.topics.iter().map(|(k, v)| (k.clone(), v)).collect(),
+        };
+        if rows.is_empty() {
+            continue;
+        }
+        for (name, kind, help, get) in COLUMNS {
+            let _ = writeln!(out, "# HELP liner_{}{} {}", scope, name, help);
+            let _ = writeln!(out, "# TYPE liner_{}{} {}", scope, name, kind);
+            for (key, c) in &rows {
+                let labels = if label.is_empty() {
+                    format!("client=\"{}\"", client)
+                } else {
+                    format!("client=\"{}\",{}=\"{}\"", client, label, escape(key))
+                };
+                let _ = writeln!(out, "liner_{}{}{{{}}} {}", scope, name, labels, get(c));
+            }
+        }
+    }
+    histogram(
+        &mut out,
+        "liner_enqueue_to_write_ms",
+        "Time from send_to / send_all to the socket write.",
+        &client,
+        &snapshot.total.enqueue_to_write,
+    );
+    histogram(
+        &mut out,
+        "liner_send_to_receive_ms",
+        "Time from the sender's encode to the receive callback (needs synchronized clocks).",
+        &client,
+        &snapshot.total.send_to_receive,
+    );
+    let _ = writeln!(out, "# HELP liner_mempool_bytes Bytes held by the message mempools.");
+    let _ = writeln!(out, "# TYPE liner_mempool_bytes gauge");
+    let _ = writeln!(out, "liner_mempool_bytes{{client=\"{}\",side=\"send\"}} {}", client, snapshot.send_mempool_bytes);
+    let _ = writeln!(out, "liner_mempool_bytes{{client=\"{}\",side=\"receive\"}} {}", client, snapshot.receive_mempool_bytes);
+    out
+}
+
+fn histogram(out: &mut String, name: &str, help: &str, client: &str, h: &LatencyHistogram) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    let mut cumulative = 0;
+    for (le, n) in LATENCY_BUCKETS_MS.iter().zip(h.buckets.iter()) {
+        cumulative += n;
+        let _ = writeln!(out, "{}_bucket{{client=\"{}\",le=\"{}\"}} {}", name, client, le, cumulative);
+    }
+    let _ = writeln!(out, "{}_bucket{{client=\"{}\",le=\"+Inf\"}} {}", name, client, h.count);
+    let _ = writeln!(out, "{}_sum{{client=\"{}\"}} {}", name, client, h.sum_ms);
+    let _ = writeln!(out, "{}_count{{client=\"{}\"}} {}", name, client, h.count);
+}
+
+/// Label value escaping of the text format: `\`, `"` and newline.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_labels_escape_and_cumulative_buckets() {
+        let mut snapshot = MetricsSnapshot::default();
+        snapshot.total.messages_sent = 3;
+        snapshot.total.enqueue_to_write.record(1);
+        snapshot.total.enqueue_to_write.record(7);
+        snapshot.total.enqueue_to_write.record(20_000);
+        snapshot.peers.insert("p\"1".to_owned(), snapshot.total.clone());
+        snapshot.send_mempool_bytes = 42;
+        let text = render("c1", &snapshot);
+        assert!(text.contains("liner_messages_sent_total{client=\"c1\"} 3\n"));
+        assert!(text.contains("liner_peer_messages_sent_total{client=\"c1\",peer=\"p\\\"1\"} 3\n"));
+        assert!(!text.contains("liner_topic_"), "no topic rows without topic traffic");
+        assert!(text.contains("liner_enqueue_to_write_ms_bucket{client=\"c1\",le=\"1\"} 1\n"));
+        assert!(text.contains("liner_enqueue_to_write_ms_bucket{client=\"c1\",le=\"10\"} 2\n"));
+        assert!(text.contains("liner_enqueue_to_write_ms_bucket{client=\"c1\",le=\"10000\"} 2\n"));
+        assert!(text.contains("liner_enqueue_to_write_ms_bucket{client=\"c1\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("liner_enqueue_to_write_ms_count{client=\"c1\"} 3\n"));
+        assert!(text.contains("liner_mempool_bytes{client=\"c1\",side=\"send\"} 42\n"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Consistent-hash ring over a topic's replica addresses (`send_to_keyed`).
+//!
+//! Points are derived only from the address strings, so every sender that sees the same
+//! replica set maps a key to the same address, independent of catalog order. When a replica
+//! joins or leaves, only keys owned by that replica move.
+
+use crate::settings;
+
+pub struct HashRing {
+    /// Addresses the ring was built from (cache key; compared as given).
+    addrs: Vec<String>,
+    /// Sorted `(point, index into addrs)`.
+    points: Vec<(u64, usize)>,
+}
+
+impl HashRing {
+    pub fn new(addrs: &[String]) -> HashRing {
+        let mut points = Vec::with_capacity(addrs.len() * settings::HASH_RING_VIRTUAL_NODES);
+        for (ix, addr) in addrs.iter().enumerate() {
+            for vnode in 0..settings::HASH_RING_VIRTUAL_NODES {
+                let mut h = fnv1a64(addr.as_bytes());
+                h = mix64(h ^ (vnode as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+                points.push((h, ix));
+            }
+        }
+        // Tie-break by address so equal points resolve the same way on every sender.
+        points.sort_unstable_by(|a, b| a.0.cmp(&b.0).then_with(|| addrs[a.1].cmp(&addrs[b.1])));
+        HashRing {
+            addrs: addrs.to_vec(),
+            points,
+        }
+    }
+
+    /// `true` if the ring was built from exactly `addrs` (no rebuild needed).
+    pub fn matches(&self, addrs: &[String]) -> bool {
+        self.addrs.as_slice() == addrs
+    }
+
+    /// Index into the address list for `key`, or `None` on an empty ring.
+    pub fn get(&self, key: &[u8]) -> Option<usize> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let h = mix64(fnv1a64(key));
+        let pos = self.points.partition_point(|p| p.0 < h);
+        let (_, ix) = self.points[pos % self.points.len()];
+        Some(ix)
+    }
+}
+
+/// FNV-1a 64: stable across processes and Rust versions (unlike `DefaultHasher`).
+fn fnv1a64(data: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in data {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h
+}
+
+/// splitmix64 finalizer: spreads FNV output of near-identical strings over the ring.
+fn mix64(mut h: u64) -> u64 {
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("127.0.0.1:{}", 4000 + i)).collect()
+    }
+
+    #[test]
+    fn empty_ring_returns_none() {
+        assert!(HashRing::new(&[]).get(b"k").is_none());
+    }
+
+    #[test]
+    fn same_key_same_addr_regardless_of_order() {
+        let a = addrs(4);
+        let mut b = a.clone();
+        b.reverse();
+        let ra = HashRing::new(&a);
+        let rb = HashRing::new(&b);
+        for i in 0..200 {
+            let key = format!("account-{i}");
+            let x = &a[ra.get(key.as_bytes()).unwrap()];
+            let y = &b[rb.get(key.as_bytes()).unwrap()];
+            assert_eq!(x, y);
+        }
+    }
+
+    #[test]
+    fn removing_replica_moves_only_its_keys() {
+        let all = addrs(4);
+        let fewer: Vec<String> = all.iter().filter(|a| *a != &all[1]).cloned().collect();
+        let r_all = HashRing::new(&all);
+        let r_fewer = HashRing::new(&fewer);
+        let mut used = std::collections::HashSet::new();
+        for i in 0..1000 {
+            let key = format!("k{i}");
+            let before = &all[r_all.get(key.as_bytes()).unwrap()];
+            let after = &fewer[r_fewer.get(key.as_bytes()).unwrap()];
+            used.insert(before.clone());
+            if before != &all[1] {
+                assert_eq!(before, after, "key {key} moved off a surviving replica");
+            }
+        }
+        assert_eq!(used.len(), 4, "every replica should own some keys");
+    }
+
+    #[test]
+    fn matches_detects_address_change() {
+        let a = addrs(2);
+        let r = HashRing::new(&a);
+        assert!(r.matches(&a));
+        assert!(!r.matches(&addrs(3)));
+    }
+}
@@ -0,0 +1,36 @@
+//! Readiness / liveness report ([`Client::health`](crate::Client::health)).
+//!
+//! Every check runs when the report is requested: the store gets a round trip, the catalog is
+//! read back, and the listener / sender threads are asked whether they have exited.
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Health {
+    /// `run` succeeded and `stop` was not called.
+    pub running: bool,
+    /// The store answered a ping.
+    pub store_ok: bool,
+    /// Error of the failed ping.
+    pub store_error: Option<String>,
+    /// The listener's accept and receive threads are running.
+    pub listener_alive: bool,
+    /// The sender's write thread is running.
+    pub sender_alive: bool,
+    /// The catalog lists this client's `unique_name` for its topic at [`published_addr`](Self::published_addr).
+    pub registered: bool,
+    pub published_addr: Option<String>,
+    /// Listener addresses whose last connect failed or whose stream was lost, sorted.
+    /// They are retried in the background.
+    pub failing_routes: Vec<String>,
+}
+
+impl Health {
+    /// Running with both worker threads alive.
+    pub fn is_live(&self) -> bool {
+        self.running && self.listener_alive && self.sender_alive
+    }
+
+    /// Live, the store answers and peers can find this client in the catalog.
+    pub fn is_ready(&self) -> bool {
+        self.is_live() && self.store_ok && self.registered
+    }
+}
@@ -32,12 +32,15 @@
 mod store;
 pub use store::{open_store, open_store_mutex, ReceiverSeedEntry, Store, StoreBackend};
 pub use store::redis;
+/// Channel crate of the [`Liner::run_with_channel`] receiver.
+pub use crossbeam_channel;
 
 mod status;
 pub use status::{
-    StatusCbackIntern, StatusEmitter, StatusMsg, LNR_LISTENER_STORE_ERROR, LNR_PEER_CONNECTED,
+    StatusCbackIntern, StatusEmitter, StatusEvent, StatusEventCb, StatusMsg, StoreOp,
+    LNR_LISTENER_DECODE_ERROR, LNR_LISTENER_FRAME_ERROR, LNR_LISTENER_STORE_ERROR, LNR_PEER_CONNECTED,
     LNR_PEER_DISCONNECTED, LNR_PEER_SUBSCRIBED, LNR_PEER_UNSUBSCRIBED, LNR_SENDER_BUSY,
-    LNR_SENDER_ROUTE_LOST, LNR_SENDER_SEND_ERROR, LNR_SENDER_STORE_ERROR,
+    LNR_SENDER_FAILOVER, LNR_SENDER_ROUTE_LOST, LNR_SENDER_SEND_ERROR, LNR_SENDER_STORE_ERROR,
 };
 
 mod error;
@@ -49,24 +52,55 @@ pub fn version() -> &'static str {
 }
 
 mod log;
-pub use log::set_log_cb;
+pub use log::{set_log_cb, set_log_cb_ex, LogLevel, LNR_LOG_DEBUG, LNR_LOG_ERROR, LNR_LOG_INFO, LNR_LOG_WARN};
+
+mod metrics;
+pub use metrics::{Counters, LatencyHistogram, MetricsSnapshot, LATENCY_BUCKETS_MS, LATENCY_BUCKET_COUNT};
+
+mod peers;
+pub use peers::PeerInfo;
+pub use mempool::{MempoolSlotStats, MempoolStats};
+
+mod health;
+pub use health::Health;
+
+pub mod trace;
+pub use trace::TraceContext;
+
+pub mod capture;
 
 mod client;
-pub use client::Client;
+pub use client::{Client, Delivery, DeliveryInfo, DeliveryTicket, PeerSendResult, RoutePolicy};
+pub use sender::EnqueueResult;
+pub use message::Priority;
 mod message;
 mod mempool;
 mod bytestream;
+pub use bytestream::FrameError;
 mod listener;
 mod sender;
 mod settings;
 mod common;
+mod hashring;
+mod topic_pattern;
+mod scheduler;
+#[cfg(feature = "tokio")]
+mod async_liner;
+#[cfg(feature = "prometheus")]
+mod exporter;
+#[cfg(feature = "tokio")]
+pub use async_liner::{AsyncError, AsyncLiner, StatusStream, StatusUpdate, Subscription};
 
 use std::collections::HashSet;
 use std::ffi::CStr;
 use std::ffi::CString;
-use std::sync::{Mutex, OnceLock};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, OnceLock};
 
 type UCback = Box<dyn FnMut(&str, &str, &[u8])>;
+type UCbackEx = Box<dyn FnMut(&DeliveryInfo, &[u8])>;
+/// Receive callback that may run on several threads at once (see [`Liner::set_receive_workers`]).
+pub type SharedUCback = Arc<dyn Fn(&str, &str, &[u8]) + Send + Sync>;
 type StatusUCback = Box<dyn FnMut(i32, &str, &str, &str)>;
 
 fn live_clients() -> &'static Mutex<HashSet<usize>> {
@@ -99,11 +133,33 @@ fn cstring_or_empty(s: &str) -> CString {
 
 extern "C" fn cb_(to: *const i8, from: *const i8,  data: *const u8, dsize: usize, udata: *mut libc::c_void){
     unsafe {    
-        if let Some(liner) = udata.cast::<Liner>().as_mut(){
-            if let Some(ucback) = liner.ucback.as_mut(){
-                let Ok(to) = CStr::from_ptr(to).to_str() else { return; };
+        if let Some(liner) = udata.cast::<Liner>().as_ref(){
+            let Ok(to) = CStr::from_ptr(to).to_str() else { return; };
+            let Ok(from) = CStr::from_ptr(from).to_str() else { return; };
+            let data = std::slice::from_raw_parts(data, dsize);
+            if let Some(ucback) = liner.shared_ucback.as_ref(){
+                (ucback)(to, from, data);
+            }else if let Some(ucback) = liner.ucback.as_ref(){
+                // `FnMut`: calls from parallel receive workers take turns.
+                let mut ucback = ucback.lock().unwrap_or_else(|e| e.into_inner());
+                (ucback)(to, from, data);
+            }
+        }
+    }
+}
+
+extern "C" fn cb_ex_(to: *const i8, from: *const i8, sender_name: *const i8, number: u64, sent_at_ms: u64,
+                     replayed: i32, data: *const u8, dsize: usize, udata: *mut libc::c_void){
+    unsafe {
+        if let Some(liner) = udata.cast::<Liner>().as_ref(){
+            if let Some(ucback) = liner.ucback_ex.as_ref(){
+                let Ok(topic) = CStr::from_ptr(to).to_str() else { return; };
                 let Ok(from) = CStr::from_ptr(from).to_str() else { return; };
-                (ucback)(to, from, std::slice::from_raw_parts(data, dsize));
+                let sender_name = CStr::from_ptr(sender_name).to_str().unwrap_or("");
+                let trace = trace::current();
+                let info = DeliveryInfo { topic, from, sender_name, number, sent_at_ms, replayed: replayed != 0, trace };
+                let mut ucback = ucback.lock().unwrap_or_else(|e| e.into_inner());
+                (ucback)(&info, std::slice::from_raw_parts(data, dsize));
             }
         }
     }
@@ -142,8 +198,12 @@ extern "C" fn status_cb_(
 
 pub struct Liner{
     hclient: *mut Client,
-    ucback: Option<UCback>,
+    ucback: Option<Mutex<UCback>>,
+    shared_ucback: Option<SharedUCback>,
+    ucback_ex: Option<Mutex<UCbackEx>>,
     status_ucback: Option<StatusUCback>,
+    /// Dropped on stop/drop so a receive thread waiting for channel room gives up.
+    channel_closed: Option<crossbeam_channel::Sender<()>>,
 }
 
 impl Liner {
@@ -221,7 +281,10 @@ impl Liner {
         Self {
             hclient,
             ucback: None,
+            shared_ucback: None,
+            ucback_ex: None,
             status_ucback: None,
+            channel_closed: None,
         }
     }
 
@@ -239,6 +302,22 @@ impl Liner {
         }
     }
 
+    /// Typed status events ([`StatusEvent`]), next to the `i32` [`Liner::set_status_callback`].
+    /// Runs on the client's background threads. Pass `None` to clear.
+    pub fn set_status_event_callback(&mut self, cb: Option<StatusEventCb>) {
+        unsafe { (*self.hclient).set_status_event_cb(cb) }
+    }
+
+    /// [`StatusEvent`]s through a bounded channel (replaces the event callback). Events raised
+    /// while `capacity` of them wait unread are dropped; background threads never wait.
+    pub fn status_events(&mut self, capacity: usize) -> Receiver<StatusEvent> {
+        let (tx, rx) = std::sync::mpsc::sync_channel(capacity);
+        self.set_status_event_callback(Some(Box::new(move |event: &StatusEvent| {
+            let _ = tx.try_send(event.clone());
+        })));
+        rx
+    }
+
     pub fn last_error_code(&self) -> i32 {
         unsafe { lnr_last_error_code(self.hclient) }
     }
@@ -263,6 +342,29 @@ impl Liner {
         }
     }
 
+    /// Log callback with the level and the client `unique_name` (`""` outside a client).
+    pub fn set_log_callback_ex(cb: LogExCbackC, udata: UData) {
+        set_log_cb_ex(cb, udata);
+    }
+
+    /// Most verbose level emitted, process-global (default [`LogLevel::Warn`]).
+    pub fn set_log_level(level: LogLevel) {
+        log::set_max_level(level);
+    }
+
+    pub fn log_level() -> LogLevel {
+        log::max_level()
+    }
+
+    /// Trace context stamped into sends from this thread (`None` clears it); see [`trace`].
+    pub fn set_trace_context(ctx: Option<TraceContext>) {
+        trace::set_current(ctx);
+    }
+
+    pub fn trace_context() -> Option<TraceContext> {
+        trace::current()
+    }
+
     pub fn set_max_message_size(bytes: usize) -> bool {
         unsafe { lnr_set_max_message_size(bytes) }
     }
@@ -291,6 +393,41 @@ impl Liner {
         unsafe { (*self.hclient).list_addresses(topic) }
     }
 
+    /// Threads of this client that run receive callbacks of different sender connections in
+    /// parallel (default `1`, the receive thread itself). Order per sender is kept. Call before `run`.
+    pub fn set_receive_workers(&mut self, n: usize) -> bool {
+        unsafe { lnr_set_receive_workers(self.hclient, n) }
+    }
+
+    pub fn receive_workers(&self) -> usize {
+        unsafe { lnr_get_receive_workers(self.hclient) }
+    }
+
+    /// Default replica selection for `send_to` (see [`RoutePolicy`]).
+    pub fn set_route_policy(&mut self, policy: RoutePolicy) -> bool {
+        unsafe { lnr_set_route_policy(self.hclient, policy as i32) }
+    }
+
+    /// Opt-in: re-route unacked messages of a lost replica of `topic` to a live one.
+    pub fn set_topic_failover(&mut self, topic: &str, enabled: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_set_topic_failover(self.hclient, topic.as_ptr(), enabled)
+        }
+    }
+
+    /// Per-topic replica selection for `send_to`; `None` falls back to the client policy.
+    pub fn set_topic_route_policy(&mut self, topic: &str, policy: Option<RoutePolicy>) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_set_topic_route_policy(
+                self.hclient,
+                topic.as_ptr(),
+                policy.map(|p| p as i32).unwrap_or(-1),
+            )
+        }
+    }
+
     pub fn pending_count(&mut self) -> Option<u64> {
         unsafe { (*self.hclient).pending_count() }
     }
@@ -299,6 +436,62 @@ impl Liner {
         unsafe { (*self.hclient).pending_by_peer() }
     }
 
+    /// Mempool occupancy per sender and listener slot (see [`Client::mempool_stats`]).
+    pub fn mempool_stats(&mut self) -> Vec<MempoolSlotStats> {
+        unsafe { (*self.hclient).mempool_stats() }
+    }
+
+    /// Release free mempool memory now; returns bytes released (see [`Client::compact_memory`]).
+    pub fn compact_memory(&mut self) -> u64 {
+        unsafe { (*self.hclient).compact_memory() }
+    }
+
+    /// Readiness / liveness report (see [`Client::health`]).
+    pub fn health(&mut self) -> Health {
+        unsafe { (*self.hclient).health() }
+    }
+
+    /// Live state of every outbound route lane and inbound connection (see [`Client::peers`]).
+    pub fn peers(&mut self) -> Vec<PeerInfo> {
+        unsafe { (*self.hclient).peers() }
+    }
+
+    /// Record every frame written or read to `path` (see [`Client::start_capture`]).
+    pub fn start_capture(&mut self, path: &str) -> bool {
+        unsafe { (*self.hclient).start_capture(path) }
+    }
+
+    pub fn stop_capture(&mut self) -> bool {
+        unsafe { (*self.hclient).stop_capture() }
+    }
+
+    /// Traffic counters and latency histograms, in total, per peer and per topic.
+    pub fn metrics_snapshot(&mut self) -> MetricsSnapshot {
+        unsafe { (*self.hclient).metrics_snapshot() }
+    }
+
+    /// Serve the metrics in Prometheus text format on `GET http://<addr>/metrics`.
+    #[cfg(feature = "prometheus")]
+    pub fn serve_metrics(&mut self, addr: &str) -> bool {
+        unsafe { (*self.hclient).serve_metrics(addr) }
+    }
+
+    /// Listen address of the metrics endpoint (resolves port `0`).
+    #[cfg(feature = "prometheus")]
+    pub fn metrics_addr(&self) -> Option<String> {
+        unsafe { (*self.hclient).metrics_addr().map(str::to_owned) }
+    }
+
+    /// Move the offline queue for listener `from_unique_name` to `to_unique_name`.
+    pub fn reassign_pending(&mut self, from_unique_name: &str, to_unique_name: &str) -> Option<u64> {
+        unsafe { (*self.hclient).reassign_pending(from_unique_name, to_unique_name) }
+    }
+
+    /// Move the offline queue for listener `from_unique_name` to a listener of `topic`.
+    pub fn reassign_pending_to_topic(&mut self, from_unique_name: &str, topic: &str) -> Option<u64> {
+        unsafe { (*self.hclient).reassign_pending_to_topic(from_unique_name, topic) }
+    }
+
     /// Address published to the store instead of the bind string. `None` clears.
     pub fn set_advertise_addr(&mut self, addr: Option<&str>) -> bool {
         unsafe {
@@ -313,6 +506,7 @@ impl Liner {
     }
 
     pub fn stop(&mut self) -> bool {
+        self.close_channel();
         unsafe { lnr_stop(self.hclient) }
     }
 
@@ -322,11 +516,63 @@ impl Liner {
 
     pub fn run(&mut self, ucback: UCback)->bool{        
         unsafe{
-            self.ucback = Some(ucback);
+            self.ucback = Some(Mutex::new(ucback));
+            self.shared_ucback = None;
+            self.ucback_ex = None;
+            let ud = self as *const Self as *mut libc::c_void;
+            lnr_run(self.hclient, cb_, ud)
+        }
+    }
+    /// Like [`Liner::run`] with a callback that messages of different senders may enter
+    /// concurrently when [`Liner::set_receive_workers`] is above `1`.
+    pub fn run_shared(&mut self, ucback: SharedUCback)->bool{
+        unsafe{
+            self.shared_ucback = Some(ucback);
+            self.ucback = None;
+            self.ucback_ex = None;
             let ud = self as *const Self as *mut libc::c_void;
             lnr_run(self.hclient, cb_, ud)
         }
     }
+    /// Like [`Liner::run`], with the sender's unique name, message number, sender timestamp
+    /// and replay flag in [`DeliveryInfo`] (auditing, latency, idempotency keys).
+    pub fn run_ex(&mut self, ucback: UCbackEx)->bool{
+        unsafe{
+            self.ucback_ex = Some(Mutex::new(ucback));
+            self.ucback = None;
+            self.shared_ucback = None;
+            let ud = self as *const Self as *mut libc::c_void;
+            lnr_run_ex(self.hclient, cb_ex_, ud)
+        }
+    }
+    /// Like [`Liner::run`], but messages go to a bounded channel instead of a callback, so they
+    /// can be pulled from any thread (`recv_timeout`, `try_recv`, select over several receivers).
+    /// Once `capacity` deliveries wait unread, the receive thread blocks and the listener stops
+    /// reading peer sockets: senders keep the rest queued. Deliveries still waiting for room
+    /// when the client stops are dropped. Returns `None` if `run` fails (see [`Liner::last_error_message`]).
+    pub fn run_with_channel(&mut self, capacity: usize) -> Option<crossbeam_channel::Receiver<Delivery>> {
+        self.close_channel();
+        let (tx, rx) = crossbeam_channel::bounded(capacity);
+        // Never sent on: dropping the sender in `close_channel` wakes a blocked delivery.
+        let (closed, closed_rx) = crossbeam_channel::bounded::<()>(0);
+        let ucback: SharedUCback = Arc::new(move |to: &str, from: &str, data: &[u8]| {
+            let delivery = Delivery { topic: to.to_owned(), from: from.to_owned(), data: data.to_vec() };
+            crossbeam_channel::select! {
+                send(tx, delivery) -> _ => {}
+                recv(closed_rx) -> _ => {}
+            }
+        });
+        self.channel_closed = Some(closed);
+        if self.run_shared(ucback) {
+            Some(rx)
+        } else {
+            self.channel_closed = None;
+            None
+        }
+    }
+    fn close_channel(&mut self) {
+        self.channel_closed = None;
+    }
     /// Send to a single peer subscribed on `topic`. `at_least_once_delivery` matches C `lnr_send_to`
     /// (persist / retry semantics; use `false` when peers use different SQLite files — see `docs/using-sqlite.md`).
     pub fn send_to(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
@@ -341,6 +587,80 @@ impl Liner {
             )
         }
     }
+    /// Send to the replica of `topic` that owns `key` on the consistent-hash ring (per-key ordering).
+    /// Same `at_least_once_delivery` semantics as [`Liner::send_to`].
+    pub fn send_to_keyed(&mut self, topic: &str, key: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            let key = cstring_or_empty(key);
+            lnr_send_to_keyed(
+                self.hclient,
+                topic.as_ptr(),
+                key.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+            )
+        }
+    }
+    /// Hold `data` in the store and [`Liner::send_to`] it at `deliver_at_ms` (ms since the Unix epoch).
+    pub fn send_to_at(&mut self, topic: &str, data: &[u8], deliver_at_ms: u64, at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_to_at(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                deliver_at_ms,
+                at_least_once_delivery,
+            )
+        }
+    }
+    /// [`Liner::send_to_at`] `delay_ms` from now.
+    pub fn send_to_after(&mut self, topic: &str, data: &[u8], delay_ms: u64, at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_to_after(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                delay_ms,
+                at_least_once_delivery,
+            )
+        }
+    }
+    /// [`Liner::send_to`] on the `priority` lane of the peer (see [`Client::send_to_priority`]).
+    pub fn send_to_priority(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool, priority: Priority) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_to_priority(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+                priority as i32,
+            )
+        }
+    }
+    /// Send to the replica of `topic` registered as `unique_name`, bypassing the route policy.
+    /// Same `at_least_once_delivery` semantics as [`Liner::send_to`].
+    pub fn send_to_peer(&mut self, topic: &str, unique_name: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            let unique_name = cstring_or_empty(unique_name);
+            lnr_send_to_peer(
+                self.hclient,
+                topic.as_ptr(),
+                unique_name.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+            )
+        }
+    }
     /// Broadcast to all peers on `topic`. Same `at_least_once_delivery` semantics as [`Liner::send_to`].
     pub fn send_all(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
         unsafe {
@@ -354,12 +674,92 @@ impl Liner {
             )
         }
     }
+    /// [`Liner::send_all`] on the `priority` lane of every peer.
+    pub fn send_all_priority(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool, priority: Priority) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_all_priority(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+                priority as i32,
+            )
+        }
+    }
+    /// One copy per consumer group plus every ungrouped peer (see [`Client::send_all_groups`]).
+    pub fn send_all_groups(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_all_groups(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+            )
+        }
+    }
+    /// Like [`Liner::send_all_groups`], with the group member picked by `key`.
+    pub fn send_all_groups_keyed(&mut self, topic: &str, key: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            let key = cstring_or_empty(key);
+            lnr_send_all_groups_keyed(
+                self.hclient,
+                topic.as_ptr(),
+                key.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+            )
+        }
+    }
+    /// Broadcast and keep `data` as the topic's retained value (see [`Client::send_all_retained`]).
+    pub fn send_all_retained(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_send_all_retained(
+                self.hclient,
+                topic.as_ptr(),
+                data.as_ptr(),
+                data.len(),
+                at_least_once_delivery,
+            )
+        }
+    }
+    pub fn clear_retained(&mut self, topic: &str) -> bool {
+        unsafe {
+            let topic = cstring_or_empty(topic);
+            lnr_clear_retained(self.hclient, topic.as_ptr())
+        }
+    }
+    /// Broadcast with a per-peer outcome (see [`Client::send_all_detailed`]).
+    pub fn send_all_detailed(&mut self, topic: &str, data: &[u8], at_least_once_delivery: bool) -> Option<Vec<PeerSendResult>> {
+        unsafe { (*self.hclient).send_all_detailed(topic, data, at_least_once_delivery) }
+    }
     pub fn subscribe(&mut self, topic: &str)->bool{
         unsafe{
             let topic = cstring_or_empty(topic);
             lnr_subscribe(self.hclient, topic.as_ptr())
         }
     }
+    /// Subscribe so the catalog row outlives the client (see [`Client::subscribe_durable`]).
+    pub fn subscribe_durable(&mut self, topic: &str)->bool{
+        unsafe{
+            let topic = cstring_or_empty(topic);
+            lnr_subscribe_durable(self.hclient, topic.as_ptr())
+        }
+    }
+    /// Subscribe as a member of consumer group `group` (see [`Client::subscribe_group`]).
+    pub fn subscribe_group(&mut self, topic: &str, group: &str)->bool{
+        unsafe{
+            let topic = cstring_or_empty(topic);
+            let group = cstring_or_empty(group);
+            lnr_subscribe_group(self.hclient, topic.as_ptr(), group.as_ptr())
+        }
+    }
     pub fn unsubscribe(&mut self, topic: &str)->bool{
         unsafe{
             let topic = cstring_or_empty(topic);
@@ -401,6 +801,7 @@ impl Liner {
 
 impl Drop for Liner {
     fn drop(&mut self) {
+        self.close_channel();
         unsafe {
             lnr_delete_client(self.hclient);
         }
@@ -565,20 +966,57 @@ pub unsafe extern "C" fn lnr_new_client(
     std::hint::black_box(lnr_new_client_sqlite);
     std::hint::black_box(lnr_set_status_cb);
     std::hint::black_box(lnr_set_log_cb);
+    std::hint::black_box(lnr_set_log_cb_ex);
+    std::hint::black_box(lnr_set_log_level);
+    std::hint::black_box(lnr_get_log_level);
+    std::hint::black_box(lnr_set_trace_context);
+    std::hint::black_box(lnr_get_trace_context);
+    std::hint::black_box(lnr_send_to_keyed);
+    std::hint::black_box(lnr_send_to_peer);
+    std::hint::black_box(lnr_send_all_detailed);
+    std::hint::black_box(lnr_send_all_groups);
+    std::hint::black_box(lnr_send_all_groups_keyed);
+    std::hint::black_box(lnr_subscribe_group);
+    std::hint::black_box(lnr_subscribe_durable);
+    std::hint::black_box(lnr_send_all_retained);
+    std::hint::black_box(lnr_send_to_at);
+    std::hint::black_box(lnr_send_to_after);
+    std::hint::black_box(lnr_send_to_priority);
+    std::hint::black_box(lnr_send_all_priority);
+    std::hint::black_box(lnr_clear_retained);
+    std::hint::black_box(lnr_set_route_policy);
+    std::hint::black_box(lnr_set_topic_route_policy);
+    std::hint::black_box(lnr_set_topic_failover);
     std::hint::black_box(lnr_list_addresses);
     std::hint::black_box(lnr_pending_count);
     std::hint::black_box(lnr_pending_by_peer);
+    std::hint::black_box(lnr_peers);
+    std::hint::black_box(lnr_health);
+    std::hint::black_box(lnr_mempool_stats);
+    std::hint::black_box(lnr_compact_memory);
+    std::hint::black_box(lnr_start_capture);
+    std::hint::black_box(lnr_stop_capture);
+    std::hint::black_box(lnr_metrics_snapshot);
+    std::hint::black_box(lnr_peer_metrics);
+    std::hint::black_box(lnr_topic_metrics);
+    #[cfg(feature = "prometheus")]
+    std::hint::black_box(lnr_serve_metrics);
+    std::hint::black_box(lnr_reassign_pending);
+    std::hint::black_box(lnr_reassign_pending_to_topic);
     std::hint::black_box(lnr_set_max_message_size);
     std::hint::black_box(lnr_get_max_message_size);
     std::hint::black_box(lnr_set_compress_threshold);
     std::hint::black_box(lnr_get_compress_threshold);
     std::hint::black_box(lnr_set_max_send_queue);
     std::hint::black_box(lnr_get_max_send_queue);
+    std::hint::black_box(lnr_set_receive_workers);
+    std::hint::black_box(lnr_get_receive_workers);
     std::hint::black_box(lnr_last_error_code);
     std::hint::black_box(lnr_last_error_message);
     std::hint::black_box(lnr_version);
     std::hint::black_box(lnr_set_advertise_addr);
     std::hint::black_box(lnr_stop);
+    std::hint::black_box(lnr_run_ex);
     std::hint::black_box(lnr_is_running);
     std::hint::black_box(lnr_advertise_addr);
     std::hint::black_box(lnr_bound_listen_addr);
@@ -593,6 +1031,8 @@ pub unsafe extern "C" fn lnr_new_client(
 
 pub struct UData(*mut libc::c_void);
 type UCbackIntern = extern "C" fn(to: *const i8, from: *const i8, data: *const u8, dsize: usize, udata: *mut libc::c_void);
+type UCbackExIntern = extern "C" fn(to: *const i8, from: *const i8, sender_name: *const i8, number: u64,
+                                    sent_at_ms: u64, replayed: i32, data: *const u8, dsize: usize, udata: *mut libc::c_void);
 
 unsafe impl Send for UData {}
 
@@ -621,6 +1061,7 @@ pub unsafe extern "C" fn lnr_set_status_cb(
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     (*client).set_status_cb(cb, UData(udata));
     true
 }
@@ -636,6 +1077,79 @@ pub unsafe extern "C" fn lnr_set_log_cb(cb: LogCbackC, udata: *mut libc::c_void)
     true
 }
 
+pub type LogExCbackC = Option<
+    extern "C" fn(level: i32, client: *const i8, message: *const i8, udata: *mut libc::c_void),
+>;
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_log_cb_ex(cb: LogExCbackC, udata: *mut libc::c_void) -> bool {
+    set_log_cb_ex(cb, UData(udata));
+    true
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_log_level(level: i32) -> bool {
+    let Some(level) = LogLevel::from_i32(level) else {
+        print_error!(&format!("unknown log level {}", level));
+        return false;
+    };
+    log::set_max_level(level);
+    true
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_get_log_level() -> i32 {
+    log::max_level() as i32
+}
+
+/// Trace context (W3C `traceparent`) stamped into sends from the calling thread.
+/// NULL or `""` clears it; `FALSE` on a malformed value (the current one is kept).
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_trace_context(traceparent: *const i8) -> bool {
+    if traceparent.is_null() {
+        trace::set_current(None);
+        return true;
+    }
+    let Ok(traceparent) = CStr::from_ptr(traceparent).to_str() else {
+        print_error!("traceparent: invalid utf8");
+        return false;
+    };
+    if traceparent.is_empty() {
+        trace::set_current(None);
+        return true;
+    }
+    let Some(ctx) = TraceContext::parse(traceparent) else {
+        print_error!(&format!("malformed traceparent {:?}", traceparent));
+        return false;
+    };
+    trace::set_current(Some(ctx));
+    true
+}
+
+/// `traceparent` of the calling thread (inside a receive callback: the message's), or NULL.
+/// Valid until the next call on the same thread.
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_get_trace_context() -> *const i8 {
+    thread_local! {
+        static TRACEPARENT: std::cell::RefCell<CString> = std::cell::RefCell::new(CString::default());
+    }
+    let Some(ctx) = trace::current() else {
+        return std::ptr::null();
+    };
+    TRACEPARENT.with(|s| {
+        let mut s = s.borrow_mut();
+        *s = cstring_or_empty(&ctx.to_string());
+        s.as_ptr()
+    })
+}
+
 /// # Safety
 #[no_mangle]
 pub unsafe extern "C" fn lnr_list_addresses(
@@ -647,6 +1161,7 @@ pub unsafe extern "C" fn lnr_list_addresses(
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     if topic.is_null() {
         print_error!("null pointer argument");
         return false;
@@ -673,6 +1188,7 @@ pub unsafe extern "C" fn lnr_pending_count(client: *mut Client) -> i64 {
     if !has_client(client) {
         return -1;
     }
+    let _log = client_log_scope(client);
     match (*client).pending_count() {
         Some(n) => i64::try_from(n).unwrap_or(i64::MAX),
         None => -1,
@@ -699,6 +1215,7 @@ pub unsafe extern "C" fn lnr_pending_by_peer(
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     let Some(rows) = (*client).pending_by_peer() else {
         return false;
     };
@@ -719,34 +1236,408 @@ pub unsafe extern "C" fn lnr_pending_by_peer(
     true
 }
 
-/// # Safety
-#[no_mangle]
-pub unsafe extern "C" fn lnr_set_max_message_size(bytes: usize) -> bool {
-    settings::set_max_message_size(bytes)
+/// C view of a [`PeerInfo`] (`lnr_peer_info`); the strings live for the callback only.
+#[repr(C)]
+pub struct LnrPeerInfo {
+    pub addr: *const i8,
+    pub topic: *const i8,
+    pub unique_name: *const i8,
+    pub inbound: i32,
+    pub priority: i32,
+    pub connection_key: i32,
+    pub connected: i32,
+    pub last_activity_ms: u64,
+    pub last_send_mess_number: u64,
+    pub last_mess_number: u64,
+    pub queue_depth: u64,
+    pub mempool_bytes: u64,
 }
 
+pub type PeerCbackC = Option<extern "C" fn(peer: *const LnrPeerInfo, udata: *mut libc::c_void)>;
+
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_get_max_message_size() -> usize {
-    settings::max_message_size()
+pub unsafe extern "C" fn lnr_peers(client: *mut Client, cb: PeerCbackC, udata: *mut libc::c_void) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    let Some(cb) = cb else {
+        print_error!("null pointer argument");
+        return false;
+    };
+    for peer in (*client).peers() {
+        let Ok(addr) = CString::new(peer.addr) else { continue };
+        let Ok(topic) = CString::new(peer.topic) else { continue };
+        let Ok(name) = CString::new(peer.unique_name) else { continue };
+        let info = LnrPeerInfo {
+            addr: addr.as_ptr(),
+            topic: topic.as_ptr(),
+            unique_name: name.as_ptr(),
+            inbound: i32::from(peer.inbound),
+            priority: peer.priority as i32,
+            connection_key: peer.connection_key,
+            connected: i32::from(peer.connected),
+            last_activity_ms: peer.last_activity_ms,
+            last_send_mess_number: peer.last_send_mess_number,
+            last_mess_number: peer.last_mess_number,
+            queue_depth: peer.queue_depth,
+            mempool_bytes: peer.mempool_bytes,
+        };
+        cb(&info, udata);
+    }
+    true
 }
 
-/// # Safety
-#[no_mangle]
-pub unsafe extern "C" fn lnr_set_compress_threshold(bytes: usize) -> bool {
-    settings::set_compress_threshold(bytes)
+/// C view of a [`MempoolSlotStats`] (`lnr_mempool_info`); `addr` lives for the callback only.
+#[repr(C)]
+pub struct LnrMempoolInfo {
+    pub addr: *const i8,
+    pub inbound: i32,
+    pub connection_key: i32,
+    pub slot: u64,
+    pub capacity_bytes: u64,
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    pub free_blocks: u64,
+    pub largest_free_block: u64,
+    pub chunks: u64,
+    pub pending_frees: u64,
+    pub fragmentation: f64,
 }
 
+pub type MempoolCbackC = Option<extern "C" fn(info: *const LnrMempoolInfo, udata: *mut libc::c_void)>;
+
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_get_compress_threshold() -> usize {
-    settings::compress_threshold()
+pub unsafe extern "C" fn lnr_mempool_stats(client: *mut Client, cb: MempoolCbackC, udata: *mut libc::c_void) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    let Some(cb) = cb else {
+        print_error!("null pointer argument");
+        return false;
+    };
+    for row in (*client).mempool_stats() {
+        let Ok(addr) = CString::new(row.addr) else { continue };
+        let s = row.stats;
+        let info = LnrMempoolInfo {
+            addr: addr.as_ptr(),
+            inbound: i32::from(row.inbound),
+            connection_key: row.connection_key,
+            slot: row.slot as u64,
+            capacity_bytes: s.capacity_bytes,
+            used_bytes: s.used_bytes,
+            free_bytes: s.free_bytes,
+            free_blocks: s.free_blocks,
+            largest_free_block: s.largest_free_block,
+            chunks: s.chunks,
+            pending_frees: s.pending_frees,
+            fragmentation: s.fragmentation(),
+        };
+        cb(&info, udata);
+    }
+    true
 }
 
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_set_max_send_queue(n: usize) -> bool {
-    settings::set_max_send_queue(n)
+pub unsafe extern "C" fn lnr_compact_memory(client: *mut Client) -> i64 {
+    if !has_client(client) {
+        return -1;
+    }
+    let _log = client_log_scope(client);
+    i64::try_from((*client).compact_memory()).unwrap_or(i64::MAX)
+}
+
+/// C view of [`Health`] (`lnr_health_info`, filled by `lnr_health`).
+#[repr(C)]
+pub struct LnrHealth {
+    pub running: i32,
+    pub store_ok: i32,
+    pub listener_alive: i32,
+    pub sender_alive: i32,
+    pub registered: i32,
+    pub failing_routes: u64,
+    pub live: i32,
+    pub ready: i32,
+}
+
+impl From<&Health> for LnrHealth {
+    fn from(h: &Health) -> Self {
+        Self {
+            running: i32::from(h.running),
+            store_ok: i32::from(h.store_ok),
+            listener_alive: i32::from(h.listener_alive),
+            sender_alive: i32::from(h.sender_alive),
+            registered: i32::from(h.registered),
+            failing_routes: h.failing_routes.len() as u64,
+            live: i32::from(h.is_live()),
+            ready: i32::from(h.is_ready()),
+        }
+    }
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_health(client: *mut Client, out: *mut LnrHealth) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if out.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let health = (*client).health();
+    out.write((&health).into());
+    true
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_start_capture(client: *mut Client, path: *const i8) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if path.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return false;
+    };
+    (*client).start_capture(path)
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_stop_capture(client: *mut Client) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    (*client).stop_capture()
+}
+
+/// C view of a [`LatencyHistogram`] (`lnr_latency`).
+#[repr(C)]
+pub struct LnrLatency {
+    pub buckets: [u64; LATENCY_BUCKET_COUNT],
+    pub count: u64,
+    pub sum_ms: u64,
+    pub max_ms: u64,
+}
+
+/// C view of [`Counters`] (`lnr_metrics`).
+#[repr(C)]
+pub struct LnrMetrics {
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub messages_received: u64,
+    pub bytes_received: u64,
+    pub payload_bytes: u64,
+    pub encoded_bytes: u64,
+    pub reconnects: u64,
+    pub route_losses: u64,
+    pub store_errors: u64,
+    pub drops: u64,
+    pub send_queue_depth: u64,
+    pub offline_backlog: u64,
+    pub enqueue_to_write: LnrLatency,
+    pub send_to_receive: LnrLatency,
+}
+
+impl From<&LatencyHistogram> for LnrLatency {
+    fn from(h: &LatencyHistogram) -> Self {
+        Self { buckets: h.buckets, count: h.count, sum_ms: h.sum_ms, max_ms: h.max_ms }
+    }
+}
+
+impl From<&Counters> for LnrMetrics {
+    fn from(c: &Counters) -> Self {
+        Self {
+            messages_sent: c.messages_sent,
+            bytes_sent: c.bytes_sent,
+            messages_received: c.messages_received,
+            bytes_received: c.bytes_received,
+            payload_bytes: c.payload_bytes,
+            encoded_bytes: c.encoded_bytes,
+            reconnects: c.reconnects,
+            route_losses: c.route_losses,
+            store_errors: c.store_errors,
+            drops: c.drops,
+            send_queue_depth: c.send_queue_depth,
+            offline_backlog: c.offline_backlog,
+            enqueue_to_write: (&c.enqueue_to_write).into(),
+            send_to_receive: (&c.send_to_receive).into(),
+        }
+    }
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_metrics_snapshot(client: *mut Client, out: *mut LnrMetrics) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if out.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let snapshot = (*client).metrics_snapshot();
+    out.write((&snapshot.total).into());
+    true
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_peer_metrics(client: *mut Client, peer: *const i8, out: *mut LnrMetrics) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if peer.is_null() || out.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(peer) = CStr::from_ptr(peer).to_str() else {
+        return false;
+    };
+    let snapshot = (*client).metrics_snapshot();
+    let counters = snapshot.peers.get(peer).cloned().unwrap_or_default();
+    out.write((&counters).into());
+    true
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_topic_metrics(client: *mut Client, topic: *const i8, out: *mut LnrMetrics) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || out.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else {
+        return false;
+    };
+    if topic.is_empty() {
+        print_error!("empty topic");
+        return false;
+    }
+    let snapshot = (*client).metrics_snapshot();
+    let counters = snapshot.topics.get(topic).cloned().unwrap_or_default();
+    out.write((&counters).into());
+    true
+}
+
+/// Exported only when built with the `prometheus` feature.
+/// # Safety
+#[cfg(feature = "prometheus")]
+#[no_mangle]
+pub unsafe extern "C" fn lnr_serve_metrics(client: *mut Client, addr: *const i8) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if addr.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(addr) = CStr::from_ptr(addr).to_str() else {
+        return false;
+    };
+    (*client).serve_metrics(addr)
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_reassign_pending(
+    client: *mut Client,
+    from_unique_name: *const i8,
+    to_unique_name: *const i8,
+) -> i64 {
+    if !has_client(client) {
+        return -1;
+    }
+    let _log = client_log_scope(client);
+    if from_unique_name.is_null() || to_unique_name.is_null() {
+        print_error!("null pointer argument");
+        return -1;
+    }
+    let Ok(from) = CStr::from_ptr(from_unique_name).to_str() else {
+        return -1;
+    };
+    let Ok(to) = CStr::from_ptr(to_unique_name).to_str() else {
+        return -1;
+    };
+    match (*client).reassign_pending(from, to) {
+        Some(n) => i64::try_from(n).unwrap_or(i64::MAX),
+        None => -1,
+    }
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_reassign_pending_to_topic(
+    client: *mut Client,
+    from_unique_name: *const i8,
+    topic: *const i8,
+) -> i64 {
+    if !has_client(client) {
+        return -1;
+    }
+    let _log = client_log_scope(client);
+    if from_unique_name.is_null() || topic.is_null() {
+        print_error!("null pointer argument");
+        return -1;
+    }
+    let Ok(from) = CStr::from_ptr(from_unique_name).to_str() else {
+        return -1;
+    };
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else {
+        return -1;
+    };
+    match (*client).reassign_pending_to_topic(from, topic) {
+        Some(n) => i64::try_from(n).unwrap_or(i64::MAX),
+        None => -1,
+    }
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_max_message_size(bytes: usize) -> bool {
+    settings::set_max_message_size(bytes)
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_get_max_message_size() -> usize {
+    settings::max_message_size()
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_compress_threshold(bytes: usize) -> bool {
+    settings::set_compress_threshold(bytes)
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_get_compress_threshold() -> usize {
+    settings::compress_threshold()
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_max_send_queue(n: usize) -> bool {
+    settings::set_max_send_queue(n)
 }
 
 /// # Safety
@@ -755,6 +1646,29 @@ pub unsafe extern "C" fn lnr_get_max_send_queue() -> usize {
     settings::max_send_queue()
 }
 
+/// Threads of `client` running receive callbacks in parallel. `FALSE` if `n == 0` or running.
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_receive_workers(client: *mut Client, n: usize) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    (*client).set_receive_workers(n)
+}
+
+/// `0` for a null handle.
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_get_receive_workers(client: *mut Client) -> usize {
+    if !has_client(client) {
+        return 0;
+    }
+    (*client).receive_workers()
+}
+
 /// Last sync-API error code (`LNR_OK` / `LNR_ERR_*`). Returns `LNR_OK` for a null handle.
 ///
 /// # Safety
@@ -763,6 +1677,7 @@ pub unsafe extern "C" fn lnr_last_error_code(client: *mut Client) -> i32 {
     if !has_client(client) {
         return ErrorCode::Ok.as_i32();
     }
+    let _log = client_log_scope(client);
     (*client).last_error().as_i32()
 }
 
@@ -772,6 +1687,7 @@ pub unsafe extern "C" fn lnr_last_error_message(client: *mut Client) -> *const i
     if !has_client(client) {
         return std::ptr::null();
     }
+    let _log = client_log_scope(client);
     (*client).last_error_message_c_str()
 }
 
@@ -783,6 +1699,90 @@ pub unsafe extern "C" fn lnr_version() -> *const i8 {
         .as_ptr()
 }
 
+/// Default replica selection for `lnr_send_to` on multi-address topics
+/// (`LNR_ROUTE_ROUND_ROBIN` / `LNR_ROUTE_LEAST_LOADED`). `FALSE` on unknown policy.
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_route_policy(client: *mut Client, policy: i32) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    let Some(policy) = RoutePolicy::from_i32(policy) else {
+        print_error!(&format!("unknown route policy {}", policy));
+        return false;
+    };
+    (*client).set_route_policy(policy);
+    true
+}
+
+/// Replica selection for `lnr_send_to` on `topic`. Negative `policy` clears the override.
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_topic_route_policy(
+    client: *mut Client,
+    topic: *const i8,
+    policy: i32,
+) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty() {
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let policy = if policy < 0 {
+        None
+    } else {
+        match RoutePolicy::from_i32(policy) {
+            Some(p) => Some(p),
+            None => {
+                print_error!(&format!("unknown route policy {}", policy));
+                return false;
+            }
+        }
+    };
+    (*client).set_topic_route_policy(topic, policy);
+    true
+}
+
+/// Enable or disable failover for `topic`: when a replica's route is lost
+/// (`LNR_SENDER_ROUTE_LOST`), its unacked messages for `topic` are re-sent to a live
+/// replica by the scheduler thread within ~100 ms, or by an earlier `lnr_send_to` /
+/// `lnr_send_all` (`LNR_SENDER_FAILOVER`).
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_set_topic_failover(
+    client: *mut Client,
+    topic: *const i8,
+    enabled: bool,
+) -> bool {
+    if !has_client(client) {
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty() {
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    (*client).set_topic_failover(topic, enabled);
+    true
+}
+
 /// Set advertise address before `lnr_run`. `addr == NULL` or empty clears.
 ///
 /// # Safety
@@ -791,6 +1791,7 @@ pub unsafe extern "C" fn lnr_set_advertise_addr(client: *mut Client, addr: *cons
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     if addr.is_null() {
         return (*client).set_advertise_addr(None);
     }
@@ -808,6 +1809,7 @@ pub unsafe extern "C" fn lnr_stop(client: *mut Client) -> bool {
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     (*client).stop()
 }
 
@@ -817,6 +1819,7 @@ pub unsafe extern "C" fn lnr_is_running(client: *mut Client) -> bool {
     if !has_client(client) {
         return false;
     }
+    let _log = client_log_scope(client);
     (*client).is_running()
 }
 
@@ -826,45 +1829,411 @@ pub unsafe extern "C" fn lnr_advertise_addr(client: *mut Client) -> *const i8 {
     if !has_client(client) {
         return std::ptr::null();
     }
-    (*client).advertise_addr_c_str()
+    let _log = client_log_scope(client);
+    (*client).advertise_addr_c_str()
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_bound_listen_addr(client: *mut Client) -> *const i8 {
+    if !has_client(client) {
+        return std::ptr::null();
+    }
+    let _log = client_log_scope(client);
+    (*client).bound_listen_addr_c_str()
+}
+
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_published_addr(client: *mut Client) -> *const i8 {
+    if !has_client(client) {
+        return std::ptr::null();
+    }
+    let _log = client_log_scope(client);
+    (*client).published_addr_c_str()
+}
+
+/// Launching a client to send messages and listen for incoming messages. 
+/// 
+/// Possible errors when launching a client:
+/// - no connection to redis
+/// - the address for the client is busy
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_run(client: *mut Client, receive_cb: UCbackIntern, udata: *mut libc::c_void)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    let udata: UData = UData(udata);
+    (*client).run(receive_cb, udata)
+}
+
+/// Like `lnr_run`, with delivery metadata in the callback (see `lnr_receive_ex_cb` in liner.h).
+///
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_run_ex(client: *mut Client, receive_cb: UCbackExIntern, udata: *mut libc::c_void)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    let udata: UData = UData(udata);
+    (*client).run_ex(receive_cb, udata)
+}
+
+/// Send message to other client.
+/// Call only when the client is already running. 
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - no other client with this topic
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    // Empty payload is rejected inside Client (`LNR_ERR_INVALID_ARG`); allow null `data` when size is 0.
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to(topic, data, at_least_once_delivery)
+}
+
+/// Same as `lnr_send_to` on the `priority` lane (`LNR_PRIORITY_*`) of the peer: a raised
+/// lane has its own queue, connection and offline list, is written first, and the listener
+/// runs its callbacks first. Order holds only within one lane. Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - no other client with this topic
+/// - unknown priority
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to_priority(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool,
+                          priority: i32)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    let Some(priority) = Priority::from_i32(priority) else {
+        print_error!(&format!("unknown priority {}", priority));
+        return false;
+    };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to_priority(topic, data, at_least_once_delivery, priority)
+}
+
+/// Schedule a message: it is kept in the store and sent like `lnr_send_to` at
+/// `deliver_at_ms` (ms since the Unix epoch). Scheduled messages survive a restart of the
+/// sender and are released by its next `lnr_run`. Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - topic pattern or own topic
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to_at(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          deliver_at_ms: u64,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to_at(topic, data, deliver_at_ms, at_least_once_delivery)
+}
+
+/// `lnr_send_to_at` `delay_ms` from now.
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to_after(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          delay_ms: u64,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to_after(topic, data, delay_ms, at_least_once_delivery)
+}
+
+/// Send message to the replica of the topic selected by `key`.
+/// Messages with the same key go to the same replica while the topic's address set
+/// is unchanged; when replicas join or leave, only keys of those replicas move.
+/// Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - no other client with this topic
+/// - empty key
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to_keyed(client: *mut Client,
+                          topic: *const i8,
+                          key: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || key.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    let Ok(key) = CStr::from_ptr(key).to_str() else { return false; };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to_keyed(topic, key, data, at_least_once_delivery)
+}
+
+/// Send message to one specific replica of the topic, the one registered as `unique_name`
+/// (resolved through the store topic directory; the route policy is bypassed).
+/// Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - no client with this unique_name on the topic
+/// - empty unique_name
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_to_peer(client: *mut Client,
+                          topic: *const i8,
+                          unique_name: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || unique_name.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic name empty");
+        return false;
+    }
+    let Ok(unique_name) = CStr::from_ptr(unique_name).to_str() else { return false; };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_to_peer(topic, unique_name, data, at_least_once_delivery)
+}
+
+/// Send message to other clients. 
+/// Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - no other client with this topic
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_all(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_all(topic, data, at_least_once_delivery)
+}
+
+/// Same as `lnr_send_all` on the `priority` lane (`LNR_PRIORITY_*`) of every peer.
+/// Call only when the client is already running.
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_all_priority(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool,
+                          priority: i32)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let Some(priority) = Priority::from_i32(priority) else {
+        print_error!(&format!("unknown priority {}", priority));
+        return false;
+    };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_all_priority(topic, data, at_least_once_delivery, priority)
 }
 
+/// Same as `lnr_send_all`, and `data` is kept as the retained value of the topic:
+/// clients subscribing later receive it before live messages. Succeeds without
+/// subscribers. Call only when the client is already running.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - topic is a pattern
+/// 
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_bound_listen_addr(client: *mut Client) -> *const i8 {
-    if !has_client(client) {
-        return std::ptr::null();
+pub unsafe extern "C" fn lnr_send_all_retained(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool)->bool{
+    if !has_client(client){
+        return false;
     }
-    (*client).bound_listen_addr_c_str()
-}
-
-/// # Safety
-#[no_mangle]
-pub unsafe extern "C" fn lnr_published_addr(client: *mut Client) -> *const i8 {
-    if !has_client(client) {
-        return std::ptr::null();
+    let _log = client_log_scope(client);
+    if topic.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
     }
-    (*client).published_addr_c_str()
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_all_retained(topic, data, at_least_once_delivery)
 }
 
-/// Launching a client to send messages and listen for incoming messages. 
+/// Drop the retained value of the topic (see `lnr_send_all_retained`).
 /// 
-/// Possible errors when launching a client:
+/// Possible errors:
 /// - no connection to redis
-/// - the address for the client is busy
 /// 
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_run(client: *mut Client, receive_cb: UCbackIntern, udata: *mut libc::c_void)->bool{
+pub unsafe extern "C" fn lnr_clear_retained(client: *mut Client,
+                          topic: *const i8)->bool{
     if !has_client(client){
         return false;
     }
-    let udata: UData = UData(udata);
-    (*client).run(receive_cb, udata)
+    let _log = client_log_scope(client);
+    if topic.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    (*client).clear_retained(topic)
 }
 
-/// Send message to other client.
-/// Call only when the client is already running. 
+/// Send message to every consumer group of the topic: each ungrouped subscriber gets
+/// a copy, each group (see `lnr_subscribe_group`) one copy for one member chosen by the route policy.
+/// Call only when the client is already running.
 /// 
 /// Possible errors:
 /// - no connection to redis
@@ -872,47 +2241,95 @@ pub unsafe extern "C" fn lnr_run(client: *mut Client, receive_cb: UCbackIntern,
 /// 
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_send_to(client: *mut Client,
+pub unsafe extern "C" fn lnr_send_all_groups(client: *mut Client,
                           topic: *const i8,
                           data: *const u8, data_size: usize,
                           at_least_once_delivery: bool)->bool{
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     if topic.is_null() || (data_size > 0 && data.is_null()) {
         print_error!("null pointer argument");
         return false;
     }
     let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
     if topic.is_empty(){
-        print_error!("topic name empty");
+        print_error!("topic.is_empty()");
         return false;
     }
-    // Empty payload is rejected inside Client (`LNR_ERR_INVALID_ARG`); allow null `data` when size is 0.
     let data = if data_size == 0 {
         &[][..]
     } else {
         std::slice::from_raw_parts(data, data_size)
     };
-    (*client).send_to(topic, data, at_least_once_delivery)
+    (*client).send_all_groups(topic, data, at_least_once_delivery)
 }
 
-/// Send message to other clients. 
+/// Same as `lnr_send_all_groups`, but the member of each group is selected by `key`
+/// (consistent hash inside the group, per-key affinity).
 /// Call only when the client is already running.
 /// 
 /// Possible errors:
 /// - no connection to redis
 /// - no other client with this topic
+/// - empty key
 /// 
 /// # Safety
 #[no_mangle]
-pub unsafe extern "C" fn lnr_send_all(client: *mut Client,
+pub unsafe extern "C" fn lnr_send_all_groups_keyed(client: *mut Client,
                           topic: *const i8,
+                          key: *const i8,
                           data: *const u8, data_size: usize,
                           at_least_once_delivery: bool)->bool{
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
+    if topic.is_null() || key.is_null() || (data_size > 0 && data.is_null()) {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let Ok(key) = CStr::from_ptr(key).to_str() else { return false; };
+    let data = if data_size == 0 {
+        &[][..]
+    } else {
+        std::slice::from_raw_parts(data, data_size)
+    };
+    (*client).send_all_groups_keyed(topic, key, data, at_least_once_delivery)
+}
+
+pub type SendResultCbackC = Option<
+    extern "C" fn(
+        addr: *const i8,
+        unique_name: *const i8,
+        status: i32,
+        error: *const i8,
+        udata: *mut libc::c_void,
+    ),
+>;
+
+/// Same as `lnr_send_all`, and `cb` is called once per peer of the topic with its
+/// `LNR_SEND_*` status, so only failed peers need a retry. No callbacks when the call
+/// fails as a whole (not running, no addresses, ...).
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_send_all_detailed(client: *mut Client,
+                          topic: *const i8,
+                          data: *const u8, data_size: usize,
+                          at_least_once_delivery: bool,
+                          cb: SendResultCbackC,
+                          udata: *mut libc::c_void)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
     if topic.is_null() || (data_size > 0 && data.is_null()) {
         print_error!("null pointer argument");
         return false;
@@ -927,7 +2344,19 @@ pub unsafe extern "C" fn lnr_send_all(client: *mut Client,
     } else {
         std::slice::from_raw_parts(data, data_size)
     };
-    (*client).send_all(topic, data, at_least_once_delivery)
+    let Some(rows) = (*client).send_all_detailed(topic, data, at_least_once_delivery) else {
+        return false;
+    };
+    let all_ok = rows.iter().all(|r| r.status == EnqueueResult::Ok);
+    if let Some(cb) = cb {
+        for row in rows {
+            let Ok(a) = CString::new(row.addr) else { continue };
+            let Ok(n) = CString::new(row.unique_name) else { continue };
+            let Ok(e) = CString::new(row.error) else { continue };
+            cb(a.as_ptr(), n.as_ptr(), row.status as i32, e.as_ptr(), udata);
+        }
+    }
+    all_ok
 }
 
 /// Subscribe to the topic and receive messages from other clients.
@@ -943,6 +2372,7 @@ pub unsafe extern "C" fn lnr_subscribe(client: *mut Client,
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     if topic.is_null() {
         print_error!("null pointer argument");
         return false;
@@ -955,6 +2385,62 @@ pub unsafe extern "C" fn lnr_subscribe(client: *mut Client,
     (*client).subscribe(topic)
 }
 
+/// Subscribe to the topic durably: the registration stays in the catalog after
+/// `lnr_stop` or a crash, and senders queue at-least-once messages offline until the
+/// client (same unique_name) subscribes durably again. `lnr_unsubscribe` ends it.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_subscribe_durable(client: *mut Client,
+                          topic: *const i8)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    (*client).subscribe_durable(topic)
+}
+
+/// Subscribe to the topic as a member of consumer group `group`:
+/// `lnr_send_all_groups` delivers each message to one member of every group.
+/// 
+/// Possible errors:
+/// - no connection to redis
+/// - empty group
+/// 
+/// # Safety
+#[no_mangle]
+pub unsafe extern "C" fn lnr_subscribe_group(client: *mut Client,
+                          topic: *const i8,
+                          group: *const i8)->bool{
+    if !has_client(client){
+        return false;
+    }
+    let _log = client_log_scope(client);
+    if topic.is_null() || group.is_null() {
+        print_error!("null pointer argument");
+        return false;
+    }
+    let Ok(topic) = CStr::from_ptr(topic).to_str() else { return false; };
+    if topic.is_empty(){
+        print_error!("topic.is_empty()");
+        return false;
+    }
+    let Ok(group) = CStr::from_ptr(group).to_str() else { return false; };
+    (*client).subscribe_group(topic, group)
+}
+
 /// Unsubscribe from the topic and do not receive messages from other clients.
 /// 
 /// Possible errors:
@@ -968,6 +2454,7 @@ pub unsafe extern "C" fn lnr_unsubscribe(client: *mut Client,
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     if topic.is_null() {
         print_error!("null pointer argument");
         return false;
@@ -993,6 +2480,7 @@ pub unsafe extern "C" fn lnr_refresh_address_topic(client: *mut Client,
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     if topic.is_null() {
         print_error!("null pointer argument");
         return false;
@@ -1009,14 +2497,53 @@ pub unsafe extern "C" fn lnr_refresh_address_topic(client: *mut Client,
 mod tests {
     use super::*;
     use std::ptr;
-    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    /// Syntax-checks the public headers as C and as C++11 (the cpp examples' standard);
+    /// skipped when no `cc` / `c++` is installed.
+    #[test]
+    fn c_and_cpp_headers_compile() {
+        let root = env!("CARGO_MANIFEST_DIR");
+        let checks = [
+            ("cc", vec!["-fsyntax-only", "-Wall", "-x", "c"], "include/liner.h"),
+            ("c++", vec!["-fsyntax-only", "-Wall", "-std=c++11", "-x", "c++"], "cpp/liner_broker.h"),
+        ];
+        for (compiler, flags, header) in checks {
+            let out = match std::process::Command::new(compiler).args(&flags).arg(format!("{}/{}", root, header)).output() {
+                Ok(out) => out,
+                Err(_) => {
+                    eprintln!("{} not found, {} not checked", compiler, header);
+                    continue;
+                }
+            };
+            assert!(out.status.success(), "{} {}:\n{}", compiler, header, String::from_utf8_lossy(&out.stderr));
+        }
+    }
 
     #[test]
     fn fns_return_false_on_null_client_without_derefing_args() {
         unsafe {
             assert!(!lnr_send_to(ptr::null_mut(), ptr::null(), ptr::null(), 0, true));
             assert!(!lnr_send_all(ptr::null_mut(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_send_to_keyed(ptr::null_mut(), ptr::null(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_send_to_peer(ptr::null_mut(), ptr::null(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_send_to_at(ptr::null_mut(), ptr::null(), ptr::null(), 0, 0, true));
+            assert!(!lnr_send_to_after(ptr::null_mut(), ptr::null(), ptr::null(), 0, 0, true));
+            assert!(!lnr_send_to_priority(ptr::null_mut(), ptr::null(), ptr::null(), 0, true, 1));
+            assert!(!lnr_send_all_priority(ptr::null_mut(), ptr::null(), ptr::null(), 0, true, 1));
+            assert!(!lnr_send_all_detailed(ptr::null_mut(), ptr::null(), ptr::null(), 0, true, None, ptr::null_mut()));
+            assert!(!lnr_set_route_policy(ptr::null_mut(), 1));
+            assert!(!lnr_set_topic_route_policy(ptr::null_mut(), ptr::null(), 1));
+            assert!(!lnr_set_topic_failover(ptr::null_mut(), ptr::null(), true));
+            assert!(!lnr_set_receive_workers(ptr::null_mut(), 2));
+            assert_eq!(lnr_get_receive_workers(ptr::null_mut()), 0);
             assert!(!lnr_subscribe(ptr::null_mut(), ptr::null()));
+            assert!(!lnr_subscribe_group(ptr::null_mut(), ptr::null(), ptr::null()));
+            assert!(!lnr_subscribe_durable(ptr::null_mut(), ptr::null()));
+            assert!(!lnr_send_all_groups(ptr::null_mut(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_send_all_groups_keyed(ptr::null_mut(), ptr::null(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_send_all_retained(ptr::null_mut(), ptr::null(), ptr::null(), 0, true));
+            assert!(!lnr_clear_retained(ptr::null_mut(), ptr::null()));
             assert!(!lnr_unsubscribe(ptr::null_mut(), ptr::null()));
             assert!(!lnr_refresh_address_topic(ptr::null_mut(), ptr::null()));
             assert!(!lnr_clear_stored_messages(ptr::null_mut()));
@@ -1026,6 +2553,7 @@ mod tests {
             assert_eq!(lnr_last_error_code(ptr::null_mut()), 0);
             assert!(!lnr_set_advertise_addr(ptr::null_mut(), ptr::null()));
             assert!(!lnr_stop(ptr::null_mut()));
+            assert!(!lnr_run_ex(ptr::null_mut(), cb_ex_, ptr::null_mut()));
             assert!(!lnr_is_running(ptr::null_mut()));
             assert!(lnr_advertise_addr(ptr::null_mut()).is_null());
             assert!(lnr_bound_listen_addr(ptr::null_mut()).is_null());
@@ -1033,6 +2561,19 @@ mod tests {
             assert!(!lnr_list_addresses(ptr::null_mut(), ptr::null(), None, ptr::null_mut()));
             assert_eq!(lnr_pending_count(ptr::null_mut()), -1);
             assert!(!lnr_pending_by_peer(ptr::null_mut(), None, ptr::null_mut()));
+            assert!(!lnr_peers(ptr::null_mut(), None, ptr::null_mut()));
+            assert!(!lnr_health(ptr::null_mut(), ptr::null_mut()));
+            assert!(!lnr_mempool_stats(ptr::null_mut(), None, ptr::null_mut()));
+            assert_eq!(lnr_compact_memory(ptr::null_mut()), -1);
+            assert!(!lnr_start_capture(ptr::null_mut(), ptr::null()));
+            assert!(!lnr_stop_capture(ptr::null_mut()));
+            assert!(!lnr_metrics_snapshot(ptr::null_mut(), ptr::null_mut()));
+            assert!(!lnr_peer_metrics(ptr::null_mut(), ptr::null(), ptr::null_mut()));
+            assert!(!lnr_topic_metrics(ptr::null_mut(), ptr::null(), ptr::null_mut()));
+            #[cfg(feature = "prometheus")]
+            assert!(!lnr_serve_metrics(ptr::null_mut(), ptr::null()));
+            assert_eq!(lnr_reassign_pending(ptr::null_mut(), ptr::null(), ptr::null()), -1);
+            assert_eq!(lnr_reassign_pending_to_topic(ptr::null_mut(), ptr::null(), ptr::null()), -1);
             assert!(lnr_last_error_message(ptr::null_mut()).is_null());
             assert!(!lnr_version().is_null());
         }
@@ -1073,6 +2614,53 @@ mod tests {
         assert!(seen.load(Ordering::SeqCst));
     }
 
+    static LOG_EX_SEEN: Mutex<Vec<(i32, String)>> = Mutex::new(Vec::new());
+
+    extern "C" fn log_ex_collect(level: i32, client: *const i8, _msg: *const i8, _udata: *mut libc::c_void) {
+        let client = unsafe { CStr::from_ptr(client) }.to_string_lossy().into_owned();
+        LOG_EX_SEEN.lock().unwrap().push((level, client));
+    }
+
+    #[test]
+    fn log_cb_ex_names_the_client_of_a_failed_call() {
+        let _lock = crate::log::test_log_hook_lock();
+        let dir = std::env::temp_dir().join(format!(
+            "liner_log_ex_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let name = format!("log_ex_{}", std::process::id());
+        let mut c = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db.to_str().unwrap(), "");
+
+        unsafe {
+            assert!(!lnr_set_log_level(0));
+            assert!(lnr_set_log_level(LNR_LOG_INFO));
+            assert_eq!(lnr_get_log_level(), LNR_LOG_INFO);
+            assert!(lnr_set_log_cb_ex(Some(log_ex_collect), ptr::null_mut()));
+        }
+        assert!(!c.send_to("", b"x", false));
+        assert!(c.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        assert!(c.stop());
+        unsafe {
+            assert!(lnr_set_log_cb_ex(None, ptr::null_mut()));
+        }
+        Liner::set_log_level(LogLevel::Warn);
+
+        let seen: Vec<i32> = LOG_EX_SEEN
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, client)| *client == name)
+            .map(|(level, _)| *level)
+            .collect();
+        assert!(seen.contains(&LNR_LOG_ERROR), "failed send_to is attributed to the client");
+        assert!(seen.iter().filter(|l| **l == LNR_LOG_INFO).count() >= 2, "run and stop at Info");
+        drop(c);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
     #[test]
     fn runtime_limits_reject_zero_and_roundtrip() {
         let _lock = settings::test_limits_lock();
@@ -1105,6 +2693,669 @@ mod tests {
             assert_eq!(s, env!("CARGO_PKG_VERSION"));
         }
     }
+
+    #[test]
+    fn run_with_channel_delivers_in_order_through_a_full_channel() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_channel_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("chan_r_{pid}");
+
+        let mut r = Liner::new_sqlite(&topic, &topic, "127.0.0.1:0", db, "");
+        let rx = r.run_with_channel(4).expect("run_with_channel");
+        let mut s = Liner::new_sqlite(&format!("chan_s_{pid}"), &format!("chan_s_{pid}"), "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+
+        // Over the listener's per-peer limit, so reads of the sender's socket pause and resume.
+        let total = 2500u32;
+        for i in 0..total {
+            assert!(s.send_to(&topic, &i.to_le_bytes(), true));
+        }
+        // Nobody reads yet: the receive thread waits for channel room.
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        assert!(r.peers().iter().any(|p| p.inbound && p.queue_depth > 0));
+        for i in 0..total {
+            let d = rx.recv_timeout(std::time::Duration::from_secs(5)).expect("delivery");
+            assert_eq!(d.topic, topic);
+            assert_eq!(d.from, format!("chan_s_{pid}"));
+            assert_eq!(d.data, i.to_le_bytes());
+        }
+
+        assert!(s.stop());
+        assert!(r.stop());
+        assert!(rx.recv_timeout(std::time::Duration::from_millis(200)).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn run_with_channel_stop_wakes_a_delivery_waiting_for_room() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_channel_stop_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("chan_stop_r_{pid}");
+
+        let mut r = Liner::new_sqlite(&topic, &topic, "127.0.0.1:0", db, "");
+        let rx = r.run_with_channel(1).expect("run_with_channel");
+        let mut s = Liner::new_sqlite(&format!("chan_stop_s_{pid}"), &format!("chan_stop_s_{pid}"), "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        for i in 0..5u32 {
+            assert!(s.send_to(&topic, &i.to_le_bytes(), false));
+        }
+        let start = std::time::Instant::now();
+        while !rx.is_full() && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(rx.is_full());
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        // The receive thread is blocked on the full channel; stop must not hang on it.
+        let start = std::time::Instant::now();
+        assert!(r.stop());
+        assert!(start.elapsed() < std::time::Duration::from_secs(2), "stop took {:?}", start.elapsed());
+        assert!(s.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn metrics_snapshot_counts_traffic_per_peer_and_topic() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_metrics_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let (rname, sname) = (format!("m_r_{pid}"), format!("m_s_{pid}"));
+
+        let got = Arc::new(Mutex::new(0usize));
+        let got_ = got.clone();
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        assert!(r.run(Box::new(move |_: &str, _: &str, _: &[u8]| *got_.lock().unwrap() += 1)));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        for _ in 0..5 {
+            assert!(s.send_to(&rname, &[7u8; 100], true));
+        }
+        let start = std::time::Instant::now();
+        while *got.lock().unwrap() < 5 && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(*got.lock().unwrap(), 5);
+
+        let sent = s.metrics_snapshot();
+        let to_r = &sent.topics[&rname];
+        assert_eq!(to_r.messages_sent, 5);
+        assert_eq!(to_r.payload_bytes, 500);
+        assert!(to_r.bytes_sent > 500);
+        assert_eq!(to_r.enqueue_to_write.count, 5);
+        let peer = &sent.peers[&rname];
+        assert!(peer.messages_sent >= 5, "sends are keyed by the listener's unique_name");
+        assert!(sent.total.messages_sent >= peer.messages_sent);
+
+        let received = r.metrics_snapshot();
+        let from_s = &received.peers[&sname];
+        assert!(from_s.messages_received >= 5);
+        let on_r = &received.topics[&rname];
+        assert_eq!(on_r.messages_received, 5);
+        assert_eq!(on_r.payload_bytes, 500);
+        assert_eq!(on_r.send_to_receive.count, 5);
+
+        let mut c = std::mem::MaybeUninit::<LnrMetrics>::uninit();
+        let topic = CString::new(rname.as_str()).unwrap();
+        assert!(unsafe { lnr_topic_metrics(r.hclient, topic.as_ptr(), c.as_mut_ptr()) });
+        let c = unsafe { c.assume_init() };
+        assert_eq!(c.messages_received, 5);
+        assert_eq!(c.send_to_receive.buckets.iter().sum::<u64>(), 5);
+
+        assert!(s.stop());
+        assert!(r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    static PEER_ROWS: Mutex<Vec<(String, i32, i32, u64)>> = Mutex::new(Vec::new());
+
+    extern "C" fn collect_peer(peer: *const LnrPeerInfo, _udata: *mut libc::c_void) {
+        let p = unsafe { &*peer };
+        let name = unsafe { CStr::from_ptr(p.unique_name) }.to_string_lossy().into_owned();
+        PEER_ROWS.lock().unwrap().push((name, p.inbound, p.connected, p.last_send_mess_number));
+    }
+
+    #[test]
+    fn peers_report_outbound_route_and_inbound_link_with_acks() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_peers_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let (rname, sname) = (format!("p_r_{pid}"), format!("p_s_{pid}"));
+
+        let got = Arc::new(Mutex::new(0usize));
+        let got_ = got.clone();
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        assert!(r.peers().is_empty(), "not running");
+        assert!(r.run(Box::new(move |_: &str, _: &str, _: &[u8]| *got_.lock().unwrap() += 1)));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        for _ in 0..5 {
+            assert!(s.send_to(&rname, b"x", true));
+        }
+        // Acks reach the sender with the periodic last-number sync.
+        let start = std::time::Instant::now();
+        let out = loop {
+            let out: Vec<PeerInfo> = s.peers().into_iter().filter(|p| p.topic == rname).collect();
+            if (out.len() == 1 && out[0].last_mess_number >= 5 && out[0].unacked() == 0) || start.elapsed() > std::time::Duration::from_secs(10) {
+                break out;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        };
+        assert_eq!(*got.lock().unwrap(), 5);
+        assert_eq!(out.len(), 1, "{:?}", out);
+        let out = &out[0];
+        assert!(!out.inbound && out.connected);
+        assert_eq!(out.addr, r.published_addr().unwrap());
+        assert_eq!(out.unique_name, rname);
+        // An internal-channel notice went first on the same connection.
+        let sent = out.last_send_mess_number;
+        assert!(sent >= 5 && out.unacked() == 0, "{:?}", out);
+        assert_eq!(out.queue_depth, 0, "at-least-once messages leave the queue once acked");
+        assert!(out.last_activity_ms > 0 && out.mempool_bytes > 0);
+
+        let inb: Vec<PeerInfo> = r.peers().into_iter().filter(|p| p.inbound && p.unique_name == sname).collect();
+        assert_eq!(inb.len(), 1, "{:?}", inb);
+        assert_eq!((inb[0].topic.as_str(), inb[0].last_send_mess_number), (sname.as_str(), sent));
+        assert!(inb[0].connected && inb[0].connection_key >= 0);
+
+        PEER_ROWS.lock().unwrap().clear();
+        assert!(unsafe { lnr_peers(r.hclient, Some(collect_peer), ptr::null_mut()) });
+        assert!(PEER_ROWS.lock().unwrap().contains(&(sname.clone(), 1, 1, sent)));
+
+        assert!(s.stop());
+        assert!(r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn health_reports_threads_registration_and_failing_routes() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_health_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let (rname, sname) = (format!("h_r_{pid}"), format!("h_s_{pid}"));
+
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        let idle = r.health();
+        assert!(idle.store_ok && !idle.running && !idle.is_live() && !idle.registered, "{:?}", idle);
+        assert!(r.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        let h = s.health();
+        assert!(h.is_ready(), "{:?}", h);
+        assert_eq!(h.published_addr, s.published_addr());
+        assert!(h.failing_routes.is_empty());
+        let r_addr = r.published_addr().unwrap();
+
+        // The sender keeps the stopped listener's route and fails to reconnect it.
+        assert!(s.send_to(&rname, b"x", false));
+        assert!(r.stop());
+        assert!(!r.health().registered);
+        let start = std::time::Instant::now();
+        let h = loop {
+            let _ = s.send_to(&rname, b"x", false);
+            let h = s.health();
+            if !h.failing_routes.is_empty() || start.elapsed() > std::time::Duration::from_secs(10) {
+                break h;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        };
+        assert_eq!(h.failing_routes, vec![r_addr]);
+        assert!(h.is_ready(), "failing routes do not make the client unready");
+
+        let mut c = std::mem::MaybeUninit::<LnrHealth>::uninit();
+        assert!(unsafe { lnr_health(s.hclient, c.as_mut_ptr()) });
+        let c = unsafe { c.assume_init() };
+        assert_eq!((c.ready, c.live, c.registered, c.failing_routes), (1, 1, 1, 1));
+
+        assert!(s.stop());
+        assert!(!s.health().is_live());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn mempool_stats_and_compact_memory_after_burst() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_mempool_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let (rname, sname) = (format!("mp_r_{pid}"), format!("mp_s_{pid}"));
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let received_ = received.clone();
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        assert!(r.mempool_stats().is_empty());
+        assert_eq!(r.compact_memory(), 0);
+        assert!(r.run(Box::new(move |_: &str, _: &str, _: &[u8]| {
+            received_.fetch_add(1, Ordering::SeqCst);
+        })));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+
+        const COUNT: usize = 64;
+        // Incompressible, so the pools hold the full size.
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        let payload: Vec<u8> = (0..16 * 1024)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+        for _ in 0..COUNT {
+            assert!(s.send_to(&rname, &payload, true));
+        }
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < COUNT && start.elapsed() < std::time::Duration::from_secs(20) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), COUNT);
+        let start = std::time::Instant::now();
+        while s.peers().iter().any(|p| p.queue_depth > 0 || p.unacked() > 0)
+            && start.elapsed() < std::time::Duration::from_secs(10)
+        {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+
+        let r_addr = r.published_addr().unwrap();
+        let inbound: Vec<MempoolSlotStats> = r.mempool_stats().into_iter().filter(|m| m.inbound).collect();
+        assert_eq!(inbound.len(), 1);
+        let before = inbound[0].stats;
+        assert!(before.capacity_bytes > 0, "{:?}", before);
+        assert_eq!(before.used_bytes, 0, "every received message was freed");
+        let outbound = s.mempool_stats();
+        assert!(outbound.iter().any(|m| !m.inbound && m.addr == r_addr && m.stats.capacity_bytes > 0), "{:?}", outbound);
+
+        assert_eq!(r.compact_memory(), before.capacity_bytes);
+        let after = r.mempool_stats().into_iter().find(|m| m.inbound).unwrap().stats;
+        assert_eq!((after.capacity_bytes, after.free_blocks, after.pending_frees), (0, 0, 0));
+        assert!(unsafe { lnr_compact_memory(s.hclient) } > 0);
+
+        extern "C" fn count_rows(info: *const LnrMempoolInfo, udata: *mut libc::c_void) {
+            let info = unsafe { &*info };
+            assert!(info.used_bytes <= info.capacity_bytes);
+            unsafe { *(udata as *mut usize) += 1 };
+        }
+        let mut rows = 0usize;
+        assert!(unsafe { lnr_mempool_stats(s.hclient, Some(count_rows), &mut rows as *mut usize as *mut libc::c_void) });
+        assert_eq!(rows, s.mempool_stats().len());
+
+        // Pools grow again after a release.
+        assert!(s.send_to(&rname, b"again", true));
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < COUNT + 1 && start.elapsed() < std::time::Duration::from_secs(10) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), COUNT + 1);
+
+        assert!(s.stop() && r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn capture_records_sent_and_received_frames() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_capture_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let (r_cap, s_cap) = (dir.join("r.cap"), dir.join("s.cap"));
+        let (r_cap, s_cap) = (r_cap.to_str().unwrap(), s_cap.to_str().unwrap());
+        let pid = std::process::id();
+        let (rname, sname) = (format!("cap_r_{pid}"), format!("cap_s_{pid}"));
+
+        let received = Arc::new(AtomicUsize::new(0));
+        let received_ = received.clone();
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        assert!(!r.stop_capture());
+        assert!(!r.start_capture(""));
+        assert!(r.start_capture(r_cap));
+        assert!(r.run(Box::new(move |_: &str, _: &str, _: &[u8]| {
+            received_.fetch_add(1, Ordering::SeqCst);
+        })));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        assert!(unsafe { lnr_start_capture(s.hclient, CString::new(s_cap).unwrap().as_ptr()) });
+        for payload in [&b"one"[..], b"two", b"three"] {
+            assert!(s.send_to(&rname, payload, true));
+        }
+        let start = std::time::Instant::now();
+        while received.load(Ordering::SeqCst) < 3 && start.elapsed() < std::time::Duration::from_secs(10) {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+        }
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+        assert!(unsafe { lnr_stop_capture(s.hclient) });
+        assert!(r.stop_capture());
+
+        let payloads = |path: &str, inbound: bool| -> Vec<(String, Vec<u8>, bool)> {
+            capture::CaptureReader::open(path)
+                .unwrap()
+                .map(|rec| rec.unwrap())
+                .filter(|rec| rec.inbound == inbound && rec.topic == rname)
+                .map(|rec| {
+                    let m = rec.decode().unwrap();
+                    (rec.peer, m.payload, m.at_least_once)
+                })
+                .collect()
+        };
+        let sent = payloads(s_cap, false);
+        let r_addr = r.published_addr().unwrap();
+        let want: Vec<Vec<u8>> = vec![b"one".to_vec(), b"two".to_vec(), b"three".to_vec()];
+        assert_eq!(sent.iter().map(|p| p.1.clone()).collect::<Vec<_>>(), want);
+        assert!(sent.iter().all(|p| p.0 == r_addr && p.2));
+        let got = payloads(r_cap, true);
+        assert_eq!(got.iter().map(|p| p.1.clone()).collect::<Vec<_>>(), want);
+
+        assert!(s.stop() && r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(feature = "prometheus")]
+    fn http_get(addr: &str, path: &str) -> String {
+        use std::io::{Read, Write};
+        let mut stream = std::net::TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", path, addr).unwrap();
+        let mut resp = String::new();
+        stream.read_to_string(&mut resp).unwrap();
+        resp
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn serve_metrics_answers_prometheus_scrape() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_prom_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let (rname, sname) = (format!("p_r_{pid}"), format!("p_s_{pid}"));
+
+        let got = Arc::new(Mutex::new(0usize));
+        let got_ = got.clone();
+        let mut r = Liner::new_sqlite(&rname, &rname, "127.0.0.1:0", db, "");
+        assert!(r.run(Box::new(move |_: &str, _: &str, _: &[u8]| *got_.lock().unwrap() += 1)));
+        let mut s = Liner::new_sqlite(&sname, &sname, "127.0.0.1:0", db, "");
+        assert!(s.serve_metrics("127.0.0.1:0"), "endpoint works before run");
+        let addr = s.metrics_addr().unwrap();
+        assert!(http_get(&addr, "/metrics").starts_with("HTTP/1.1 200 OK"));
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        for _ in 0..3 {
+            assert!(s.send_to(&rname, b"abc", true));
+        }
+        let start = std::time::Instant::now();
+        while *got.lock().unwrap() < 3 && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(*got.lock().unwrap(), 3);
+
+        let resp = http_get(&addr, "/metrics");
+        assert!(resp.starts_with("HTTP/1.1 200 OK"), "{}", resp);
+        assert!(resp.contains("Content-Type: text/plain; version=0.0.4"));
+        let series = format!("liner_topic_messages_sent_total{{client=\"{}\",topic=\"{}\"}} 3\n", sname, rname);
+        assert!(resp.contains(&series), "{}", resp);
+        assert!(resp.contains("liner_enqueue_to_write_ms_count"));
+        assert!(resp.contains("side=\"send\""));
+        assert!(http_get(&addr, "/other").starts_with("HTTP/1.1 404"));
+
+        assert!(!s.serve_metrics(&r.bound_listen_addr().unwrap()), "port in use");
+        assert_eq!(s.last_error_code(), ErrorCode::Bind as i32);
+
+        assert!(s.stop());
+        assert!(r.stop());
+        drop(s);
+        assert!(std::net::TcpStream::connect(&addr).is_err(), "endpoint closes with the client");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn run_ex_reports_sender_number_timestamp_and_replay() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_run_ex_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let topic = format!("ex_events_{pid}");
+        let name = format!("ex_r_{pid}");
+        let sender = format!("ex_s_{pid}");
+
+        type Seen = Vec<(String, String, u64, u64, bool, Vec<u8>)>;
+        let seen: Arc<Mutex<Seen>> = Arc::new(Mutex::new(Vec::new()));
+        let wait_for = |n: usize| {
+            let start = std::time::Instant::now();
+            while seen.lock().unwrap().len() < n && start.elapsed() < std::time::Duration::from_secs(5) {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            seen.lock().unwrap().clone()
+        };
+        let recorder = |seen: Arc<Mutex<Seen>>| -> UCbackEx {
+            Box::new(move |info: &DeliveryInfo, data: &[u8]| {
+                seen.lock().unwrap().push((
+                    info.from.to_string(),
+                    info.sender_name.to_string(),
+                    info.number,
+                    info.sent_at_ms,
+                    info.replayed,
+                    data.to_vec(),
+                ));
+            })
+        };
+
+        // Durable subscriber goes down: the sender queues in its offline store.
+        let mut a = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db, "");
+        assert!(a.run_ex(recorder(seen.clone())));
+        assert!(a.subscribe_durable(&topic));
+        assert!(a.stop());
+        let mut s = Liner::new_sqlite(&sender, &sender, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+        let before = common::current_time_ms();
+        assert!(s.send_all(&topic, b"queued", true));
+        std::thread::sleep(std::time::Duration::from_millis(300));
+
+        // Back under the same unique_name: the backlog is replayed, then live traffic.
+        let mut b = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db, "");
+        assert!(b.run_ex(recorder(seen.clone())));
+        assert!(b.subscribe_durable(&topic));
+        assert_eq!(wait_for(1).len(), 1, "backlog not delivered");
+        assert!(s.send_all(&topic, b"live", true));
+        let got = wait_for(2);
+        assert_eq!(got.len(), 2);
+        for (from, sender_name, _, sent_at, _, _) in &got {
+            assert_eq!(from, &sender);
+            assert_eq!(sender_name, &sender);
+            assert!(*sent_at >= before && *sent_at <= common::current_time_ms());
+        }
+        assert_eq!((got[0].4, got[0].5.as_slice()), (true, b"queued".as_slice()));
+        assert_eq!((got[1].4, got[1].5.as_slice()), (false, b"live".as_slice()));
+        assert!(got[1].2 > got[0].2);
+
+        assert!(s.stop());
+        assert!(b.stop());
+        drop(a);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn status_events_report_rejected_frames_typed_and_as_c_kind() {
+        use std::io::Write;
+        let dir = std::env::temp_dir().join(format!(
+            "liner_status_ev_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let name = format!("status_ev_{}", std::process::id());
+        let mut c = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db.to_str().unwrap(), "");
+        let kinds: Arc<Mutex<Vec<(i32, String, String)>>> = Arc::new(Mutex::new(Vec::new()));
+        let kinds_ = kinds.clone();
+        c.set_status_callback(Some(Box::new(move |kind: i32, _: &str, peer: &str, message: &str| {
+            kinds_.lock().unwrap().push((kind, peer.to_owned(), message.to_owned()));
+        })));
+        let events = c.status_events(16);
+        assert!(c.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+
+        let mut peer = std::net::TcpStream::connect(c.bound_listen_addr().unwrap()).unwrap();
+        let addr = peer.local_addr().unwrap().to_string();
+        peer.write_all(&u32::MAX.to_be_bytes()).unwrap();
+        let event = events.recv_timeout(std::time::Duration::from_secs(5)).expect("no status event");
+        assert_eq!(
+            event,
+            StatusEvent::FrameRejected {
+                topic: String::new(),
+                addr: addr.clone(),
+                error: FrameError::Oversized { len: u32::MAX as usize, max: settings::max_message_size() },
+            }
+        );
+        assert_eq!(event.kind(), LNR_LISTENER_FRAME_ERROR);
+        let kinds = kinds.lock().unwrap().clone();
+        assert_eq!(kinds.len(), 1);
+        assert_eq!((kinds[0].0, kinds[0].1.as_str()), (LNR_LISTENER_FRAME_ERROR, addr.as_str()));
+        assert!(kinds[0].2.starts_with("frame from "), "{}", kinds[0].2);
+
+        assert!(c.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn trace_context_of_the_send_is_current_in_the_receive_callback() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_trace_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let name = format!("trace_r_{pid}");
+        let sender = format!("trace_s_{pid}");
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+        type Seen = Vec<(Option<TraceContext>, Option<String>)>;
+        let seen: Arc<Mutex<Seen>> = Arc::new(Mutex::new(Vec::new()));
+        let seen_ = seen.clone();
+        let mut r = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db, "");
+        assert!(r.run_ex(Box::new(move |info: &DeliveryInfo, _: &[u8]| {
+            let current = unsafe { lnr_get_trace_context() };
+            let current = (!current.is_null())
+                .then(|| unsafe { CStr::from_ptr(current) }.to_string_lossy().into_owned());
+            seen_.lock().unwrap().push((info.trace, current));
+        })));
+        let mut s = Liner::new_sqlite(&sender, &sender, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+
+        unsafe {
+            assert!(!lnr_set_trace_context(c"00-zz".as_ptr()));
+            assert!(lnr_set_trace_context(CString::new(traceparent).unwrap().as_ptr()));
+        }
+        assert!(s.send_to(&name, b"traced", true));
+        unsafe {
+            assert!(lnr_set_trace_context(ptr::null()));
+            assert!(lnr_get_trace_context().is_null());
+        }
+        assert!(s.send_to(&name, b"plain", true));
+
+        let start = std::time::Instant::now();
+        while seen.lock().unwrap().len() < 2 && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let got = seen.lock().unwrap().clone();
+        assert_eq!(got.len(), 2);
+        assert_eq!(got[0].0, TraceContext::parse(traceparent));
+        assert_eq!(got[0].1.as_deref(), Some(traceparent));
+        assert_eq!(got[1], (None, None), "context of the previous message leaked");
+
+        assert!(s.stop());
+        assert!(r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn scheduled_send_keeps_the_trace_context_it_was_scheduled_under() {
+        let dir = std::env::temp_dir().join(format!(
+            "liner_trace_sched_{}_{}",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("shared.sqlite");
+        let db = db.to_str().unwrap();
+        let pid = std::process::id();
+        let name = format!("trace_sched_r_{pid}");
+        let sender = format!("trace_sched_s_{pid}");
+        let ctx = TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01");
+
+        let seen: Arc<Mutex<Vec<Option<TraceContext>>>> = Arc::new(Mutex::new(Vec::new()));
+        let seen_ = seen.clone();
+        let mut r = Liner::new_sqlite(&name, &name, "127.0.0.1:0", db, "");
+        assert!(r.run_ex(Box::new(move |info: &DeliveryInfo, _: &[u8]| {
+            seen_.lock().unwrap().push(info.trace);
+        })));
+        let mut s = Liner::new_sqlite(&sender, &sender, "127.0.0.1:0", db, "");
+        assert!(s.run(Box::new(|_: &str, _: &str, _: &[u8]| {})));
+
+        Liner::set_trace_context(ctx);
+        assert!(s.send_to_after(&name, b"traced", 0, true));
+        Liner::set_trace_context(None);
+        assert!(s.send_to_after(&name, b"plain", 200, true));
+
+        let start = std::time::Instant::now();
+        while seen.lock().unwrap().len() < 2 && start.elapsed() < std::time::Duration::from_secs(5) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(*seen.lock().unwrap(), vec![ctx, None]);
+
+        assert!(s.stop());
+        assert!(r.stop());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
 
 
@@ -1120,6 +3371,7 @@ pub unsafe extern "C" fn lnr_clear_stored_messages(client: *mut Client)->bool{
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     (*client).clear_stored_messages()
 }
 
@@ -1135,6 +3387,7 @@ pub unsafe extern "C" fn lnr_clear_addresses_of_topic(client: *mut Client)->bool
     if !has_client(client){
         return false;
     }
+    let _log = client_log_scope(client);
     (*client).clear_addresses_of_topic()
 }
 
@@ -1167,11 +3420,29 @@ fn has_client(client: *mut Client)->bool{
         .unwrap_or(false)
 }
 
+/// Attribute this FFI call's log records to `client` (already checked by `has_client`).
+unsafe fn client_log_scope(client: *mut Client) -> log::ClientScope {
+    log::client_scope((*client).log_name())
+}
+
 #[macro_export]
 macro_rules! print_error {
     ($arg:expr) => {{
-        let line = format!("Error {}:{}: {}", file!(), line!(), $arg);
-        $crate::log::emit_error_line(&line);
+        $crate::log::emit($crate::log::LogLevel::Error, file!(), line!(), &format!("{}", $arg));
+    }}
+}
+
+#[macro_export]
+macro_rules! print_warn {
+    ($arg:expr) => {{
+        $crate::log::emit($crate::log::LogLevel::Warn, file!(), line!(), &format!("{}", $arg));
+    }}
+}
+
+#[macro_export]
+macro_rules! print_info {
+    ($arg:expr) => {{
+        $crate::log::emit($crate::log::LogLevel::Info, file!(), line!(), &format!("{}", $arg));
     }}
 }
 
@@ -1179,7 +3450,9 @@ macro_rules! print_error {
 #[cfg(feature = "liner_debug")]
 #[macro_export]
 macro_rules! print_debug {
-    ($( $args:expr ),*) => { println!("Debug", $( $args ),* ) }
+    ($arg:expr) => {{
+        $crate::log::emit($crate::log::LogLevel::Debug, file!(), line!(), &format!("{}", $arg));
+    }}
 }
 
 // Non-debug version
@@ -1,13 +1,19 @@
-use crate::message::Message;
-use crate::mempool::Mempool;
+use crate::bytestream::FrameError;
+use crate::message::{Message, Priority};
+use crate::mempool::{self, Mempool, MempoolSlotStats};
+use crate::metrics::{Metrics, ReceivedSample};
+use crate::peers::PeerInfo;
 use crate::store::Store;
 use crate::settings;
 use crate::common;
-use crate::{UCbackIntern, UData};
-use crate::{print_error, print_debug};
-use crate::status::{StatusEmitter, StatusMsg, LNR_LISTENER_STORE_ERROR};
-
-use std::collections::HashMap;
+use crate::log;
+use crate::trace;
+use crate::topic_pattern;
+use crate::{UCbackExIntern, UData};
+use crate::{print_error, print_debug, print_warn};
+use crate::status::{StatusEmitter, StatusEvent, StoreOp};
+
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 use std::thread::JoinHandle;
 use std::thread;
@@ -24,15 +30,69 @@ use mio::{Events, Interest, Poll, Token, Waker};
 struct ReadStream{
     stream: Option<TcpStream>,
     is_active: bool,
-    is_close: bool
+    is_close: bool,
+    /// Reads stopped by backpressure; the poll thread resumes them (see [`Backpressure`]).
+    is_paused: bool,
+    /// Bytes already read from the socket when reads paused; parsed first on resume.
+    unread: Vec<u8>,
+}
+
+/// Peers whose socket reads are paused until the receive thread drains their slot
+/// below [`settings::LISTENER_MAX_PENDING_PER_PEER`]. Read tasks never wait on the
+/// shared rayon pool: they stop, and the poll thread starts a new one once woken.
+struct Backpressure{
+    paused: Mutex<HashSet<usize>>,
+    waker: Arc<Waker>,
+}
+
+impl Backpressure{
+    fn new(waker: Arc<Waker>) -> Backpressure{
+        Backpressure{ paused: Mutex::new(HashSet::new()), waker }
+    }
+    /// Called by a read task after it put its stream back. Wakes the poll thread at once,
+    /// as the slot may have drained before the peer was listed.
+    fn pause(&self, token: usize){
+        if let Ok(mut paused) = self.paused.lock(){
+            paused.insert(token);
+        }
+        self.wake();
+    }
+    /// The receive thread took messages: let the poll thread re-check paused peers.
+    fn notify_drained(&self){
+        if self.paused.lock().is_ok_and(|p| !p.is_empty()){
+            self.wake();
+        }
+    }
+    /// Paused peers whose slot is below the limit again; they are no longer listed.
+    fn take_ready(&self, messages: &Arc<Mutex<MessList>>) -> Vec<usize>{
+        let Ok(mut paused) = self.paused.lock() else {
+            return Vec::new();
+        };
+        let ready: Vec<usize> = paused.iter().copied().filter(|ix| !slot_is_full(*ix, messages)).collect();
+        for ix in &ready{
+            paused.remove(ix);
+        }
+        ready
+    }
+    fn wake(&self){
+        if let Err(err) = self.waker.wake() {
+            print_error!(&format!("unable to wake stream thread: {}", err));
+        }
+    }
 }
 
 struct Sender{
     sender_topic: String,
+    /// Sender's unique name; empty if it did not record one in the store.
+    sender_name: String,
     connection_key: i32,
     last_mess_num: u64,
     last_mess_num_preview: u64,
     last_mess_num_saved: u64,
+    /// Peer socket address of the slot.
+    addr: String,
+    connected: bool,
+    last_activity_ms: u64,
 }
 
 type MessList = Vec<Option<Vec<Message>>>; 
@@ -40,6 +100,33 @@ type MempoolList = Vec<Arc<Mutex<Mempool>>>;
 type SenderList = Vec<Sender>;
 type ReadStreamList = Vec<Arc<Mutex<ReadStream>>>; 
 
+/// Topic key → topic of the client's own topic and its subscriptions (patterns included).
+/// `version` changes on every edit, so [`TopicResolver`] can keep per-key results.
+#[derive(Default)]
+struct ListenerTopics{
+    topics: HashMap<i32, String>,
+    version: u64,
+}
+
+impl ListenerTopics{
+    fn insert(&mut self, topic_key: i32, topic: String){
+        self.topics.insert(topic_key, topic);
+        self.version += 1;
+    }
+    fn remove(&mut self, topic_key: i32){
+        self.topics.remove(&topic_key);
+        self.version += 1;
+    }
+}
+
+impl From<HashMap<i32, String>> for ListenerTopics{
+    fn from(topics: HashMap<i32, String>) -> Self{
+        ListenerTopics{ topics, version: 0 }
+    }
+}
+/// Retained `(topic, sender_topic, payload)` values waiting for the receive callback.
+pub type RetainedList = Vec<(String, String, Vec<u8>)>;
+
 /// Accept/token index `ix` is shared across `streams[ix]`, `senders[ix]`, `mempools[ix]`,
 /// and `messages[ix]`, and is owned by one `SocketAddr` in the accept map for the life of
 /// that peer identity. Do **not** recycle `ix` into a free-list for a different address:
@@ -49,32 +136,61 @@ pub struct Listener{
     stream_thread: Option<JoinHandle<()>>,
     receive_thread: Option<JoinHandle<()>>,
     receive_thread_cvar: Arc<(Mutex<bool>, Condvar)>,
-    listener_topic: Arc<Mutex<HashMap<i32, String>>>,
+    listener_topic: Arc<Mutex<ListenerTopics>>,
+    retained: Arc<Mutex<RetainedList>>,
+    mempools: Arc<Mutex<MempoolList>>,
+    senders: Arc<Mutex<SenderList>>,
+    messages: Arc<Mutex<MessList>>,
+    metrics: Metrics,
     is_close: Arc<AtomicBool>,
     waker: Arc<Waker>,
 }
 
+/// Per-listener handles the poll thread hands to each [`read_stream`] task.
+#[derive(Clone)]
+struct ListenerShared{
+    senders: Arc<Mutex<SenderList>>,
+    mempools: Arc<Mutex<MempoolList>>,
+    messages: Arc<Mutex<MessList>>,
+    receive_thread_cvar: Arc<(Mutex<bool>, Condvar)>,
+    backpressure: Arc<Backpressure>,
+    status_emitter: StatusEmitter,
+    db: Arc<Mutex<dyn Store>>,
+}
+
+/// Receive callback of a listener and the threads that run it (see [`Dispatch`]).
+pub(crate) struct ReceiveCallback{
+    pub cb: UCbackExIntern,
+    pub udata: UData,
+    pub workers: usize,
+}
+
 impl Listener {
     pub fn new(mut listener: TcpListener,
-               db: Arc<Mutex<dyn Store>>, source_topic: &str, subscriptions: &HashMap<i32, String>, receive_cb: UCbackIntern, udata: UData,
+               db: Arc<Mutex<dyn Store>>, source_topic: &str, subscriptions: &HashMap<i32, String>, receive: ReceiveCallback,
                status_emitter: StatusEmitter)->Result<Listener, String>{
+        let ReceiveCallback{ cb: receive_cb, udata, workers: receive_workers } = receive;
         #[cfg(test)]
         if test_force_listener_new_error_load() {
             return Err("test inject: listener new failed".to_string());
         }
         let mut poll = Poll::new().map_err(|e| format!("couldn't create poll queue: {}", e))?;
         let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new(Vec::new()));
-        let mut messages_ = messages.clone();
-        let mut mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(Vec::new()));
+        let messages_ = messages.clone();
+        let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(Vec::new()));
         let mempools_= mempools.clone();
-        let mut senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(Vec::new()));
+        let mempools_stats = mempools.clone();
+        let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(Vec::new()));
         let senders_ = senders.clone();
+        let senders_stats = senders.clone();
+        let messages_stats = messages.clone();
         db.lock().map_err(|_| "db lock poisoned".to_string())?.set_source_topic(source_topic);
         let db_ = db.clone();
+        let metrics = status_emitter.metrics().clone();
         let status_emitter_stream = status_emitter.clone();
         let status_emitter_recv = status_emitter;
       
-        let listener_topic: Arc<Mutex<HashMap<i32, String>>> = Arc::new(Mutex::new(HashMap::new())); // key listener_topic, value key
+        let listener_topic: Arc<Mutex<ListenerTopics>> = Arc::new(Mutex::new(ListenerTopics::default()));
         let topic_key = db
             .lock()
             .map_err(|_| "db lock poisoned".to_string())?
@@ -85,6 +201,9 @@ impl Listener {
             for s in subscriptions.iter(){
                 lr.insert(*s.0, s.1.clone());
             }
+            for (key, topic) in lr.topics.iter(){
+                metrics.name_topic(*key, topic);
+            }
         }
 
         let receive_thread_cvar: Arc<(Mutex<bool>, Condvar)> = Arc::new((Mutex::new(false), Condvar::new()));
@@ -98,7 +217,21 @@ impl Listener {
         let waker = Arc::new(
             Waker::new(poll.registry(), WAKER).map_err(|e| format!("couldn't create Waker: {}", e))?,
         );
+        let is_close = Arc::new(AtomicBool::new(false));
+        let is_close_stream = is_close.clone();
+        let backpressure = Arc::new(Backpressure::new(waker.clone()));
+        let backpressure_recv = backpressure.clone();
+        let shared = ListenerShared{
+            senders,
+            mempools,
+            messages: messages_,
+            receive_thread_cvar: receive_thread_cvar_,
+            backpressure,
+            status_emitter: status_emitter_stream,
+            db,
+        };
         let stream_thread = thread::spawn(move|| {            
+            let _log = log::client_scope(shared.status_emitter.client_name());
             let mut streams: ReadStreamList = Vec::new();
             // Sticky SocketAddr → slot index. Kept across TCP close so reconnect reuses the
             // same streams/senders/mempools/messages index (never hand a freed ix to another addr).
@@ -112,74 +245,182 @@ impl Listener {
                     }
                 }
                 let mut has_wake = false;
+                let mut has_resume = false;
                 for ev in &events {
                     match ev.token() {                    
                         SERVER => {
-                            listener_accept(&poll, &mut address, &mut streams, &mut senders, &listener, 
-                                &mut mempools, &mut messages_);
+                            listener_accept(&poll, &mut address, &mut streams, &shared.senders, &listener, 
+                                &shared.mempools, &shared.messages);
                         }
                         WAKER => {
-                            has_wake = true;
-                            break;
+                            if is_close_stream.load(Ordering::Relaxed){
+                                has_wake = true;
+                                break;
+                            }
+                            has_resume = true;
                         }
                         client =>{
                             if let Some(stream) = streams.get(client.0){
-                                read_stream(client, stream, &senders,
-                                            db.clone(), &mempools, &messages_, &receive_thread_cvar_,
-                                            status_emitter_stream.clone());
+                                read_stream(client, stream, &shared);
                             }
                         }                        
                     }
                 }
+                if has_resume && !has_wake{
+                    // Edge-triggered: the socket won't signal again for data already there.
+                    for ix in shared.backpressure.take_ready(&shared.messages){
+                        if let Some(stream) = streams.get(ix){
+                            if let Ok(mut s) = stream.lock(){
+                                s.is_paused = false;
+                            }
+                            read_stream(Token(ix), stream, &shared);
+                        }
+                    }
+                }
                 cleanup_closed_streams(&poll, &mut streams);
                 if has_wake{
                     break;
                 }
             }
-            update_last_mess_number(&senders, &db, &status_emitter_stream);       
+            update_last_mess_number(&shared.senders, &shared.db, &shared.status_emitter);
         });
 
         let receive_thread_cvar_ = receive_thread_cvar.clone();
-        let is_close = Arc::new(AtomicBool::new(false));
         let is_close_ = is_close.clone();
         let listener_topic_ = listener_topic.clone();
+        let retained: Arc<Mutex<RetainedList>> = Arc::new(Mutex::new(Vec::new()));
+        let retained_ = retained.clone();
         let receive_thread = thread::spawn(move|| {
+            let _log = log::client_scope(status_emitter_recv.client_name());
             let mut prev_time: [u64; 1] = [common::current_time_ms(); 1];
-            let mut buff_data: Vec<u8> = vec![0; 4086];
+            let mut dispatch = Dispatch::new(receive_workers, receive_thread_cvar_.clone());
+            let mut topics = TopicResolver::new(listener_topic_, db_.clone())
+                .with_retained(retained_)
+                .with_status(status_emitter_recv.clone());
             while !is_close_.load(Ordering::Relaxed){
                 let (lock, cvar) = &*receive_thread_cvar_;
                 let mut has_new_mess = false;
                 if let Ok(mut _started) = lock.lock(){
-                    has_new_mess = messages.lock().unwrap().iter().any(|m: &Option<Vec<Message>>| m.is_some());                 
+                    has_new_mess = topics.has_retained() || dispatch.has_ready(&messages);
                     if !has_new_mess{
                         *_started = false;
                         _started = cvar.wait_timeout(_started, Duration::from_millis(settings::LISTENER_THREAD_WAIT_TIMEOUT_MS)).unwrap().0;
-                        has_new_mess = *_started || messages.lock().unwrap().iter().any(|m: &Option<Vec<Message>>| m.is_some());
+                        has_new_mess = *_started || dispatch.has_ready(&messages);
                     }
                 }
                 if has_new_mess{
-                    do_receive_cb(&messages, &mempools_, &senders_, &listener_topic_, receive_cb, &mut buff_data, &udata); 
+                    dispatch.deliver_retained(&topics, receive_cb, &udata);
+                    do_receive_cb(&messages, &mempools_, &senders_, &mut topics, receive_cb, &mut dispatch, &udata); 
+                    backpressure_recv.notify_drained();
                 } 
                 let ctime = common::current_time_ms();
                 if timeout_update_last_mess_number(ctime, &mut prev_time[0]){                    
                     update_last_mess_number(&senders_, &db_, &status_emitter_recv);
                 }
             }
+            // `udata` must outlive callbacks still running on the pool.
+            dispatch.wait_idle();
         });        
         Ok(Self{            
             stream_thread: Some(stream_thread),
             receive_thread: Some(receive_thread),
             receive_thread_cvar,
             listener_topic,
+            retained,
+            mempools: mempools_stats,
+            senders: senders_stats,
+            messages: messages_stats,
+            metrics,
             is_close,
             waker
         })
     }
     pub fn subscribe(&mut self, topic: &str, topic_key: i32){
+        self.metrics.name_topic(topic_key, topic);
         self.listener_topic.lock().unwrap().insert(topic_key, topic.to_owned());
     }   
     pub fn unsubscribe(&mut self, topic_key: i32){
-        self.listener_topic.lock().unwrap().remove(&topic_key);
+        self.listener_topic.lock().unwrap().remove(topic_key);
+    }
+    /// Bytes held by the per-connection receive mempools.
+    pub fn mempool_bytes(&self) -> u64 {
+        mempool::capacity_bytes(&self.mempools)
+    }
+    /// Mempool occupancy of every connection slot, in accept order.
+    pub fn mempool_stats(&self) -> Vec<MempoolSlotStats> {
+        let slots: Vec<(String, i32)> = self
+            .senders
+            .lock()
+            .map(|s| s.iter().map(|s| (s.addr.clone(), s.connection_key)).collect())
+            .unwrap_or_default();
+        let mempools: MempoolList = self.mempools.lock().map(|m| m.clone()).unwrap_or_default();
+        mempools
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, mp)| {
+                let stats = mp.lock().ok()?.stats();
+                let (addr, connection_key) = slots.get(ix).cloned().unwrap_or_else(|| (String::new(), -1));
+                Some(MempoolSlotStats { inbound: true, slot: ix, addr, connection_key, stats })
+            })
+            .collect()
+    }
+    /// [`Mempool::compact`] every slot pool not in use right now; returns bytes released.
+    pub fn compact_memory(&self) -> u64 {
+        mempool::compact_idle(&self.mempools)
+    }
+    /// Both the accept and the receive thread are still running.
+    pub fn is_alive(&self) -> bool {
+        [&self.stream_thread, &self.receive_thread]
+            .iter()
+            .all(|t| t.as_ref().is_some_and(|t| !t.is_finished()))
+    }
+    /// Live state of every inbound connection slot, in accept order.
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let Ok(senders) = self.senders.lock() else {
+            return Vec::new();
+        };
+        let mempools: MempoolList = self.mempools.lock().map(|m| m.clone()).unwrap_or_default();
+        let depths: Vec<u64> = self
+            .messages
+            .lock()
+            .map(|ml| ml.iter().map(|m| m.as_ref().map_or(0, |m| m.len() as u64)).collect())
+            .unwrap_or_default();
+        senders
+            .iter()
+            .enumerate()
+            .map(|(ix, s)| PeerInfo {
+                inbound: true,
+                addr: s.addr.clone(),
+                topic: s.sender_topic.clone(),
+                unique_name: s.sender_name.clone(),
+                priority: Priority::Normal,
+                connection_key: s.connection_key,
+                connected: s.connected,
+                last_activity_ms: s.last_activity_ms,
+                last_send_mess_number: s.last_mess_num_preview,
+                last_mess_number: s.last_mess_num_saved,
+                queue_depth: depths.get(ix).copied().unwrap_or(0),
+                mempool_bytes: mempools
+                    .get(ix)
+                    .and_then(|mp| mp.lock().ok().map(|mp| mp.capacity_bytes() as u64))
+                    .unwrap_or(0),
+            })
+            .collect()
+    }
+    /// Queue retained values for the receive callback. Call before [`subscribe`](Self::subscribe)
+    /// of their topic: they are delivered ahead of any live message on it.
+    pub fn deliver_retained(&self, retained: RetainedList){
+        if retained.is_empty(){
+            return;
+        }
+        let (lock, cvar) = &*self.receive_thread_cvar;
+        if let Ok(mut started) = lock.lock(){
+            if let Ok(mut pending) = self.retained.lock(){
+                pending.extend(retained);
+            }
+            *started = true;
+            cvar.notify_one();
+        }
     }
 }
 
@@ -210,104 +451,376 @@ pub fn test_force_listener_new_error() -> ForceListenerNewErrorGuard {
     ForceListenerNewErrorGuard
 }
 
+/// How the receive thread runs callbacks: inline, or on a pool with one batch in
+/// flight per sender connection (order within a `connection_key` is kept). The pool is
+/// handed at most one batch per idle worker, so a raised priority lane never queues behind
+/// batches of a lower one.
+pub(crate) enum Dispatch{
+    Serial(Vec<u8>),
+    Pool(DispatchPool),
+}
+
+pub(crate) struct DispatchPool{
+    pool: rayon::ThreadPool,
+    workers: usize,
+    /// Slots whose batch is still running; their new messages wait in the buffer.
+    busy: Arc<(Mutex<HashSet<usize>>, Condvar)>,
+    receive_thread_cvar: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Dispatch{
+    fn new(workers: usize, receive_thread_cvar: Arc<(Mutex<bool>, Condvar)>) -> Dispatch{
+        if workers > 1{
+            match rayon::ThreadPoolBuilder::new()
+                .num_threads(workers)
+                .thread_name(|i| format!("liner-receive-{}", i))
+                .build(){
+                Ok(pool) => {
+                    return Dispatch::Pool(DispatchPool{
+                        pool,
+                        workers,
+                        busy: Arc::new((Mutex::new(HashSet::new()), Condvar::new())),
+                        receive_thread_cvar,
+                    });
+                }
+                Err(err) => {
+                    print_warn!(&format!("couldn't build receive pool, dispatch inline: {}", err));
+                }
+            }
+        }
+        Dispatch::Serial(vec![0; 4086])
+    }
+    fn is_busy(&self, ix: usize) -> bool{
+        match self {
+            Dispatch::Serial(_) => false,
+            Dispatch::Pool(p) => p.busy.0.lock().map(|b| b.contains(&ix)).unwrap_or(false),
+        }
+    }
+    /// Batches that can start now.
+    fn free_workers(&self) -> usize{
+        match self {
+            Dispatch::Serial(_) => usize::MAX,
+            Dispatch::Pool(p) => p.busy.0.lock().map(|b| p.workers.saturating_sub(b.len())).unwrap_or(0),
+        }
+    }
+    /// A slot has messages, no batch of it is running and a worker is free.
+    fn has_ready(&self, message_buffer: &Arc<Mutex<MessList>>) -> bool{
+        self.free_workers() > 0 &&
+            message_buffer.lock().unwrap().iter().enumerate().any(|(ix, m)| m.is_some() && !self.is_busy(ix))
+    }
+    /// Retained values go ahead of live messages of their topic: on the pool, batches
+    /// already in flight finish before the retained callbacks run.
+    fn deliver_retained(&self, topics: &TopicResolver, receive_cb: UCbackExIntern, udata: &UData){
+        if topics.has_retained(){
+            self.wait_idle();
+            topics.deliver_retained(receive_cb, udata);
+        }
+    }
+    fn wait_idle(&self){
+        if let Dispatch::Pool(p) = self{
+            let (lock, cvar) = &*p.busy;
+            if let Ok(mut busy) = lock.lock(){
+                while !busy.is_empty(){
+                    busy = cvar.wait(busy).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// One sender connection's messages with their topics resolved on the receive thread.
+struct ReceiveBatch{
+    ix: usize,
+    topic_from: CString,
+    sender_name: CString,
+    mempool: Arc<Mutex<Mempool>>,
+    status: StatusEmitter,
+    topics: HashMap<i32, CString>,
+    mess: Vec<Message>,
+}
+
 fn do_receive_cb(message_buffer: &Arc<Mutex<MessList>>,
                  mempools: &Arc<Mutex<MempoolList>>,
                  senders: &Arc<Mutex<SenderList>>,
-                 listener_topic: &Arc<Mutex<HashMap<i32, String>>>,
-                 receive_cb: UCbackIntern,
-                 buff_data: &mut Vec<u8>,
+                 topics: &mut TopicResolver,
+                 receive_cb: UCbackExIntern,
+                 dispatch: &mut Dispatch,
                  udata: &UData){
 
-    let mut mess_from_buff: Vec<Option<Vec<Message>>> = Vec::new();
-    for m in message_buffer.lock().unwrap().iter_mut(){
-        mess_from_buff.push(m.take());
-    }
-    for (ix, mess) in mess_from_buff.into_iter().enumerate(){
-        if let Some(mess) = mess{
-            let sender_topic = match senders.lock() {
-                Ok(s) => match s.get(ix) {
-                    Some(sender) => sender.sender_topic.clone(),
-                    None => {
-                        print_error!(&format!("do_receive_cb: sender index out of bounds: {}", ix));
-                        continue;
-                    }
-                },
-                Err(_) => {
-                    print_error!("do_receive_cb: senders lock poisoned");
+    let mut mess_from_buff: Vec<(usize, Vec<Message>)> = Vec::new();
+    {
+        let mut buffer = message_buffer.lock().unwrap();
+        let mut ready: Vec<(usize, Priority)> = buffer.iter().enumerate()
+            .filter(|(ix, m)| m.is_some() && !dispatch.is_busy(*ix))
+            .map(|(ix, m)| (ix, m.as_ref().and_then(|m| m.first()).map(|m| m.priority()).unwrap_or_default()))
+            .collect();
+        // A connection carries one priority lane: run callbacks of raised lanes first.
+        // Slots beyond the free workers stay buffered and compete again next round.
+        ready.sort_by_key(|(_, priority)| std::cmp::Reverse(*priority));
+        ready.truncate(dispatch.free_workers());
+        for (ix, _) in ready{
+            if let Some(m) = buffer[ix].take() {
+                mess_from_buff.push((ix, m));
+            }
+        }
+    }
+    for (ix, mess) in mess_from_buff{
+        let (sender_topic, sender_name) = match senders.lock() {
+            Ok(s) => match s.get(ix) {
+                Some(sender) => (sender.sender_topic.clone(), sender.sender_name.clone()),
+                None => {
+                    print_error!(&format!("do_receive_cb: sender index out of bounds: {}", ix));
                     continue;
                 }
-            };
-            let topic_from = CString::new(sender_topic.as_bytes()).unwrap_or_else(|_| CString::new("").unwrap());
-            let mut last_mess_num = 0;
-            let mut topic_cstr_cache: HashMap<i32, CString> = HashMap::new();
-            let mempool = match mempools.lock() {
-                Ok(mp) => match mp.get(ix) {
-                    Some(m) => m.clone(),
-                    None => {
-                        print_error!(&format!("do_receive_cb: mempool index out of bounds: {}", ix));
-                        continue;
-                    }
-                },
-                Err(_) => {
-                    print_error!("do_receive_cb: mempools lock poisoned");
+            },
+            Err(_) => {
+                print_error!("do_receive_cb: senders lock poisoned");
+                continue;
+            }
+        };
+        let topic_from = CString::new(sender_topic.as_bytes()).unwrap_or_else(|_| CString::new("").unwrap());
+        let sender_name = CString::new(sender_name.as_bytes()).unwrap_or_else(|_| CString::new("").unwrap());
+        let mempool = match mempools.lock() {
+            Ok(mp) => match mp.get(ix) {
+                Some(m) => m.clone(),
+                None => {
+                    print_error!(&format!("do_receive_cb: mempool index out of bounds: {}", ix));
                     continue;
                 }
-            };
-            for m in mess{
-                // Important: don't hold the `listener_topic` lock while calling `receive_cb`:
-                // callback can be slow, and we don't want to block subscribe/unsubscribe.
-                if !topic_cstr_cache.contains_key(&m.listener_topic_key) {
-                    let topic = match listener_topic.lock() {
-                        Ok(lt) => lt.get(&m.listener_topic_key).cloned(),
-                        Err(_) => {
-                            print_error!("do_receive_cb: listener_topic lock poisoned");
-                            None
-                        }
-                    };
-                    if let Some(topic) = topic {
-                        let topic_to = CString::new(topic.as_bytes())
-                            .unwrap_or_else(|_| CString::new("").unwrap());
-                        topic_cstr_cache.insert(m.listener_topic_key, topic_to);
+            },
+            Err(_) => {
+                print_error!("do_receive_cb: mempools lock poisoned");
+                continue;
+            }
+        };
+        // Important: don't hold the `listener_topic` lock while calling `receive_cb`:
+        // callback can be slow, and we don't want to block subscribe/unsubscribe.
+        let mut topic_cstr_cache: HashMap<i32, CString> = HashMap::new();
+        for m in mess.iter(){
+            if !topic_cstr_cache.contains_key(&m.listener_topic_key) {
+                // Retained values queued before this topic was subscribed go first.
+                dispatch.deliver_retained(topics, receive_cb, udata);
+                if let Some(topic) = topics.resolve(m.listener_topic_key) {
+                    let topic_to = CString::new(topic.as_bytes())
+                        .unwrap_or_else(|_| CString::new("").unwrap());
+                    topic_cstr_cache.insert(m.listener_topic_key, topic_to);
+                }
+            }
+        }
+        let status = topics.status.clone();
+        let batch = ReceiveBatch{ ix, topic_from, sender_name, mempool, status, topics: topic_cstr_cache, mess };
+        match dispatch {
+            Dispatch::Serial(buff_data) => {
+                let last_mess_num = deliver_batch(&batch, receive_cb, buff_data, udata);
+                set_sender_last_mess_num(senders, ix, last_mess_num);
+            }
+            Dispatch::Pool(p) => {
+                if let Ok(mut busy) = p.busy.0.lock(){
+                    busy.insert(ix);
+                }
+                let senders = senders.clone();
+                let busy = p.busy.clone();
+                let receive_thread_cvar = p.receive_thread_cvar.clone();
+                let udata = UData(udata.0);
+                let client = log::current_client();
+                p.pool.spawn(move || {
+                    let _log = client.as_ref().map(log::client_scope);
+                    let mut buff_data: Vec<u8> = Vec::new();
+                    let last_mess_num = deliver_batch(&batch, receive_cb, &mut buff_data, &udata);
+                    set_sender_last_mess_num(&senders, batch.ix, last_mess_num);
+                    let (lock, cvar) = &*busy;
+                    if let Ok(mut busy) = lock.lock(){
+                        busy.remove(&batch.ix);
+                        cvar.notify_all();
                     }
+                    // Messages of this sender that arrived meanwhile are ready now.
+                    receive_thread_notify(&receive_thread_cvar);
+                });
+            }
+        }
+    }
+}
+
+/// Runs the callback for each message of `batch` in order; returns the highest message number.
+fn deliver_batch(batch: &ReceiveBatch, receive_cb: UCbackExIntern, buff_data: &mut Vec<u8>, udata: &UData) -> u64{
+    let mut last_mess_num = 0;
+    let mut received: Vec<ReceivedSample> = Vec::with_capacity(batch.mess.len());
+    for m in batch.mess.iter(){
+        if let Some(topic_to) = batch.topics.get(&m.listener_topic_key) {
+            let mlen = m.get_data(&batch.mempool, buff_data);
+            let sent_at_ms = m.sent_at_ms();
+            let (wire_bytes, encoded_bytes) = (m.wire_len(), m.encoded_len());
+            let _trace = trace::delivery_scope(m.trace_context());
+            m.free(&batch.mempool);
+            if mlen == 0 && encoded_bytes > 0 && batch.status.is_enabled() {
+                batch.status.emit_event(StatusEvent::DecodeFailed{
+                    topic: topic_to.to_str().unwrap_or("").to_owned(),
+                    unique_name: batch.sender_name.to_str().unwrap_or("").to_owned(),
+                    number: m.number_mess,
+                });
+            }
+            receive_cb(topic_to.as_c_str().as_ptr(), 
+                    batch.topic_from.as_c_str().as_ptr(), 
+                    batch.sender_name.as_c_str().as_ptr(),
+                    m.number_mess, sent_at_ms, m.is_replayed() as i32,
+                    buff_data[..mlen].as_ptr(), mlen, 
+                    udata.0);
+            received.push(ReceivedSample{
+                topic: topic_to.to_str().unwrap_or(""),
+                wire_bytes,
+                payload_bytes: mlen,
+                encoded_bytes,
+                latency_ms: (sent_at_ms > 0).then(|| common::current_time_ms().saturating_sub(sent_at_ms)),
+            });
+        } else {
+            print_debug!(&format!("unsubscribe on topic_key {}", m.listener_topic_key));
+            // Important: always free the message, even if it won't be delivered.
+            m.free(&batch.mempool);
+        }
+        if m.number_mess > last_mess_num {
+            last_mess_num = m.number_mess;
+        }
+    }
+    batch.status.metrics().record_received(batch.sender_name.to_str().unwrap_or(""), &received);
+    last_mess_num
+}
+
+fn set_sender_last_mess_num(senders: &Arc<Mutex<SenderList>>, ix: usize, last_mess_num: u64){
+    if let Ok(mut senders) = senders.lock() {
+        if let Some(sender) = senders.get_mut(ix) {
+            if sender.last_mess_num < last_mess_num {
+                sender.last_mess_num = last_mess_num;
+            }
+        } else {
+            print_error!(&format!("do_receive_cb: sender index out of bounds at update: {}", ix));
+        }
+    } else {
+        print_error!("do_receive_cb: senders lock poisoned at update");
+    }
+}
+
+/// Wire `topic_key` → topic delivered to the receive callback: exact subscriptions first,
+/// then pattern subscriptions (`orders.#`) matched against the key's concrete topic name.
+struct TopicResolver{
+    listener_topic: Arc<Mutex<ListenerTopics>>,
+    db: Arc<Mutex<dyn Store>>,
+    /// Key → name from the store; a key never changes its topic, so misses are cached too.
+    names: HashMap<i32, Option<String>>,
+    /// Key → matching pattern subscription result (`None`: no pattern matches), valid while
+    /// [`ListenerTopics::version`] equals `matched_version`.
+    matched: HashMap<i32, Option<String>>,
+    matched_version: u64,
+    /// Queued by [`Listener::deliver_retained`]; drained before any topic is newly resolved.
+    retained: Arc<Mutex<RetainedList>>,
+    /// Handed to each [`ReceiveBatch`] for the delivery counters and decode errors.
+    status: StatusEmitter,
+}
+
+impl TopicResolver {
+    fn new(listener_topic: Arc<Mutex<ListenerTopics>>, db: Arc<Mutex<dyn Store>>)->TopicResolver{
+        TopicResolver{
+            listener_topic,
+            db,
+            names: HashMap::new(),
+            matched: HashMap::new(),
+            matched_version: 0,
+            retained: Arc::new(Mutex::new(Vec::new())),
+            status: StatusEmitter::new(),
+        }
+    }
+
+    fn with_retained(mut self, retained: Arc<Mutex<RetainedList>>)->TopicResolver{
+        self.retained = retained;
+        self
+    }
+
+    fn with_status(mut self, status: StatusEmitter)->TopicResolver{
+        self.status = status;
+        self
+    }
+
+    fn has_retained(&self)->bool{
+        self.retained.lock().map(|r| !r.is_empty()).unwrap_or(false)
+    }
+
+    fn deliver_retained(&self, receive_cb: UCbackExIntern, udata: &UData){
+        let pending = match self.retained.lock() {
+            Ok(mut r) => std::mem::take(&mut *r),
+            Err(_) => {
+                print_error!("deliver_retained: retained lock poisoned");
+                return;
+            }
+        };
+        for (topic, sender_topic, payload) in pending {
+            let topic_to = CString::new(topic.as_bytes()).unwrap_or_else(|_| CString::new("").unwrap());
+            let topic_from = CString::new(sender_topic.as_bytes()).unwrap_or_else(|_| CString::new("").unwrap());
+            // Retained values carry no sender identity, wire number or trace context.
+            let _trace = trace::delivery_scope(None);
+            receive_cb(topic_to.as_c_str().as_ptr(),
+                       topic_from.as_c_str().as_ptr(),
+                       c"".as_ptr(), 0, 0, 0,
+                       payload.as_ptr(), payload.len(),
+                       udata.0);
+        }
+    }
+
+    fn resolve(&mut self, topic_key: i32)->Option<String>{
+        let patterns: Vec<String> = match self.listener_topic.lock() {
+            Ok(lt) => {
+                if let Some(topic) = lt.topics.get(&topic_key) {
+                    return Some(topic.clone());
                 }
-                if let Some(topic_to) = topic_cstr_cache.get(&m.listener_topic_key) {
-                    let mlen = m.get_data(&mempool, buff_data);
-                    m.free(&mempool);
-                    receive_cb(topic_to.as_c_str().as_ptr(), 
-                            topic_from.as_c_str().as_ptr(), 
-                            buff_data[..mlen].as_ptr(), mlen, 
-                            udata.0);
-                } else {
-                    print_debug!(&format!("unsubscribe on topic_key {}", m.listener_topic_key));
-                    // Important: always free the message, even if it won't be delivered.
-                    m.free(&mempool);
+                if lt.version != self.matched_version {
+                    self.matched.clear();
+                    self.matched_version = lt.version;
                 }
-                if m.number_mess > last_mess_num {
-                    last_mess_num = m.number_mess;
+                if let Some(matched) = self.matched.get(&topic_key) {
+                    return matched.clone();
                 }
+                lt.topics.values().filter(|t| topic_pattern::is_pattern(t)).cloned().collect()
             }
-            if let Ok(mut senders) = senders.lock() {
-                if let Some(sender) = senders.get_mut(ix) {
-                    if sender.last_mess_num < last_mess_num {
-                        sender.last_mess_num = last_mess_num;
-                    }
-                } else {
-                    print_error!(&format!("do_receive_cb: sender index out of bounds at update: {}", ix));
-                }
-            } else {
-                print_error!("do_receive_cb: senders lock poisoned at update");
+            Err(_) => {
+                print_error!("do_receive_cb: listener_topic lock poisoned");
+                return None;
             }
+        };
+        if patterns.is_empty() {
+            self.matched.insert(topic_key, None);
+            return None;
         }
+        let name = match self.names.get(&topic_key) {
+            Some(name) => name.clone(),
+            None => {
+                let name = match self.db.lock() {
+                    Ok(mut db) => match db.get_topic_by_key(topic_key) {
+                        Ok(name) => name,
+                        Err(err) => {
+                            print_error!(&format!("do_receive_cb: get_topic_by_key {}: {}", topic_key, err));
+                            return None;
+                        }
+                    },
+                    Err(_) => {
+                        print_error!("do_receive_cb: db lock poisoned");
+                        return None;
+                    }
+                };
+                self.names.insert(topic_key, name.clone());
+                name
+            }
+        };
+        let matched = name.filter(|name| patterns.iter().any(|p| topic_pattern::matches(p, name)));
+        self.matched.insert(topic_key, matched.clone());
+        matched
     }
 }
 
 fn listener_accept(poll: &Poll, 
                    address: &mut HashMap<SocketAddr, usize>,
                    streams: &mut ReadStreamList,
-                   senders: &mut Arc<Mutex<SenderList>>,
+                   senders: &Arc<Mutex<SenderList>>,
                    listener: &TcpListener,
-                   mempools: &mut Arc<Mutex<MempoolList>>,
-                   messages: &mut Arc<Mutex<MessList>>){
+                   mempools: &Arc<Mutex<MempoolList>>,
+                   messages: &Arc<Mutex<MessList>>){
     
     loop {
         match listener.accept() {
@@ -325,9 +838,12 @@ fn listener_accept(poll: &Poll,
                             stream: Some(stream),
                             is_active: false,
                             is_close: false,
+                            is_paused: false,
+                            unread: Vec::new(),
                         })));    
                         if let Ok(mut s) = senders.lock() {
-                            s.push(Sender{sender_topic: "".to_owned(), connection_key: -1, last_mess_num: 0, last_mess_num_preview: 0, last_mess_num_saved: 0});
+                            s.push(Sender{sender_topic: "".to_owned(), sender_name: "".to_owned(), connection_key: -1, last_mess_num: 0, last_mess_num_preview: 0, last_mess_num_saved: 0,
+                                          addr: addr.to_string(), connected: true, last_activity_ms: common::current_time_ms()});
                         } else {
                             print_error!("listener_accept: senders lock poisoned");
                         }
@@ -349,7 +865,15 @@ fn listener_accept(poll: &Poll,
                             stream: Some(stream),
                             is_active: false,
                             is_close: false,
+                            is_paused: false,
+                            unread: Vec::new(),
                         }));    
+                        if let Ok(mut s) = senders.lock() {
+                            if let Some(sender) = s.get_mut(ix) {
+                                sender.connected = true;
+                                sender.last_activity_ms = common::current_time_ms();
+                            }
+                        }
                     }
                 }else{
                     print_error!(format!("couldn't poll.registry() stream"));
@@ -369,14 +893,9 @@ fn listener_accept(poll: &Poll,
 
 fn read_stream(token: Token,
                stream: &Arc<Mutex<ReadStream>>,
-               senders: &Arc<Mutex<SenderList>>,
-               db: Arc<Mutex<dyn Store>>,
-               mempools: &Arc<Mutex<MempoolList>>,
-               messages: &Arc<Mutex<MessList>>,
-               receive_thread_cvar: &Arc<(Mutex<bool>, Condvar)>,
-               status_emitter: StatusEmitter){
+               shared: &ListenerShared){
     if let Ok(mut stream) = stream.lock(){
-        if !stream.is_active && !stream.is_close{
+        if !stream.is_active && !stream.is_close && !stream.is_paused{
             stream.is_active = true;
         }else{
             return;
@@ -385,7 +904,8 @@ fn read_stream(token: Token,
         return;
     }
     let stream = stream.clone();
-    let senders = senders.clone();
+    let ListenerShared{ senders, mempools, messages, receive_thread_cvar, backpressure, status_emitter, db } =
+        shared.clone();
     let mempool = match mempools.lock() {
         Ok(mp) => match mp.get(token.0) {
             Some(m) => m.clone(),
@@ -405,9 +925,9 @@ fn read_stream(token: Token,
             return;
         }
     };
-    let tcp_stream = match stream.lock() {
+    let (tcp_stream, unread) = match stream.lock() {
         Ok(mut s) => match s.stream.take() {
-            Some(ts) => ts,
+            Some(ts) => (ts, std::mem::take(&mut s.unread)),
             None => {
                 s.is_active = false;
                 return;
@@ -418,8 +938,6 @@ fn read_stream(token: Token,
             return;
         }
     };
-    let messages = messages.clone();
-    let receive_thread_cvar = receive_thread_cvar.clone();
 
     rayon::spawn(move || {           
         let mut mess_buff: Vec<Message> = Vec::with_capacity(settings::LISTENER_RECEIVE_FLUSH_BATCH);      
@@ -433,9 +951,19 @@ fn read_stream(token: Token,
         }
         let reader_stream = tcp_stream;
         let mut is_shutdown = false;
+        let mut frame_error = None;
+        let mut has_frames = false;
+        let mut is_paused = false;
+        let mut stash = Vec::new();
         {
-            let mut reader = BufReader::with_capacity(settings::READ_BUFFER_CAPASITY, &reader_stream);
-            while let Some(mess) = Message::from_stream(&mempool, reader.by_ref(), &mut is_shutdown){
+            let mut reader = BufReader::with_capacity(settings::READ_BUFFER_CAPASITY, unread.as_slice().chain(&reader_stream));
+            while let Some(mess) = Message::read_from(&mempool, reader.by_ref())
+                .unwrap_or_else(|err| { frame_error = Some(err); None }){
+                has_frames = true;
+                if status_emitter.capture().is_enabled() {
+                    let peer = reader_stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                    status_emitter.capture_frame(true, &peer, &mess, &mempool);
+                }
                 if last_mess_num == 0{
                     let mut connection_key = None;
                     let mut sender_topic = String::new();
@@ -444,6 +972,7 @@ fn read_stream(token: Token,
                             if sender.connection_key == -1{
                                 sender.connection_key = mess.connection_key(&mempool);
                                 sender.sender_topic = get_sender_topic(&db, sender.connection_key, &status_emitter);
+                                sender.sender_name = get_sender_name(&db, sender.connection_key);
                             }
                             connection_key = Some(sender.connection_key);
                             sender_topic = sender.sender_topic.clone();
@@ -474,11 +1003,20 @@ fn read_stream(token: Token,
                             &receive_thread_cvar,
                             &mempool,
                         );
+                        if slot_is_full(token.0, &messages) {
+                            is_paused = true;
+                            break;
+                        }
                     }
                 }else{
                     mess.free(&mempool);
                 }
             }
+            if is_paused {
+                stash.extend_from_slice(reader.buffer());
+                let (rest, _) = reader.into_inner().into_inner();
+                stash.extend_from_slice(rest);
+            }
         }
         flush_received_messages(
             token.0,
@@ -487,9 +1025,25 @@ fn read_stream(token: Token,
             &receive_thread_cvar,
             &mempool,
         );
+        if let Some(err) = frame_error {
+            is_shutdown = true;
+            if err != FrameError::Closed && status_emitter.is_enabled() {
+                let topic = senders.lock().ok()
+                    .and_then(|s| s.get(token.0).map(|s| s.sender_topic.clone()))
+                    .unwrap_or_default();
+                let addr = reader_stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                status_emitter.emit_event(StatusEvent::FrameRejected{ topic, addr, error: err });
+            }
+        }
         if let Ok(mut senders) = senders.lock(){
             if let Some(sender) = senders.get_mut(token.0){
                 sender.last_mess_num_preview = last_mess_num;
+                if has_frames {
+                    sender.last_activity_ms = common::current_time_ms();
+                }
+                if is_shutdown {
+                    sender.connected = false;
+                }
             } else {
                 print_error!(&format!("read_stream: sender index out of bounds for token {}", token.0));
             }    
@@ -501,8 +1055,13 @@ fn read_stream(token: Token,
             // Put stream back so the poll thread can deregister it if needed.
             stream.stream = Some(reader_stream);
             stream.is_close = is_shutdown;
+            stream.is_paused = is_paused;
+            stream.unread = stash;
             stream.is_active = false;
         }
+        if is_paused {
+            backpressure.pause(token.0);
+        }
     });
 }
 
@@ -560,16 +1119,14 @@ fn update_last_mess_number(senders: &Arc<Mutex<SenderList>>,
                 Ok(()) => succeeded.push((ix, last_mess_num)),
                 Err(err) => {
                     print_error!(&format!("couldn't db.set_last_mess_number: {}", err));
+                    status_emitter.metrics().record_store_error("", &sender_topic);
                     if status_emitter.is_enabled() {
-                        let ck = connection_key.to_string();
-                        let err_s = err.to_string();
-                        status_emitter.emit_msg(
-                            LNR_LISTENER_STORE_ERROR,
-                            &sender_topic,
-                            "",
-                            StatusMsg::SetLastMessNumberFromListener,
-                            &[&ck, &err_s],
-                        );
+                        status_emitter.emit_event(StatusEvent::ListenerStoreError {
+                            topic: sender_topic.clone(),
+                            connection_key: Some(connection_key),
+                            op: StoreOp::SetLastMessNumber,
+                            error: err.to_string(),
+                        });
                     }
                 }
             }
@@ -597,20 +1154,30 @@ fn get_last_mess_number(db: &Arc<Mutex<dyn Store>>, connection_key: i32, default
         Err(err)=>{
             print_error!(&format!("couldn't get_last_mess_number_for_listener: {}", err));
             if status_emitter.is_enabled() {
-                let err_s = err.to_string();
-                status_emitter.emit_msg(
-                    LNR_LISTENER_STORE_ERROR,
-                    sender_topic,
-                    "",
-                    StatusMsg::GetLastMessNumberForListener,
-                    &[&err_s],
-                );
+                status_emitter.emit_event(StatusEvent::ListenerStoreError {
+                    topic: sender_topic.to_owned(),
+                    connection_key: Some(connection_key),
+                    op: StoreOp::GetLastMessNumberForListener,
+                    error: err.to_string(),
+                });
             }
             default_mess_number
         }
     }
 }
 
+fn get_sender_name(db: &Arc<Mutex<dyn Store>>, connection_key: i32)->String{
+    match db.lock().unwrap().get_sender_name_by_connection_key(connection_key){
+        Ok(v)=>{
+            v.unwrap_or_default()
+        },
+        Err(err)=>{
+            print_error!(&format!("couldn't get_sender_name, conn_key {}, err {}", connection_key, err));
+            "".to_string()
+        }
+    }
+}
+
 fn get_sender_topic(db: &Arc<Mutex<dyn Store>>, connection_key: i32,
                     status_emitter: &StatusEmitter)->String{
     match db.lock().unwrap().get_sender_topic_by_connection_key(connection_key){
@@ -620,15 +1187,12 @@ fn get_sender_topic(db: &Arc<Mutex<dyn Store>>, connection_key: i32,
         Err(err)=>{
             print_error!(&format!("couldn't get_sender_topic, conn_key {}, err {}", connection_key, err));
             if status_emitter.is_enabled() {
-                let ck = connection_key.to_string();
-                let err_s = err.to_string();
-                status_emitter.emit_msg(
-                    LNR_LISTENER_STORE_ERROR,
-                    "",
-                    "",
-                    StatusMsg::GetSenderTopicByConnectionKey,
-                    &[&ck, &err_s],
-                );
+                status_emitter.emit_event(StatusEvent::ListenerStoreError {
+                    topic: String::new(),
+                    connection_key: Some(connection_key),
+                    op: StoreOp::GetSenderTopic,
+                    error: err.to_string(),
+                });
             }
             "".to_string()
         }
@@ -684,6 +1248,15 @@ fn flush_received_messages(
     }
 }
 
+/// The receive thread still holds [`settings::LISTENER_MAX_PENDING_PER_PEER`] messages of
+/// this peer (slow callback / full channel): stop reading its socket. The sender then sees a
+/// full TCP window and keeps the rest in its own queue.
+fn slot_is_full(token: usize, messages: &Arc<Mutex<MessList>>) -> bool{
+    messages.lock().is_ok_and(|m| {
+        m.get(token).and_then(|slot| slot.as_ref()).map_or(0, Vec::len) >= settings::LISTENER_MAX_PENDING_PER_PEER
+    })
+}
+
 fn receive_thread_notify(receive_thread_cvar: &Arc<(Mutex<bool>, Condvar)>){
     let (lock, cvar) = &**receive_thread_cvar;
     *lock.lock().unwrap() = true;
@@ -693,9 +1266,43 @@ fn receive_thread_notify(receive_thread_cvar: &Arc<(Mutex<bool>, Condvar)>){
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::store::{open_store_mutex, StoreBackend};
     use std::ffi::CStr;
     use std::sync::OnceLock;
 
+    fn test_db() -> Arc<Mutex<dyn Store>> {
+        open_store_mutex("listener_test", StoreBackend::Sqlite { path: ":memory:".to_string() })
+            .unwrap()
+    }
+
+    #[test]
+    fn topic_resolver_matches_pattern_subscriptions_by_key_name() {
+        let db = test_db();
+        let (eu, pay) = {
+            let mut db = db.lock().unwrap();
+            (db.get_topic_key("orders.eu.created").unwrap(), db.get_topic_key("payments.eu").unwrap())
+        };
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(900, "orders.#".to_string())]))));
+        let mut topics = TopicResolver::new(listener_topic.clone(), db);
+
+        assert_eq!(topics.resolve(eu).as_deref(), Some("orders.eu.created"));
+        assert_eq!(topics.resolve(pay), None);
+        assert_eq!(topics.resolve(12345), None);
+        assert_eq!(topics.resolve(900).as_deref(), Some("orders.#"));
+        // Hits and misses are kept until the subscriptions change.
+        assert_eq!(topics.matched.get(&eu), Some(&Some("orders.eu.created".to_string())));
+        assert_eq!(topics.matched.get(&pay), Some(&None));
+        assert_eq!(topics.resolve(eu).as_deref(), Some("orders.eu.created"));
+
+        listener_topic.lock().unwrap().insert(901, "payments.*".to_string());
+        assert_eq!(topics.resolve(pay).as_deref(), Some("payments.eu"), "a new pattern clears cached misses");
+
+        // Match runs against current subscriptions; the cached name does not keep delivery alive.
+        listener_topic.lock().unwrap().remove(900);
+        assert_eq!(topics.resolve(eu), None);
+    }
+
     #[test]
     fn timeout_update_last_mess_number_updates_only_after_timeout() {
         let mut prev = 1_000u64;
@@ -732,24 +1339,30 @@ mod tests {
         assert!(observed);
     }
 
-    type CbRecord = (String, String, Vec<u8>);
+    /// `(to, from, data, sender_name, number, replayed)`.
+    type CbRecord = (String, String, Vec<u8>, String, u64, bool);
     static CB_RECORDS: OnceLock<Mutex<Vec<CbRecord>>> = OnceLock::new();
 
     extern "C" fn test_receive_cb(
         to: *const i8,
         from: *const i8,
+        sender_name: *const i8,
+        number: u64,
+        _sent_at_ms: u64,
+        replayed: i32,
         data: *const u8,
         dsize: usize,
         udata: *mut libc::c_void,
     ) {
         let to = unsafe { CStr::from_ptr(to) }.to_string_lossy().to_string();
         let from = unsafe { CStr::from_ptr(from) }.to_string_lossy().to_string();
+        let sender_name = unsafe { CStr::from_ptr(sender_name) }.to_string_lossy().to_string();
         let data = unsafe { std::slice::from_raw_parts(data, dsize) }.to_vec();
 
         // Prefer the passed udata storage (more isolated per-test).
         if !udata.is_null() {
             let storage = unsafe { &*(udata as *const Mutex<Vec<CbRecord>>) };
-            storage.lock().unwrap().push((to, from, data));
+            storage.lock().unwrap().push((to, from, data, sender_name, number, replayed != 0));
             return;
         }
 
@@ -757,7 +1370,7 @@ mod tests {
             .get_or_init(|| Mutex::new(Vec::new()))
             .lock()
             .unwrap()
-            .push((to, from, data));
+            .push((to, from, data, sender_name, number, replayed != 0));
     }
 
     fn make_udata_ptr() -> (*mut libc::c_void, *mut Mutex<Vec<CbRecord>>) {
@@ -778,13 +1391,17 @@ mod tests {
         let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(vec![mempool.clone()]));
         let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(vec![Sender {
             sender_topic: "from_topic".to_string(),
+            sender_name: String::new(),
             connection_key: 1,
             last_mess_num: 0,
             last_mess_num_preview: 0,
             last_mess_num_saved: 0,
+            addr: String::new(),
+            connected: true,
+            last_activity_ms: 0,
         }]));
-        let listener_topic: Arc<Mutex<HashMap<i32, String>>> =
-            Arc::new(Mutex::new(HashMap::from([(7, "to_topic".to_string())])));
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(7, "to_topic".to_string())]))));
 
         let data = b"hello";
         let msg = Message::new(mempool.clone(), 1, 7, 1, data, false).unwrap();
@@ -792,13 +1409,13 @@ mod tests {
 
         let (udata_ptr, raw_mutex) = make_udata_ptr();
         let udata = unsafe { udata_from_ptr(udata_ptr) };
-        let mut buff = vec![0u8; 16];
+        let mut buff = Dispatch::Serial(vec![0u8; 16]);
 
         do_receive_cb(
             &messages,
             &mempools,
             &senders,
-            &listener_topic,
+            &mut TopicResolver::new(listener_topic.clone(), test_db()),
             test_receive_cb,
             &mut buff,
             &udata,
@@ -820,26 +1437,30 @@ mod tests {
         let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(vec![mempool.clone()]));
         let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(vec![Sender {
             sender_topic: "from_topic".to_string(),
+            sender_name: String::new(),
             connection_key: 1,
             last_mess_num: 0,
             last_mess_num_preview: 0,
             last_mess_num_saved: 0,
+            addr: String::new(),
+            connected: true,
+            last_activity_ms: 0,
         }]));
-        let listener_topic: Arc<Mutex<HashMap<i32, String>>> =
-            Arc::new(Mutex::new(HashMap::new()));
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::default()));
 
         let msg = Message::new(mempool.clone(), 1, 123, 1, b"hello", false).unwrap();
         messages.lock().unwrap()[0] = Some(vec![msg]);
 
         let (udata_ptr, raw_mutex) = make_udata_ptr();
         let udata = unsafe { udata_from_ptr(udata_ptr) };
-        let mut buff = vec![0u8; 16];
+        let mut buff = Dispatch::Serial(vec![0u8; 16]);
 
         do_receive_cb(
             &messages,
             &mempools,
             &senders,
-            &listener_topic,
+            &mut TopicResolver::new(listener_topic.clone(), test_db()),
             test_receive_cb,
             &mut buff,
             &udata,
@@ -858,26 +1479,30 @@ mod tests {
         let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(vec![mempool.clone()]));
         let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(vec![Sender {
             sender_topic: "from\0topic".to_string(),
+            sender_name: String::new(),
             connection_key: 1,
             last_mess_num: 0,
             last_mess_num_preview: 0,
             last_mess_num_saved: 0,
+            addr: String::new(),
+            connected: true,
+            last_activity_ms: 0,
         }]));
-        let listener_topic: Arc<Mutex<HashMap<i32, String>>> =
-            Arc::new(Mutex::new(HashMap::from([(7, "to\0topic".to_string())])));
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(7, "to\0topic".to_string())]))));
 
         let msg = Message::new(mempool.clone(), 1, 7, 1, b"hello", false).unwrap();
         messages.lock().unwrap()[0] = Some(vec![msg]);
 
         let (udata_ptr, raw_mutex) = make_udata_ptr();
         let udata = unsafe { udata_from_ptr(udata_ptr) };
-        let mut buff = vec![0u8; 16];
+        let mut buff = Dispatch::Serial(vec![0u8; 16]);
 
         do_receive_cb(
             &messages,
             &mempools,
             &senders,
-            &listener_topic,
+            &mut TopicResolver::new(listener_topic.clone(), test_db()),
             test_receive_cb,
             &mut buff,
             &udata,
@@ -892,6 +1517,204 @@ mod tests {
         assert_eq!(records[0].1, "");
     }
 
+    extern "C" fn slow_receive_cb(
+        to: *const i8,
+        from: *const i8,
+        sender_name: *const i8,
+        number: u64,
+        sent_at_ms: u64,
+        replayed: i32,
+        data: *const u8,
+        dsize: usize,
+        udata: *mut libc::c_void,
+    ) {
+        std::thread::sleep(Duration::from_millis(5));
+        test_receive_cb(to, from, sender_name, number, sent_at_ms, replayed, data, dsize, udata);
+    }
+
+    #[test]
+    fn pool_dispatch_runs_senders_in_parallel_and_keeps_per_sender_order() {
+        let peers = 4usize;
+        let per_peer = 20u64;
+        let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new((0..peers).map(|_| None).collect()));
+        let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(
+            (0..peers).map(|_| Arc::new(Mutex::new(Mempool::new()))).collect(),
+        ));
+        let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(
+            (0..peers)
+                .map(|p| Sender {
+                    sender_topic: format!("from_{}", p),
+                    sender_name: format!("name_{}", p),
+                    connection_key: p as i32,
+                    last_mess_num: 0,
+                    last_mess_num_preview: 0,
+                    last_mess_num_saved: 0,
+                    addr: String::new(),
+                    connected: true,
+                    last_activity_ms: 0,
+                })
+                .collect(),
+        ));
+        for p in 0..peers {
+            let mempool = mempools.lock().unwrap()[p].clone();
+            let mess = (1..=per_peer)
+                .map(|n| Message::new(mempool.clone(), p as i32, 7, n, &n.to_le_bytes(), false).unwrap())
+                .collect();
+            messages.lock().unwrap()[p] = Some(mess);
+        }
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(7, "to_topic".to_string())]))));
+        let receive_thread_cvar: Arc<(Mutex<bool>, Condvar)> =
+            Arc::new((Mutex::new(false), Condvar::new()));
+
+        let (udata_ptr, raw_mutex) = make_udata_ptr();
+        let udata = unsafe { udata_from_ptr(udata_ptr) };
+        let mut dispatch = Dispatch::new(peers, receive_thread_cvar);
+        assert!(matches!(dispatch, Dispatch::Pool(_)));
+
+        let start = std::time::Instant::now();
+        do_receive_cb(
+            &messages,
+            &mempools,
+            &senders,
+            &mut TopicResolver::new(listener_topic, test_db()),
+            slow_receive_cb,
+            &mut dispatch,
+            &udata,
+        );
+        // Every slot is in flight: nothing else is ready until its batch ends.
+        assert!(!dispatch.has_ready(&messages));
+        dispatch.wait_idle();
+        let elapsed = start.elapsed();
+
+        let records = unsafe { &*raw_mutex }.lock().unwrap().clone();
+        unsafe { drop(Box::from_raw(raw_mutex)); }
+        assert_eq!(records.len(), peers * per_peer as usize);
+        for p in 0..peers {
+            let got: Vec<u64> = records
+                .iter()
+                .filter(|r| r.1 == format!("from_{}", p))
+                .map(|r| {
+                    assert_eq!(r.3, format!("name_{}", p));
+                    assert_eq!(r.4, u64::from_le_bytes(r.2[..8].try_into().unwrap()));
+                    r.4
+                })
+                .collect();
+            assert_eq!(got, (1..=per_peer).collect::<Vec<_>>());
+            assert_eq!(senders.lock().unwrap()[p].last_mess_num, per_peer);
+        }
+        // Serial dispatch would take peers * per_peer * 5ms = 400ms.
+        assert!(elapsed < Duration::from_millis(300), "elapsed {:?}", elapsed);
+    }
+
+    #[test]
+    fn pool_dispatch_keeps_raised_lanes_and_retained_values_first() {
+        let priorities = [Priority::Normal, Priority::Urgent, Priority::Normal, Priority::High];
+        let peers = priorities.len();
+        let per_peer = 3u64;
+        let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(
+            (0..peers).map(|_| Arc::new(Mutex::new(Mempool::new()))).collect(),
+        ));
+        let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(
+            (0..peers)
+                .map(|p| Sender {
+                    sender_topic: format!("from_{}", p),
+                    sender_name: format!("name_{}", p),
+                    connection_key: p as i32,
+                    last_mess_num: 0,
+                    last_mess_num_preview: 0,
+                    last_mess_num_saved: 0,
+                    addr: String::new(),
+                    connected: true,
+                    last_activity_ms: 0,
+                })
+                .collect(),
+        ));
+        let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new(
+            priorities
+                .iter()
+                .enumerate()
+                .map(|(p, priority)| {
+                    let mempool = mempools.lock().unwrap()[p].clone();
+                    Some((1..=per_peer)
+                        .map(|n| {
+                            let mut m = Message::new(mempool.clone(), p as i32, 7, n, &n.to_le_bytes(), false).unwrap();
+                            m.set_priority(*priority);
+                            m
+                        })
+                        .collect())
+                })
+                .collect(),
+        ));
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(7, "to_topic".to_string())]))));
+        let retained: Arc<Mutex<RetainedList>> = Arc::new(Mutex::new(Vec::new()));
+        let mut topics = TopicResolver::new(listener_topic, test_db()).with_retained(retained.clone());
+        let receive_thread_cvar: Arc<(Mutex<bool>, Condvar)> =
+            Arc::new((Mutex::new(false), Condvar::new()));
+
+        let (udata_ptr, raw_mutex) = make_udata_ptr();
+        let udata = unsafe { udata_from_ptr(udata_ptr) };
+        let mut dispatch = Dispatch::new(2, receive_thread_cvar);
+
+        do_receive_cb(&messages, &mempools, &senders, &mut topics, slow_receive_cb, &mut dispatch, &udata);
+        // Both workers took the raised lanes; the normal ones wait in the buffer.
+        assert_eq!(dispatch.free_workers(), 0);
+        assert!(!dispatch.has_ready(&messages));
+        let waiting: Vec<bool> = messages.lock().unwrap().iter().map(|m| m.is_some()).collect();
+        assert_eq!(waiting, vec![true, false, true, false]);
+        dispatch.wait_idle();
+        assert!(dispatch.has_ready(&messages));
+
+        do_receive_cb(&messages, &mempools, &senders, &mut topics, slow_receive_cb, &mut dispatch, &udata);
+        // A retained value queued meanwhile runs after the batches in flight.
+        retained.lock().unwrap().push(("to_topic".to_string(), "retained_from".to_string(), b"r".to_vec()));
+        dispatch.deliver_retained(&topics, test_receive_cb, &udata);
+
+        let records = unsafe { &*raw_mutex }.lock().unwrap().clone();
+        unsafe { drop(Box::from_raw(raw_mutex)); }
+        let first = per_peer as usize * 2;
+        assert_eq!(records.len(), first * 2 + 1);
+        assert!(records[..first].iter().all(|r| r.1 == "from_1" || r.1 == "from_3"));
+        assert!(records[first..first * 2].iter().all(|r| r.1 == "from_0" || r.1 == "from_2"));
+        assert_eq!(records[first * 2].1, "retained_from");
+    }
+
+    #[test]
+    fn backpressure_resumes_paused_peer_once_its_slot_drains() {
+        let mempool: Arc<Mutex<Mempool>> = Arc::new(Mutex::new(Mempool::new()));
+        let backlog: Vec<Message> = (0..settings::LISTENER_MAX_PENDING_PER_PEER as u64)
+            .map(|n| Message::new(mempool.clone(), 1, 7, n + 1, b"x", false).unwrap())
+            .collect();
+        let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new(vec![Some(backlog), None]));
+        assert!(slot_is_full(0, &messages));
+        assert!(!slot_is_full(1, &messages));
+
+        let mut poll = Poll::new().unwrap();
+        let waker = Arc::new(Waker::new(poll.registry(), Token(7)).unwrap());
+        let backpressure = Backpressure::new(waker);
+        let mut events = Events::with_capacity(4);
+        let mut woken = |poll: &mut Poll| {
+            poll.poll(&mut events, Some(Duration::from_millis(50))).unwrap();
+            events.iter().any(|e| e.token() == Token(7))
+        };
+
+        backpressure.pause(0);
+        assert!(woken(&mut poll), "pause wakes the poll thread to re-check the slot");
+        assert!(backpressure.take_ready(&messages).is_empty(), "slot still full");
+
+        let drained = messages.lock().unwrap()[0].take().unwrap();
+        backpressure.notify_drained();
+        assert!(woken(&mut poll));
+        assert_eq!(backpressure.take_ready(&messages), vec![0]);
+
+        backpressure.notify_drained();
+        assert!(!woken(&mut poll), "nothing paused, no wake");
+        for m in drained {
+            m.free(&mempool);
+        }
+    }
+
     #[test]
     fn concurrent_producers_and_consumer_do_not_deadlock_and_deliver_all() {
         let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new(vec![None]));
@@ -899,13 +1722,17 @@ mod tests {
         let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(vec![mempool.clone()]));
         let senders: Arc<Mutex<SenderList>> = Arc::new(Mutex::new(vec![Sender {
             sender_topic: "from_topic".to_string(),
+            sender_name: String::new(),
             connection_key: 1,
             last_mess_num: 0,
             last_mess_num_preview: 0,
             last_mess_num_saved: 0,
+            addr: String::new(),
+            connected: true,
+            last_activity_ms: 0,
         }]));
-        let listener_topic: Arc<Mutex<HashMap<i32, String>>> =
-            Arc::new(Mutex::new(HashMap::from([(7, "to_topic".to_string())])));
+        let listener_topic: Arc<Mutex<ListenerTopics>> =
+            Arc::new(Mutex::new(ListenerTopics::from(HashMap::from([(7, "to_topic".to_string())]))));
 
         let receive_thread_cvar: Arc<(Mutex<bool>, Condvar)> =
             Arc::new((Mutex::new(false), Condvar::new()));
@@ -922,10 +1749,10 @@ mod tests {
         let messages_c = messages.clone();
         let mempools_c = mempools.clone();
         let senders_c = senders.clone();
-        let listener_topic_c = listener_topic.clone();
+        let mut topics = TopicResolver::new(listener_topic.clone(), test_db());
         let cvar_c = receive_thread_cvar.clone();
         let consumer = std::thread::spawn(move || {
-            let mut buff = vec![0u8; 64];
+            let mut buff = Dispatch::Serial(vec![0u8; 64]);
             let start = std::time::Instant::now();
             loop {
                 let raw_mutex = raw_mutex_addr as *const Mutex<Vec<CbRecord>>;
@@ -956,7 +1783,7 @@ mod tests {
                     &messages_c,
                     &mempools_c,
                     &senders_c,
-                    &listener_topic_c,
+                    &mut topics,
                     test_receive_cb,
                     &mut buff,
                     &udata,
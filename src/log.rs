@@ -1,13 +1,64 @@
-//! Process-global error log sink (optional). Default remains stderr via `eprintln!`.
+//! Process-global log sink. Default remains stderr via `eprintln!`; with the **`tracing`**
+//! feature, records become `tracing` events instead.
+//!
+//! Every record has a [`LogLevel`] and, when emitted inside a [`client_scope`], the
+//! `unique_name` of the client it belongs to. Client threads (sender, listener, receive pool,
+//! scheduler, metrics exporter) open the scope once; `Client` methods open it per call.
 
 use crate::UData;
-use std::sync::{Mutex, OnceLock};
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
 
 pub type LogCbackIntern =
     extern "C" fn(message: *const i8, udata: *mut libc::c_void);
 
+pub type LogExCbackIntern =
+    extern "C" fn(level: i32, client: *const i8, message: *const i8, udata: *mut libc::c_void);
+
+pub const LNR_LOG_ERROR: i32 = 1;
+pub const LNR_LOG_WARN: i32 = 2;
+pub const LNR_LOG_INFO: i32 = 3;
+pub const LNR_LOG_DEBUG: i32 = 4;
+
+/// Severity of a log record; the `i32` values are the C `LNR_LOG_*` constants.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error = LNR_LOG_ERROR,
+    Warn = LNR_LOG_WARN,
+    Info = LNR_LOG_INFO,
+    Debug = LNR_LOG_DEBUG,
+}
+
+impl LogLevel {
+    pub fn from_i32(level: i32) -> Option<LogLevel> {
+        match level {
+            LNR_LOG_ERROR => Some(LogLevel::Error),
+            LNR_LOG_WARN => Some(LogLevel::Warn),
+            LNR_LOG_INFO => Some(LogLevel::Info),
+            LNR_LOG_DEBUG => Some(LogLevel::Debug),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Error => "Error",
+            LogLevel::Warn => "Warn",
+            LogLevel::Info => "Info",
+            LogLevel::Debug => "Debug",
+        }
+    }
+}
+
+/// Most verbose level emitted; `Warn` by default.
+static MAX_LEVEL: AtomicI32 = AtomicI32::new(LNR_LOG_WARN);
+
 struct LogSink {
     cb: Option<LogCbackIntern>,
+    cb_ex: Option<LogExCbackIntern>,
     udata: UData,
 }
 
@@ -16,11 +67,16 @@ fn log_sink() -> &'static Mutex<LogSink> {
     SINK.get_or_init(|| {
         Mutex::new(LogSink {
             cb: None,
+            cb_ex: None,
             udata: UData::null(),
         })
     })
 }
 
+thread_local! {
+    static CLIENT: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
 /// Serialize tests that install/clear the process-global log hook.
 #[cfg(test)]
 pub fn test_log_hook_lock() -> std::sync::MutexGuard<'static, ()> {
@@ -29,27 +85,145 @@ pub fn test_log_hook_lock() -> std::sync::MutexGuard<'static, ()> {
 }
 
 /// Install or clear the global log callback. `None` restores stderr-only behavior.
+/// Replaces a callback installed with [`set_log_cb_ex`].
 pub fn set_log_cb(cb: Option<LogCbackIntern>, udata: UData) {
     if let Ok(mut sink) = log_sink().lock() {
         sink.cb = cb;
+        sink.cb_ex = None;
+        sink.udata = udata;
+    }
+}
+
+/// Like [`set_log_cb`], with the level and the client `unique_name` passed separately
+/// (`""` outside any client). Replaces a callback installed with [`set_log_cb`].
+pub fn set_log_cb_ex(cb: Option<LogExCbackIntern>, udata: UData) {
+    if let Ok(mut sink) = log_sink().lock() {
+        sink.cb = None;
+        sink.cb_ex = cb;
         sink.udata = udata;
     }
 }
 
-/// Emit an error line: hook if set, otherwise `eprintln!`.
-pub fn emit_error_line(line: &str) {
-    let (cb, udata) = match log_sink().lock() {
-        Ok(sink) => (sink.cb, sink.udata.0),
-        Err(_) => {
-            eprintln!("{}", line);
+pub fn set_max_level(level: LogLevel) {
+    MAX_LEVEL.store(level as i32, Ordering::Relaxed);
+}
+
+pub fn max_level() -> LogLevel {
+    LogLevel::from_i32(MAX_LEVEL.load(Ordering::Relaxed)).unwrap_or(LogLevel::Warn)
+}
+
+/// Attributes records of this thread to client `name` until dropped (restores the outer scope).
+pub struct ClientScope {
+    prev: Option<Arc<str>>,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+
+/// An empty `name` clears the attribution for the scope.
+pub fn client_scope(name: &Arc<str>) -> ClientScope {
+    let next = (!name.is_empty()).then(|| name.clone());
+    let prev = CLIENT.with(|c| std::mem::replace(&mut *c.borrow_mut(), next));
+    ClientScope {
+        prev,
+        #[cfg(feature = "tracing")]
+        _span: tracing::info_span!("liner", client = &**name).entered(),
+    }
+}
+
+impl Drop for ClientScope {
+    fn drop(&mut self) {
+        let prev = self.prev.take();
+        CLIENT.with(|c| *c.borrow_mut() = prev);
+    }
+}
+
+/// Client of the innermost scope on this thread, to carry it over to pool threads.
+pub fn current_client() -> Option<Arc<str>> {
+    CLIENT.with(|c| c.borrow().clone())
+}
+
+/// Emit one record: the callback if set, otherwise `tracing` (feature) or stderr.
+pub fn emit(level: LogLevel, file: &str, line: u32, message: &str) {
+    if level > max_level() {
+        return;
+    }
+    let client = current_client();
+    let (cb, cb_ex, udata) = match log_sink().lock() {
+        Ok(sink) => (sink.cb, sink.cb_ex, sink.udata.0),
+        Err(_) => (None, None, std::ptr::null_mut()),
+    };
+    if let Some(cb_ex) = cb_ex {
+        let client = CString::new(client.as_deref().unwrap_or("")).unwrap_or_default();
+        if let Ok(c) = CString::new(format!("{}:{}: {}", file, line, message)) {
+            cb_ex(level as i32, client.as_ptr(), c.as_ptr(), udata);
             return;
         }
+    }
+    let text = match &client {
+        Some(name) => format!("{} {}:{}: [{}] {}", level.as_str(), file, line, name, message),
+        None => format!("{} {}:{}: {}", level.as_str(), file, line, message),
     };
     if let Some(cb) = cb {
-        if let Ok(c) = std::ffi::CString::new(line) {
+        if let Ok(c) = CString::new(text.as_str()) {
             cb(c.as_ptr(), udata);
             return;
         }
     }
-    eprintln!("{}", line);
+    #[cfg(feature = "tracing")]
+    {
+        let client = client.as_deref().unwrap_or("");
+        match level {
+            LogLevel::Error => tracing::error!(client, file, line, "{}", message),
+            LogLevel::Warn => tracing::warn!(client, file, line, "{}", message),
+            LogLevel::Info => tracing::info!(client, file, line, "{}", message),
+            LogLevel::Debug => tracing::debug!(client, file, line, "{}", message),
+        }
+    }
+    #[cfg(not(feature = "tracing"))]
+    eprintln!("{}", text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static SEEN: Mutex<Vec<(i32, String, String)>> = Mutex::new(Vec::new());
+
+    extern "C" fn collect(level: i32, client: *const i8, message: *const i8, _udata: *mut libc::c_void) {
+        let s = |p: *const i8| unsafe { std::ffi::CStr::from_ptr(p) }.to_string_lossy().into_owned();
+        SEEN.lock().unwrap().push((level, s(client), s(message)));
+    }
+
+    #[test]
+    fn scoped_records_carry_client_and_respect_max_level() {
+        let _lock = test_log_hook_lock();
+        SEEN.lock().unwrap().clear();
+        set_log_cb_ex(Some(collect), UData::null());
+        {
+            let _outer = client_scope(&Arc::from("a"));
+            emit(LogLevel::Error, "f.rs", 1, "one");
+            {
+                let _inner = client_scope(&Arc::from("b"));
+                emit(LogLevel::Warn, "f.rs", 2, "two");
+            }
+            emit(LogLevel::Info, "f.rs", 3, "filtered at Warn");
+        }
+        emit(LogLevel::Error, "f.rs", 4, "no client");
+        set_max_level(LogLevel::Debug);
+        emit(LogLevel::Debug, "f.rs", 5, "debug");
+        set_max_level(LogLevel::Warn);
+        set_log_cb_ex(None, UData::null());
+
+        // Other tests log concurrently through the same global sink.
+        let seen: Vec<_> = SEEN.lock().unwrap().iter().filter(|r| r.2.starts_with("f.rs:")).cloned().collect();
+        assert_eq!(
+            seen,
+            vec![
+                (LNR_LOG_ERROR, "a".to_owned(), "f.rs:1: one".to_owned()),
+                (LNR_LOG_WARN, "b".to_owned(), "f.rs:2: two".to_owned()),
+                (LNR_LOG_ERROR, String::new(), "f.rs:4: no client".to_owned()),
+                (LNR_LOG_DEBUG, String::new(), "f.rs:5: debug".to_owned()),
+            ]
+        );
+    }
 }
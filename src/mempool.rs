@@ -1,6 +1,7 @@
 
 use std::collections::BTreeMap;
 use std::collections::btree_map;
+use std::sync::{Arc, Mutex};
 
 use crate::settings;
 
@@ -12,6 +13,70 @@ pub struct Mempool{
     free_count: usize,
 }
 
+/// Occupancy of one mempool ([`Client::mempool_stats`](crate::Client::mempool_stats)).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MempoolStats {
+    /// Backing size: `chunks` × chunk size.
+    pub capacity_bytes: u64,
+    /// Bytes held by messages that are queued, in flight or waiting for the callback.
+    pub used_bytes: u64,
+    pub free_bytes: u64,
+    /// Contiguous free regions.
+    pub free_blocks: u64,
+    pub largest_free_block: u64,
+    pub chunks: u64,
+    /// Frees since the last merge pass; the pool merges and shrinks on its own every
+    /// `MEMPOOL_FREE_COUNT_FOR_RESIZE` frees (or with [`Client::compact_memory`](crate::Client::compact_memory)).
+    pub pending_frees: u64,
+}
+
+impl MempoolStats {
+    /// `0.0` when the free space is one block (or none), towards `1.0` as it splits into
+    /// many small ones: `1 - largest_free_block / free_bytes`.
+    pub fn fragmentation(&self) -> f64 {
+        if self.free_bytes == 0 {
+            return 0.0;
+        }
+        1.0 - self.largest_free_block as f64 / self.free_bytes as f64
+    }
+}
+
+/// Mempool of one listener or sender slot.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MempoolSlotStats {
+    /// `false`: a sender slot (one per route lane); `true`: a listener connection slot.
+    pub inbound: bool,
+    /// Slot index, stable for the client's run.
+    pub slot: usize,
+    /// Sender: the listener's address. Listener: the sender's socket address.
+    pub addr: String,
+    /// Wire connection key (`-1` for a listener slot before its first message).
+    pub connection_key: i32,
+    pub stats: MempoolStats,
+}
+
+/// Compact every pool of a slot list that is not locked right now; returns bytes released.
+pub fn compact_idle(mempools: &Mutex<Vec<Arc<Mutex<Mempool>>>>) -> u64 {
+    let pools: Vec<Arc<Mutex<Mempool>>> = match mempools.lock() {
+        Ok(mps) => mps.clone(),
+        Err(_) => return 0,
+    };
+    pools
+        .iter()
+        .filter_map(|mp| mp.try_lock().ok().map(|mut mp| mp.compact() as u64))
+        .sum()
+}
+
+/// Sum of [`Mempool::capacity_bytes`] over a connection-indexed pool list.
+pub fn capacity_bytes(mempools: &Mutex<Vec<Arc<Mutex<Mempool>>>>) -> u64 {
+    let Ok(mps) = mempools.lock() else {
+        return 0;
+    };
+    mps.iter()
+        .filter_map(|mp| mp.lock().ok().map(|mp| mp.capacity_bytes() as u64))
+        .sum()
+}
+
 impl Mempool{   
     pub fn new()->Mempool{
         Mempool{
@@ -74,6 +139,20 @@ impl Mempool{
            (self.free_len - req_size) < (settings::MEMPOOL_MIN_PERCENT_FOR_COMPRESS * csz as f32) as usize{
             return None;
         }
+        let req = self.coalesce(req_size);
+        let buff_len_bytes = self.buff.len() * settings::MEMPOOL_CHUNK_SIZE_BYTE;
+        if buff_len_bytes > 0
+            && self.free_len > (settings::MEMPOOL_MIN_PERCENT_FOR_RESIZE * buff_len_bytes as f32) as usize
+            && buff_len_bytes > settings::MEMPOOL_OVER_SIZE_MB * 1024 * 1024
+        {
+            let reserved_end = req.map_or(0, |(pos, len)| pos + len);
+            let _ = self.shrink_free_tail(buff_len_bytes, reserved_end, req.is_some());
+        }
+        req
+    }
+    /// Merge adjacent free blocks; with `req_size > 0` also reserve the smallest merged block
+    /// that fits it.
+    fn coalesce(&mut self, req_size: usize)->Option<(usize, usize)>{
         let mut free_len_all = 0;
         let mut free_mem_pos: BTreeMap<usize, usize> = BTreeMap::new(); // pos, len
         for m in &self.free_mem{
@@ -139,14 +218,6 @@ impl Mempool{
                 self.free_mem_insert_pos(free_len, free_pos);
             }                
         }
-        let buff_len_bytes = self.buff.len() * settings::MEMPOOL_CHUNK_SIZE_BYTE;
-        if buff_len_bytes > 0
-            && self.free_len > (settings::MEMPOOL_MIN_PERCENT_FOR_RESIZE * buff_len_bytes as f32) as usize
-            && buff_len_bytes > settings::MEMPOOL_OVER_SIZE_MB * 1024 * 1024
-        {
-            let reserved_end = if has_req_mem { req_pos + req_size } else { 0 };
-            let _ = self.shrink_free_tail(buff_len_bytes, reserved_end, has_req_mem);
-        }
         if has_req_mem{    
             Some((req_pos, req_size))
         }else{
@@ -280,6 +351,78 @@ impl Mempool{
         let _ = self.shrink_free_tail(buff_len_bytes, 0, false);
     }
 
+    /// Bytes of allocated chunks (used and free).
+    pub fn capacity_bytes(&self) -> usize {
+        self.buff.len() * settings::MEMPOOL_CHUNK_SIZE_BYTE
+    }
+
+    /// Current occupancy. Adjacent free blocks are counted as one, whether or not the
+    /// pool has merged them yet.
+    pub fn stats(&self) -> MempoolStats {
+        let mut free: Vec<(usize, usize)> = self
+            .free_mem
+            .iter()
+            .flat_map(|(len, entry)| entry.1.iter().map(move |pos| (*pos, *len)))
+            .collect();
+        free.sort_unstable();
+        let mut stats = MempoolStats {
+            chunks: self.buff.len() as u64,
+            capacity_bytes: self.capacity_bytes() as u64,
+            pending_frees: self.free_count as u64,
+            ..MempoolStats::default()
+        };
+        let mut run: Option<(usize, usize)> = None; // pos, len
+        for (pos, len) in free.into_iter().chain(std::iter::once((usize::MAX, 0))) {
+            match run {
+                Some((start, run_len)) if start + run_len == pos => run = Some((start, run_len + len)),
+                _ => {
+                    if let Some((_, run_len)) = run {
+                        stats.free_bytes += run_len as u64;
+                        stats.free_blocks += 1;
+                        stats.largest_free_block = stats.largest_free_block.max(run_len as u64);
+                    }
+                    run = Some((pos, len));
+                }
+            }
+        }
+        stats.used_bytes = stats.capacity_bytes.saturating_sub(stats.free_bytes);
+        stats
+    }
+
+    /// Split a free block ending at `buff_len_bytes` at its first chunk boundary, so the whole
+    /// chunks after it can be shrunk.
+    fn align_free_tail(&mut self, buff_len_bytes: usize) {
+        let chunk = settings::MEMPOOL_CHUNK_SIZE_BYTE;
+        let tail = self.free_mem.iter().find_map(|(len, entry)| {
+            entry.1.iter().position(|pos| pos + len == buff_len_bytes).map(|index| (*len, index, entry.1[index]))
+        });
+        let Some((len, index, pos)) = tail else {
+            return;
+        };
+        let boundary = pos.next_multiple_of(chunk);
+        if boundary == pos || boundary >= buff_len_bytes {
+            return;
+        }
+        self.free_mem_remove_pos(len, index);
+        self.free_mem_insert_pos(boundary - pos, pos);
+        self.free_mem_insert_pos(buff_len_bytes - boundary, boundary);
+    }
+
+    /// Merge free blocks and release every whole free chunk at the end of the pool now,
+    /// without the size thresholds of the periodic pass. Live allocations never move.
+    /// Returns the bytes released.
+    pub fn compact(&mut self) -> usize {
+        let before = self.capacity_bytes();
+        if self.free_mem.values().any(|entry| !entry.1.is_empty()) {
+            let _ = self.coalesce(0);
+            self.align_free_tail(before);
+            let _ = self.shrink_free_tail(before, 0, false);
+            self.buff.shrink_to_fit();
+        }
+        self.free_count = 0;
+        before - self.capacity_bytes()
+    }
+
     #[cfg(test)]
     pub fn debug_free_len(&self) -> usize {
         self.free_len
@@ -733,6 +876,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn stats_merge_adjacent_free_blocks() {
+        let mut mp = Mempool::new();
+        let chunk = settings::MEMPOOL_CHUNK_SIZE_BYTE;
+        let (p1, l1) = mp.alloc(256);
+        let (p2, l2) = mp.alloc(256);
+        let _live = mp.alloc(256);
+        mp.free(p1, l1);
+        mp.free(p2, l2);
+
+        let s = mp.stats();
+        assert_eq!((s.chunks, s.capacity_bytes), (1, chunk as u64));
+        assert_eq!(s.used_bytes, 256);
+        assert_eq!(s.free_bytes, chunk as u64 - 256);
+        assert_eq!(s.free_blocks, 2, "0..512 and the tail after the live block");
+        assert_eq!(s.largest_free_block, chunk as u64 - 768);
+        assert_eq!(s.pending_frees, 2);
+        assert!(s.fragmentation() > 0.0 && s.fragmentation() < 0.01);
+        assert_eq!(Mempool::new().stats().fragmentation(), 0.0);
+    }
+
+    #[test]
+    fn compact_releases_free_tail_below_resize_thresholds() {
+        let mut mp = Mempool::new();
+        let chunk = settings::MEMPOOL_CHUNK_SIZE_BYTE;
+        let blocks: Vec<(usize, usize)> = (0..64).map(|_| mp.alloc(chunk / 4 + 100)).collect();
+        let grown = mp.capacity_bytes();
+        assert!(grown >= 16 * chunk);
+
+        // Keep one block in the middle: everything after it is released.
+        let keep = blocks[20];
+        for &(p, l) in &blocks {
+            if (p, l) != keep {
+                mp.free(p, l);
+            }
+        }
+        let released = mp.compact();
+        let kept_chunks = (keep.0 + keep.1).div_ceil(chunk);
+        assert_eq!(mp.buff.len(), kept_chunks);
+        assert_eq!(released, grown - kept_chunks * chunk);
+        let s = mp.stats();
+        assert_eq!((s.used_bytes, s.pending_frees), (keep.1 as u64, 0));
+
+        mp.free(keep.0, keep.1);
+        mp.compact();
+        assert_eq!(mp.capacity_bytes(), 0);
+        let (p, l) = mp.alloc(512);
+        assert_eq!((p, l), (0, 512), "pool grows again after a full release");
+    }
+
     #[test]
     fn free_restores_missing_size_bucket() {
         let mut mp = Mempool::new();
@@ -1,12 +1,26 @@
-use crate::{bytestream, print_error};
+use crate::{bytestream, common, print_error};
+use crate::bytestream::FrameError;
 use crate::mempool::Mempool;
 use crate::settings;
+use crate::trace::TraceContext;
 use std::cell::Cell;
 use std::io::{Write, Read};
 use std::sync::{Arc, Mutex};
 
 const COMPRESS: u8 = 0x01;
 const AT_LEAST_ONCE_DELIVERY: u8 = 0x02;
+/// Two flag bits carry the [`Priority`]; older peers ignore them.
+const PRIORITY_MASK: u8 = 0x0C;
+const PRIORITY_SHIFT: u8 = 2;
+/// A u64 sender clock (ms since the Unix epoch) follows the payload. Older peers read
+/// the payload by its length and ignore the trailer.
+const SENT_AT: u8 = 0x10;
+/// Set by the sender when the message is resent from its offline queue.
+const REPLAYED: u8 = 0x20;
+/// A [`TraceContext`] follows the sent-at trailer.
+const TRACE: u8 = 0x40;
+const SENT_AT_LEN: usize = 8;
+const TRACE_LEN: usize = TraceContext::WIRE_LEN;
 
 /// Wire header: u64 number + i32 connection_key + i32 topic_key + u8 flags.
 const HEADER_LEN: usize = 8 + 4 + 4 + 1;
@@ -14,18 +28,60 @@ const HEADER_LEN: usize = 8 + 4 + 4 + 1;
 /// Uncompressed framed message body size for a raw application payload
 /// (what the bytestream `u32` length header carries — excludes that outer length itself).
 /// Compression can only shrink the body; use this for early send-side rejection.
-pub fn framed_body_size_raw(payload_len: usize) -> usize {
+/// Every message carries the sent-at trailer; the trace trailer only when `traced`
+/// (the [`TRACE`] flag, set by [`Message::new_traced`] for `Some` context).
+pub fn framed_body_size_raw(payload_len: usize, traced: bool) -> usize {
     HEADER_LEN
         .saturating_add(std::mem::size_of::<u32>())
         .saturating_add(payload_len)
+        .saturating_add(SENT_AT_LEN)
+        .saturating_add(if traced { TRACE_LEN } else { 0 })
 }
 
 /// `true` if an uncompressed encoding of `payload_len` would exceed [`settings::max_message_size`].
-pub fn payload_exceeds_max_message_size(payload_len: usize) -> bool {
-    let body = framed_body_size_raw(payload_len);
+pub fn payload_exceeds_max_message_size(payload_len: usize, traced: bool) -> bool {
+    let body = framed_body_size_raw(payload_len, traced);
     body == 0 || body > settings::max_message_size()
 }
 
+/// Send lane of a message. Each level above `Normal` gets its own queue, connection and
+/// offline list per peer, so it does not wait behind bulk traffic. Values match C `LNR_PRIORITY_*`.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Priority {
+    #[default]
+    Normal = 0,
+    High = 1,
+    Urgent = 2,
+}
+
+impl Priority {
+    /// Lanes above `Normal`, highest first.
+    pub const RAISED: [Priority; 2] = [Priority::Urgent, Priority::High];
+
+    pub fn from_i32(v: i32) -> Option<Priority> {
+        match v {
+            0 => Some(Priority::Normal),
+            1 => Some(Priority::High),
+            2 => Some(Priority::Urgent),
+            _ => None,
+        }
+    }
+
+    fn from_flags(flags: u8) -> Priority {
+        Priority::from_i32(i32::from((flags & PRIORITY_MASK) >> PRIORITY_SHIFT)).unwrap_or_default()
+    }
+
+    /// Listener name under which the store keys this lane's connection (wire key, offline
+    /// queue, ack number). `Normal` keeps the plain name, so existing queues stay valid.
+    pub fn lane_listener_name(self, listener_name: &str) -> String {
+        match self {
+            Priority::Normal => listener_name.to_string(),
+            _ => format!("{}\x1fp{}", listener_name, self as i32),
+        }
+    }
+}
+
 pub struct Message{
     pub number_mess: u64,
     pub listener_topic_key: i32,
@@ -47,10 +103,20 @@ impl Message{
     /// should be committed by the caller in that case).
     pub fn new(mempool: Arc<Mutex<Mempool>>, connection_key: i32, listener_topic_key: i32,
                number_mess: u64, data: &[u8], at_least_once_delivery: bool) -> Option<Message> {
-        let mut flags = 0;
+        Message::new_traced(mempool, connection_key, listener_topic_key, number_mess, data, at_least_once_delivery, None)
+    }
+
+    /// [`Message::new`] with `trace` stamped after the sent-at trailer.
+    pub fn new_traced(mempool: Arc<Mutex<Mempool>>, connection_key: i32, listener_topic_key: i32,
+               number_mess: u64, data: &[u8], at_least_once_delivery: bool,
+               trace: Option<TraceContext>) -> Option<Message> {
+        let mut flags = SENT_AT;
         if at_least_once_delivery{
             flags |= AT_LEAST_ONCE_DELIVERY;
         }
+        if trace.is_some(){
+            flags |= TRACE;
+        }
         let number_mess_len = std::mem::size_of::<u64>();
         let connection_key_len = std::mem::size_of::<i32>();
         let listener_topic_key_len = std::mem::size_of::<i32>();
@@ -70,7 +136,9 @@ impl Message{
                                connection_key_len + 
                                listener_topic_key_len +              
                                flags_len +
-                               data_len;
+                               data_len +
+                               SENT_AT_LEN +
+                               if trace.is_some() { TRACE_LEN } else { 0 };
         let Ok(mut mp) = mempool.lock() else {
             print_error!("Message::new: mempool lock poisoned");
             return None;
@@ -94,6 +162,10 @@ impl Message{
                 mp.write_array(data_pos, data);
             }
         }
+        mp.write_num(data_pos + data_len, common::current_time_ms());
+        if let Some(trace) = trace{
+            mp.write_data(data_pos + data_len + SENT_AT_LEN, &trace.to_wire());
+        }
         drop(mp);
         Some(Message{
             number_mess,
@@ -127,11 +199,22 @@ impl Message{
     
     pub fn from_stream<T>(mempool: &Arc<Mutex<Mempool>>, stream: &mut T, is_shutdown: &mut bool) -> Option<Message>
         where T: Read{
-        let (mem_alloc_pos, mem_alloc_length, is_shutdown_) = bytestream::read_stream(stream, mempool);
-        if mem_alloc_length == 0{
-            *is_shutdown = is_shutdown_;
-            return None;
+        match Message::read_from(mempool, stream) {
+            Ok(mess) => mess,
+            Err(_) => {
+                *is_shutdown = true;
+                None
+            }
         }
+    }
+
+    /// [`Message::from_stream`] with the reason a frame was rejected; `Ok(None)` when no
+    /// frame is available yet. Any `Err` means the stream must be shut down.
+    pub fn read_from<T>(mempool: &Arc<Mutex<Mempool>>, stream: &mut T) -> Result<Option<Message>, FrameError>
+        where T: Read{
+        let Some((mem_alloc_pos, mem_alloc_length)) = bytestream::read_frame(stream, mempool)? else {
+            return Ok(None);
+        };
         if mem_alloc_length < HEADER_LEN + std::mem::size_of::<u32>() {
             print_error!(&format!(
                 "message too short: {} (min {})",
@@ -141,8 +224,7 @@ impl Message{
             if let Ok(mut mp) = mempool.lock() {
                 mp.free(mem_alloc_pos, mem_alloc_length);
             }
-            *is_shutdown = true;
-            return None;
+            return Err(FrameError::TooShort { len: mem_alloc_length, min: HEADER_LEN + std::mem::size_of::<u32>() });
         }
         if let Ok(mp) = mempool.lock(){
             let number_mess_pos = mem_alloc_pos; 
@@ -171,11 +253,10 @@ impl Message{
                 if let Ok(mut mp) = mempool.lock() {
                     mp.free(mem_alloc_pos, mem_alloc_length);
                 }
-                *is_shutdown = true;
-                return None;
+                return Err(FrameError::PayloadOverrun { need, have: mem_alloc_length });
             }
 
-            return Some(Message{
+            return Ok(Some(Message{
                 number_mess,
                 listener_topic_key,
                 flags,
@@ -183,13 +264,13 @@ impl Message{
                 mem_alloc_length,
                 mempool: mempool.clone(),
                 freed: Cell::new(false),
-            });
+            }));
         }
         // Lock failed after alloc — free to avoid leak.
         if let Ok(mut mp) = mempool.lock() {
             mp.free(mem_alloc_pos, mem_alloc_length);
         }
-        None        
+        Ok(None)
     }
     pub fn to_stream<T>(&self, mempool: &Arc<Mutex<Mempool>>, stream: &mut T)->bool 
         where T: Write{        
@@ -239,9 +320,80 @@ impl Message{
     pub fn at_least_once_delivery(&self)->bool{
         self.flags & AT_LEAST_ONCE_DELIVERY > 0
     }
+    pub fn priority(&self)->Priority{
+        Priority::from_flags(self.flags)
+    }
+    /// Tag the encoded header with `priority` (`Message::new` writes `Normal`).
+    pub fn set_priority(&mut self, priority: Priority)->bool{
+        let flags = (self.flags & !PRIORITY_MASK) | (((priority as u8) << PRIORITY_SHIFT) & PRIORITY_MASK);
+        let Ok(mut mp) = self.mempool.lock() else {
+            return false;
+        };
+        mp.write_num(self.mem_alloc_pos + data_pos() - std::mem::size_of::<u8>(), flags);
+        self.flags = flags;
+        true
+    }
     fn is_compressed(&self)->bool{
         self.flags & COMPRESS > 0
     }
+    pub fn is_replayed(&self)->bool{
+        self.flags & REPLAYED > 0
+    }
+    /// Tag the encoded header as resent from the offline queue.
+    pub fn set_replayed(&mut self)->bool{
+        let flags = self.flags | REPLAYED;
+        let Ok(mut mp) = self.mempool.lock() else {
+            return false;
+        };
+        mp.write_num(self.mem_alloc_pos + data_pos() - std::mem::size_of::<u8>(), flags);
+        self.flags = flags;
+        true
+    }
+    /// Framed body size on the wire (what the bytestream `u32` length header carries).
+    pub fn wire_len(&self)->usize{
+        self.mem_alloc_length
+    }
+    /// Payload size as carried on the wire (after compression).
+    pub fn encoded_len(&self)->usize{
+        let Ok(mp) = self.mempool.lock() else {
+            return 0;
+        };
+        mp.read_u32(self.mem_alloc_pos + data_pos()) as usize
+    }
+    /// Sender clock (ms since the Unix epoch) when the message was encoded; `0` from older peers.
+    pub fn sent_at_ms(&self)->u64{
+        if self.flags & SENT_AT == 0 {
+            return 0;
+        }
+        let size_u32 = std::mem::size_of::<u32>();
+        let Ok(mp) = self.mempool.lock() else {
+            return 0;
+        };
+        let data_len = mp.read_u32(self.mem_alloc_pos + data_pos()) as usize;
+        let pos = data_pos() + size_u32 + data_len;
+        if pos + SENT_AT_LEN > self.mem_alloc_length {
+            return 0;
+        }
+        mp.read_u64(self.mem_alloc_pos + pos)
+    }
+    /// Trace context stamped by the sender; `None` if the send carried none.
+    pub fn trace_context(&self)->Option<TraceContext>{
+        if self.flags & TRACE == 0 || self.flags & SENT_AT == 0 {
+            return None;
+        }
+        let size_u32 = std::mem::size_of::<u32>();
+        let Ok(mp) = self.mempool.lock() else {
+            return None;
+        };
+        let data_len = mp.read_u32(self.mem_alloc_pos + data_pos()) as usize;
+        let pos = data_pos() + size_u32 + data_len + SENT_AT_LEN;
+        if pos + TRACE_LEN > self.mem_alloc_length {
+            return None;
+        }
+        let mut wire = [0u8; TRACE_LEN];
+        mp.read_data(self.mem_alloc_pos + pos, &mut wire);
+        TraceContext::from_wire(&wire)
+    }
     pub fn connection_key(&self, mempool: &Arc<Mutex<Mempool>>)->i32{
         let number_mess_len = std::mem::size_of::<u64>(); 
         let key_pos = self.mem_alloc_pos + number_mess_len;
@@ -249,7 +401,36 @@ impl Message{
     }   
 }
 
-fn data_pos()->usize{ 
+/// `number_mess` of a stored blob (`to_stream` output: u32 length + header + payload).
+pub fn stored_number_mess(blob: &[u8]) -> Option<u64> {
+    let pos = std::mem::size_of::<u32>();
+    if blob.len() < pos + HEADER_LEN {
+        return None;
+    }
+    Some(u64::from_be_bytes(blob[pos..pos + 8].try_into().ok()?))
+}
+
+/// Rewrite the header of a stored blob in place so it can be queued on another connection.
+/// `listener_topic_key` of `None` keeps the original. Returns `false` for a truncated blob.
+pub fn rewrite_stored_header(
+    blob: &mut [u8],
+    connection_key: i32,
+    listener_topic_key: Option<i32>,
+    number_mess: u64,
+) -> bool {
+    let pos = std::mem::size_of::<u32>();
+    if blob.len() < pos + HEADER_LEN {
+        return false;
+    }
+    blob[pos..pos + 8].copy_from_slice(&number_mess.to_be_bytes());
+    blob[pos + 8..pos + 12].copy_from_slice(&connection_key.to_be_bytes());
+    if let Some(tk) = listener_topic_key {
+        blob[pos + 12..pos + 16].copy_from_slice(&tk.to_be_bytes());
+    }
+    true
+}
+
+fn data_pos()->usize{
     let number_mess_pos = 0; 
     let number_mess_len = std::mem::size_of::<u64>();        
     let connection_key_pos = number_mess_pos + number_mess_len;
@@ -354,6 +535,69 @@ mod tests {
         assert!(decoded.at_least_once_delivery());
     }
 
+    #[test]
+    fn priority_flag_roundtrips_next_to_other_flags() {
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let mut msg = Message::new(mempool.clone(), 1, 1, 1, b"x", true).unwrap();
+        assert_eq!(msg.priority(), Priority::Normal);
+        assert!(msg.set_priority(Priority::Urgent));
+
+        let mut wire = Vec::new();
+        assert!(msg.to_stream(&mempool, &mut wire));
+        let mut shutdown = false;
+        let decoded = Message::from_stream(&mempool, &mut &wire[..], &mut shutdown).unwrap();
+        assert_eq!(decoded.priority(), Priority::Urgent);
+        assert!(decoded.at_least_once_delivery());
+        let mut out = Vec::new();
+        let len = decoded.get_data(&mempool, &mut out);
+        assert_eq!(&out[..len], b"x");
+    }
+
+    #[test]
+    fn sent_at_and_replayed_roundtrip_after_payload() {
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let before = common::current_time_ms();
+        let mut msg = Message::new(mempool.clone(), 1, 1, 1, b"abc", true).unwrap();
+        assert!(!msg.is_replayed());
+        assert!(msg.set_replayed());
+
+        let mut wire = Vec::new();
+        assert!(msg.to_stream(&mempool, &mut wire));
+        let mut shutdown = false;
+        let decoded = Message::from_stream(&mempool, &mut &wire[..], &mut shutdown).unwrap();
+        assert!(decoded.is_replayed());
+        assert!(decoded.at_least_once_delivery());
+        let sent_at = decoded.sent_at_ms();
+        assert!(sent_at >= before && sent_at <= common::current_time_ms());
+        let mut out = Vec::new();
+        let len = decoded.get_data(&mempool, &mut out);
+        assert_eq!(&out[..len], b"abc");
+    }
+
+    #[test]
+    fn trace_context_roundtrips_after_sent_at() {
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let trace = TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        let msg = Message::new_traced(mempool.clone(), 1, 1, 1, b"abc", false, Some(trace)).unwrap();
+        assert_eq!(msg.wire_len(), framed_body_size_raw(3, true));
+        let untraced = Message::new(mempool.clone(), 1, 1, 2, b"abc", false).unwrap();
+        assert_eq!(untraced.wire_len(), framed_body_size_raw(3, false));
+        assert_eq!(msg.wire_len() - untraced.wire_len(), TRACE_LEN);
+
+        let mut wire = Vec::new();
+        assert!(msg.to_stream(&mempool, &mut wire));
+        let mut shutdown = false;
+        let decoded = Message::from_stream(&mempool, &mut &wire[..], &mut shutdown).unwrap();
+        assert_eq!(decoded.trace_context(), Some(trace));
+        assert!(decoded.sent_at_ms() > 0);
+        let mut out = Vec::new();
+        let len = decoded.get_data(&mempool, &mut out);
+        assert_eq!(&out[..len], b"abc");
+
+        let plain = Message::new(mempool.clone(), 1, 1, 2, b"abc", false).unwrap();
+        assert_eq!(plain.trace_context(), None);
+    }
+
     #[test]
     fn connection_key_supports_negative_values() {
         let mempool = Arc::new(Mutex::new(Mempool::new()));
@@ -437,6 +681,27 @@ mod tests {
         assert!(Message::from_stream(&mempool, &mut &wire[..], &mut shutdown).is_none());
     }
 
+    #[test]
+    fn rewrite_stored_header_changes_key_and_number_only() {
+        let (_, mut wire) = roundtrip(5, 7, 3, b"payload", true);
+        assert_eq!(stored_number_mess(&wire), Some(3));
+        assert!(rewrite_stored_header(&mut wire, -9, None, 100));
+        assert_eq!(stored_number_mess(&wire), Some(100));
+
+        let mempool = Arc::new(Mutex::new(Mempool::new()));
+        let mut shutdown = false;
+        let m = Message::from_stream(&mempool, &mut &wire[..], &mut shutdown).unwrap();
+        assert_eq!(m.number_mess, 100);
+        assert_eq!(m.connection_key(&mempool), -9);
+        assert_eq!(m.listener_topic_key, 7);
+        assert!(m.at_least_once_delivery());
+        let mut out = Vec::new();
+        let n = m.get_data(&mempool, &mut out);
+        assert_eq!(&out[..n], b"payload");
+
+        assert!(!rewrite_stored_header(&mut [0u8; 8], 1, None, 1));
+    }
+
     #[test]
     fn free_is_idempotent_via_drop() {
         let mempool = Arc::new(Mutex::new(Mempool::new()));
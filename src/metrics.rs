@@ -0,0 +1,355 @@
+//! Per-client traffic counters and latency histograms ([`Client::metrics_snapshot`](crate::Client::metrics_snapshot)).
+//!
+//! The sender and listener threads record into a shared [`Metrics`]; counters live for the
+//! whole client (they survive `stop` / `run`). Queue depth and offline backlog are gauges
+//! filled in by the client when the snapshot is taken.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds (ms, inclusive) of the latency histogram buckets; one more bucket counts
+/// everything above the last bound.
+pub const LATENCY_BUCKETS_MS: [u64; 13] = [1, 2, 5, 10, 25, 50, 100, 250, 500, 1000, 2500, 5000, 10000];
+/// Number of histogram buckets, including the overflow one.
+pub const LATENCY_BUCKET_COUNT: usize = LATENCY_BUCKETS_MS.len() + 1;
+
+/// Latency distribution in milliseconds.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// Samples per bucket of [`LATENCY_BUCKETS_MS`] (not cumulative); the last is the overflow.
+    pub buckets: [u64; LATENCY_BUCKET_COUNT],
+    pub count: u64,
+    pub sum_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, ms: u64) {
+        let i = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|&b| ms <= b)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[i] += 1;
+        self.count += 1;
+        self.sum_ms = self.sum_ms.saturating_add(ms);
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    fn merge(&mut self, other: &LatencyHistogram) {
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a += b;
+        }
+        self.count += other.count;
+        self.sum_ms = self.sum_ms.saturating_add(other.sum_ms);
+        self.max_ms = self.max_ms.max(other.max_ms);
+    }
+
+    /// Mean in ms; `0.0` without samples.
+    pub fn mean_ms(&self) -> f64 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        self.sum_ms as f64 / self.count as f64
+    }
+
+    /// Upper bound of the bucket holding quantile `q` (`0.0..=1.0`), capped at `max_ms`.
+    /// `0` without samples.
+    pub fn quantile_ms(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return LATENCY_BUCKETS_MS.get(i).map_or(self.max_ms, |&b| b.min(self.max_ms));
+            }
+        }
+        self.max_ms
+    }
+}
+
+/// Counters of one peer, one topic, or the whole client.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Counters {
+    /// Messages written to a peer's socket.
+    pub messages_sent: u64,
+    /// Framed bytes written (header, payload as encoded, trailer).
+    pub bytes_sent: u64,
+    /// Messages handed to the receive callback.
+    pub messages_received: u64,
+    /// Framed bytes of the received messages.
+    pub bytes_received: u64,
+    /// Application payload bytes of sent and received messages before compression.
+    pub payload_bytes: u64,
+    /// The same payloads as carried on the wire (after compression).
+    pub encoded_bytes: u64,
+    /// Successful connects to a peer after its route was lost.
+    pub reconnects: u64,
+    /// Connect failures and stream closes (`LNR_SENDER_ROUTE_LOST`).
+    pub route_losses: u64,
+    /// Background store errors (`LNR_SENDER_STORE_ERROR`, `LNR_LISTENER_STORE_ERROR`).
+    pub store_errors: u64,
+    /// Best-effort messages discarded unwritten when their route was lost or the client stopped.
+    pub drops: u64,
+    /// Messages in the in-memory send queue (gauge, at snapshot time).
+    pub send_queue_depth: u64,
+    /// At-least-once messages in the offline store (gauge, at snapshot time).
+    pub offline_backlog: u64,
+    /// From `send_*` enqueue to the socket write.
+    pub enqueue_to_write: LatencyHistogram,
+    /// From the sender's timestamp in the message header to the receive callback. Needs
+    /// synchronized clocks on both hosts.
+    pub send_to_receive: LatencyHistogram,
+}
+
+impl Counters {
+    /// `payload_bytes / encoded_bytes`: `1.0` without compression or traffic.
+    pub fn compression_ratio(&self) -> f64 {
+        if self.encoded_bytes == 0 {
+            return 1.0;
+        }
+        self.payload_bytes as f64 / self.encoded_bytes as f64
+    }
+
+    pub(crate) fn merge(&mut self, other: &Counters) {
+        self.messages_sent += other.messages_sent;
+        self.bytes_sent += other.bytes_sent;
+        self.messages_received += other.messages_received;
+        self.bytes_received += other.bytes_received;
+        self.payload_bytes += other.payload_bytes;
+        self.encoded_bytes += other.encoded_bytes;
+        self.reconnects += other.reconnects;
+        self.route_losses += other.route_losses;
+        self.store_errors += other.store_errors;
+        self.drops += other.drops;
+        self.send_queue_depth += other.send_queue_depth;
+        self.offline_backlog += other.offline_backlog;
+        self.enqueue_to_write.merge(&other.enqueue_to_write);
+        self.send_to_receive.merge(&other.send_to_receive);
+    }
+}
+
+/// Point-in-time copy of a client's metrics.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub total: Counters,
+    /// Keyed by the peer's `unique_name`. Traffic to a listener address the store no longer
+    /// names is keyed by that address.
+    pub peers: BTreeMap<String, Counters>,
+    /// Keyed by the listener topic (sent) or the delivered topic (received).
+    pub topics: BTreeMap<String, Counters>,
+    /// Bytes held by the sender's and the listener's mempools (`0` while not running).
+    pub send_mempool_bytes: u64,
+    pub receive_mempool_bytes: u64,
+}
+
+/// One message written by the sender: wire topic key, framed bytes, enqueue-to-write ms.
+pub(crate) type SentSample = (i32, usize, u64);
+
+/// One message delivered by the listener.
+pub(crate) struct ReceivedSample<'a> {
+    pub topic: &'a str,
+    pub wire_bytes: usize,
+    pub payload_bytes: usize,
+    pub encoded_bytes: usize,
+    pub latency_ms: Option<u64>,
+}
+
+#[derive(Default)]
+struct MetricsInner {
+    total: Counters,
+    peers: HashMap<String, Counters>,
+    topics: HashMap<String, Counters>,
+    /// Sender wire topic key -> listener topic.
+    topic_names: HashMap<i32, String>,
+}
+
+impl MetricsInner {
+    /// Apply `f` to the total and, when non-empty, to `peer` and `topic`.
+    fn update(&mut self, peer: &str, topic: &str, f: impl Fn(&mut Counters)) {
+        f(&mut self.total);
+        if !peer.is_empty() {
+            f(self.peers.entry(peer.to_owned()).or_default());
+        }
+        if !topic.is_empty() {
+            f(self.topics.entry(topic.to_owned()).or_default());
+        }
+    }
+}
+
+/// Reaches the sender and listener threads inside the client's `StatusEmitter`.
+///
+/// [`Clone`] is a single `Arc` bump (not a deep copy).
+#[derive(Clone, Default)]
+pub struct Metrics {
+    shared: Arc<Mutex<MetricsInner>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with(&self, f: impl FnOnce(&mut MetricsInner)) {
+        if let Ok(mut inner) = self.shared.lock() {
+            f(&mut inner);
+        }
+    }
+
+    /// Name a wire `topic_key` so the sender's writes are counted per topic (the listener
+    /// names its own topics too, for frame capture).
+    pub fn name_topic(&self, topic_key: i32, topic: &str) {
+        self.with(|m| {
+            m.topic_names.insert(topic_key, topic.to_owned());
+        });
+    }
+
+    /// Name of a `topic_key` given to [`name_topic`](Self::name_topic); empty if unknown.
+    pub fn topic_name(&self, topic_key: i32) -> String {
+        self.shared
+            .lock()
+            .ok()
+            .and_then(|m| m.topic_names.get(&topic_key).cloned())
+            .unwrap_or_default()
+    }
+
+    /// A payload of `payload_len` bytes was encoded into `encoded_len` bytes for `peer`.
+    pub fn record_encoded(&self, peer: &str, topic: &str, payload_len: usize, encoded_len: usize) {
+        self.with(|m| {
+            m.update(peer, topic, |c| {
+                c.payload_bytes += payload_len as u64;
+                c.encoded_bytes += encoded_len as u64;
+            })
+        });
+    }
+
+    /// One write batch to `peer`.
+    pub(crate) fn record_sent(&self, peer: &str, samples: &[SentSample]) {
+        if samples.is_empty() {
+            return;
+        }
+        self.with(|m| {
+            for &(topic_key, bytes, latency_ms) in samples {
+                let topic = m.topic_names.get(&topic_key).cloned().unwrap_or_default();
+                m.update(peer, &topic, |c| {
+                    c.messages_sent += 1;
+                    c.bytes_sent += bytes as u64;
+                    c.enqueue_to_write.record(latency_ms);
+                });
+            }
+        });
+    }
+
+    /// One delivered batch from the sender `peer` (its `unique_name`).
+    pub(crate) fn record_received(&self, peer: &str, samples: &[ReceivedSample]) {
+        if samples.is_empty() {
+            return;
+        }
+        self.with(|m| {
+            for s in samples {
+                m.update(peer, s.topic, |c| {
+                    c.messages_received += 1;
+                    c.bytes_received += s.wire_bytes as u64;
+                    c.payload_bytes += s.payload_bytes as u64;
+                    c.encoded_bytes += s.encoded_bytes as u64;
+                    if let Some(ms) = s.latency_ms {
+                        c.send_to_receive.record(ms);
+                    }
+                });
+            }
+        });
+    }
+
+    pub fn record_reconnect(&self, peer: &str) {
+        self.with(|m| m.update(peer, "", |c| c.reconnects += 1));
+    }
+
+    pub fn record_route_lost(&self, peer: &str, topic: &str) {
+        self.with(|m| m.update(peer, topic, |c| c.route_losses += 1));
+    }
+
+    /// `peer` / `topic` may be empty when the failing path does not know them.
+    pub fn record_store_error(&self, peer: &str, topic: &str) {
+        self.with(|m| m.update(peer, topic, |c| c.store_errors += 1));
+    }
+
+    pub fn record_drops(&self, peer: &str, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.with(|m| m.update(peer, "", |c| c.drops += n as u64));
+    }
+
+    /// Copy of the counters; gauges are left at zero.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let Ok(m) = self.shared.lock() else {
+            return MetricsSnapshot::default();
+        };
+        MetricsSnapshot {
+            total: m.total.clone(),
+            peers: m.peers.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            topics: m.topics.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            ..MetricsSnapshot::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_buckets_and_quantiles() {
+        let mut h = LatencyHistogram::default();
+        for ms in [0, 1, 3, 3, 40, 20_000] {
+            h.record(ms);
+        }
+        assert_eq!(h.count, 6);
+        assert_eq!(h.buckets[0], 2);
+        assert_eq!(h.buckets[2], 2);
+        assert_eq!(h.buckets[5], 1);
+        assert_eq!(h.buckets[LATENCY_BUCKET_COUNT - 1], 1);
+        assert_eq!(h.max_ms, 20_000);
+        assert_eq!(h.quantile_ms(0.5), 5);
+        assert_eq!(h.quantile_ms(0.8), 50);
+        assert_eq!(h.quantile_ms(1.0), 20_000);
+        assert_eq!(LatencyHistogram::default().quantile_ms(0.5), 0);
+    }
+
+    #[test]
+    fn records_roll_up_into_peer_topic_and_total() {
+        let m = Metrics::new();
+        m.name_topic(7, "orders");
+        m.record_encoded("10.0.0.1:1", "orders", 1000, 250);
+        m.record_sent("10.0.0.1:1", &[(7, 280, 3), (8, 40, 1)]);
+        m.record_received(
+            "alice",
+            &[ReceivedSample {
+                topic: "orders",
+                wire_bytes: 30,
+                payload_bytes: 10,
+                encoded_bytes: 10,
+                latency_ms: Some(12),
+            }],
+        );
+        m.record_route_lost("10.0.0.1:1", "orders");
+        m.record_store_error("", "");
+        m.record_drops("10.0.0.1:1", 2);
+
+        let s = m.snapshot();
+        assert_eq!(s.total.messages_sent, 2);
+        assert_eq!(s.total.bytes_sent, 320);
+        assert_eq!(s.total.messages_received, 1);
+        assert_eq!(s.total.store_errors, 1);
+        let peer = &s.peers["10.0.0.1:1"];
+        assert_eq!((peer.messages_sent, peer.route_losses, peer.drops), (2, 1, 2));
+        assert_eq!(peer.compression_ratio(), 4.0);
+        let orders = &s.topics["orders"];
+        assert_eq!((orders.messages_sent, orders.messages_received), (1, 1));
+        assert_eq!(orders.send_to_receive.max_ms, 12);
+        assert_eq!(s.peers["alice"].bytes_received, 30);
+        assert_eq!(s.topics.len(), 1, "unnamed topic keys only count toward peer and total");
+    }
+}
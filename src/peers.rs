@@ -0,0 +1,44 @@
+//! Live state of peer links ([`Client::peers`](crate::Client::peers)).
+//!
+//! One row per outbound route lane (a `WriteStream` of the sender thread, or a route still
+//! waiting for its first connect) and per inbound connection slot of the listener. Rows are
+//! read when the call is made; nothing is kept between calls.
+
+use crate::message::Priority;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerInfo {
+    /// `false`: a listener this client sends to; `true`: a sender connected to this client.
+    pub inbound: bool,
+    /// Outbound: the listener's address. Inbound: the sender's socket address.
+    pub addr: String,
+    /// Outbound: the listener topic the route was opened for. Inbound: the sender's topic
+    /// (empty until its first message).
+    pub topic: String,
+    /// Peer's `unique_name`; empty if the store has none (inbound: until the first message).
+    pub unique_name: String,
+    /// Outbound lane; `Normal` for inbound links.
+    pub priority: Priority,
+    /// Wire connection key (`-1` for an inbound link before its first message).
+    pub connection_key: i32,
+    /// The TCP stream is up. Outbound routes are reconnected by the sender thread.
+    pub connected: bool,
+    /// Last connect or message, ms since Unix epoch; `0` if never.
+    pub last_activity_ms: u64,
+    /// Outbound: number of the last message written. Inbound: the last one received.
+    pub last_send_mess_number: u64,
+    /// Number acked by the listener (inbound: this client's ack).
+    pub last_mess_number: u64,
+    /// Outbound: messages waiting to be written or acked. Inbound: received messages waiting
+    /// for the receive callback.
+    pub queue_depth: u64,
+    /// Bytes held by the link's mempool.
+    pub mempool_bytes: u64,
+}
+
+impl PeerInfo {
+    /// Messages sent (or received) and not acked yet.
+    pub fn unacked(&self) -> u64 {
+        self.last_send_mess_number.saturating_sub(self.last_mess_number)
+    }
+}
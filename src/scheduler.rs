@@ -0,0 +1,68 @@
+//! Background tick for scheduled delivery (`send_to_at` / `send_to_after`) and topic failover.
+//!
+//! The thread only wakes every [`SCHEDULER_THREAD_WAIT_TIMEOUT_MS`](settings::SCHEDULER_THREAD_WAIT_TIMEOUT_MS)
+//! and calls `release`; due messages and unacked ones of lost routes live in the store or the
+//! sender, so nothing is lost when the thread (or the process) stops between ticks.
+
+use crate::UData;
+use crate::print_error;
+use crate::settings;
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+pub type ReleaseFn = fn(&UData);
+
+pub struct Scheduler {
+    close: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Scheduler {
+    pub fn new(release: ReleaseFn, udata: UData) -> Scheduler {
+        let close = Arc::new((Mutex::new(false), Condvar::new()));
+        let close_ = close.clone();
+        let thread = thread::spawn(move || {
+            let (lock, cvar) = &*close_;
+            loop {
+                {
+                    let Ok(closed) = lock.lock() else {
+                        print_error!("scheduler_thread: close lock poisoned");
+                        return;
+                    };
+                    let Ok((closed, _)) = cvar.wait_timeout_while(
+                        closed,
+                        Duration::from_millis(settings::SCHEDULER_THREAD_WAIT_TIMEOUT_MS),
+                        |closed| !*closed,
+                    ) else {
+                        return;
+                    };
+                    if *closed {
+                        return;
+                    }
+                }
+                release(&udata);
+            }
+        });
+        Scheduler {
+            close,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.close;
+        if let Ok(mut closed) = lock.lock() {
+            *closed = true;
+        }
+        cvar.notify_all();
+        if let Some(thread) = self.thread.take() {
+            if let Err(err) = thread.join() {
+                print_error!(&format!("scheduler_thread.join, {:?}", err));
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
-use crate::mempool::Mempool;
-use crate::message::Message;
+use crate::mempool::{self, Mempool, MempoolSlotStats};
+use crate::message::{Message, Priority};
+use crate::metrics::{Metrics, SentSample};
+use crate::peers::PeerInfo;
 use crate::store::Store;
 use crate::{print_error, print_debug};
 use crate::settings;
 use crate::common;
-use crate::status::{
-    StatusEmitter, StatusMsg, LNR_SENDER_ROUTE_LOST, LNR_SENDER_SEND_ERROR, LNR_SENDER_STORE_ERROR,
-};
+use crate::log;
+use crate::trace;
+use crate::status::{StatusEmitter, StatusEvent, StoreOp};
 
 use std::thread::JoinHandle;
 use std::time::Duration;
@@ -22,9 +24,11 @@ struct WriteStream{
     connection_key: i32,
     address: String,
     topic: String,
+    priority: Priority,
     stream: Arc<Option<TcpStream>>,
     last_send_mess_number: u64,
     last_mess_number: u64,
+    last_activity_ms: u64,
     is_active: bool,
     has_close_request: bool,
     is_closed: bool,
@@ -36,9 +40,11 @@ impl WriteStream {
             connection_key: 0,
             address: "".to_string(),
             topic: "".to_string(),
+            priority: Priority::Normal,
             stream: Arc::new(None),
             last_send_mess_number: 0,
             last_mess_number: 0,
+            last_activity_ms: 0,
             is_active: false,
             has_close_request: true,
             is_closed: true,
@@ -52,34 +58,57 @@ struct Address{
     connection_key: i32,
     address: String,
     topic: String,
+    priority: Priority,
 }
 
 type MempoolList = Vec<Arc<Mutex<Mempool>>>;
 type MessList = Vec<Option<Vec<Message>>>; 
 type WriteStreamList = Vec<Arc<Mutex<WriteStream>>>; 
 
+/// Route health seen by the wdelay thread, shared with the client send path.
+#[derive(Default)]
+struct RouteFailures {
+    /// Connect failures not yet drained by the client (evicted from its route cache once).
+    failed: HashSet<String>,
+    /// Addresses without a live stream (connect failed or stream closed) until the next
+    /// successful connect.
+    down: HashSet<String>,
+    /// Route-loss events not yet drained by the client (failover candidates).
+    lost: HashSet<String>,
+}
+
 pub struct Sender{
-    addrs_for: HashMap<String, usize>, // key addr, value addr_index
+    addrs_for: HashMap<String, usize>, // key addr, value addr_index (Normal lane)
+    /// (Normal-lane index, raised priority) -> index of that lane's own route.
+    lanes: HashMap<(usize, Priority), usize>,
     addrs_new: Arc<Mutex<Vec<Address>>>,
     messages: Arc<Mutex<MessList>>, 
     mempools: Arc<Mutex<MempoolList>>, 
+    /// Current stream of every route index, as published by the wdelay thread (a closed
+    /// placeholder until the first connect).
+    routes: Arc<Mutex<WriteStreamList>>,
     last_mess_number: Vec<u64>,
     connection_key: Vec<i32>,
     topic_keys: HashMap<String, i32>, // listener_topic -> wire topic_key
-    failed_addrs: Arc<Mutex<HashSet<String>>>,
+    last_enqueued: Option<usize>, // route index of the last accepted send_to
+    failed_addrs: Arc<Mutex<RouteFailures>>,
     has_failed_addrs: Arc<AtomicBool>,
     is_new_addr: Arc<AtomicBool>,
     is_close: Arc<AtomicBool>,
     delay_write_cvar: Arc<(Mutex<bool>, Condvar)>,
     wdelay_thread: Option<JoinHandle<()>>,
+    metrics: Metrics,
 }
 
-/// Result of enqueueing a message on the sender worklist.
+/// Result of enqueueing a message on the sender worklist. Values match C `LNR_SEND_*`.
+#[repr(i32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum EnqueueResult {
-    Ok,
-    Busy,
-    Fail,
+    Ok = 0,
+    /// Peer's in-memory queue is at `max_send_queue`.
+    Busy = 1,
+    /// Route could not be prepared or the message could not be queued.
+    Fail = 2,
 }
 
 impl Sender {
@@ -101,11 +130,14 @@ impl Sender {
         let is_new_addr_ = is_new_addr.clone();
         let is_close = Arc::new(AtomicBool::new(false));
         let is_close_ = is_close.clone();
-        let failed_addrs: Arc<Mutex<HashSet<String>>> = Arc::new(Mutex::new(HashSet::new()));
+        let failed_addrs: Arc<Mutex<RouteFailures>> = Arc::new(Mutex::new(RouteFailures::default()));
         let failed_addrs_ = failed_addrs.clone();
         let has_failed_addrs = Arc::new(AtomicBool::new(false));
         let has_failed_addrs_ = has_failed_addrs.clone();
+        let routes: Arc<Mutex<WriteStreamList>> = Arc::new(Mutex::new(Vec::new()));
+        let routes_ = routes.clone();
         let wdelay_thread = thread::spawn(move||{
+            let _log = log::client_scope(status_emitter_thread.client_name());
             let mut streams: WriteStreamList = Vec::new();
             let mut prev_time: [u64; 2] = [common::current_time_ms(); 2];
             let mut ppl_wait = false;
@@ -160,6 +192,7 @@ impl Sender {
                         &has_failed_addrs_,
                         &status_emitter_thread,
                     );
+                    publish_routes(&streams, &routes_);
                 }
                 if timeout_update_last_mess_number(ctime, &mut prev_time[1]){
                     update_last_mess_number(
@@ -176,6 +209,7 @@ impl Sender {
                     &db_thread,
                     &messages_,
                     &mempools_,
+                    &failed_addrs_,
                     &status_emitter_thread,
                 );
             }
@@ -190,18 +224,22 @@ impl Sender {
         });
         Self{
             addrs_for,
+            lanes: HashMap::new(),
             addrs_new,
             messages,
             mempools,
+            routes,
             last_mess_number: Vec::new(),
             connection_key: Vec::new(),
             topic_keys: HashMap::new(),
+            last_enqueued: None,
             failed_addrs,
             has_failed_addrs,
             is_new_addr,
             is_close,
             delay_write_cvar,
             wdelay_thread: Some(wdelay_thread),
+            metrics: status_emitter.metrics().clone(),
         }
     }
 
@@ -212,17 +250,39 @@ impl Sender {
         let taken = self
             .failed_addrs
             .lock()
-            .map(|mut g| std::mem::take(&mut *g))
+            .map(|mut g| std::mem::take(&mut g.failed))
             .unwrap_or_default();
         // Only clear the flag if nothing new arrived during the take.
         if let Ok(g) = self.failed_addrs.lock() {
-            if g.is_empty() {
+            if g.failed.is_empty() {
                 self.has_failed_addrs.store(false, Ordering::Relaxed);
             }
         }
         taken
     }
 
+    /// Route index of `addr`'s `priority` lane.
+    fn route_ix(&self, addr: &str, priority: Priority) -> Option<usize> {
+        let &ix = self.addrs_for.get(addr)?;
+        match priority {
+            Priority::Normal => Some(ix),
+            _ => self.lanes.get(&(ix, priority)).copied(),
+        }
+    }
+
+    /// Route indices of every open lane of `addr`, highest priority first.
+    fn lane_ixs(&self, addr: &str) -> Vec<(usize, Priority)> {
+        let Some(&ix) = self.addrs_for.get(addr) else {
+            return Vec::new();
+        };
+        let mut out: Vec<(usize, Priority)> = Priority::RAISED
+            .iter()
+            .filter_map(|&p| self.lanes.get(&(ix, p)).map(|&lane| (lane, p)))
+            .collect();
+        out.push((ix, Priority::Normal));
+        out
+    }
+
     /// Total in-memory messages waiting to send (all peer slots). Not store/offline depth.
     #[cfg(test)]
     pub(crate) fn send_queue_depth(&self) -> u64 {
@@ -235,41 +295,345 @@ impl Sender {
     }
 
     /// Per-peer in-memory send queue depth for known routes (`addrs_for`).
-    #[cfg(test)]
     pub(crate) fn send_queue_depth_by_peer(&self) -> Vec<(String, u64)> {
         let Ok(mess) = self.messages.lock() else {
             return Vec::new();
         };
         let mut rows = Vec::with_capacity(self.addrs_for.len());
-        for (addr, &ix) in &self.addrs_for {
-            let n = mess
-                .get(ix)
-                .and_then(|s| s.as_ref())
+        for addr in self.addrs_for.keys() {
+            let n = self
+                .lane_ixs(addr)
+                .into_iter()
+                .filter_map(|(ix, _)| mess.get(ix).and_then(|s| s.as_ref()))
                 .map(|v| v.len() as u64)
-                .unwrap_or(0);
+                .sum();
             rows.push((addr.clone(), n));
         }
         rows
     }
 
-    /// True when enqueue needs the shared store (unknown addr and/or topic_key).
-    pub fn needs_store_for_send(&self, addr_to: &str, listener_topic: &str) -> bool {
-        !self.addrs_for.contains_key(addr_to) || !self.topic_keys.contains_key(listener_topic)
+    /// Addresses that lost their route (connect fail / stream close) since the last call.
+    pub fn drain_lost_addrs(&mut self) -> HashSet<String> {
+        self.failed_addrs
+            .lock()
+            .map(|mut g| std::mem::take(&mut g.lost))
+            .unwrap_or_default()
+    }
+
+    /// Put addresses back for the next [`drain_lost_addrs`](Self::drain_lost_addrs).
+    pub fn requeue_lost_addrs(&mut self, addrs: Vec<String>) {
+        if addrs.is_empty() {
+            return;
+        }
+        if let Ok(mut g) = self.failed_addrs.lock() {
+            g.lost.extend(addrs);
+        }
+    }
+
+    /// Wire topic key of a topic already used for sending.
+    pub fn topic_key(&self, listener_topic: &str) -> Option<i32> {
+        self.topic_keys.get(listener_topic).copied()
+    }
+
+    /// Remove not-yet-acked messages for `listener_topic_key` queued toward `addr`, in memory
+    /// and in the offline queues of its lanes. Returns decoded `(payload, at_least_once, priority)`,
+    /// higher lanes first and each lane in send order; messages for other topics stay where they were.
+    pub fn take_unacked(
+        &mut self,
+        db: &mut dyn Store,
+        addr: &str,
+        listener_topic_key: i32,
+    ) -> Vec<(Vec<u8>, bool, Priority)> {
+        let mut out = Vec::new();
+        for (ix, priority) in self.lane_ixs(addr) {
+            for (payload, at_least_once) in self.take_unacked_lane(db, ix, listener_topic_key) {
+                out.push((payload, at_least_once, priority));
+            }
+        }
+        out
+    }
+
+    fn take_unacked_lane(
+        &mut self,
+        db: &mut dyn Store,
+        ix: usize,
+        listener_topic_key: i32,
+    ) -> Vec<(Vec<u8>, bool)> {
+        let Some(&connection_key) = self.connection_key.get(ix) else {
+            return Vec::new();
+        };
+        let mempool = match self.mempools.lock() {
+            Ok(mps) => match mps.get(ix) {
+                Some(mp) => mp.clone(),
+                None => return Vec::new(),
+            },
+            Err(_) => {
+                print_error!("take_unacked: mempools lock poisoned");
+                return Vec::new();
+            }
+        };
+        let acked = match db.get_last_mess_number_for_sender(connection_key) {
+            Ok(n) => n,
+            Err(err) => {
+                print_error!(&format!(
+                    "take_unacked: get_last_mess_number_for_sender {}: {}",
+                    connection_key, err
+                ));
+                return Vec::new();
+            }
+        };
+        let mut taken: Vec<Message> = Vec::new();
+        match db.load_messages_for_sender(&mempool, connection_key) {
+            Ok(stored) => {
+                let (mine, rest): (Vec<Message>, Vec<Message>) = stored
+                    .into_iter()
+                    .partition(|m| m.listener_topic_key == listener_topic_key);
+                if !rest.is_empty() {
+                    if let Err(err) = db.save_messages_from_sender(&mempool, connection_key, rest) {
+                        print_error!(&format!("take_unacked: save_messages_from_sender {}", err));
+                    }
+                }
+                taken.extend(mine);
+            }
+            Err(err) => {
+                print_error!(&format!("take_unacked: load_messages_for_sender {}", err));
+            }
+        }
+        if let Ok(mut mess_lock) = self.messages.lock() {
+            if let Some(slot) = mess_lock.get_mut(ix) {
+                if let Some(queued) = slot.take() {
+                    let (mine, rest): (Vec<Message>, Vec<Message>) = queued
+                        .into_iter()
+                        .partition(|m| m.listener_topic_key == listener_topic_key);
+                    *slot = Some(rest);
+                    taken.extend(mine);
+                }
+            }
+        }
+        taken.sort_by_key(|m| m.number_mess);
+        let mut out = Vec::with_capacity(taken.len());
+        let mut buff = Vec::new();
+        let mut prev = 0;
+        for m in taken {
+            // Offline copies of an at-least-once message can also still sit in memory.
+            if m.number_mess > acked && m.number_mess != prev {
+                let len = m.get_data(&mempool, &mut buff);
+                out.push((buff[..len].to_vec(), m.at_least_once_delivery()));
+            }
+            prev = m.number_mess;
+            m.free(&mempool);
+        }
+        out
+    }
+
+    /// True while `addr` has no live stream (last connect failed or stream closed);
+    /// cleared by the next successful connect.
+    pub fn is_route_down(&self, addr: &str) -> bool {
+        self.failed_addrs
+            .lock()
+            .map(|g| g.down.contains(addr))
+            .unwrap_or(false)
+    }
+
+    /// The write thread is still running.
+    pub fn is_alive(&self) -> bool {
+        self.wdelay_thread.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    /// Addresses without a live stream (see [`is_route_down`](Self::is_route_down)), sorted.
+    pub fn down_routes(&self) -> Vec<String> {
+        let mut out: Vec<String> = self
+            .failed_addrs
+            .lock()
+            .map(|g| g.down.iter().cloned().collect())
+            .unwrap_or_default();
+        out.sort();
+        out
+    }
+
+    /// Bytes held by the per-connection send mempools.
+    pub fn mempool_bytes(&self) -> u64 {
+        mempool::capacity_bytes(&self.mempools)
+    }
+
+    /// In-memory messages queued for `addr` on all lanes (`0` for an unknown route).
+    /// Not store/offline depth.
+    pub fn queue_depth(&self, addr: &str) -> u64 {
+        let ixs = self.lane_ixs(addr);
+        let Ok(mess) = self.messages.lock() else {
+            return 0;
+        };
+        ixs.into_iter()
+            .filter_map(|(ix, _)| mess.get(ix).and_then(|s| s.as_ref()))
+            .map(|v| v.len() as u64)
+            .sum()
+    }
+
+    /// Live state of every route lane, in route order.
+    pub fn peers(&self) -> Vec<PeerInfo> {
+        let routes: WriteStreamList = match self.routes.lock() {
+            Ok(r) => r.clone(),
+            Err(_) => return Vec::new(),
+        };
+        let mempools: MempoolList = self.mempools.lock().map(|m| m.clone()).unwrap_or_default();
+        let mut rows = Vec::with_capacity(routes.len());
+        for stream in routes {
+            let Ok(s) = stream.lock() else {
+                continue;
+            };
+            let queue_depth = self
+                .messages
+                .lock()
+                .ok()
+                .and_then(|ml| ml.get(s.ix).and_then(|m| m.as_ref()).map(|m| m.len() as u64))
+                .unwrap_or(0);
+            let mempool_bytes = mempools
+                .get(s.ix)
+                .and_then(|mp| mp.lock().ok().map(|mp| mp.capacity_bytes() as u64))
+                .unwrap_or(0);
+            rows.push(PeerInfo {
+                inbound: false,
+                addr: s.address.clone(),
+                topic: s.topic.clone(),
+                unique_name: String::new(),
+                priority: s.priority,
+                connection_key: s.connection_key,
+                connected: !s.is_closed && !s.has_close_request,
+                last_activity_ms: s.last_activity_ms,
+                last_send_mess_number: s.last_send_mess_number,
+                last_mess_number: s.last_mess_number,
+                queue_depth,
+                mempool_bytes,
+            });
+        }
+        rows
+    }
+
+    /// Mempool occupancy of every slot, in slot order.
+    pub fn mempool_stats(&self) -> Vec<MempoolSlotStats> {
+        let mut lanes: HashMap<usize, (String, i32)> = HashMap::new();
+        if let Ok(routes) = self.routes.lock() {
+            for stream in routes.iter() {
+                if let Ok(s) = stream.lock() {
+                    lanes.insert(s.ix, (s.address.clone(), s.connection_key));
+                }
+            }
+        }
+        let mempools: MempoolList = self.mempools.lock().map(|m| m.clone()).unwrap_or_default();
+        mempools
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, mp)| {
+                let stats = mp.lock().ok()?.stats();
+                let (addr, connection_key) = lanes.remove(&ix).unwrap_or_else(|| (String::new(), -1));
+                Some(MempoolSlotStats { inbound: false, slot: ix, addr, connection_key, stats })
+            })
+            .collect()
+    }
+
+    /// [`Mempool::compact`] every slot pool not in use right now; returns bytes released.
+    pub fn compact_memory(&self) -> u64 {
+        mempool::compact_idle(&self.mempools)
+    }
+
+    /// Wire connection key of a warm route to `addr`.
+    pub fn route_connection_key(&self, addr: &str) -> Option<i32> {
+        self.addrs_for
+            .get(addr)
+            .and_then(|&ix| self.connection_key.get(ix).copied())
+    }
+
+    /// Addresses with a route on `connection_key` (one listener reached at several addresses).
+    pub fn route_addrs_for_key(&self, connection_key: i32) -> Vec<String> {
+        self.addrs_for
+            .iter()
+            .filter(|(_, &ix)| self.connection_key.get(ix) == Some(&connection_key))
+            .map(|(addr, _)| addr.clone())
+            .collect()
+    }
+
+    /// Listener topics this sender has sent on.
+    pub fn sent_topics(&self) -> impl Iterator<Item = &String> {
+        self.topic_keys.keys()
+    }
+
+    /// Last number assigned on a warm connection key (`None` if this sender has no route for it).
+    pub fn last_mess_number_for_key(&self, connection_key: i32) -> Option<u64> {
+        let ix = self.connection_key.iter().position(|&ck| ck == connection_key)?;
+        self.last_mess_number.get(ix).copied()
     }
 
-    /// Warm `addrs_for` / `topic_keys` (and kick connect for new addr). Hold store only here —
+    /// `(connection_key, number_mess)` of the last message accepted by [`send_to`](Self::send_to).
+    pub fn last_enqueued(&self) -> Option<(i32, u64)> {
+        let ix = self.last_enqueued?;
+        Some((*self.connection_key.get(ix)?, *self.last_mess_number.get(ix)?))
+    }
+
+    /// Pull messages reassigned into `connection_key`'s offline queue onto its warm route and
+    /// continue numbering after `last_number`. No-op for a key without a route: the queue is
+    /// loaded on its first connect.
+    pub fn adopt_offline(&mut self, db: &mut dyn Store, connection_key: i32, last_number: u64) {
+        let Some(ix) = self.connection_key.iter().position(|&ck| ck == connection_key) else {
+            return;
+        };
+        if let Some(n) = self.last_mess_number.get_mut(ix) {
+            *n = (*n).max(last_number);
+        }
+        let mempool = match self.mempools.lock() {
+            Ok(mps) => match mps.get(ix) {
+                Some(mp) => mp.clone(),
+                None => return,
+            },
+            Err(_) => {
+                print_error!("adopt_offline: mempools lock poisoned");
+                return;
+            }
+        };
+        let mut mess = match db.load_messages_for_sender(&mempool, connection_key) {
+            Ok(m) => m,
+            Err(err) => {
+                print_error!(&format!("adopt_offline: load_messages_for_sender {}", err));
+                return;
+            }
+        };
+        for m in mess.iter_mut() {
+            m.set_replayed();
+        }
+        if mess.is_empty() {
+            return;
+        }
+        if let Ok(mut mess_lock) = self.messages.lock() {
+            if let Some(slot) = mess_lock.get_mut(ix) {
+                // Numbers are above everything queued in memory, so append keeps send order.
+                match slot.as_mut() {
+                    Some(queued) => queued.extend(mess),
+                    None => *slot = Some(mess),
+                }
+            }
+        }
+        wdelay_thread_notify(&self.delay_write_cvar);
+    }
+
+    /// True when enqueue needs the shared store (unknown addr, lane and/or topic_key).
+    pub fn needs_store_for_send(&self, addr_to: &str, listener_topic: &str, priority: Priority) -> bool {
+        self.route_ix(addr_to, priority).is_none() || !self.topic_keys.contains_key(listener_topic)
+    }
+
+    /// Warm `addrs_for` / `topic_keys` (and kick connect for new addr). A raised `priority`
+    /// also opens that lane next to the `Normal` route. Hold store only here —
     /// not across `Message::new` / compress.
     pub fn ensure_send_route(
         &mut self,
         db: &mut dyn Store,
         addr_to: &str,
         listener_topic: &str,
+        priority: Priority,
     ) -> bool {
         if !self.addrs_for.contains_key(addr_to) {
-            if !self.append_new_state(db, addr_to, listener_topic) {
+            if !self.append_new_state(db, addr_to, listener_topic, Priority::Normal) {
                 return false;
             }
-            let ix = self.addrs_for.len();
+            let ix = self.connection_key.len() - 1;
             let connection_key = match self.connection_key.get(ix) {
                 Some(&ck) => ck,
                 None => {
@@ -286,13 +650,18 @@ impl Sender {
                 connection_key,
                 address: addr_to.to_string(),
                 topic: listener_topic.to_string(),
+                priority: Priority::Normal,
             });
             self.is_new_addr.store(true, Ordering::Relaxed);
         }
+        if self.route_ix(addr_to, priority).is_none() && !self.append_lane(db, addr_to, listener_topic, priority) {
+            return false;
+        }
         if !self.topic_keys.contains_key(listener_topic) {
             match db.get_topic_key(listener_topic) {
                 Ok(key) => {
                     self.topic_keys.insert(listener_topic.to_owned(), key);
+                    self.metrics.name_topic(key, listener_topic);
                 }
                 Err(err) => {
                     print_error!(format!(
@@ -306,15 +675,63 @@ impl Sender {
         true
     }
 
-    /// Enqueue message. Caller must have warm route (`ensure_send_route` or prior sends).
+    /// Open the raised `priority` lane of a route whose `Normal` lane exists.
+    fn append_lane(&mut self, db: &mut dyn Store, addr_to: &str, listener_topic: &str, priority: Priority) -> bool {
+        let Some(&normal_ix) = self.addrs_for.get(addr_to) else {
+            return false;
+        };
+        if !self.append_new_state(db, addr_to, listener_topic, priority) {
+            return false;
+        }
+        let ix = self.connection_key.len() - 1;
+        self.lanes.insert((normal_ix, priority), ix);
+        self.addrs_new.lock().unwrap().push(Address {
+            ix,
+            connection_key: self.connection_key[ix],
+            address: addr_to.to_string(),
+            topic: listener_topic.to_string(),
+            priority,
+        });
+        self.is_new_addr.store(true, Ordering::Relaxed);
+        true
+    }
+
+    /// Enqueue message on `addr_to`'s `priority` lane. Caller must have warm route
+    /// (`ensure_send_route` or prior sends).
     pub fn send_to(
         &mut self,
         addr_to: &str,
         listener_topic: &str,
         data: &[u8],
         at_least_once_delivery: bool,
+        priority: Priority,
+    ) -> EnqueueResult {
+        self.enqueue(addr_to, listener_topic, data, at_least_once_delivery, priority, false)
+    }
+
+    /// `send_to` for a payload moved off another route's queue (`take_unacked`): the
+    /// receiver sees it as replayed.
+    pub fn resend_to(
+        &mut self,
+        addr_to: &str,
+        listener_topic: &str,
+        data: &[u8],
+        at_least_once_delivery: bool,
+        priority: Priority,
+    ) -> EnqueueResult {
+        self.enqueue(addr_to, listener_topic, data, at_least_once_delivery, priority, true)
+    }
+
+    fn enqueue(
+        &mut self,
+        addr_to: &str,
+        listener_topic: &str,
+        data: &[u8],
+        at_least_once_delivery: bool,
+        priority: Priority,
+        replayed: bool,
     ) -> EnqueueResult {
-        let Some(&ix) = self.addrs_for.get(addr_to) else {
+        let Some(ix) = self.route_ix(addr_to, priority) else {
             print_error!(&format!("send_to: address not prepared: {}", addr_to));
             return EnqueueResult::Fail;
         };
@@ -380,20 +797,30 @@ impl Sender {
                 return EnqueueResult::Fail;
             }
         };
-        let Some(mess) = Message::new(
+        let Some(mut mess) = Message::new_traced(
             mempool,
             connection_key,
             listener_topic_key,
             number_mess,
             data,
             at_least_once_delivery,
+            trace::current(),
         ) else {
             return EnqueueResult::Fail;
         };
+        if priority != Priority::Normal && !mess.set_priority(priority) {
+            return EnqueueResult::Fail;
+        }
+        if replayed && !mess.set_replayed() {
+            return EnqueueResult::Fail;
+        }
+        let encoded_len = mess.encoded_len();
         match self.send_mess_notify(mess, ix, cap) {
             EnqueueResult::Ok => {
+                self.metrics.record_encoded(addr_to, listener_topic, data.len(), encoded_len);
                 // Commit sequence only after enqueue succeeded.
                 self.last_mess_number[ix] = number_mess;
+                self.last_enqueued = Some(ix);
                 EnqueueResult::Ok
             }
             other => other,
@@ -443,7 +870,7 @@ impl Sender {
         }
     }
      
-    fn append_new_state(&mut self, db: &mut dyn Store, addr_to: &str, listener_topic: &str)->bool{
+    fn append_new_state(&mut self, db: &mut dyn Store, addr_to: &str, listener_topic: &str, priority: Priority)->bool{
         let listener_name = match db.get_listener_unique_name(listener_topic, addr_to) {
             Ok(name) => name,
             Err(_) => {
@@ -451,14 +878,15 @@ impl Sender {
                 return false;
             }
         };
-        let connection_key = match db.get_connection_key_for_sender(&listener_name) {
+        // Each lane is its own wire connection: key, ack number and offline queue.
+        let connection_key = match db.get_connection_key_for_sender(&priority.lane_listener_name(&listener_name)) {
             Ok(ck) => ck,
             Err(err) => {
                 print_error!(&format!("get_connection_key_for_sender from db: {}", err));
                 return false;
             }
         };
-        let mut last_mess_num = match db.get_last_mess_number_for_sender(connection_key) {
+        let acked = match db.get_last_mess_number_for_sender(connection_key) {
             Ok(n) => n,
             Err(err) => {
                 print_error!(&format!(
@@ -468,6 +896,7 @@ impl Sender {
                 return false;
             }
         };
+        let mut last_mess_num = acked;
 
         let mempool = Arc::new(Mutex::new(Mempool::new()));
         if let Ok(last_mess) = db.load_last_message_for_sender(&mempool, connection_key) {
@@ -480,8 +909,10 @@ impl Sender {
         } else {
             print_error!("db.load_last_message_for_sender");
         }
-        if let Err(err) = db.save_listener_for_sender(addr_to, listener_topic, &listener_name) {
-            print_error!(&format!("db.save_listener_for_sender {}", err));
+        if priority == Priority::Normal {
+            if let Err(err) = db.save_listener_for_sender(addr_to, listener_topic, &listener_name) {
+                print_error!(&format!("db.save_listener_for_sender {}", err));
+            }
         }
         if let Err(err) = db.set_sender_topic_by_connection_key_from_sender(connection_key) {
             print_error!(&format!("db.set_sender_topic_by_connection_key_from_sender {}", err));
@@ -509,6 +940,17 @@ impl Sender {
             }
             return false;
         }
+        if let Ok(mut routes) = self.routes.lock() {
+            let mut placeholder = WriteStream::new();
+            placeholder.ix = self.connection_key.len() - 1;
+            placeholder.connection_key = connection_key;
+            placeholder.address = addr_to.to_string();
+            placeholder.topic = listener_topic.to_string();
+            placeholder.priority = priority;
+            placeholder.last_send_mess_number = acked;
+            placeholder.last_mess_number = acked;
+            routes.push(Arc::new(Mutex::new(placeholder)));
+        }
         true
     }
 
@@ -516,14 +958,28 @@ impl Sender {
         match db.get_listeners_of_sender() {
             Ok(addr_topic) => {
                 for t in addr_topic{
-                    if self.append_new_state(db, &t.0, &t.1){
-                        let ix = self.addrs_for.len();
+                    if self.append_new_state(db, &t.0, &t.1, Priority::Normal){
+                        let ix = self.connection_key.len() - 1;
                         let connection_key = self.connection_key[ix];
                         self.addrs_new.lock().unwrap().push(Address{ix, 
                                                                     connection_key,
                                                                     address: t.0.clone(),
-                                                                    topic: t.1.clone()});
-                        self.addrs_for.insert(t.0, ix);
+                                                                    topic: t.1.clone(),
+                                                                    priority: Priority::Normal});
+                        self.addrs_for.insert(t.0.clone(), ix);
+                        // Raised lanes are not saved as listeners; reopen those with a backlog.
+                        let Ok(name) = db.get_listener_unique_name(&t.1, &t.0) else {
+                            continue;
+                        };
+                        for priority in Priority::RAISED {
+                            let pending = match db.find_connection_key_for_sender(&priority.lane_listener_name(&name)) {
+                                Ok(Some(ck)) => db.count_pending_messages(ck).unwrap_or(0),
+                                _ => 0,
+                            };
+                            if pending > 0 {
+                                self.append_lane(db, &t.0, &t.1, priority);
+                            }
+                        }
                     }
                 }
             },
@@ -550,17 +1006,16 @@ fn send_mess_to_listener(streams: &WriteStreamList,
                          status_emitter: &StatusEmitter){
     // Snapshot writable indices under the messages lock, then spawn writes without
     // holding it (enqueue / other peers must not stall on this).
-    let to_write: Vec<usize> = {
+    let mut to_write: Vec<(Priority, usize)> = {
         let mess_lock = messages.lock().unwrap();
         let mut out = Vec::new();
         for (ix, mess) in mess_lock.iter().enumerate() {
             if let Some(stream) = streams.get(ix) {
                 if let Some(mess) = mess.as_ref() {
                     if let Some(last) = mess.last() {
-                        let has_mess =
-                            last.number_mess > stream.lock().unwrap().last_send_mess_number;
-                        if has_mess {
-                            out.push(ix);
+                        let stream = stream.lock().unwrap();
+                        if last.number_mess > stream.last_send_mess_number {
+                            out.push((stream.priority, ix));
                         }
                     }
                 }
@@ -568,7 +1023,9 @@ fn send_mess_to_listener(streams: &WriteStreamList,
         }
         out
     };
-    for ix in to_write {
+    // Raised lanes start writing first.
+    to_write.sort_by_key(|&(priority, _)| std::cmp::Reverse(priority));
+    for (_, ix) in to_write {
         if let Some(stream) = streams.get(ix) {
             write_stream(stream, messages, mempools, delay_write_cvar.clone(), status_emitter.clone());
         }
@@ -688,13 +1145,16 @@ fn update_last_mess_number(streams: &mut WriteStreamList,
             },
             Err(err)=>{
                 print_error!(&format!("get_last_mess_number_for_sender from db, {}", err));
-                status_emitter.emit_msg(
-                    LNR_SENDER_STORE_ERROR,
-                    "",
-                    "",
-                    StatusMsg::GetLastMessNumber,
-                    &[&err],
-                );
+                status_emitter.metrics().record_store_error("", "");
+                if status_emitter.is_enabled() {
+                    status_emitter.emit_event(StatusEvent::SenderStoreError {
+                        topic: String::new(),
+                        addr: String::new(),
+                        connection_key: None,
+                        op: StoreOp::GetLastMessNumber,
+                        error: err.to_string(),
+                    });
+                }
             }
         }
     }
@@ -705,7 +1165,7 @@ fn append_streams(streams: &mut WriteStreamList,
                   db: &Arc<Mutex<dyn Store>>,
                   messages: &Arc<Mutex<MessList>>,
                   mempools: &Arc<Mutex<MempoolList>>,
-                  failed_addrs: &Arc<Mutex<HashSet<String>>>,
+                  failed_addrs: &Arc<Mutex<RouteFailures>>,
                   has_failed_addrs: &Arc<AtomicBool>,
                   status_emitter: &StatusEmitter){
     // Take the queue so we don't hold `addrs` across connect/db (ensure_send_route also pushes here).
@@ -719,6 +1179,11 @@ fn append_streams(streams: &mut WriteStreamList,
                 let _ = stream.set_nonblocking(false);
                 // Avoid Nagle + delayed-ACK ~40ms stalls on small end-of-batch writes.
                 let _ = stream.set_nodelay(true);
+                if let Ok(mut failed) = failed_addrs.lock() {
+                    if failed.down.remove(&addr.address) {
+                        status_emitter.metrics().record_reconnect(&addr.address);
+                    }
+                }
                 let mempool = match mempools.lock() {
                     Ok(mps) => match mps.get(addr.ix) {
                         Some(mp) => mp.clone(),
@@ -736,6 +1201,9 @@ fn append_streams(streams: &mut WriteStreamList,
                 };
                 match db.lock().unwrap().load_messages_for_sender(&mempool, addr.connection_key){
                     Ok(mut mess_from_db) =>{
+                        for m in mess_from_db.iter_mut() {
+                            m.set_replayed();
+                        }
                         if let Ok(mut mess_lock) = messages.lock(){
                             if let Some(slot) = mess_lock.get_mut(addr.ix) {
                                 if let Some(mut mess_for_send) = slot.take() {
@@ -749,15 +1217,15 @@ fn append_streams(streams: &mut WriteStreamList,
                     },
                     Err(err)=>{
                         print_error!(&format!("db.load_messages_for_sender, {} {}", addr.address, err));
+                        status_emitter.metrics().record_store_error(&addr.address, &addr.topic);
                         if status_emitter.is_enabled() {
-                            let err_s = err.to_string();
-                            status_emitter.emit_msg(
-                                LNR_SENDER_STORE_ERROR,
-                                &addr.topic,
-                                "",
-                                StatusMsg::LoadMessagesForSender,
-                                &[&addr.address, &err_s],
-                            );
+                            status_emitter.emit_event(StatusEvent::SenderStoreError {
+                                topic: addr.topic.clone(),
+                                addr: addr.address.clone(),
+                                connection_key: Some(addr.connection_key),
+                                op: StoreOp::LoadMessages,
+                                error: err.to_string(),
+                            });
                         }
                     }
                 }
@@ -792,37 +1260,41 @@ fn append_streams(streams: &mut WriteStreamList,
                                                        connection_key: addr.connection_key,  
                                                        address: addr.address.clone(),
                                                        topic: addr.topic.clone(),
+                                                       priority: addr.priority,
                                                        stream: Arc::new(Some(stream)), 
                                                        last_send_mess_number: last_ack_mess_number,
                                                        last_mess_number: last_ack_mess_number,
+                                                       last_activity_ms: common::current_time_ms(),
                                                        is_active: false, has_close_request: false, is_closed: false};
                 while addr.ix >= streams.len() {
                     streams.push(Arc::new(Mutex::new(WriteStream::new())));
                 }
                 streams[addr.ix] = Arc::new(Mutex::new(wstream));                
             },
-            Err(_err)=>{
+            Err(err)=>{
                 if let Ok(mut failed) = failed_addrs.lock() {
-                    failed.insert(addr.address.clone());
+                    failed.failed.insert(addr.address.clone());
+                    failed.down.insert(addr.address.clone());
+                    failed.lost.insert(addr.address.clone());
                 }
                 has_failed_addrs.store(true, Ordering::Relaxed);
-                print_debug!(&format!("tcp connect, {} {}", _err, addr.address));
+                print_debug!(&format!("tcp connect, {} {}", err, addr.address));
+                status_emitter.metrics().record_route_lost(&addr.address, &addr.topic);
                 if status_emitter.is_enabled() {
-                    let err_s = _err.to_string();
-                    status_emitter.emit_msg(
-                        LNR_SENDER_ROUTE_LOST,
-                        &addr.topic,
-                        "",
-                        StatusMsg::TcpConnectFailed,
-                        &[&err_s, &addr.address],
-                    );
+                    status_emitter.emit_event(StatusEvent::RouteLost {
+                        topic: addr.topic.clone(),
+                        addr: addr.address.clone(),
+                        io_error: Some(err.kind()),
+                        error: err.to_string(),
+                    });
                 }
                 if let Ok(mut mess_lock) = messages.lock() {
                     if let Some(slot) = mess_lock.get_mut(addr.ix) {
                         if let Some(mess) = slot.take() {
                             // Release messages before db — append_new_state/emit hold db then messages.
                             drop(mess_lock);
-                            save_mess_to_db(mess, db, addr.ix, addr.connection_key, mempools, status_emitter);
+                            let dropped = save_mess_to_db(mess, db, addr.ix, addr.connection_key, mempools, status_emitter);
+                            status_emitter.metrics().record_drops(&addr.address, dropped);
                         }
                     } else {
                         print_error!(&format!("append_streams: messages index out of bounds on connect fail {}", addr.ix));
@@ -840,6 +1312,19 @@ fn append_streams(streams: &mut WriteStreamList,
     }
 }
 
+/// Copy the connected streams of the wdelay thread to the route table read by `Sender::peers`.
+fn publish_routes(streams: &WriteStreamList, routes: &Arc<Mutex<WriteStreamList>>) {
+    let Ok(mut routes) = routes.lock() else {
+        return;
+    };
+    for (ix, stream) in streams.iter().enumerate() {
+        let has_addr = stream.lock().map(|s| !s.address.is_empty()).unwrap_or(false);
+        if has_addr && ix < routes.len() && !Arc::ptr_eq(&routes[ix], stream) {
+            routes[ix] = stream.clone();
+        }
+    }
+}
+
 fn write_stream(stream: &Arc<Mutex<WriteStream>>,
                 messages: &Arc<Mutex<MessList>>,
                 mempools: &Arc<Mutex<MempoolList>>,
@@ -874,6 +1359,9 @@ fn write_stream(stream: &Arc<Mutex<WriteStream>>,
         }
         if let Some(tcp_stream) = arc_stream.as_ref(){
             let mut buff: Vec<Message> = Vec::new();
+            let mut written: Vec<SentSample> = Vec::new();
+            // Positions in `buff` of the frames written; captured once the flush went through.
+            let mut captured: Vec<usize> = Vec::new();
             let mut writer = BufWriter::with_capacity(settings::WRITE_BUFFER_CAPASITY, tcp_stream); 
             let mempool = match mempools.lock() {
                 Ok(mps) => match mps.get(ix) {
@@ -915,15 +1403,20 @@ fn write_stream(stream: &Arc<Mutex<WriteStream>>,
                     let num_mess = mess.number_mess;
                     if !is_shutdown && last_send_mess_number < num_mess{
                         last_send_mess_number = num_mess;
-                        if !mess.to_stream(&mempool, &mut writer){
+                        if mess.to_stream(&mempool, &mut writer){
+                            captured.push(buff.len());
+                            let waited = common::current_time_ms().saturating_sub(mess.sent_at_ms());
+                            written.push((mess.listener_topic_key, mess.wire_len(), waited));
+                        }else{
                             is_shutdown = true;
-                            status_emitter.emit_msg(
-                                LNR_SENDER_SEND_ERROR,
-                                &topic,
-                                "",
-                                StatusMsg::WriteFailed,
-                                &[&address],
-                            );
+                            if status_emitter.is_enabled() {
+                                status_emitter.emit_event(StatusEvent::SendFailed {
+                                    topic: topic.clone(),
+                                    addr: address.clone(),
+                                    io_error: None,
+                                    error: String::new(),
+                                });
+                            }
                         }
                     }
                     buff.push(mess);
@@ -935,19 +1428,27 @@ fn write_stream(stream: &Arc<Mutex<WriteStream>>,
                     continue;
                 }
                 if status_emitter.is_enabled() {
-                    let err_s = err.to_string();
-                    let kind_s = err.kind().to_string();
-                    status_emitter.emit_msg(
-                        LNR_SENDER_SEND_ERROR,
-                        &topic,
-                        "",
-                        StatusMsg::FlushFailed,
-                        &[&address, &err_s, &kind_s],
-                    );
+                    status_emitter.emit_event(StatusEvent::SendFailed {
+                        topic: topic.clone(),
+                        addr: address.clone(),
+                        io_error: Some(err.kind()),
+                        error: err.to_string(),
+                    });
                 }
                 is_shutdown = true;
                 break;
             }
+            if !is_shutdown {
+                for &i in &captured {
+                    status_emitter.capture_frame(false, &address, &buff[i], &mempool);
+                }
+                status_emitter.metrics().record_sent(&address, &written);
+                if !written.is_empty() {
+                    if let Ok(mut s) = stream.lock() {
+                        s.last_activity_ms = common::current_time_ms();
+                    }
+                }
+            }
             let mut last_mess_number = 0;
             if let Ok(stream) = stream.lock(){
                 last_mess_number = stream.last_mess_number;
@@ -1003,6 +1504,7 @@ fn check_streams_close(streams: &mut WriteStreamList,
                        db: &Arc<Mutex<dyn Store>>,
                        messages: &Arc<Mutex<MessList>>,
                        mempools: &Arc<Mutex<MempoolList>>,
+                       failed_addrs: &Arc<Mutex<RouteFailures>>,
                        status_emitter: &StatusEmitter){
     for stream in streams.iter(){
         if let Ok(mut stream) = stream.lock(){
@@ -1019,7 +1521,8 @@ fn check_streams_close(streams: &mut WriteStreamList,
                     if let Some(slot) = ml.get_mut(ix) {
                         if let Some(mess) = slot.take() {
                             drop(ml);
-                            save_mess_to_db(mess, db, ix, connection_key, mempools, status_emitter);
+                            let dropped = save_mess_to_db(mess, db, ix, connection_key, mempools, status_emitter);
+                            status_emitter.metrics().record_drops(&address, dropped);
                         }
                     } else {
                         print_error!(&format!("check_streams_close: messages index out of bounds {}", ix));
@@ -1027,14 +1530,21 @@ fn check_streams_close(streams: &mut WriteStreamList,
                 } else {
                     print_error!("check_streams_close: messages lock poisoned");
                 }
-                status_emitter.emit_msg(
-                    LNR_SENDER_ROUTE_LOST,
-                    &topic,
-                    "",
-                    StatusMsg::StreamClosed,
-                    &[&address],
-                );
-                addrs_new.lock().unwrap().push(Address{ix, connection_key, address, topic});
+                if let Ok(mut failed) = failed_addrs.lock() {
+                    failed.down.insert(address.clone());
+                    failed.lost.insert(address.clone());
+                }
+                status_emitter.metrics().record_route_lost(&address, &topic);
+                if status_emitter.is_enabled() {
+                    status_emitter.emit_event(StatusEvent::RouteLost {
+                        topic: topic.clone(),
+                        addr: address.clone(),
+                        io_error: None,
+                        error: String::new(),
+                    });
+                }
+                let priority = stream.priority;
+                addrs_new.lock().unwrap().push(Address{ix, connection_key, address, topic, priority});
             
                 stream.is_closed = true;
             }
@@ -1042,23 +1552,26 @@ fn check_streams_close(streams: &mut WriteStreamList,
     }  
 }
 
+/// Persist the at-least-once messages of `mess` and free the rest. Returns how many
+/// best-effort messages were discarded before being acked.
 fn save_mess_to_db(mess: Vec<Message>, db: &Arc<Mutex<dyn Store>>,
                    ix: usize, connection_key: i32, mempools: &Arc<Mutex<MempoolList>>,
-                   status_emitter: &StatusEmitter){                    
+                   status_emitter: &StatusEmitter)->usize{
     let mut last_send_mess_number: u64 = 0;
-    if let Ok(num) = db.lock().unwrap().get_last_mess_number_for_sender(connection_key){
-        last_send_mess_number = num;
-    }else{
-        print_error!(format!("couldn't db.get_last_mess_number_for_sender, connection_key {}", connection_key));
-        if status_emitter.is_enabled() {
-            let ck = connection_key.to_string();
-            status_emitter.emit_msg(
-                LNR_SENDER_STORE_ERROR,
-                "",
-                "",
-                StatusMsg::GetLastMessNumberConnKey,
-                &[&ck],
-            );
+    match db.lock().unwrap().get_last_mess_number_for_sender(connection_key){
+        Ok(num) => last_send_mess_number = num,
+        Err(err) => {
+            print_error!(format!("couldn't db.get_last_mess_number_for_sender, connection_key {}", connection_key));
+            status_emitter.metrics().record_store_error("", "");
+            if status_emitter.is_enabled() {
+                status_emitter.emit_event(StatusEvent::SenderStoreError {
+                    topic: String::new(),
+                    addr: String::new(),
+                    connection_key: Some(connection_key),
+                    op: StoreOp::GetLastMessNumber,
+                    error: err.to_string(),
+                });
+            }
         }
     }
     let mempool = match mempools.lock() {
@@ -1066,12 +1579,12 @@ fn save_mess_to_db(mess: Vec<Message>, db: &Arc<Mutex<dyn Store>>,
             Some(mp) => mp.clone(),
             None => {
                 print_error!(&format!("save_mess_to_db: mempool index out of bounds {}", ix));
-                return;
+                return 0;
             }
         },
         Err(_) => {
             print_error!("save_mess_to_db: mempools lock poisoned");
-            return;
+            return 0;
         }
     };
 
@@ -1079,10 +1592,14 @@ fn save_mess_to_db(mess: Vec<Message>, db: &Arc<Mutex<dyn Store>>,
     // `save_messages_from_sender` on the store (frees encoded messages internally).
     let mut to_save: Vec<Message> = Vec::new();
     let mut to_free: Vec<Message> = Vec::new();
+    let mut dropped = 0;
     for m in mess {
-        if m.at_least_once_delivery() && m.number_mess > last_send_mess_number {
+        if m.number_mess <= last_send_mess_number {
+            to_free.push(m);
+        } else if m.at_least_once_delivery() {
             to_save.push(m);
         } else {
+            dropped += 1;
             to_free.push(m);
         }
     }
@@ -1097,16 +1614,15 @@ fn save_mess_to_db(mess: Vec<Message>, db: &Arc<Mutex<dyn Store>>,
                 "db.save_messages_from_sender, connection_key {}, err {}",
                 connection_key, err
             ));
+            status_emitter.metrics().record_store_error("", "");
             if status_emitter.is_enabled() {
-                let ck = connection_key.to_string();
-                let err_s = err.to_string();
-                status_emitter.emit_msg(
-                    LNR_SENDER_STORE_ERROR,
-                    "",
-                    "",
-                    StatusMsg::SaveMessagesFromSender,
-                    &[&ck, &err_s],
-                );
+                status_emitter.emit_event(StatusEvent::SenderStoreError {
+                    topic: String::new(),
+                    addr: String::new(),
+                    connection_key: Some(connection_key),
+                    op: StoreOp::SaveMessages,
+                    error: err.to_string(),
+                });
             }
         }
     }
@@ -1114,6 +1630,7 @@ fn save_mess_to_db(mess: Vec<Message>, db: &Arc<Mutex<dyn Store>>,
     for m in to_free {
         m.free(&mempool);
     }
+    dropped
 }
 
 fn close_streams(streams: &WriteStreamList,
@@ -1127,7 +1644,7 @@ fn close_streams(streams: &WriteStreamList,
             stream.has_close_request = true;
         }
     }
-    let mut pending: Vec<(usize, i32, Vec<Message>)> = Vec::new();
+    let mut pending: Vec<(usize, i32, String, Vec<Message>)> = Vec::new();
     {
         let mut messages = messages.lock().unwrap();
         for (ix, mess) in messages.iter_mut().enumerate() {
@@ -1135,23 +1652,26 @@ fn close_streams(streams: &WriteStreamList,
                 if mess_for_send.is_empty() {
                     continue;
                 }
-                let connection_key = streams
+                let route = streams
                     .get(ix)
-                    .and_then(|s| s.lock().ok().map(|s| s.connection_key))
+                    .and_then(|s| s.lock().ok().map(|s| (s.connection_key, s.address.clone())))
                     .or_else(|| {
-                        addrs_new
-                            .lock()
-                            .ok()
-                            .and_then(|addrs| addrs.iter().find(|a| a.ix == ix).map(|a| a.connection_key))
+                        addrs_new.lock().ok().and_then(|addrs| {
+                            addrs
+                                .iter()
+                                .find(|a| a.ix == ix)
+                                .map(|a| (a.connection_key, a.address.clone()))
+                        })
                     });
-                if let Some(connection_key) = connection_key {
-                    pending.push((ix, connection_key, mess_for_send));
+                if let Some((connection_key, address)) = route {
+                    pending.push((ix, connection_key, address, mess_for_send));
                 }
             }
         }
     }
-    for (ix, connection_key, mess_for_send) in pending {
-        save_mess_to_db(mess_for_send, db, ix, connection_key, mempools, status_emitter);
+    for (ix, connection_key, address, mess_for_send) in pending {
+        let dropped = save_mess_to_db(mess_for_send, db, ix, connection_key, mempools, status_emitter);
+        status_emitter.metrics().record_drops(&address, dropped);
     }
 }
 
@@ -1205,9 +1725,11 @@ mod tests {
             connection_key: 777,
             address: addr.to_string(),
             topic: "test_topic".to_string(),
+            priority: Priority::Normal,
             stream: Arc::new(Some(server)),
             last_send_mess_number: 0,
             last_mess_number: 0,
+            last_activity_ms: 0,
             is_active: false,
             has_close_request: false,
             is_closed: false,
@@ -1244,6 +1766,67 @@ mod tests {
         );
     }
 
+    #[test]
+    fn write_stream_captures_frames_only_after_a_successful_flush() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let path = std::env::temp_dir().join(format!(
+            "liner_send_capture_{}_{}.cap",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        let path = path.to_str().unwrap().to_owned();
+        let status_emitter = StatusEmitter::new();
+        status_emitter.capture().start(&path).unwrap();
+
+        let run = |payload: &[u8], fail_flush: bool| {
+            let _client = TcpStream::connect(addr).unwrap();
+            let (server, _) = listener.accept().unwrap();
+            if fail_flush {
+                // Writes to a shut-down half fail: the frame fits the buffer, the flush does not.
+                server.shutdown(std::net::Shutdown::Write).unwrap();
+            }
+            let mempool = Arc::new(Mutex::new(Mempool::new()));
+            let messages: Arc<Mutex<MessList>> = Arc::new(Mutex::new(vec![None]));
+            let mempools: Arc<Mutex<MempoolList>> = Arc::new(Mutex::new(vec![mempool.clone()]));
+            let mess = Message::new(mempool.clone(), 777, 42, 1, payload, true).unwrap();
+            messages.lock().unwrap()[0] = Some(vec![mess]);
+            let stream = Arc::new(Mutex::new(WriteStream {
+                ix: 0,
+                connection_key: 777,
+                address: addr.to_string(),
+                topic: "test_topic".to_string(),
+                priority: Priority::Normal,
+                stream: Arc::new(Some(server)),
+                last_send_mess_number: 0,
+                last_mess_number: 0,
+                last_activity_ms: 0,
+                is_active: false,
+                has_close_request: false,
+                is_closed: false,
+            }));
+            let cvar: Arc<(Mutex<bool>, Condvar)> = Arc::new((Mutex::new(false), Condvar::new()));
+            write_stream(&stream, &messages, &mempools, cvar, status_emitter.clone());
+            assert!(
+                wait_until(Duration::from_secs(1), || !stream.lock().unwrap().is_active),
+                "write_stream didn't finish in time"
+            );
+            for m in messages.lock().unwrap()[0].take().unwrap_or_default() {
+                m.free(&mempool);
+            }
+        };
+        run(b"lost", true);
+        run(b"sent", false);
+        assert!(status_emitter.capture().stop());
+
+        let captured: Vec<Vec<u8>> = crate::capture::CaptureReader::open(&path)
+            .unwrap()
+            .map(|rec| rec.unwrap().decode().unwrap().payload)
+            .collect();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(captured, vec![b"sent".to_vec()]);
+    }
+
     #[test]
     fn write_stream_keeps_message_in_queue_for_at_least_once_until_confirmed() {
         let listener = TcpListener::bind("127.0.0.1:0").unwrap();
@@ -1267,10 +1850,12 @@ mod tests {
             connection_key: 888,
             address: addr.to_string(),
             topic: "test_topic".to_string(),
+            priority: Priority::Normal,
             stream: Arc::new(Some(server)),
             last_send_mess_number: 0,
             // Not yet confirmed by receiver (db update would set this later).
             last_mess_number: 0,
+            last_activity_ms: 0,
             is_active: false,
             has_close_request: false,
             is_closed: false,
@@ -1323,9 +1908,11 @@ mod tests {
             connection_key: 999,
             address: addr.to_string(),
             topic: "test_topic".to_string(),
+            priority: Priority::Normal,
             stream: Arc::new(Some(server)),
             last_send_mess_number: 0,
             last_mess_number: 0,
+            last_activity_ms: 0,
             is_active: false,
             has_close_request: false,
             is_closed: false,
@@ -1379,9 +1966,11 @@ mod tests {
             connection_key: 0,
             address: "127.0.0.1:0".to_string(),
             topic: String::new(),
+            priority: Priority::Normal,
             stream: Arc::new(None),
             last_send_mess_number: 0,
             last_mess_number: 0,
+            last_activity_ms: 0,
             is_active: false,
             has_close_request: false,
             is_closed: false,
@@ -8,6 +8,9 @@ pub const READ_BUFFER_CAPASITY: usize = 64 * 1024;
 /// Flush read messages to the receive thread (and wake it) every N messages so
 /// a large burst does not delay delivery until the whole stream drain finishes.
 pub const LISTENER_RECEIVE_FLUSH_BATCH: usize = 100;
+/// Read messages per peer the receive thread may hold before the listener stops
+/// reading that socket until the callback / channel catches up.
+pub const LISTENER_MAX_PENDING_PER_PEER: usize = 10 * LISTENER_RECEIVE_FLUSH_BATCH;
 pub const BYTESTREAM_READ_BUFFER_SIZE: usize = 8 * 1024;
 pub const BYTESTREAM_WRITE_BUFFER_SIZE: usize = 8 * 1024;
 /// Default max framed message size (also initial value of [`max_message_size`]).
@@ -32,6 +35,34 @@ pub const MEMPOOL_CHUNK_SIZE_BYTE: usize = 256 * 1024;
 pub const MEMPOOL_MIN_PERCENT_FOR_RESIZE: f32 = 0.25;
 pub const MEMPOOL_OVER_SIZE_MB: usize = 64;
 
+/// Virtual nodes per replica address on the `send_to_keyed` consistent-hash ring.
+pub const HASH_RING_VIRTUAL_NODES: usize = 64;
+/// Max age of a cached offline-backlog count used by `RoutePolicy::LeastLoaded`.
+pub const ROUTE_BACKLOG_REFRESH_MS: u64 = 1000;
+/// How often the scheduler thread checks the store for due `send_to_at` messages and
+/// fails over lost routes (`set_topic_failover`).
+pub const SCHEDULER_THREAD_WAIT_TIMEOUT_MS: u64 = 100;
+/// Max scheduled messages released per scheduler tick.
+pub const SCHEDULED_RELEASE_BATCH: usize = 1000;
+/// Retry delay of a due at-least-once message that found no route.
+pub const SCHEDULED_RETRY_MS: u64 = 1000;
+/// Items buffered per `AsyncLiner` stream. A full subscription holds back the receive
+/// thread; a full status stream drops further events until it is read.
+#[cfg(feature = "tokio")]
+pub const ASYNC_STREAM_CAPACITY: usize = 1024;
+/// How often `AsyncLiner::confirmed` re-reads the listener's ack number from the store.
+#[cfg(feature = "tokio")]
+pub const ASYNC_CONFIRM_POLL_MS: u64 = 20;
+/// Accept poll interval of the metrics exporter (also bounds how long stopping it takes).
+#[cfg(feature = "prometheus")]
+pub const EXPORTER_THREAD_WAIT_TIMEOUT_MS: u64 = 100;
+/// Read / write timeout of one scrape connection.
+#[cfg(feature = "prometheus")]
+pub const EXPORTER_IO_TIMEOUT_MS: u64 = 1000;
+/// Request head bytes read before the request line is parsed anyway.
+#[cfg(feature = "prometheus")]
+pub const EXPORTER_MAX_REQUEST_SIZE: usize = 8 * 1024;
+
 static MAX_MESSAGE_SIZE: AtomicUsize = AtomicUsize::new(BYTESTREAM_MAX_MESSAGE_SIZE);
 static COMPRESS_THRESHOLD: AtomicUsize = AtomicUsize::new(MIN_SIZE_DATA_FOR_COMPRESS_BYTE);
 /// 0 = unlimited (default).
@@ -1,5 +1,10 @@
 //! Status / error callback kinds and a thread-safe emitter for FFI callbacks.
 
+use crate::bytestream::FrameError;
+use crate::capture::CaptureSink;
+use crate::mempool::Mempool;
+use crate::message::Message;
+use crate::metrics::Metrics;
 use crate::UData;
 use std::collections::HashMap;
 use std::ffi::{CStr, CString};
@@ -24,6 +29,12 @@ pub const LNR_SENDER_SEND_ERROR: i32 = 7;
 pub const LNR_LISTENER_STORE_ERROR: i32 = 8;
 /// Sender: sync enqueue rejected because peer send queue is full (`max_send_queue`).
 pub const LNR_SENDER_BUSY: i32 = 9;
+/// Sender: unacked messages of a lost route were re-routed to another replica (topic failover).
+pub const LNR_SENDER_FAILOVER: i32 = 10;
+/// Listener: a peer's frame was rejected (oversized, truncated, malformed); its stream is closed.
+pub const LNR_LISTENER_FRAME_ERROR: i32 = 11;
+/// Listener: a received payload could not be decompressed (delivered empty).
+pub const LNR_LISTENER_DECODE_ERROR: i32 = 12;
 
 /// Keys into the status detail message map ([`status_msg_templates`]).
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -40,6 +51,9 @@ pub enum StatusMsg {
     GetLastMessNumberForListener,
     GetSenderTopicByConnectionKey,
     SendQueueFull,
+    Failover,
+    FrameRejected,
+    DecodeFailed,
 }
 
 /// Template strings for [`StatusMsg`]. Placeholders are `{}` in order of `args`.
@@ -83,6 +97,9 @@ fn status_msg_templates() -> &'static HashMap<StatusMsg, &'static str> {
                 "get_sender_topic conn_key {}: {}",
             ),
             (StatusMsg::SendQueueFull, "send queue full"),
+            (StatusMsg::Failover, "rerouted {} messages from {} to {}"),
+            (StatusMsg::FrameRejected, "frame from {}: {}"),
+            (StatusMsg::DecodeFailed, "decode failed: message {} from {}"),
         ])
     })
 }
@@ -105,6 +122,159 @@ fn render_status_msg(msg: StatusMsg, args: &[&str]) -> String {
     out
 }
 
+/// Store operation of a [`StatusEvent::SenderStoreError`] / [`StatusEvent::ListenerStoreError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StoreOp {
+    /// Sender: acked number of a connection.
+    GetLastMessNumber,
+    /// Sender: offline queue of a reconnected route.
+    LoadMessages,
+    /// Sender: unacked messages of a lost route.
+    SaveMessages,
+    /// Listener: ack of received messages.
+    SetLastMessNumber,
+    /// Listener: acked number of a new inbound connection.
+    GetLastMessNumberForListener,
+    /// Listener: sender topic of a new inbound connection.
+    GetSenderTopic,
+}
+
+/// Typed status event for Rust callers ([`crate::Client::set_status_event_cb`]). The C
+/// callback gets the same events as `kind` plus `topic` / `peer` / `message` strings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum StatusEvent {
+    /// A peer on a related topic ran (`LNR_PEER_CONNECTED`).
+    PeerConnected { topic: String, unique_name: String },
+    /// A peer on a related topic stopped (`LNR_PEER_DISCONNECTED`).
+    PeerDisconnected { topic: String, unique_name: String },
+    /// A peer subscribed to a related topic (`LNR_PEER_SUBSCRIBED`).
+    PeerSubscribed { topic: String, unique_name: String },
+    /// A peer unsubscribed from a related topic (`LNR_PEER_UNSUBSCRIBED`).
+    PeerUnsubscribed { topic: String, unique_name: String },
+    /// TCP connect failed (`io_error` set) or the stream closed (`LNR_SENDER_ROUTE_LOST`).
+    RouteLost { topic: String, addr: String, io_error: Option<std::io::ErrorKind>, error: String },
+    /// Write or flush failed after an accepted send (`LNR_SENDER_SEND_ERROR`).
+    SendFailed { topic: String, addr: String, io_error: Option<std::io::ErrorKind>, error: String },
+    /// Background store error on the send side (`LNR_SENDER_STORE_ERROR`).
+    SenderStoreError { topic: String, addr: String, connection_key: Option<i32>, op: StoreOp, error: String },
+    /// Background store error on the receive side (`LNR_LISTENER_STORE_ERROR`).
+    ListenerStoreError { topic: String, connection_key: Option<i32>, op: StoreOp, error: String },
+    /// A send was rejected with `queue_depth` messages already queued for `addr` (`LNR_SENDER_BUSY`).
+    SenderBusy { topic: String, addr: String, queue_depth: u64 },
+    /// `rerouted` unacked messages of lost `from_addr` moved to `to_addr` (`LNR_SENDER_FAILOVER`).
+    Failover { topic: String, from_addr: String, to_addr: String, rerouted: usize },
+    /// Frame from `addr` rejected; the stream is closed (`LNR_LISTENER_FRAME_ERROR`).
+    /// `topic` is the sender's, empty before its first message.
+    FrameRejected { topic: String, addr: String, error: FrameError },
+    /// Payload of message `number` from `unique_name` on `topic` could not be decompressed
+    /// (`LNR_LISTENER_DECODE_ERROR`).
+    DecodeFailed { topic: String, unique_name: String, number: u64 },
+}
+
+impl StatusEvent {
+    /// `LNR_*` kind passed to the C callback.
+    pub fn kind(&self) -> i32 {
+        match self {
+            StatusEvent::PeerConnected { .. } => LNR_PEER_CONNECTED,
+            StatusEvent::PeerDisconnected { .. } => LNR_PEER_DISCONNECTED,
+            StatusEvent::PeerSubscribed { .. } => LNR_PEER_SUBSCRIBED,
+            StatusEvent::PeerUnsubscribed { .. } => LNR_PEER_UNSUBSCRIBED,
+            StatusEvent::RouteLost { .. } => LNR_SENDER_ROUTE_LOST,
+            StatusEvent::SendFailed { .. } => LNR_SENDER_SEND_ERROR,
+            StatusEvent::SenderStoreError { .. } => LNR_SENDER_STORE_ERROR,
+            StatusEvent::ListenerStoreError { .. } => LNR_LISTENER_STORE_ERROR,
+            StatusEvent::SenderBusy { .. } => LNR_SENDER_BUSY,
+            StatusEvent::Failover { .. } => LNR_SENDER_FAILOVER,
+            StatusEvent::FrameRejected { .. } => LNR_LISTENER_FRAME_ERROR,
+            StatusEvent::DecodeFailed { .. } => LNR_LISTENER_DECODE_ERROR,
+        }
+    }
+
+    /// Topic the event relates to (may be empty).
+    pub fn topic(&self) -> &str {
+        match self {
+            StatusEvent::PeerConnected { topic, .. }
+            | StatusEvent::PeerDisconnected { topic, .. }
+            | StatusEvent::PeerSubscribed { topic, .. }
+            | StatusEvent::PeerUnsubscribed { topic, .. }
+            | StatusEvent::RouteLost { topic, .. }
+            | StatusEvent::SendFailed { topic, .. }
+            | StatusEvent::SenderStoreError { topic, .. }
+            | StatusEvent::ListenerStoreError { topic, .. }
+            | StatusEvent::SenderBusy { topic, .. }
+            | StatusEvent::Failover { topic, .. }
+            | StatusEvent::FrameRejected { topic, .. }
+            | StatusEvent::DecodeFailed { topic, .. } => topic,
+        }
+    }
+
+    /// `peer` string of the C callback.
+    fn c_peer(&self) -> &str {
+        match self {
+            StatusEvent::PeerConnected { unique_name, .. }
+            | StatusEvent::PeerDisconnected { unique_name, .. }
+            | StatusEvent::PeerSubscribed { unique_name, .. }
+            | StatusEvent::PeerUnsubscribed { unique_name, .. }
+            | StatusEvent::DecodeFailed { unique_name, .. } => unique_name,
+            StatusEvent::SenderBusy { addr, .. } | StatusEvent::FrameRejected { addr, .. } => addr,
+            StatusEvent::Failover { to_addr, .. } => to_addr,
+            _ => "",
+        }
+    }
+
+    /// `message` string of the C callback.
+    fn c_message(&self) -> String {
+        match self {
+            StatusEvent::PeerConnected { .. } => "client_connected".to_owned(),
+            StatusEvent::PeerDisconnected { .. } => "client_disconnected".to_owned(),
+            StatusEvent::PeerSubscribed { .. } => "subscribed".to_owned(),
+            StatusEvent::PeerUnsubscribed { .. } => "unsubscribed".to_owned(),
+            StatusEvent::RouteLost { addr, io_error: Some(_), error, .. } => {
+                render_status_msg(StatusMsg::TcpConnectFailed, &[error, addr])
+            }
+            StatusEvent::RouteLost { addr, .. } => render_status_msg(StatusMsg::StreamClosed, &[addr]),
+            StatusEvent::SendFailed { addr, io_error: Some(kind), error, .. } => {
+                render_status_msg(StatusMsg::FlushFailed, &[addr, error, &kind.to_string()])
+            }
+            StatusEvent::SendFailed { addr, .. } => render_status_msg(StatusMsg::WriteFailed, &[addr]),
+            StatusEvent::SenderStoreError { addr, connection_key, op, error, .. } => match (op, connection_key) {
+                (StoreOp::LoadMessages, _) => render_status_msg(StatusMsg::LoadMessagesForSender, &[addr, error]),
+                (StoreOp::SaveMessages, Some(ck)) => {
+                    render_status_msg(StatusMsg::SaveMessagesFromSender, &[&ck.to_string(), error])
+                }
+                (_, Some(ck)) => render_status_msg(StatusMsg::GetLastMessNumberConnKey, &[&ck.to_string()]),
+                _ => render_status_msg(StatusMsg::GetLastMessNumber, &[error]),
+            },
+            StatusEvent::ListenerStoreError { connection_key, op, error, .. } => {
+                let ck = connection_key.map(|ck| ck.to_string()).unwrap_or_default();
+                match op {
+                    StoreOp::SetLastMessNumber => {
+                        render_status_msg(StatusMsg::SetLastMessNumberFromListener, &[&ck, error])
+                    }
+                    StoreOp::GetSenderTopic => {
+                        render_status_msg(StatusMsg::GetSenderTopicByConnectionKey, &[&ck, error])
+                    }
+                    _ => render_status_msg(StatusMsg::GetLastMessNumberForListener, &[error]),
+                }
+            }
+            StatusEvent::SenderBusy { .. } => render_status_msg(StatusMsg::SendQueueFull, &[]),
+            StatusEvent::Failover { from_addr, to_addr, rerouted, .. } => {
+                render_status_msg(StatusMsg::Failover, &[&rerouted.to_string(), from_addr, to_addr])
+            }
+            StatusEvent::FrameRejected { addr, error, .. } => {
+                render_status_msg(StatusMsg::FrameRejected, &[addr, &error.to_string()])
+            }
+            StatusEvent::DecodeFailed { unique_name, number, .. } => {
+                render_status_msg(StatusMsg::DecodeFailed, &[&number.to_string(), unique_name])
+            }
+        }
+    }
+}
+
+/// Rust-side status sink; runs on the thread that raised the event.
+pub type StatusEventCb = Box<dyn FnMut(&StatusEvent) + Send>;
+
 pub type StatusCbackIntern = extern "C" fn(
     kind: i32,
     topic: *const i8,
@@ -116,15 +286,21 @@ pub type StatusCbackIntern = extern "C" fn(
 struct StatusEmitterInner {
     cb: Option<StatusCbackIntern>,
     udata: UData,
+    event_cb: Option<StatusEventCb>,
 }
 
 struct StatusEmitterShared {
     inner: Mutex<StatusEmitterInner>,
     /// Lock-free fast path when no user callback is registered.
     enabled: AtomicBool,
+    metrics: Metrics,
+    capture: CaptureSink,
+    /// `unique_name` of the owning client, for log attribution (`""` when unattached).
+    client: Arc<str>,
 }
 
-/// Shared between `Client` and background sender threads.
+/// Shared between `Client` and background sender threads. Also carries the client's
+/// [`Metrics`], whose counters track the same background events, and its name for log scopes.
 ///
 /// [`Clone`] is a single `Arc` bump (not a deep copy).
 #[derive(Clone)]
@@ -134,27 +310,85 @@ pub struct StatusEmitter {
 
 impl StatusEmitter {
     pub fn new() -> Self {
+        Self::for_client("")
+    }
+
+    pub fn for_client(name: &str) -> Self {
         Self {
             shared: Arc::new(StatusEmitterShared {
                 inner: Mutex::new(StatusEmitterInner {
                     cb: None,
                     udata: UData::null(),
+                    event_cb: None,
                 }),
                 enabled: AtomicBool::new(false),
+                metrics: Metrics::new(),
+                capture: CaptureSink::default(),
+                client: Arc::from(name),
             }),
         }
     }
 
+    /// Name for [`log::client_scope`](crate::log::client_scope) on threads of this client.
+    pub fn client_name(&self) -> &Arc<str> {
+        &self.shared.client
+    }
+
+    /// Counters kept for the client's lifetime, recorded whether or not a callback is set.
+    pub fn metrics(&self) -> &Metrics {
+        &self.shared.metrics
+    }
+
+    /// Frame capture file of the client ([`Client::start_capture`](crate::Client::start_capture)).
+    pub(crate) fn capture(&self) -> &CaptureSink {
+        &self.shared.capture
+    }
+
+    /// Record `mess` if a capture is on; `peer` is the remote address of the stream.
+    #[inline]
+    pub(crate) fn capture_frame(&self, inbound: bool, peer: &str, mess: &Message, mempool: &Arc<Mutex<Mempool>>) {
+        let capture = &self.shared.capture;
+        if capture.is_enabled() {
+            let topic = self.shared.metrics.topic_name(mess.listener_topic_key);
+            capture.record(inbound, peer, &topic, mess, mempool);
+        }
+    }
+
     #[inline]
     pub fn is_enabled(&self) -> bool {
         self.shared.enabled.load(Ordering::Relaxed)
     }
 
     pub fn set_callback(&self, cb: Option<StatusCbackIntern>, udata: UData) {
-        self.shared.enabled.store(cb.is_some(), Ordering::Relaxed);
         if let Ok(mut inner) = self.shared.inner.lock() {
             inner.cb = cb;
             inner.udata = udata;
+            self.shared.enabled.store(inner.cb.is_some() || inner.event_cb.is_some(), Ordering::Relaxed);
+        }
+    }
+
+    /// Typed counterpart of [`StatusEmitter::set_callback`]; both may be set at once.
+    pub fn set_event_callback(&self, cb: Option<StatusEventCb>) {
+        if let Ok(mut inner) = self.shared.inner.lock() {
+            inner.event_cb = cb;
+            self.shared.enabled.store(inner.cb.is_some() || inner.event_cb.is_some(), Ordering::Relaxed);
+        }
+    }
+
+    /// Deliver `event` to the typed callback and, rendered, to the C callback.
+    pub fn emit_event(&self, event: StatusEvent) {
+        if !self.is_enabled() {
+            return;
+        }
+        let Ok(mut inner) = self.shared.inner.lock() else {
+            return;
+        };
+        if inner.cb.is_some() {
+            let message = event.c_message();
+            Self::call_c(&inner, event.kind(), event.topic(), event.c_peer(), &message);
+        }
+        if let Some(event_cb) = inner.event_cb.as_mut() {
+            event_cb(&event);
         }
     }
 
@@ -179,6 +413,10 @@ impl StatusEmitter {
         let Ok(inner) = self.shared.inner.lock() else {
             return;
         };
+        Self::call_c(&inner, kind, topic, peer, message);
+    }
+
+    fn call_c(inner: &StatusEmitterInner, kind: i32, topic: &str, peer: &str, message: &str) {
         let Some(cb) = inner.cb else {
             return;
         };
@@ -234,4 +472,32 @@ mod tests {
             "stream closed: 127.0.0.1:1"
         );
     }
+
+    #[test]
+    fn events_render_the_c_kind_peer_and_message() {
+        let event = StatusEvent::SenderStoreError {
+            topic: String::new(),
+            addr: String::new(),
+            connection_key: Some(7),
+            op: StoreOp::SaveMessages,
+            error: "busy".to_owned(),
+        };
+        assert_eq!(event.kind(), LNR_SENDER_STORE_ERROR);
+        assert_eq!(event.c_message(), "save_messages_from_sender connection_key 7: busy");
+        let event = StatusEvent::RouteLost {
+            topic: "t".to_owned(),
+            addr: "127.0.0.1:1".to_owned(),
+            io_error: Some(std::io::ErrorKind::ConnectionRefused),
+            error: "refused".to_owned(),
+        };
+        assert_eq!((event.kind(), event.topic(), event.c_peer()), (LNR_SENDER_ROUTE_LOST, "t", ""));
+        assert_eq!(event.c_message(), "tcp connect failed: refused 127.0.0.1:1");
+        let event = StatusEvent::Failover {
+            topic: "t".to_owned(),
+            from_addr: "a".to_owned(),
+            to_addr: "b".to_owned(),
+            rerouted: 3,
+        };
+        assert_eq!((event.c_peer(), event.c_message().as_str()), ("b", "rerouted 3 messages from a to b"));
+    }
 }
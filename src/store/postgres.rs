@@ -2,9 +2,9 @@
 //!
 //! Enable with Cargo feature **`postgres`** (`--features postgres`).
 
-use crate::{message::Message, mempool::Mempool, print_error};
+use crate::{message::{self, Message, Priority}, mempool::Mempool, print_error, topic_pattern, trace::TraceContext};
 
-use super::store::{DbError, DbResult, ReceiverSeedEntry, Store};
+use super::store::{DbError, DbResult, ReceiverSeedEntry, ScheduledMessage, Store};
 use postgres::{Client, Error, NoTls};
 
 use std::collections::{HashMap, HashSet};
@@ -59,6 +59,9 @@ CREATE TABLE IF NOT EXISTS topic_addr (
     topic TEXT NOT NULL,
     addr TEXT NOT NULL,
     client_name TEXT NOT NULL,
+    grp TEXT NOT NULL DEFAULT '',
+    -- topic_pattern::is_pattern(topic), set on insert.
+    is_pattern BOOLEAN NOT NULL DEFAULT FALSE,
     PRIMARY KEY (topic, addr)
 );
 
@@ -85,6 +88,11 @@ CREATE TABLE IF NOT EXISTS conn_sender (
     sender_topic TEXT NOT NULL
 );
 
+CREATE TABLE IF NOT EXISTS conn_sender_name (
+    connection_key INTEGER PRIMARY KEY,
+    unique_name TEXT NOT NULL
+);
+
 CREATE TABLE IF NOT EXISTS conn_mess_number (
     connection_key INTEGER PRIMARY KEY,
     v BIGINT NOT NULL
@@ -97,6 +105,24 @@ CREATE TABLE IF NOT EXISTS conn_messages (
 );
 CREATE INDEX IF NOT EXISTS idx_conn_messages_ck
     ON conn_messages(connection_key, id);
+
+CREATE TABLE IF NOT EXISTS retained (
+    topic TEXT PRIMARY KEY,
+    sender_topic TEXT NOT NULL,
+    payload BYTEA NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS scheduled (
+    id BIGSERIAL PRIMARY KEY,
+    sender_key TEXT NOT NULL,
+    due_ms BIGINT NOT NULL,
+    topic TEXT NOT NULL,
+    at_least_once BOOLEAN NOT NULL,
+    payload BYTEA NOT NULL,
+    traceparent TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_scheduled_due
+    ON scheduled(sender_key, due_ms, id);
 ";
 
 pub struct Postgres {
@@ -129,6 +155,26 @@ impl Postgres {
         let _ = client.batch_execute(
             "ALTER TABLE sender_listener ADD COLUMN IF NOT EXISTS client_name TEXT NOT NULL DEFAULT ''",
         );
+        let _ = client.batch_execute(
+            "ALTER TABLE topic_addr ADD COLUMN IF NOT EXISTS grp TEXT NOT NULL DEFAULT ''",
+        );
+        // Fails once the column exists, so the backfill runs only for an older catalog.
+        if client
+            .batch_execute("ALTER TABLE topic_addr ADD COLUMN is_pattern BOOLEAN NOT NULL DEFAULT FALSE")
+            .is_ok()
+        {
+            let rows = map_pg(client.query("SELECT DISTINCT topic FROM topic_addr", &[]))?;
+            for row in rows {
+                let topic: String = map_pg(row.try_get(0))?;
+                if topic_pattern::is_pattern(&topic) {
+                    map_pg(client.execute("UPDATE topic_addr SET is_pattern = TRUE WHERE topic = $1", &[&topic]))?;
+                }
+            }
+        }
+        // Pattern rows only, for get_pattern_subscribers (same predicate as its query).
+        map_pg(client.batch_execute(
+            "CREATE INDEX IF NOT EXISTS idx_topic_addr_is_pattern ON topic_addr(addr) WHERE is_pattern",
+        ))?;
         // SET does not accept bind parameters ($1) in PostgreSQL.
         map_pg(client.batch_execute(&format!("SET lock_timeout = '{LOCK_TIMEOUT}'")))?;
 
@@ -213,9 +259,9 @@ impl Postgres {
         let mut tx = map_pg(self.client.transaction())?;
         for e in entries {
             map_pg(tx.execute(
-                "INSERT INTO topic_addr (topic, addr, client_name) VALUES ($1, $2, $3)
+                "INSERT INTO topic_addr (topic, addr, client_name, is_pattern) VALUES ($1, $2, $3, $4)
                  ON CONFLICT (topic, addr) DO UPDATE SET client_name = EXCLUDED.client_name",
-                &[&e.topic, &e.addr, &e.client_name],
+                &[&e.topic, &e.addr, &e.client_name, &topic_pattern::is_pattern(&e.topic)],
             ))?;
             let topic_key = FIRST_ISOLATED_TOPIC_KEY;
             map_pg(tx.execute(
@@ -276,9 +322,9 @@ impl Store for Postgres {
         let localhost = self.source_localhost.clone();
         let unique = self.unique_name.clone();
         map_pg(self.client.execute(
-            "INSERT INTO topic_addr (topic, addr, client_name) VALUES ($1, $2, $3)
+            "INSERT INTO topic_addr (topic, addr, client_name, is_pattern) VALUES ($1, $2, $3, $4)
              ON CONFLICT (topic, addr) DO UPDATE SET client_name = EXCLUDED.client_name",
-            &[&topic, &localhost, &unique],
+            &[&topic, &localhost, &unique, &topic_pattern::is_pattern(topic)],
         ))?;
         self.init_addresses_of_topic(topic)?;
         Ok(())
@@ -294,6 +340,17 @@ impl Store for Postgres {
         Ok(())
     }
 
+    fn unregist_topic_elsewhere(&mut self, topic: &str) -> DbResult<()> {
+        let localhost = self.source_localhost.clone();
+        let unique = self.unique_name.clone();
+        map_pg(self.client.execute(
+            "DELETE FROM topic_addr WHERE topic = $1 AND client_name = $2 AND addr <> $3",
+            &[&topic, &unique, &localhost],
+        ))?;
+        self.init_addresses_of_topic(topic)?;
+        Ok(())
+    }
+
     fn clear_addresses_of_topic(&mut self) -> DbResult<()> {
         let topic = self.source_topic.clone();
         map_pg(
@@ -313,15 +370,19 @@ impl Store for Postgres {
             let addr: String = map_pg(row.try_get(0))?;
             let listener_topic: String = map_pg(row.try_get(1))?;
             if let Ok(listener_name) = self.get_listener_unique_name(&listener_topic, &addr) {
-                if let Ok(connection_key) = self.get_connection_key_for_sender(&listener_name) {
-                    map_pg(self.client.execute(
-                        "DELETE FROM conn_messages WHERE connection_key = $1",
-                        &[&connection_key],
-                    ))?;
-                    map_pg(self.client.execute(
-                        "DELETE FROM conn_mess_number WHERE connection_key = $1",
-                        &[&connection_key],
-                    ))?;
+                // Priority lanes have their own keys next to the plain listener name.
+                for priority in std::iter::once(Priority::Normal).chain(Priority::RAISED) {
+                    let lane = priority.lane_listener_name(&listener_name);
+                    if let Ok(Some(connection_key)) = self.find_connection_key_for_sender(&lane) {
+                        map_pg(self.client.execute(
+                            "DELETE FROM conn_messages WHERE connection_key = $1",
+                            &[&connection_key],
+                        ))?;
+                        map_pg(self.client.execute(
+                            "DELETE FROM conn_mess_number WHERE connection_key = $1",
+                            &[&connection_key],
+                        ))?;
+                    }
                 }
             }
         }
@@ -419,6 +480,161 @@ impl Store for Postgres {
         Ok(out)
     }
 
+    fn get_pattern_subscribers(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        let rows = map_pg(self.client.query(
+            "SELECT topic, addr, client_name FROM topic_addr WHERE is_pattern ORDER BY addr ASC",
+            &[],
+        ))?;
+        let mut out: Vec<(String, String)> = Vec::new();
+        for row in rows {
+            let pattern: String = map_pg(row.try_get(0))?;
+            let addr: String = map_pg(row.try_get(1))?;
+            let name: String = map_pg(row.try_get(2))?;
+            if !topic_pattern::matches(&pattern, topic) || out.iter().any(|(a, _)| *a == addr)
+            {
+                continue;
+            }
+            self.unique_name_cache
+                .insert(cache_name_key(topic, &addr), name.clone());
+            out.push((addr, name));
+        }
+        Ok(out)
+    }
+
+    fn set_topic_group(&mut self, topic: &str, group: &str) -> DbResult<()> {
+        let localhost = self.source_localhost.clone();
+        map_pg(self.client.execute(
+            "UPDATE topic_addr SET grp = $1 WHERE topic = $2 AND addr = $3",
+            &[&group, &topic, &localhost],
+        ))?;
+        Ok(())
+    }
+
+    fn get_topic_groups(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        let rows = map_pg(self.client.query(
+            "SELECT topic, addr, grp FROM topic_addr WHERE grp <> '' ORDER BY addr ASC",
+            &[],
+        ))?;
+        let mut exact: Vec<(String, String)> = Vec::new();
+        let mut by_pattern: Vec<(String, String)> = Vec::new();
+        for row in rows {
+            let t: String = map_pg(row.try_get(0))?;
+            let addr: String = map_pg(row.try_get(1))?;
+            let group: String = map_pg(row.try_get(2))?;
+            if t == topic {
+                exact.push((addr, group));
+            } else if topic_pattern::is_pattern(&t) && topic_pattern::matches(&t, topic) {
+                by_pattern.push((addr, group));
+            }
+        }
+        for (addr, group) in by_pattern {
+            if !exact.iter().any(|(a, _)| *a == addr) {
+                exact.push((addr, group));
+            }
+        }
+        Ok(exact)
+    }
+
+    fn get_topic_by_key(&mut self, topic_key: i32) -> DbResult<Option<String>> {
+        let rows = map_pg(self.client.query(
+            "SELECT topic FROM topic_key WHERE k = $1 LIMIT 2",
+            &[&topic_key],
+        ))?;
+        // Seeded catalogs share one wire key across topics: ambiguous, so unknown.
+        if rows.len() != 1 {
+            return Ok(None);
+        }
+        Ok(Some(map_pg(rows[0].try_get(0))?))
+    }
+
+    fn save_retained(&mut self, topic: &str, sender_topic: &str, payload: &[u8]) -> DbResult<()> {
+        map_pg(self.client.execute(
+            "INSERT INTO retained (topic, sender_topic, payload) VALUES ($1, $2, $3)
+             ON CONFLICT (topic) DO UPDATE SET sender_topic = EXCLUDED.sender_topic, payload = EXCLUDED.payload",
+            &[&topic, &sender_topic, &payload],
+        ))?;
+        Ok(())
+    }
+
+    fn clear_retained(&mut self, topic: &str) -> DbResult<()> {
+        map_pg(self.client.execute("DELETE FROM retained WHERE topic = $1", &[&topic]))?;
+        Ok(())
+    }
+
+    fn load_retained(&mut self, topic: &str) -> DbResult<Vec<(String, String, Vec<u8>)>> {
+        // Patterns are matched here; a concrete topic only needs its own row.
+        let all = topic_pattern::is_pattern(topic);
+        let rows = map_pg(self.client.query(
+            "SELECT topic, sender_topic, payload FROM retained
+             WHERE $1 OR topic = $2 ORDER BY topic ASC",
+            &[&all, &topic],
+        ))?;
+        let mut out = Vec::new();
+        for row in rows {
+            let t: String = map_pg(row.try_get(0))?;
+            if !topic_pattern::matches(topic, &t) {
+                continue;
+            }
+            out.push((t, map_pg(row.try_get(1))?, map_pg(row.try_get(2))?));
+        }
+        Ok(out)
+    }
+
+    fn schedule_message(
+        &mut self,
+        topic: &str,
+        at_least_once: bool,
+        due_ms: u64,
+        payload: &[u8],
+        trace: Option<TraceContext>,
+    ) -> DbResult<()> {
+        let sk = sender_key(&self.unique_name, &self.source_topic);
+        let due = i64::try_from(due_ms).unwrap_or(i64::MAX);
+        let traceparent = trace.map(|t| t.to_string());
+        map_pg(self.client.execute(
+            "INSERT INTO scheduled (sender_key, due_ms, topic, at_least_once, payload, traceparent)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+            &[&sk, &due, &topic, &at_least_once, &payload, &traceparent],
+        ))?;
+        Ok(())
+    }
+
+    fn load_due_scheduled(&mut self, now_ms: u64, limit: usize) -> DbResult<Vec<ScheduledMessage>> {
+        let sk = sender_key(&self.unique_name, &self.source_topic);
+        let now = i64::try_from(now_ms).unwrap_or(i64::MAX);
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let rows = map_pg(self.client.query(
+            "SELECT id, topic, at_least_once, payload, traceparent FROM scheduled
+             WHERE sender_key = $1 AND due_ms <= $2 ORDER BY due_ms ASC, id ASC LIMIT $3",
+            &[&sk, &now, &limit],
+        ))?;
+        let mut out = Vec::with_capacity(rows.len());
+        for row in rows {
+            out.push(ScheduledMessage {
+                id: map_pg(row.try_get(0))?,
+                topic: map_pg(row.try_get(1))?,
+                at_least_once: map_pg(row.try_get(2))?,
+                payload: map_pg(row.try_get(3))?,
+                trace: map_pg(row.try_get::<_, Option<String>>(4))?.as_deref().and_then(TraceContext::parse),
+            });
+        }
+        Ok(out)
+    }
+
+    fn remove_scheduled(&mut self, id: i64) -> DbResult<()> {
+        map_pg(self.client.execute("DELETE FROM scheduled WHERE id = $1", &[&id]))?;
+        Ok(())
+    }
+
+    fn reschedule_message(&mut self, id: i64, due_ms: u64) -> DbResult<()> {
+        let due = i64::try_from(due_ms).unwrap_or(i64::MAX);
+        map_pg(self.client.execute(
+            "UPDATE scheduled SET due_ms = $2 WHERE id = $1",
+            &[&id, &due],
+        ))?;
+        Ok(())
+    }
+
     fn count_pending_messages(&mut self, connection_key: i32) -> DbResult<usize> {
         let row = map_pg(self.client.query_one(
             "SELECT COUNT(*)::bigint FROM conn_messages WHERE connection_key = $1",
@@ -486,6 +702,11 @@ impl Store for Postgres {
              ON CONFLICT (connection_key) DO UPDATE SET sender_topic = EXCLUDED.sender_topic",
             &[&connection_key, &source_topic],
         ))?;
+        map_pg(self.client.execute(
+            "INSERT INTO conn_sender_name (connection_key, unique_name) VALUES ($1, $2)
+             ON CONFLICT (connection_key) DO UPDATE SET unique_name = EXCLUDED.unique_name",
+            &[&connection_key, &self.unique_name],
+        ))?;
         Ok(())
     }
 
@@ -497,6 +718,17 @@ impl Store for Postgres {
         map_pg(row.try_get(0))
     }
 
+    fn get_sender_name_by_connection_key(&mut self, connection_key: i32) -> DbResult<Option<String>> {
+        let row = map_pg(self.client.query_opt(
+            "SELECT unique_name FROM conn_sender_name WHERE connection_key = $1",
+            &[&connection_key],
+        ))?;
+        match row {
+            Some(row) => Ok(Some(map_pg(row.try_get(0))?)),
+            None => Ok(None),
+        }
+    }
+
     fn set_last_mess_number_from_listener(&mut self, connection_key: i32, val: u64) -> DbResult<()> {
         let v = i64::try_from(val).map_err(|_| DbError::new("mess_number too large for i64"))?;
         map_pg(self.client.execute(
@@ -619,9 +851,59 @@ impl Store for Postgres {
         Ok(out)
     }
 
+    fn reassign_pending_messages(
+        &mut self,
+        from_connection_key: i32,
+        to_connection_key: i32,
+        listener_topic_key: Option<i32>,
+        min_number: u64,
+    ) -> DbResult<(usize, u64)> {
+        let acked = self.get_last_mess_number_for_sender(to_connection_key)?;
+        let mut tx = map_pg(self.client.transaction())?;
+        let rows = map_pg(tx.query(
+            "SELECT id, payload FROM conn_messages WHERE connection_key = $1 ORDER BY id ASC FOR UPDATE",
+            &[&from_connection_key],
+        ))?;
+        let pairs: Vec<(i64, Vec<u8>)> = rows
+            .into_iter()
+            .map(|row| Ok((map_pg(row.try_get(0))?, map_pg(row.try_get(1))?)))
+            .collect::<DbResult<_>>()?;
+        let tail: Option<Vec<u8>> = match map_pg(tx.query_opt(
+            "SELECT payload FROM conn_messages WHERE connection_key = $1 ORDER BY id DESC LIMIT 1",
+            &[&to_connection_key],
+        ))? {
+            Some(row) => Some(map_pg(row.try_get(0))?),
+            None => None,
+        };
+        let mut number = min_number
+            .max(acked)
+            .max(tail.as_deref().and_then(message::stored_number_mess).unwrap_or(0));
+        let mut moved = 0;
+        for (id, mut blob) in pairs {
+            map_pg(tx.execute("DELETE FROM conn_messages WHERE id = $1", &[&id]))?;
+            if !message::rewrite_stored_header(&mut blob, to_connection_key, listener_topic_key, number + 1) {
+                print_error!("reassign_pending_messages: truncated blob dropped");
+                continue;
+            }
+            number += 1;
+            // New row id keeps the moved messages behind the target's existing queue.
+            map_pg(tx.execute(
+                "INSERT INTO conn_messages (connection_key, payload) VALUES ($1, $2)",
+                &[&to_connection_key, &blob],
+            ))?;
+            moved += 1;
+        }
+        map_pg(tx.commit())?;
+        Ok((moved, number))
+    }
+
     fn seed_receivers(&mut self, entries: &[ReceiverSeedEntry]) -> DbResult<()> {
         self.seed_receivers_pg(entries)
     }
+
+    fn ping(&mut self) -> DbResult<()> {
+        map_pg(self.client.batch_execute("SELECT 1"))
+    }
 }
 
 #[cfg(test)]
@@ -1,6 +1,6 @@
-use crate::{message::Message, mempool::Mempool, print_error};
+use crate::{message::{self, Message}, mempool::Mempool, print_error, topic_pattern, trace::TraceContext};
 
-use super::store::{DbError, DbResult, ReceiverSeedEntry, Store};
+use super::store::{DbError, DbResult, ReceiverSeedEntry, ScheduledMessage, Store};
 use ::redis::{Commands, ConnectionLike, ErrorKind, RedisResult};
 
 use std::collections::HashMap;
@@ -79,6 +79,9 @@ impl Redis {
         let topic_k = redis_safe(topic);
         let dbconn = self.get_dbconn()?;
         let () = dbconn.hset(&format!("lnr_topic:{topic_k}:addr"), localhost, unique)?;
+        if topic_pattern::is_pattern(topic) {
+            let () = dbconn.sadd("lnr_topic_patterns", topic)?;
+        }
         self.init_addresses_of_topic(topic)?;
         Ok(())
     }
@@ -86,7 +89,29 @@ impl Redis {
         let localhost = self.source_localhost.to_string();
         let topic_k = redis_safe(topic);
         let dbconn = self.get_dbconn()?;
-        let () = dbconn.hdel(&format!("lnr_topic:{topic_k}:addr"), localhost)?;
+        let () = dbconn.hdel(&format!("lnr_topic:{topic_k}:addr"), &localhost)?;
+        let () = dbconn.hdel(format!("lnr_topic:{topic_k}:group"), localhost)?;
+        if topic_pattern::is_pattern(topic) {
+            let left: usize = dbconn.hlen(format!("lnr_topic:{topic_k}:addr"))?;
+            if left == 0 {
+                let () = dbconn.srem("lnr_topic_patterns", topic)?;
+            }
+        }
+        self.init_addresses_of_topic(topic)?;
+        Ok(())
+    }
+    pub fn unregist_topic_elsewhere(&mut self, topic: &str)->RedisResult<()>{
+        let localhost = self.source_localhost.to_string();
+        let unique = self.unique_name.to_string();
+        let topic_k = redis_safe(topic);
+        let dbconn = self.get_dbconn()?;
+        let rows: Vec<(String, String)> = dbconn.hgetall(format!("lnr_topic:{topic_k}:addr"))?;
+        for (addr, name) in rows {
+            if name == unique && addr != localhost {
+                let () = dbconn.hdel(format!("lnr_topic:{topic_k}:addr"), &addr)?;
+                let () = dbconn.hdel(format!("lnr_topic:{topic_k}:group"), &addr)?;
+            }
+        }
         self.init_addresses_of_topic(topic)?;
         Ok(())
     }
@@ -218,6 +243,188 @@ impl Redis {
         Ok(rows)
     }
 
+    pub fn get_pattern_subscribers(&mut self, topic: &str) -> RedisResult<Vec<(String, String)>> {
+        let dbconn = self.get_dbconn()?;
+        let mut patterns: Vec<String> = dbconn.smembers("lnr_topic_patterns")?;
+        patterns.sort();
+        let mut out: Vec<(String, String)> = Vec::new();
+        for pattern in patterns {
+            if !topic_pattern::matches(&pattern, topic) {
+                continue;
+            }
+            let pattern_k = redis_safe(&pattern);
+            let rows: Vec<(String, String)> =
+                dbconn.hgetall(format!("lnr_topic:{pattern_k}:addr"))?;
+            for (addr, name) in rows {
+                if !out.iter().any(|(a, _)| *a == addr) {
+                    out.push((addr, name));
+                }
+            }
+        }
+        for (addr, name) in &out {
+            self.unique_name_cache
+                .insert(cache_name_key(topic, addr), name.clone());
+        }
+        Ok(out)
+    }
+
+    pub fn set_topic_group(&mut self, topic: &str, group: &str) -> RedisResult<()> {
+        let localhost = self.source_localhost.to_string();
+        let topic_k = redis_safe(topic);
+        let dbconn = self.get_dbconn()?;
+        if group.is_empty() {
+            dbconn.hdel(format!("lnr_topic:{topic_k}:group"), localhost)
+        } else {
+            dbconn.hset(format!("lnr_topic:{topic_k}:group"), localhost, group)
+        }
+    }
+
+    pub fn get_topic_groups(&mut self, topic: &str) -> RedisResult<Vec<(String, String)>> {
+        let dbconn = self.get_dbconn()?;
+        let topic_k = redis_safe(topic);
+        let mut out: Vec<(String, String)> = dbconn.hgetall(format!("lnr_topic:{topic_k}:group"))?;
+        out.sort();
+        let mut patterns: Vec<String> = dbconn.smembers("lnr_topic_patterns")?;
+        patterns.sort();
+        for pattern in patterns {
+            if !topic_pattern::matches(&pattern, topic) {
+                continue;
+            }
+            let pattern_k = redis_safe(&pattern);
+            let rows: Vec<(String, String)> =
+                dbconn.hgetall(format!("lnr_topic:{pattern_k}:group"))?;
+            for (addr, group) in rows {
+                if !out.iter().any(|(a, _)| *a == addr) {
+                    out.push((addr, group));
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn get_topic_by_key(&mut self, topic_key: i32) -> RedisResult<Option<String>> {
+        let dbconn = self.get_dbconn()?;
+        dbconn.get(format!("lnr_topic_key:{topic_key}"))
+    }
+
+    pub fn save_retained(&mut self, topic: &str, sender_topic: &str, payload: &[u8]) -> RedisResult<()> {
+        let dbconn = self.get_dbconn()?;
+        redis::pipe()
+            .atomic()
+            .hset("lnr_retained", topic, payload).ignore()
+            .hset("lnr_retained_from", topic, sender_topic).ignore()
+            .query(dbconn)
+    }
+
+    pub fn clear_retained(&mut self, topic: &str) -> RedisResult<()> {
+        let dbconn = self.get_dbconn()?;
+        redis::pipe()
+            .atomic()
+            .hdel("lnr_retained", topic).ignore()
+            .hdel("lnr_retained_from", topic).ignore()
+            .query(dbconn)
+    }
+
+    pub fn load_retained(&mut self, topic: &str) -> RedisResult<Vec<(String, String, Vec<u8>)>> {
+        let dbconn = self.get_dbconn()?;
+        let topics: Vec<String> = if topic_pattern::is_pattern(topic) {
+            let mut all: Vec<String> = dbconn.hkeys("lnr_retained")?;
+            all.retain(|t| topic_pattern::matches(topic, t));
+            all.sort();
+            all
+        } else {
+            vec![topic.to_string()]
+        };
+        let mut out = Vec::new();
+        for t in topics {
+            let payload: Option<Vec<u8>> = dbconn.hget("lnr_retained", &t)?;
+            if let Some(payload) = payload {
+                let from: Option<String> = dbconn.hget("lnr_retained_from", &t)?;
+                out.push((t, from.unwrap_or_default(), payload));
+            }
+        }
+        Ok(out)
+    }
+
+    fn scheduled_key(&self) -> String {
+        format!(
+            "lnr_sender:{}:{}:scheduled",
+            redis_safe(&self.unique_name),
+            redis_safe(&self.source_topic)
+        )
+    }
+
+    pub fn schedule_message(
+        &mut self,
+        topic: &str,
+        at_least_once: bool,
+        due_ms: u64,
+        payload: &[u8],
+        trace: Option<TraceContext>,
+    ) -> RedisResult<()> {
+        let zkey = self.scheduled_key();
+        let dbconn = self.get_dbconn()?;
+        let id: i64 = dbconn.incr("lnr_unique_key", 1)?;
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .hset(format!("lnr_scheduled:{id}"), "topic", topic).ignore()
+            .hset(format!("lnr_scheduled:{id}"), "alo", i32::from(at_least_once)).ignore()
+            .hset(format!("lnr_scheduled:{id}"), "payload", payload).ignore();
+        if let Some(trace) = trace {
+            pipe.hset(format!("lnr_scheduled:{id}"), "trace", trace.to_string()).ignore();
+        }
+        pipe.zadd(zkey, id, due_ms).ignore().query(dbconn)
+    }
+
+    pub fn load_due_scheduled(&mut self, now_ms: u64, limit: usize) -> RedisResult<Vec<ScheduledMessage>> {
+        let zkey = self.scheduled_key();
+        let dbconn = self.get_dbconn()?;
+        let count = isize::try_from(limit).unwrap_or(isize::MAX);
+        let ids: Vec<i64> = dbconn.zrangebyscore_limit(&zkey, "-inf", now_ms, 0, count)?;
+        let mut out = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (topic, alo, payload, trace): (Option<String>, Option<i32>, Option<Vec<u8>>, Option<String>) =
+                redis::cmd("HMGET")
+                    .arg(format!("lnr_scheduled:{id}"))
+                    .arg("topic")
+                    .arg("alo")
+                    .arg("payload")
+                    .arg("trace")
+                    .query(dbconn)?;
+            match (topic, payload) {
+                (Some(topic), Some(payload)) => out.push(ScheduledMessage {
+                    id,
+                    topic,
+                    at_least_once: alo.unwrap_or(0) != 0,
+                    payload,
+                    trace: trace.as_deref().and_then(TraceContext::parse),
+                }),
+                // Body lost (e.g. expired by hand): drop the dangling index entry.
+                _ => {
+                    let () = dbconn.zrem(&zkey, id)?;
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn remove_scheduled(&mut self, id: i64) -> RedisResult<()> {
+        let zkey = self.scheduled_key();
+        let dbconn = self.get_dbconn()?;
+        redis::pipe()
+            .atomic()
+            .zrem(zkey, id).ignore()
+            .del(format!("lnr_scheduled:{id}")).ignore()
+            .query(dbconn)
+    }
+
+    pub fn reschedule_message(&mut self, id: i64, due_ms: u64) -> RedisResult<()> {
+        let zkey = self.scheduled_key();
+        let dbconn = self.get_dbconn()?;
+        // XX: only move an entry that is still scheduled.
+        redis::cmd("ZADD").arg(zkey).arg("XX").arg(due_ms).arg(id).query(dbconn)
+    }
+
     pub fn count_pending_messages(&mut self, connection_key: i32) -> RedisResult<usize> {
         let dbconn = self.get_dbconn()?;
         let llen: Option<usize> =
@@ -326,6 +533,8 @@ impl Redis {
             let res: RedisResult<String> = dbconn.get(&format!("lnr_topic:{topic_k}:key"));
             if let Ok(key) = res{
                 let value = parse_i32_res(&key, "invalid topic key")?;
+                // Backfill the reverse entry for keys created before it existed.
+                dbconn.set_nx::<_,_,()>(format!("lnr_topic_key:{value}"), topic)?;
                 self.topic_key_cache.insert(topic.to_owned(), value);
                 Ok(value)
             }else{
@@ -340,20 +549,27 @@ impl Redis {
         let dbconn = self.get_dbconn()?; 
         *value = dbconn.incr("lnr_unique_key", 1)?;
         let topic_k = redis_safe(topic);
-        dbconn.set::<_,_,()>(&format!("lnr_topic:{topic_k}:key"), value)?;
+        dbconn.set::<_,_,()>(&format!("lnr_topic:{topic_k}:key"), *value)?;
+        dbconn.set::<_,_,()>(format!("lnr_topic_key:{value}"), topic)?;
         Ok(())
     }
 
     pub fn set_sender_topic_by_connection_key_from_sender(&mut self, connection_key: i32)->RedisResult<()>{
         let source_topic: String = self.source_topic.clone();
+        let unique_name: String = self.unique_name.clone();
         let dbconn = self.get_dbconn()?;
         let () = dbconn.set(&format!("lnr_connection:{}:sender", connection_key), source_topic)?;
+        let () = dbconn.set(format!("lnr_connection:{}:sender_name", connection_key), unique_name)?;
         Ok(())
     }
     pub fn get_sender_topic_by_connection_key(&mut self, connection_key: i32)->RedisResult<String>{
         let dbconn = self.get_dbconn()?;
         dbconn.get(&format!("lnr_connection:{}:sender", connection_key))
     }
+    pub fn get_sender_name_by_connection_key(&mut self, connection_key: i32)->RedisResult<Option<String>>{
+        let dbconn = self.get_dbconn()?;
+        dbconn.get(format!("lnr_connection:{}:sender_name", connection_key))
+    }
     
     pub fn set_last_mess_number_from_listener(&mut self, connection_key: i32, val: u64)->RedisResult<()>{
         let dbconn = self.get_dbconn()?;
@@ -446,6 +662,45 @@ impl Redis {
         Ok(out)
     }
           
+    pub fn reassign_pending_messages(
+        &mut self,
+        from_connection_key: i32,
+        to_connection_key: i32,
+        listener_topic_key: Option<i32>,
+        min_number: u64,
+    ) -> RedisResult<(usize, u64)> {
+        let acked = self.get_last_mess_number_for_sender(to_connection_key)?;
+        let from_key = format!("lnr_connection:{}:messages", from_connection_key);
+        let to_key = format!("lnr_connection:{}:messages", to_connection_key);
+        let dbconn = self.get_dbconn()?;
+        let blobs: Vec<Vec<u8>> = dbconn.lrange(&from_key, 0, -1)?;
+        if blobs.is_empty() {
+            return Ok((0, min_number.max(acked)));
+        }
+        let tail: Vec<Vec<u8>> = dbconn.lrange(&to_key, -1, -1)?;
+        let mut number = min_number
+            .max(acked)
+            .max(tail.first().and_then(|b| message::stored_number_mess(b)).unwrap_or(0));
+        let taken = blobs.len();
+        let mut moved: Vec<Vec<u8>> = Vec::with_capacity(taken);
+        for mut blob in blobs {
+            if !message::rewrite_stored_header(&mut blob, to_connection_key, listener_topic_key, number + 1) {
+                print_error!("reassign_pending_messages: truncated blob dropped");
+                continue;
+            }
+            number += 1;
+            moved.push(blob);
+        }
+        // Trim exactly what was read so blobs appended meanwhile stay on the old key.
+        let mut pipe = redis::pipe();
+        pipe.atomic().ltrim(&from_key, taken as isize, -1).ignore();
+        if !moved.is_empty() {
+            pipe.rpush(&to_key, &moved).ignore();
+        }
+        let () = pipe.query(dbconn)?;
+        Ok((moved.len(), number))
+    }
+
     fn get_dbconn(&mut self)->RedisResult<&mut redis::Connection>{
         if !self.conn.is_open(){
             let client = redis::Client::open(self.conn_str.clone())?;
@@ -459,6 +714,12 @@ impl Redis {
     pub fn seed_receivers(&mut self, _entries: &[ReceiverSeedEntry]) -> RedisResult<()> {
         Ok(())
     }
+
+    pub fn ping(&mut self) -> RedisResult<()> {
+        let dbconn = self.get_dbconn()?;
+        let _: String = redis::cmd("PING").query(dbconn)?;
+        Ok(())
+    }
 }
 
 impl Store for Redis {
@@ -478,6 +739,10 @@ impl Store for Redis {
         map_db(Redis::unregist_topic(self, topic))
     }
 
+    fn unregist_topic_elsewhere(&mut self, topic: &str) -> DbResult<()> {
+        map_db(Redis::unregist_topic_elsewhere(self, topic))
+    }
+
     fn clear_addresses_of_topic(&mut self) -> DbResult<()> {
         map_db(Redis::clear_addresses_of_topic(self))
     }
@@ -520,6 +785,57 @@ impl Store for Redis {
         map_db(Redis::get_topic_directory(self, topic))
     }
 
+    fn get_pattern_subscribers(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        map_db(Redis::get_pattern_subscribers(self, topic))
+    }
+
+    fn set_topic_group(&mut self, topic: &str, group: &str) -> DbResult<()> {
+        map_db(Redis::set_topic_group(self, topic, group))
+    }
+
+    fn get_topic_groups(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        map_db(Redis::get_topic_groups(self, topic))
+    }
+
+    fn get_topic_by_key(&mut self, topic_key: i32) -> DbResult<Option<String>> {
+        map_db(Redis::get_topic_by_key(self, topic_key))
+    }
+
+    fn save_retained(&mut self, topic: &str, sender_topic: &str, payload: &[u8]) -> DbResult<()> {
+        map_db(Redis::save_retained(self, topic, sender_topic, payload))
+    }
+
+    fn clear_retained(&mut self, topic: &str) -> DbResult<()> {
+        map_db(Redis::clear_retained(self, topic))
+    }
+
+    fn load_retained(&mut self, topic: &str) -> DbResult<Vec<(String, String, Vec<u8>)>> {
+        map_db(Redis::load_retained(self, topic))
+    }
+
+    fn schedule_message(
+        &mut self,
+        topic: &str,
+        at_least_once: bool,
+        due_ms: u64,
+        payload: &[u8],
+        trace: Option<TraceContext>,
+    ) -> DbResult<()> {
+        map_db(Redis::schedule_message(self, topic, at_least_once, due_ms, payload, trace))
+    }
+
+    fn load_due_scheduled(&mut self, now_ms: u64, limit: usize) -> DbResult<Vec<ScheduledMessage>> {
+        map_db(Redis::load_due_scheduled(self, now_ms, limit))
+    }
+
+    fn remove_scheduled(&mut self, id: i64) -> DbResult<()> {
+        map_db(Redis::remove_scheduled(self, id))
+    }
+
+    fn reschedule_message(&mut self, id: i64, due_ms: u64) -> DbResult<()> {
+        map_db(Redis::reschedule_message(self, id, due_ms))
+    }
+
     fn count_pending_messages(&mut self, connection_key: i32) -> DbResult<usize> {
         map_db(Redis::count_pending_messages(self, connection_key))
     }
@@ -547,6 +863,10 @@ impl Store for Redis {
         map_db(Redis::get_sender_topic_by_connection_key(self, connection_key))
     }
 
+    fn get_sender_name_by_connection_key(&mut self, connection_key: i32) -> DbResult<Option<String>> {
+        map_db(Redis::get_sender_name_by_connection_key(self, connection_key))
+    }
+
     fn set_last_mess_number_from_listener(&mut self, connection_key: i32, val: u64) -> DbResult<()> {
         map_db(Redis::set_last_mess_number_from_listener(
             self,
@@ -601,9 +921,29 @@ impl Store for Redis {
         ))
     }
 
+    fn reassign_pending_messages(
+        &mut self,
+        from_connection_key: i32,
+        to_connection_key: i32,
+        listener_topic_key: Option<i32>,
+        min_number: u64,
+    ) -> DbResult<(usize, u64)> {
+        map_db(Redis::reassign_pending_messages(
+            self,
+            from_connection_key,
+            to_connection_key,
+            listener_topic_key,
+            min_number,
+        ))
+    }
+
     fn seed_receivers(&mut self, entries: &[ReceiverSeedEntry]) -> DbResult<()> {
         map_db(Redis::seed_receivers(self, entries))
     }
+
+    fn ping(&mut self) -> DbResult<()> {
+        map_db(Redis::ping(self))
+    }
 }
 
 fn encode_and_free_messages(mempool: &Arc<Mutex<Mempool>>, mess: Vec<Message>) -> Vec<Vec<u8>> {
@@ -1,14 +1,17 @@
 //! SQLite-backed [`Store`](super::store::Store) implementation (parity with Redis `Redis`).
 
-use crate::{message::Message, mempool::Mempool, print_error};
+use crate::{message::{self, Message, Priority}, mempool::Mempool, print_error, topic_pattern, trace::TraceContext};
 
-use super::store::{DbError, DbResult, ReceiverSeedEntry, Store};
+use super::store::{DbError, DbResult, ReceiverSeedEntry, ScheduledMessage, Store};
 use rusqlite::{params, Connection, OptionalExtension};
 
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 const BUSY_MS: i32 = 5000;
+/// Scans only `idx_topic_addr_is_pattern`.
+const PATTERN_SUBSCRIBERS_SQL: &str =
+    "SELECT topic, addr, client_name FROM topic_addr WHERE is_pattern = 1 ORDER BY addr ASC";
 
 /// First `connection_key` on an **empty** SQLite file (isolated-DB pair model); not passed in `receivers_json`.
 pub(crate) const FIRST_ISOLATED_CONNECTION_KEY: i32 = 1;
@@ -73,6 +76,9 @@ impl Sqlite {
                 topic TEXT NOT NULL,
                 addr TEXT NOT NULL,
                 client_name TEXT NOT NULL,
+                grp TEXT NOT NULL DEFAULT '',
+                -- topic_pattern::is_pattern(topic), set on insert.
+                is_pattern INTEGER NOT NULL DEFAULT 0,
                 PRIMARY KEY (topic, addr)
             );
 
@@ -99,6 +105,11 @@ impl Sqlite {
                 sender_topic TEXT NOT NULL
             );
 
+            CREATE TABLE IF NOT EXISTS conn_sender_name (
+                connection_key INTEGER PRIMARY KEY,
+                unique_name TEXT NOT NULL
+            );
+
             CREATE TABLE IF NOT EXISTS conn_mess_number (
                 connection_key INTEGER PRIMARY KEY,
                 v INTEGER NOT NULL
@@ -111,6 +122,24 @@ impl Sqlite {
             );
             CREATE INDEX IF NOT EXISTS idx_conn_messages_ck
                 ON conn_messages(connection_key, id);
+
+            CREATE TABLE IF NOT EXISTS retained (
+                topic TEXT PRIMARY KEY,
+                sender_topic TEXT NOT NULL,
+                payload BLOB NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS scheduled (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                sender_key TEXT NOT NULL,
+                due_ms INTEGER NOT NULL,
+                topic TEXT NOT NULL,
+                at_least_once INTEGER NOT NULL,
+                payload BLOB NOT NULL,
+                traceparent TEXT
+            );
+            CREATE INDEX IF NOT EXISTS idx_scheduled_due
+                ON scheduled(sender_key, due_ms, id);
             ",
         )
         .map_err(|e| DbError::new(e.to_string()))?;
@@ -119,6 +148,29 @@ impl Sqlite {
             "ALTER TABLE sender_listener ADD COLUMN client_name TEXT NOT NULL DEFAULT ''",
             [],
         );
+        let _ = conn.execute(
+            "ALTER TABLE topic_addr ADD COLUMN grp TEXT NOT NULL DEFAULT ''",
+            [],
+        );
+        if conn
+            .execute("ALTER TABLE topic_addr ADD COLUMN is_pattern INTEGER NOT NULL DEFAULT 0", [])
+            .is_ok()
+        {
+            let topics: Vec<String> = conn
+                .prepare("SELECT DISTINCT topic FROM topic_addr")
+                .and_then(|mut stmt| stmt.query_map([], |r| r.get(0))?.collect())
+                .map_err(|e| DbError::new(e.to_string()))?;
+            for topic in topics.iter().filter(|t| topic_pattern::is_pattern(t)) {
+                conn.execute("UPDATE topic_addr SET is_pattern = 1 WHERE topic = ?1", params![topic])
+                    .map_err(|e| DbError::new(e.to_string()))?;
+            }
+        }
+        // Pattern rows only, for get_pattern_subscribers (same predicate as its query).
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_topic_addr_is_pattern ON topic_addr(addr) WHERE is_pattern = 1",
+            [],
+        )
+        .map_err(|e| DbError::new(e.to_string()))?;
 
         Ok(Sqlite {
             unique_name: unique_name.to_string(),
@@ -208,8 +260,8 @@ impl Sqlite {
         let tx = map_sql(self.conn.transaction())?;
         for e in entries {
             map_sql(tx.execute(
-                "INSERT OR REPLACE INTO topic_addr (topic, addr, client_name) VALUES (?1, ?2, ?3)",
-                params![&e.topic, &e.addr, &e.client_name],
+                "INSERT OR REPLACE INTO topic_addr (topic, addr, client_name, is_pattern) VALUES (?1, ?2, ?3, ?4)",
+                params![&e.topic, &e.addr, &e.client_name, topic_pattern::is_pattern(&e.topic)],
             ))?;
             map_sql(tx.execute(
                 "INSERT OR REPLACE INTO topic_key (topic, k) VALUES (?1, ?2)",
@@ -266,8 +318,8 @@ impl Store for Sqlite {
         let localhost = self.source_localhost.clone();
         let unique = self.unique_name.clone();
         map_sql(self.conn.execute(
-            "INSERT OR REPLACE INTO topic_addr (topic, addr, client_name) VALUES (?1, ?2, ?3)",
-            params![topic, localhost, unique],
+            "INSERT OR REPLACE INTO topic_addr (topic, addr, client_name, is_pattern) VALUES (?1, ?2, ?3, ?4)",
+            params![topic, localhost, unique, topic_pattern::is_pattern(topic)],
         ))?;
         self.init_addresses_of_topic(topic)?;
         Ok(())
@@ -283,6 +335,17 @@ impl Store for Sqlite {
         Ok(())
     }
 
+    fn unregist_topic_elsewhere(&mut self, topic: &str) -> DbResult<()> {
+        let localhost = self.source_localhost.clone();
+        let unique = self.unique_name.clone();
+        map_sql(self.conn.execute(
+            "DELETE FROM topic_addr WHERE topic = ?1 AND client_name = ?2 AND addr <> ?3",
+            params![topic, unique, localhost],
+        ))?;
+        self.init_addresses_of_topic(topic)?;
+        Ok(())
+    }
+
     fn clear_addresses_of_topic(&mut self) -> DbResult<()> {
         let topic = self.source_topic.clone();
         map_sql(
@@ -309,15 +372,19 @@ impl Store for Sqlite {
         };
         for (addr, listener_topic) in listeners {
             if let Ok(listener_name) = self.get_listener_unique_name(&listener_topic, &addr) {
-                if let Ok(connection_key) = self.get_connection_key_for_sender(&listener_name) {
-                    map_sql(self.conn.execute(
-                        "DELETE FROM conn_messages WHERE connection_key = ?1",
-                        params![connection_key],
-                    ))?;
-                    map_sql(self.conn.execute(
-                        "DELETE FROM conn_mess_number WHERE connection_key = ?1",
-                        params![connection_key],
-                    ))?;
+                // Priority lanes have their own keys next to the plain listener name.
+                for priority in std::iter::once(Priority::Normal).chain(Priority::RAISED) {
+                    let lane = priority.lane_listener_name(&listener_name);
+                    if let Ok(Some(connection_key)) = self.find_connection_key_for_sender(&lane) {
+                        map_sql(self.conn.execute(
+                            "DELETE FROM conn_messages WHERE connection_key = ?1",
+                            params![connection_key],
+                        ))?;
+                        map_sql(self.conn.execute(
+                            "DELETE FROM conn_mess_number WHERE connection_key = ?1",
+                            params![connection_key],
+                        ))?;
+                    }
                 }
             }
         }
@@ -417,6 +484,168 @@ impl Store for Sqlite {
         Ok(out)
     }
 
+    fn get_pattern_subscribers(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        let mut stmt = map_sql(self.conn.prepare(PATTERN_SUBSCRIBERS_SQL))?;
+        let rows = map_sql(stmt.query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))
+        }))?;
+        let mut out: Vec<(String, String)> = Vec::new();
+        for row in rows {
+            let (pattern, addr, name) = map_sql(row)?;
+            if !topic_pattern::matches(&pattern, topic) || out.iter().any(|(a, _)| *a == addr)
+            {
+                continue;
+            }
+            self.unique_name_cache
+                .insert(cache_name_key(topic, &addr), name.clone());
+            out.push((addr, name));
+        }
+        Ok(out)
+    }
+
+    fn set_topic_group(&mut self, topic: &str, group: &str) -> DbResult<()> {
+        let localhost = self.source_localhost.clone();
+        map_sql(self.conn.execute(
+            "UPDATE topic_addr SET grp = ?1 WHERE topic = ?2 AND addr = ?3",
+            params![group, topic, localhost],
+        ))?;
+        Ok(())
+    }
+
+    fn get_topic_groups(&mut self, topic: &str) -> DbResult<Vec<(String, String)>> {
+        let mut stmt = map_sql(self.conn.prepare(
+            "SELECT topic, addr, grp FROM topic_addr WHERE grp <> '' ORDER BY addr ASC",
+        ))?;
+        let rows = map_sql(stmt.query_map([], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))
+        }))?;
+        let mut exact: Vec<(String, String)> = Vec::new();
+        let mut by_pattern: Vec<(String, String)> = Vec::new();
+        for row in rows {
+            let (t, addr, group) = map_sql(row)?;
+            if t == topic {
+                exact.push((addr, group));
+            } else if topic_pattern::is_pattern(&t) && topic_pattern::matches(&t, topic) {
+                by_pattern.push((addr, group));
+            }
+        }
+        for (addr, group) in by_pattern {
+            if !exact.iter().any(|(a, _)| *a == addr) {
+                exact.push((addr, group));
+            }
+        }
+        Ok(exact)
+    }
+
+    fn get_topic_by_key(&mut self, topic_key: i32) -> DbResult<Option<String>> {
+        let mut stmt = map_sql(
+            self.conn
+                .prepare("SELECT topic FROM topic_key WHERE k = ?1 LIMIT 2"),
+        )?;
+        let rows = map_sql(stmt.query_map(params![topic_key], |r| r.get::<_, String>(0)))?;
+        let mut topics = Vec::new();
+        for row in rows {
+            topics.push(map_sql(row)?);
+        }
+        // Seeded catalogs share wire key 1 across topics: ambiguous, so unknown.
+        if topics.len() == 1 {
+            Ok(topics.pop())
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn save_retained(&mut self, topic: &str, sender_topic: &str, payload: &[u8]) -> DbResult<()> {
+        map_sql(self.conn.execute(
+            "INSERT INTO retained (topic, sender_topic, payload) VALUES (?1, ?2, ?3)
+             ON CONFLICT(topic) DO UPDATE SET sender_topic = excluded.sender_topic, payload = excluded.payload",
+            params![topic, sender_topic, payload],
+        ))?;
+        Ok(())
+    }
+
+    fn clear_retained(&mut self, topic: &str) -> DbResult<()> {
+        map_sql(self.conn.execute("DELETE FROM retained WHERE topic = ?1", params![topic]))?;
+        Ok(())
+    }
+
+    fn load_retained(&mut self, topic: &str) -> DbResult<Vec<(String, String, Vec<u8>)>> {
+        // Patterns are matched here; a concrete topic only needs its own row.
+        let mut stmt = map_sql(self.conn.prepare(
+            "SELECT topic, sender_topic, payload FROM retained
+             WHERE ?1 OR topic = ?2 ORDER BY topic ASC",
+        ))?;
+        let all = topic_pattern::is_pattern(topic);
+        let rows = map_sql(stmt.query_map(params![all, topic], |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Vec<u8>>(2)?))
+        }))?;
+        let mut out = Vec::new();
+        for row in rows {
+            let row = map_sql(row)?;
+            if topic_pattern::matches(topic, &row.0) {
+                out.push(row);
+            }
+        }
+        Ok(out)
+    }
+
+    fn schedule_message(
+        &mut self,
+        topic: &str,
+        at_least_once: bool,
+        due_ms: u64,
+        payload: &[u8],
+        trace: Option<TraceContext>,
+    ) -> DbResult<()> {
+        let sk = sender_key(&self.unique_name, &self.source_topic);
+        let due = i64::try_from(due_ms).unwrap_or(i64::MAX);
+        let traceparent = trace.map(|t| t.to_string());
+        map_sql(self.conn.execute(
+            "INSERT INTO scheduled (sender_key, due_ms, topic, at_least_once, payload, traceparent)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![sk, due, topic, at_least_once, payload, traceparent],
+        ))?;
+        Ok(())
+    }
+
+    fn load_due_scheduled(&mut self, now_ms: u64, limit: usize) -> DbResult<Vec<ScheduledMessage>> {
+        let sk = sender_key(&self.unique_name, &self.source_topic);
+        let now = i64::try_from(now_ms).unwrap_or(i64::MAX);
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let mut stmt = map_sql(self.conn.prepare(
+            "SELECT id, topic, at_least_once, payload, traceparent FROM scheduled
+             WHERE sender_key = ?1 AND due_ms <= ?2 ORDER BY due_ms ASC, id ASC LIMIT ?3",
+        ))?;
+        let rows = map_sql(stmt.query_map(params![sk, now, limit], |r| {
+            Ok(ScheduledMessage {
+                id: r.get(0)?,
+                topic: r.get(1)?,
+                at_least_once: r.get(2)?,
+                payload: r.get(3)?,
+                trace: r.get::<_, Option<String>>(4)?.as_deref().and_then(TraceContext::parse),
+            })
+        }))?;
+        let mut out = Vec::new();
+        for row in rows {
+            out.push(map_sql(row)?);
+        }
+        Ok(out)
+    }
+
+    fn remove_scheduled(&mut self, id: i64) -> DbResult<()> {
+        map_sql(self.conn.execute("DELETE FROM scheduled WHERE id = ?1", params![id]))?;
+        Ok(())
+    }
+
+    fn reschedule_message(&mut self, id: i64, due_ms: u64) -> DbResult<()> {
+        let due = i64::try_from(due_ms).unwrap_or(i64::MAX);
+        map_sql(self.conn.execute(
+            "UPDATE scheduled SET due_ms = ?2 WHERE id = ?1",
+            params![id, due],
+        ))?;
+        Ok(())
+    }
+
     fn count_pending_messages(&mut self, connection_key: i32) -> DbResult<usize> {
         let n: i64 = map_sql(self.conn.query_row(
             "SELECT COUNT(*) FROM conn_messages WHERE connection_key = ?1",
@@ -494,6 +723,10 @@ impl Store for Sqlite {
             "INSERT OR REPLACE INTO conn_sender (connection_key, sender_topic) VALUES (?1, ?2)",
             params![connection_key, source_topic],
         ))?;
+        map_sql(self.conn.execute(
+            "INSERT OR REPLACE INTO conn_sender_name (connection_key, unique_name) VALUES (?1, ?2)",
+            params![connection_key, self.unique_name],
+        ))?;
         Ok(())
     }
 
@@ -505,6 +738,18 @@ impl Store for Sqlite {
         ))
     }
 
+    fn get_sender_name_by_connection_key(&mut self, connection_key: i32) -> DbResult<Option<String>> {
+        map_sql(
+            self.conn
+                .query_row(
+                    "SELECT unique_name FROM conn_sender_name WHERE connection_key = ?1",
+                    params![connection_key],
+                    |r| r.get(0),
+                )
+                .optional(),
+        )
+    }
+
     fn set_last_mess_number_from_listener(&mut self, connection_key: i32, val: u64) -> DbResult<()> {
         let v = i64::try_from(val).map_err(|_| DbError::new("mess_number too large for i64"))?;
         map_sql(self.conn.execute(
@@ -643,16 +888,144 @@ impl Store for Sqlite {
         Ok(out)
     }
 
+    fn reassign_pending_messages(
+        &mut self,
+        from_connection_key: i32,
+        to_connection_key: i32,
+        listener_topic_key: Option<i32>,
+        min_number: u64,
+    ) -> DbResult<(usize, u64)> {
+        let acked = self.get_last_mess_number_for_sender(to_connection_key)?;
+        let tx = map_sql(self.conn.transaction())?;
+        let rows: Vec<(i64, Vec<u8>)> = {
+            let mut stmt = map_sql(tx.prepare(
+                "SELECT id, payload FROM conn_messages WHERE connection_key = ?1 ORDER BY id ASC",
+            ))?;
+            let rows = map_sql(stmt.query_map(params![from_connection_key], |r| {
+                Ok((r.get::<_, i64>(0)?, r.get::<_, Vec<u8>>(1)?))
+            }))?;
+            let mut p = Vec::new();
+            for row in rows {
+                p.push(map_sql(row)?);
+            }
+            p
+        };
+        let tail: Option<Vec<u8>> = map_sql(
+            tx.query_row(
+                "SELECT payload FROM conn_messages WHERE connection_key = ?1 ORDER BY id DESC LIMIT 1",
+                params![to_connection_key],
+                |r| r.get(0),
+            )
+            .optional(),
+        )?;
+        let mut number = min_number
+            .max(acked)
+            .max(tail.as_deref().and_then(message::stored_number_mess).unwrap_or(0));
+        let mut moved = 0;
+        for (id, mut blob) in rows {
+            map_sql(tx.execute("DELETE FROM conn_messages WHERE id = ?1", params![id]))?;
+            if !message::rewrite_stored_header(&mut blob, to_connection_key, listener_topic_key, number + 1) {
+                print_error!("reassign_pending_messages: truncated blob dropped");
+                continue;
+            }
+            number += 1;
+            // New row id keeps the moved messages behind the target's existing queue.
+            map_sql(tx.execute(
+                "INSERT INTO conn_messages (connection_key, payload) VALUES (?1, ?2)",
+                params![to_connection_key, blob],
+            ))?;
+            moved += 1;
+        }
+        map_sql(tx.commit())?;
+        Ok((moved, number))
+    }
+
     fn seed_receivers(&mut self, entries: &[ReceiverSeedEntry]) -> DbResult<()> {
         self.seed_receivers_sqlite(entries)
     }
+
+    fn ping(&mut self) -> DbResult<()> {
+        map_sql(self.conn.query_row("SELECT 1", [], |r| r.get::<_, i32>(0))).map(|_| ())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::settings;
     use crate::store::{ReceiverSeedEntry, Store};
 
+    #[test]
+    fn sqlite_pattern_subscribers_scan_only_the_pattern_index() {
+        let mut db = Sqlite::new("u", ":memory:").unwrap();
+        let mut seed = vec![
+            ReceiverSeedEntry { topic: "orders.#".into(), addr: "127.0.0.1:1".into(), client_name: "a".into() },
+            ReceiverSeedEntry { topic: "orders.*.eu".into(), addr: "127.0.0.1:2".into(), client_name: "b".into() },
+        ];
+        // Plain clients: a source topic and the internal channel row (its name holds a `#`).
+        for i in 10..20 {
+            let addr = format!("127.0.0.1:{i}");
+            let client_name = format!("plain_{i}");
+            seed.push(ReceiverSeedEntry { topic: client_name.clone(), addr: addr.clone(), client_name: client_name.clone() });
+            seed.push(ReceiverSeedEntry { topic: settings::INTERNAL_CHANNEL_TOPIC.into(), addr, client_name });
+        }
+        db.seed_receivers(&seed).unwrap();
+
+        let plan: String = db
+            .conn
+            .query_row(&format!("EXPLAIN QUERY PLAN {PATTERN_SUBSCRIBERS_SQL}"), [], |r| r.get(3))
+            .unwrap();
+        assert!(plan.contains("idx_topic_addr_is_pattern"), "{}", plan);
+        let mut stmt = db.conn.prepare(PATTERN_SUBSCRIBERS_SQL).unwrap();
+        let read = stmt.query_map([], |r| r.get::<_, String>(0)).unwrap().count();
+        assert_eq!(read, 2, "only the pattern rows are read");
+        // The scan steps through the partial index only, never the 22 catalog rows.
+        assert!(stmt.get_status(rusqlite::StatementStatus::FullscanStep) <= 2);
+        drop(stmt);
+
+        assert_eq!(
+            db.get_pattern_subscribers("orders.x.eu").unwrap(),
+            vec![
+                ("127.0.0.1:1".to_string(), "a".to_string()),
+                ("127.0.0.1:2".to_string(), "b".to_string()),
+            ]
+        );
+        assert!(db.get_pattern_subscribers(settings::INTERNAL_CHANNEL_TOPIC).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sqlite_marks_pattern_rows_of_an_older_catalog() {
+        let path = std::env::temp_dir().join(format!(
+            "liner_pattern_migrate_{}_{}.sqlite",
+            std::process::id(),
+            std::time::UNIX_EPOCH.elapsed().unwrap().as_nanos()
+        ));
+        let path = path.to_str().unwrap();
+        {
+            let conn = Connection::open(path).unwrap();
+            conn.execute_batch(
+                "CREATE TABLE topic_addr (
+                     topic TEXT NOT NULL, addr TEXT NOT NULL, client_name TEXT NOT NULL,
+                     PRIMARY KEY (topic, addr));
+                 INSERT INTO topic_addr VALUES ('orders.#', '127.0.0.1:1', 'a');
+                 INSERT INTO topic_addr VALUES ('__#internal_channel', '127.0.0.1:1', 'a');",
+            )
+            .unwrap();
+        }
+        let mut db = Sqlite::new("u", path).unwrap();
+        assert_eq!(
+            db.get_pattern_subscribers("orders.eu").unwrap(),
+            vec![("127.0.0.1:1".to_string(), "a".to_string())]
+        );
+        let marked: i64 = db
+            .conn
+            .query_row("SELECT count(*) FROM topic_addr WHERE is_pattern = 1", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(marked, 1);
+        drop(db);
+        let _ = std::fs::remove_file(path);
+    }
+
     #[test]
     fn sqlite_seed_receivers_empty_noop() {
         let mut db = Sqlite::new("u", ":memory:").unwrap();
@@ -873,4 +1246,54 @@ mod tests {
             .unwrap()
             .is_none());
     }
+
+    #[test]
+    fn sqlite_scheduled_messages_due_in_order_per_sender() {
+        let mut db = Sqlite::new("u", ":memory:").unwrap();
+        db.set_source_topic("st");
+        let ctx = TraceContext::parse("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01").unwrap();
+        db.schedule_message("t", true, 300, b"c", None).unwrap();
+        db.schedule_message("t", false, 100, b"a", Some(ctx)).unwrap();
+        db.schedule_message("t", true, 200, b"b", None).unwrap();
+        db.set_source_topic("other");
+        db.schedule_message("t", true, 50, b"x", None).unwrap();
+        db.set_source_topic("st");
+
+        assert!(db.load_due_scheduled(99, 10).unwrap().is_empty());
+        let due = db.load_due_scheduled(250, 10).unwrap();
+        let payloads: Vec<&[u8]> = due.iter().map(|m| m.payload.as_slice()).collect();
+        assert_eq!(payloads, vec![&b"a"[..], &b"b"[..]]);
+        assert!(!due[0].at_least_once);
+        assert_eq!(due[0].trace, Some(ctx));
+        assert_eq!(due[1].trace, None);
+
+        db.remove_scheduled(due[0].id).unwrap();
+        db.reschedule_message(due[1].id, 400).unwrap();
+        let due = db.load_due_scheduled(1000, 10).unwrap();
+        let payloads: Vec<&[u8]> = due.iter().map(|m| m.payload.as_slice()).collect();
+        assert_eq!(payloads, vec![&b"c"[..], &b"b"[..]]);
+    }
+
+    #[test]
+    fn sqlite_reassign_pending_renumbers_behind_target_queue() {
+        let mut db = Sqlite::new("u", ":memory:").unwrap();
+        db.set_source_topic("st");
+        let pool = Arc::new(Mutex::new(Mempool::new()));
+        let from = vec![
+            Message::new(pool.clone(), 1, 10, 1, b"a", true).unwrap(),
+            Message::new(pool.clone(), 1, 10, 2, b"b", true).unwrap(),
+        ];
+        db.save_messages_from_sender(&pool, 1, from).unwrap();
+        let to = vec![Message::new(pool.clone(), 2, 10, 7, b"x", true).unwrap()];
+        db.save_messages_from_sender(&pool, 2, to).unwrap();
+
+        assert_eq!(db.reassign_pending_messages(1, 2, None, 5).unwrap(), (2, 9));
+        assert_eq!(db.count_pending_messages(1).unwrap(), 0);
+        let loaded = db.load_messages_for_sender(&pool, 2).unwrap();
+        let numbers: Vec<u64> = loaded.iter().map(|m| m.number_mess).collect();
+        assert_eq!(numbers, vec![7, 8, 9]);
+        assert!(loaded.iter().all(|m| m.connection_key(&pool) == 2));
+
+        assert_eq!(db.reassign_pending_messages(1, 2, None, 9).unwrap(), (0, 9));
+    }
 }
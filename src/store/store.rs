@@ -5,6 +5,7 @@ use std::sync::{Arc, Mutex};
 
 use crate::message::Message;
 use crate::mempool::Mempool;
+use crate::trace::TraceContext;
 
 #[derive(Debug, Clone)]
 pub struct DbError(String);
@@ -28,6 +29,17 @@ pub struct ReceiverSeedEntry {
     pub client_name: String,
 }
 
+/// Message held by [`Store::schedule_message`] until its due time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledMessage {
+    pub id: i64,
+    pub topic: String,
+    pub at_least_once: bool,
+    pub payload: Vec<u8>,
+    /// Trace context current when the message was scheduled.
+    pub trace: Option<TraceContext>,
+}
+
 impl DbError {
     pub fn new(msg: impl Into<String>) -> Self {
         DbError(msg.into())
@@ -51,6 +63,9 @@ pub trait Store: Send {
 
     fn regist_topic(&mut self, topic: &str) -> DbResult<()>;
     fn unregist_topic(&mut self, topic: &str) -> DbResult<()>;
+    /// Drop this client's (`unique_name`) registrations under `topic` at addresses other than
+    /// `source_localhost`, e.g. left by an earlier run of a durable subscription.
+    fn unregist_topic_elsewhere(&mut self, topic: &str) -> DbResult<()>;
     fn clear_addresses_of_topic(&mut self) -> DbResult<()>;
     fn clear_stored_messages(&mut self) -> DbResult<()>;
 
@@ -71,6 +86,54 @@ pub trait Store: Send {
         topic: &str,
     ) -> DbResult<Vec<(String, String)>>;
 
+    /// Catalog rows `(addr, unique_name)` of pattern subscriptions (`orders.*`, `orders.#`)
+    /// that match the concrete `topic`. Names are also cached under `topic`, so
+    /// [`get_listener_unique_name`](Self::get_listener_unique_name) resolves these addresses.
+    fn get_pattern_subscribers(&mut self, topic: &str) -> DbResult<Vec<(String, String)>>;
+
+    /// Set (or clear with `""`) the consumer group of this client's registration under `topic`.
+    /// Call after [`regist_topic`](Self::regist_topic); unregistering drops the group.
+    fn set_topic_group(&mut self, topic: &str, group: &str) -> DbResult<()>;
+
+    /// `(addr, group)` of grouped registrations under `topic` and of matching pattern
+    /// subscriptions (exact rows win for one address). Ungrouped addresses are omitted.
+    fn get_topic_groups(&mut self, topic: &str) -> DbResult<Vec<(String, String)>>;
+
+    /// Topic name registered under wire `topic_key`, or `None` if unknown.
+    fn get_topic_by_key(&mut self, topic_key: i32) -> DbResult<Option<String>>;
+
+    /// Keep `payload` from `sender_topic` as the retained value of `topic`, replacing the previous one.
+    fn save_retained(&mut self, topic: &str, sender_topic: &str, payload: &[u8]) -> DbResult<()>;
+
+    /// Drop the retained value of `topic` (`Ok` if there is none).
+    fn clear_retained(&mut self, topic: &str) -> DbResult<()>;
+
+    /// Retained `(topic, sender_topic, payload)` of `topic`; for a pattern, of every matching
+    /// concrete topic, ordered by topic.
+    fn load_retained(&mut self, topic: &str) -> DbResult<Vec<(String, String, Vec<u8>)>>;
+
+    /// Hold `payload` for `topic` until `due_ms` (ms since the Unix epoch). Rows belong to
+    /// this client's `unique_name` / `source_topic`, so they survive a restart of the sender.
+    /// `trace` is kept with the row and handed back by [`load_due_scheduled`](Self::load_due_scheduled).
+    fn schedule_message(
+        &mut self,
+        topic: &str,
+        at_least_once: bool,
+        due_ms: u64,
+        payload: &[u8],
+        trace: Option<TraceContext>,
+    ) -> DbResult<()>;
+
+    /// Up to `limit` of this client's scheduled messages due at `now_ms`, earliest first.
+    /// They stay stored until [`remove_scheduled`](Self::remove_scheduled).
+    fn load_due_scheduled(&mut self, now_ms: u64, limit: usize) -> DbResult<Vec<ScheduledMessage>>;
+
+    /// Drop a released scheduled message (`Ok` if it is already gone).
+    fn remove_scheduled(&mut self, id: i64) -> DbResult<()>;
+
+    /// Move a scheduled message to `due_ms`, e.g. to retry a release that found no route.
+    fn reschedule_message(&mut self, id: i64, due_ms: u64) -> DbResult<()>;
+
     /// Offline queue length for `connection_key` (0 if absent).
     fn count_pending_messages(&mut self, connection_key: i32) -> DbResult<usize>;
 
@@ -80,8 +143,11 @@ pub trait Store: Send {
     fn get_connection_key_for_sender(&mut self, listener_name: &str) -> DbResult<i32>;
     fn get_topic_key(&mut self, topic: &str) -> DbResult<i32>;
 
+    /// Records this client's source topic and unique name as the sender of `connection_key`.
     fn set_sender_topic_by_connection_key_from_sender(&mut self, connection_key: i32) -> DbResult<()>;
     fn get_sender_topic_by_connection_key(&mut self, connection_key: i32) -> DbResult<String>;
+    /// Sender unique name of `connection_key`; `None` if the sender never recorded it (older peer, seeded key).
+    fn get_sender_name_by_connection_key(&mut self, connection_key: i32) -> DbResult<Option<String>>;
 
     fn set_last_mess_number_from_listener(&mut self, connection_key: i32, val: u64) -> DbResult<()>;
     fn get_last_mess_number_for_listener(&mut self, connection_key: i32) -> DbResult<u64>;
@@ -106,8 +172,24 @@ pub trait Store: Send {
         connection_key: i32,
     ) -> DbResult<Option<Message>>;
 
+    /// Move the whole offline queue of `from_connection_key` to the tail of
+    /// `to_connection_key`'s queue, in order. Headers are rewritten to the new key and
+    /// renumbered after the largest of `min_number`, the target's acked number and its last
+    /// queued message, so the new listener's dedup accepts them. `listener_topic_key` of
+    /// `Some` also rewrites the wire topic. Returns `(moved, last assigned number)`.
+    fn reassign_pending_messages(
+        &mut self,
+        from_connection_key: i32,
+        to_connection_key: i32,
+        listener_topic_key: Option<i32>,
+        min_number: u64,
+    ) -> DbResult<(usize, u64)>;
+
     /// Upsert remote listener catalog (SQLite). Empty slice is always `Ok(())`.
     /// **SQLite:** also seeds `topic_key` (wire key **1** per catalog topic), `conn_sender` for the first channel, and `topic_addr`; see `docs/using-sqlite.md`.
     /// **Redis:** no-op — deployments use a shared catalog, not `receivers_json` seeding.
     fn seed_receivers(&mut self, entries: &[ReceiverSeedEntry]) -> DbResult<()>;
+
+    /// Cheapest round trip to the backend (health check); reconnects like any other call.
+    fn ping(&mut self) -> DbResult<()>;
 }
@@ -0,0 +1,91 @@
+//! Hierarchical topic patterns for `subscribe` (`orders.*.created`, `orders.#`).
+//!
+//! Topics are split on `.`. A segment that is exactly `*` matches one segment; a trailing
+//! segment `#` matches zero or more segments. `*` / `#` inside a longer segment are literal,
+//! so plain names such as `__#internal_channel` are never patterns.
+
+pub const SEPARATOR: char = '.';
+pub const ONE_SEGMENT: &str = "*";
+pub const REST_SEGMENTS: &str = "#";
+
+/// `true` if `topic` contains a wildcard segment.
+pub fn is_pattern(topic: &str) -> bool {
+    topic
+        .split(SEPARATOR)
+        .any(|s| s == ONE_SEGMENT || s == REST_SEGMENTS)
+}
+
+/// `false` if a `#` segment is followed by more segments (`orders.#.created`).
+pub fn is_valid(topic: &str) -> bool {
+    let mut segs = topic.split(SEPARATOR).peekable();
+    while let Some(s) = segs.next() {
+        if s == REST_SEGMENTS && segs.peek().is_some() {
+            return false;
+        }
+    }
+    true
+}
+
+/// `true` if the concrete `topic` matches `pattern`. A `#` that is not the last segment
+/// matches nothing. A pattern without wildcards matches only the identical topic.
+pub fn matches(pattern: &str, topic: &str) -> bool {
+    let mut pat = pattern.split(SEPARATOR).peekable();
+    let mut top = topic.split(SEPARATOR);
+    while let Some(p) = pat.next() {
+        if p == REST_SEGMENTS {
+            return pat.peek().is_none();
+        }
+        match top.next() {
+            Some(t) if p == ONE_SEGMENT || p == t => {}
+            _ => return false,
+        }
+    }
+    top.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_names_are_not_patterns() {
+        assert!(!is_pattern("orders.eu.created"));
+        assert!(!is_pattern("__#internal_channel"));
+        assert!(!is_pattern("a*.b"));
+        assert!(is_pattern("orders.*.created"));
+        assert!(is_pattern("orders.#"));
+        assert!(is_pattern("#"));
+    }
+
+    #[test]
+    fn hash_must_be_last() {
+        assert!(is_valid("orders.#"));
+        assert!(is_valid("orders.*.created"));
+        assert!(is_valid("plain"));
+        assert!(!is_valid("orders.#.created"));
+    }
+
+    #[test]
+    fn star_matches_exactly_one_segment() {
+        assert!(matches("orders.*.created", "orders.eu.created"));
+        assert!(!matches("orders.*.created", "orders.created"));
+        assert!(!matches("orders.*.created", "orders.eu.x.created"));
+        assert!(!matches("orders.*", "orders.eu.created"));
+    }
+
+    #[test]
+    fn hash_matches_the_rest() {
+        assert!(matches("orders.#", "orders"));
+        assert!(matches("orders.#", "orders.eu"));
+        assert!(matches("orders.#", "orders.eu.created"));
+        assert!(matches("#", "anything.at.all"));
+        assert!(!matches("orders.#", "payments.eu"));
+        assert!(!matches("orders.#.created", "orders.eu.created"));
+    }
+
+    #[test]
+    fn plain_pattern_matches_only_itself() {
+        assert!(matches("orders.eu", "orders.eu"));
+        assert!(!matches("orders.eu", "orders.eu.created"));
+    }
+}
@@ -0,0 +1,206 @@
+//! W3C trace context (`traceparent`) propagation.
+//!
+//! A send on a thread with a current [`TraceContext`] stamps it into the message. While the
+//! receive callback runs, the message's context is current on the receive thread, so sends
+//! made from the callback continue the same trace. With the **`tracing`** feature the
+//! callback also runs inside a `liner.receive` span carrying the trace and parent span ids.
+
+use std::cell::Cell;
+use std::fmt;
+
+/// Length of a version `00` `traceparent` header value.
+pub const TRACEPARENT_LEN: usize = 55;
+
+/// Trace id, parent span id and trace flags of a W3C `traceparent`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TraceContext {
+    pub trace_id: [u8; 16],
+    pub span_id: [u8; 8],
+    pub flags: u8,
+}
+
+impl TraceContext {
+    /// Bytes of the wire trailer: trace id, span id, flags.
+    pub(crate) const WIRE_LEN: usize = 16 + 8 + 1;
+
+    /// Parse `version-traceid-spanid-flags`. Versions above `00` are read by their `00`
+    /// prefix; all-zero ids and version `ff` are rejected.
+    pub fn parse(traceparent: &str) -> Option<TraceContext> {
+        let s = traceparent.trim().as_bytes();
+        if s.len() < TRACEPARENT_LEN || (s.len() > TRACEPARENT_LEN && s[TRACEPARENT_LEN] != b'-') {
+            return None;
+        }
+        let version = hex_byte(&s[0..2])?;
+        if version == 0xff || (version == 0 && s.len() != TRACEPARENT_LEN) {
+            return None;
+        }
+        if s[2] != b'-' || s[35] != b'-' || s[52] != b'-' {
+            return None;
+        }
+        let mut ctx = TraceContext { trace_id: [0; 16], span_id: [0; 8], flags: hex_byte(&s[53..55])? };
+        for (i, b) in ctx.trace_id.iter_mut().enumerate() {
+            *b = hex_byte(&s[3 + 2 * i..5 + 2 * i])?;
+        }
+        for (i, b) in ctx.span_id.iter_mut().enumerate() {
+            *b = hex_byte(&s[36 + 2 * i..38 + 2 * i])?;
+        }
+        ctx.is_valid().then_some(ctx)
+    }
+
+    /// The `sampled` trace flag.
+    pub fn is_sampled(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+
+    fn is_valid(&self) -> bool {
+        self.trace_id != [0; 16] && self.span_id != [0; 8]
+    }
+
+    pub(crate) fn to_wire(self) -> [u8; Self::WIRE_LEN] {
+        let mut out = [0; Self::WIRE_LEN];
+        out[..16].copy_from_slice(&self.trace_id);
+        out[16..24].copy_from_slice(&self.span_id);
+        out[24] = self.flags;
+        out
+    }
+
+    pub(crate) fn from_wire(b: &[u8]) -> Option<TraceContext> {
+        if b.len() < Self::WIRE_LEN {
+            return None;
+        }
+        let ctx = TraceContext {
+            trace_id: b[..16].try_into().ok()?,
+            span_id: b[16..24].try_into().ok()?,
+            flags: b[24],
+        };
+        ctx.is_valid().then_some(ctx)
+    }
+}
+
+/// The version `00` `traceparent` value.
+impl fmt::Display for TraceContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "00-{}-{}-{:02x}", Hex(&self.trace_id), Hex(&self.span_id), self.flags)
+    }
+}
+
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|b| write!(f, "{:02x}", b))
+    }
+}
+
+/// Two lowercase hex digits (the spec forbids uppercase).
+fn hex_byte(s: &[u8]) -> Option<u8> {
+    let digit = |c: u8| match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        _ => None,
+    };
+    Some(digit(s[0])? << 4 | digit(s[1])?)
+}
+
+thread_local! {
+    static CURRENT: Cell<Option<TraceContext>> = const { Cell::new(None) };
+}
+
+/// Context stamped into sends from this thread, if any.
+pub fn current() -> Option<TraceContext> {
+    CURRENT.with(|c| c.get())
+}
+
+/// Replace this thread's context (`None` clears it); returns the previous one.
+pub fn set_current(ctx: Option<TraceContext>) -> Option<TraceContext> {
+    CURRENT.with(|c| c.replace(ctx))
+}
+
+/// Makes a context current on this thread until dropped (restores the outer one).
+pub struct TraceScope {
+    prev: Option<TraceContext>,
+    #[cfg(feature = "tracing")]
+    _span: Option<tracing::span::EnteredSpan>,
+}
+
+pub fn scope(ctx: TraceContext) -> TraceScope {
+    TraceScope {
+        prev: set_current(Some(ctx)),
+        #[cfg(feature = "tracing")]
+        _span: None,
+    }
+}
+
+/// Context of a message for the duration of its receive callback; `None` hides the
+/// context of the previous message.
+pub(crate) fn delivery_scope(ctx: Option<TraceContext>) -> TraceScope {
+    TraceScope {
+        prev: set_current(ctx),
+        #[cfg(feature = "tracing")]
+        _span: ctx.map(|ctx| {
+            tracing::info_span!(
+                "liner.receive",
+                trace_id = %Hex(&ctx.trace_id),
+                parent_id = %Hex(&ctx.span_id),
+                sampled = ctx.is_sampled()
+            )
+            .entered()
+        }),
+    }
+}
+
+impl Drop for TraceScope {
+    fn drop(&mut self) {
+        set_current(self.prev);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TP: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn traceparent_parses_and_formats_back() {
+        let ctx = TraceContext::parse(TP).unwrap();
+        assert_eq!(ctx.trace_id[0], 0x4b);
+        assert_eq!(ctx.span_id[7], 0xb7);
+        assert!(ctx.is_sampled());
+        assert_eq!(ctx.to_string(), TP);
+        assert_eq!(TraceContext::from_wire(&ctx.to_wire()), Some(ctx));
+        // Future versions are read by their 00 prefix.
+        assert_eq!(TraceContext::parse(&format!("01{}-extra", &TP[2..])), Some(ctx));
+
+        for bad in [
+            "",
+            &TP[..54],
+            TP.to_uppercase().as_str(),
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00_4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            format!("{}-extra", TP).as_str(),
+        ] {
+            assert_eq!(TraceContext::parse(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn scopes_nest_and_restore() {
+        let a = TraceContext::parse(TP).unwrap();
+        let b = TraceContext { flags: 0, ..a };
+        assert_eq!(current(), None);
+        {
+            let _a = scope(a);
+            {
+                let _none = delivery_scope(None);
+                assert_eq!(current(), None);
+                let _b = scope(b);
+                assert_eq!(current(), Some(b));
+            }
+            assert_eq!(current(), Some(a));
+        }
+        assert_eq!(current(), None);
+    }
+}
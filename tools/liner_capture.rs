@@ -0,0 +1,218 @@
+//! Reads capture files written by `Client::start_capture` (`lnr_start_capture`).
+//!
+//! ```text
+//! liner_capture print <file>
+//! liner_capture replay <file> <topic> (--sqlite <path> | --redis <url>)
+//!                      [--direction sent|received] [--from-topic <topic>] [--timing]
+//! ```
+//!
+//! `print` lists one line per frame: capture time, direction, peer, topic, message number,
+//! connection key, priority, flags and payload.
+//!
+//! `replay` sends the payload of every matching frame, in capture order, to `<topic>` through a
+//! fresh `Liner` on `127.0.0.1:0`, keeping each frame's priority and delivery mode. Only frames of
+//! one direction are replayed (`sent` by default) so a capture of both sides does not double up;
+//! internal notices are skipped. `--timing` keeps the gaps between capture timestamps. The tool
+//! exits once the peers acked everything or after 30 s.
+
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use std::thread;
+
+use liner_broker::capture::{CaptureReader, CaptureRecord};
+use liner_broker::Liner;
+
+const INTERNAL_TOPIC_PREFIX: &str = "__#";
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+const PREVIEW_LEN: usize = 48;
+
+fn usage() -> ExitCode {
+    eprintln!("usage: liner_capture print <file>");
+    eprintln!("       liner_capture replay <file> <topic> (--sqlite <path> | --redis <url>)");
+    eprintln!("                            [--direction sent|received] [--from-topic <topic>] [--timing]");
+    ExitCode::from(2)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = match args.first().map(String::as_str) {
+        Some("print") if args.len() == 2 => print(&args[1]),
+        Some("replay") if args.len() >= 3 => match ReplayOpts::parse(&args[3..]) {
+            Some(opts) => replay(&args[1], &args[2], &opts),
+            None => return usage(),
+        },
+        _ => return usage(),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("liner_capture: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn direction(rec: &CaptureRecord) -> &'static str {
+    if rec.inbound { "recv" } else { "sent" }
+}
+
+fn preview(payload: &[u8]) -> String {
+    let head = &payload[..payload.len().min(PREVIEW_LEN)];
+    let mut out = if head.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        format!("{:?}", String::from_utf8_lossy(head))
+    } else {
+        head.iter().map(|b| format!("{:02x}", b)).collect()
+    };
+    if payload.len() > head.len() {
+        out.push_str("...");
+    }
+    out
+}
+
+fn print(path: &str) -> Result<(), String> {
+    let reader = CaptureReader::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut count = 0;
+    for rec in reader {
+        let rec = rec.map_err(|e| format!("{}: record {}: {}", path, count + 1, e))?;
+        count += 1;
+        let topic = if rec.topic.is_empty() { "?" } else { rec.topic.as_str() };
+        match rec.decode() {
+            Some(m) => {
+                let mut flags = Vec::new();
+                if m.at_least_once {
+                    flags.push("at_least_once");
+                }
+                if m.replayed {
+                    flags.push("replayed");
+                }
+                if m.trace.is_some() {
+                    flags.push("traced");
+                }
+                println!(
+                    "{} {} {} {} #{} key={} topic_key={} {:?} [{}] {} bytes {}",
+                    rec.ts_ms,
+                    direction(&rec),
+                    rec.peer,
+                    topic,
+                    m.number,
+                    m.connection_key,
+                    m.topic_key,
+                    m.priority,
+                    flags.join(","),
+                    m.payload.len(),
+                    preview(&m.payload)
+                );
+            }
+            None => println!(
+                "{} {} {} {} malformed frame, {} bytes",
+                rec.ts_ms,
+                direction(&rec),
+                rec.peer,
+                topic,
+                rec.frame.len()
+            ),
+        }
+    }
+    eprintln!("{} frames", count);
+    Ok(())
+}
+
+enum StoreArg {
+    Sqlite(String),
+    Redis(String),
+}
+
+struct ReplayOpts {
+    store: StoreArg,
+    inbound: bool,
+    from_topic: Option<String>,
+    timing: bool,
+}
+
+impl ReplayOpts {
+    fn parse(args: &[String]) -> Option<ReplayOpts> {
+        let mut store = None;
+        let mut inbound = false;
+        let mut from_topic = None;
+        let mut timing = false;
+        let mut it = args.iter();
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--sqlite" => store = Some(StoreArg::Sqlite(it.next()?.clone())),
+                "--redis" => store = Some(StoreArg::Redis(it.next()?.clone())),
+                "--direction" => {
+                    inbound = match it.next()?.as_str() {
+                        "sent" => false,
+                        "received" => true,
+                        _ => return None,
+                    }
+                }
+                "--from-topic" => from_topic = Some(it.next()?.clone()),
+                "--timing" => timing = true,
+                _ => return None,
+            }
+        }
+        Some(ReplayOpts { store: store?, inbound, from_topic, timing })
+    }
+}
+
+fn replay(path: &str, topic: &str, opts: &ReplayOpts) -> Result<(), String> {
+    let mut records = Vec::new();
+    for rec in CaptureReader::open(path).map_err(|e| format!("{}: {}", path, e))? {
+        let rec = rec.map_err(|e| format!("{}: {}", path, e))?;
+        if rec.inbound != opts.inbound || rec.topic.starts_with(INTERNAL_TOPIC_PREFIX) {
+            continue;
+        }
+        if opts.from_topic.as_ref().is_some_and(|t| *t != rec.topic) {
+            continue;
+        }
+        records.push(rec);
+    }
+
+    let name = format!("liner_capture_{}", std::process::id());
+    let mut client = match &opts.store {
+        StoreArg::Sqlite(db) => Liner::new_sqlite(&name, &name, "127.0.0.1:0", db, ""),
+        StoreArg::Redis(url) => Liner::new(&name, &name, "127.0.0.1:0", url),
+    };
+    if !client.run(Box::new(|_: &str, _: &str, _: &[u8]| {})) {
+        return Err(format!("client run failed: {}", client.last_error_message()));
+    }
+
+    let mut sent = 0;
+    let mut prev_ts = None;
+    for rec in &records {
+        let Some(m) = rec.decode() else {
+            eprintln!("skip malformed frame at {}", rec.ts_ms);
+            continue;
+        };
+        if opts.timing {
+            if let Some(prev) = prev_ts {
+                thread::sleep(Duration::from_millis(rec.ts_ms.saturating_sub(prev)));
+            }
+            prev_ts = Some(rec.ts_ms);
+        }
+        if !client.send_to_priority(topic, &m.payload, m.at_least_once, m.priority) {
+            let err = format!("send #{} failed: {}", m.number, client.last_error_message());
+            client.stop();
+            return Err(err);
+        }
+        sent += 1;
+    }
+
+    let start = Instant::now();
+    let drained = loop {
+        let peers = client.peers();
+        let idle = peers.iter().filter(|p| !p.inbound).all(|p| p.queue_depth == 0 && p.unacked() == 0);
+        if idle || start.elapsed() > DRAIN_TIMEOUT {
+            break idle;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+    client.stop();
+    eprintln!("replayed {} of {} frames to {}", sent, records.len(), topic);
+    if drained {
+        Ok(())
+    } else {
+        Err("timed out waiting for acks".to_owned())
+    }
+}